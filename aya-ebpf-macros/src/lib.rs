@@ -8,6 +8,8 @@ mod cgroup_sockopt;
 mod cgroup_sysctl;
 mod fentry;
 mod fexit;
+mod flow_dissector;
+mod iter;
 mod kprobe;
 mod lsm;
 mod map;
@@ -32,6 +34,8 @@ use cgroup_sockopt::CgroupSockopt;
 use cgroup_sysctl::CgroupSysctl;
 use fentry::FEntry;
 use fexit::FExit;
+use flow_dissector::FlowDissector;
+use iter::Iter;
 use kprobe::{KProbe, KProbeKind};
 use lsm::Lsm;
 use map::Map;
@@ -48,6 +52,20 @@ use tracepoint::TracePoint;
 use uprobe::{UProbe, UProbeKind};
 use xdp::Xdp;
 
+/// Marks a static as an eBPF map.
+///
+/// The map is placed in the `maps` section and named after the static, unless a different name
+/// is passed with the `name` argument. User space looks the map up by that name, for example
+/// with `Ebpf::map`. The key and value types of the map determine its key and value sizes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{macros::map, maps::HashMap};
+///
+/// #[map(name = "PIDS")]
+/// static PID_COUNTS: HashMap<u32, u64> = HashMap::with_max_entries(1024, 0);
+/// ```
 #[proc_macro_attribute]
 pub fn map(attrs: TokenStream, item: TokenStream) -> TokenStream {
     match Map::parse(attrs.into(), item.into()) {
@@ -142,6 +160,37 @@ pub fn xdp(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
     .into()
 }
+
+/// Marks a function as an eBPF Classifier program that can be attached to the traffic control
+/// ingress or egress hook of a network interface.
+///
+/// Classifiers are attached in direct-action mode, so the value returned by the program is the
+/// verdict for the packet: one of the `TC_ACT_*` constants in `aya_ebpf::bindings`, for
+/// instance `TC_ACT_OK` to let the packet through, `TC_ACT_SHOT` to drop it, `TC_ACT_REDIRECT`
+/// after calling `bpf_redirect`, or `TC_ACT_PIPE` to run the next filter on the hook.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.1.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{
+///     bindings::{TC_ACT_OK, TC_ACT_SHOT},
+///     macros::classifier,
+///     programs::TcContext,
+/// };
+///
+/// #[classifier]
+/// pub fn drop_jumbo_frames(ctx: TcContext) -> i32 {
+///     if ctx.len() > 1500 {
+///         TC_ACT_SHOT as i32
+///     } else {
+///         TC_ACT_OK as i32
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn classifier(attrs: TokenStream, item: TokenStream) -> TokenStream {
     match SchedClassifier::parse(attrs.into(), item.into()) {
@@ -282,6 +331,48 @@ pub fn raw_tracepoint(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Marks a function as a BPF iterator program.
+///
+/// Iterator programs are run once for each element of a kernel object collection, like tasks or
+/// sockets, and write their output to a seq_file that user space reads. The iterator target is
+/// provided with `target`, for example `target = "task"`. You may also provide `sleepable` to
+/// mark the program as sleepable. Arguments should be comma separated.
+///
+/// The program returns 0 to continue with the next element.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.8.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(non_camel_case_types)]
+/// use aya_ebpf::{macros::iter, programs::IterContext};
+/// # struct task_struct {}
+///
+/// #[iter(target = "task")]
+/// pub fn dump_task(ctx: IterContext) -> i32 {
+///     let task: *const task_struct = ctx.item();
+///     if task.is_null() {
+///         // the last call, after all the tasks have been visited
+///         return 0;
+///     }
+///     if ctx.seq_num() == 0 {
+///         let _ = ctx.seq_write(b"tasks:\n");
+///     }
+///     0
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn iter(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Iter::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.into_compile_error(),
+    }
+    .into()
+}
+
 /// Marks a function as an LSM program that can be attached to Linux LSM hooks.
 /// Used to implement security policy and audit logging.
 ///
@@ -569,6 +660,37 @@ pub fn sk_lookup(attrs: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Marks a function as an eBPF Flow Dissector program that can be attached to
+/// a network namespace.
+///
+/// The program returns `BPF_OK` after filling in the flow keys, `BPF_DROP` if
+/// the packet could not be dissected, or `BPF_FLOW_DISSECTOR_CONTINUE` to fall
+/// back to the kernel's built-in flow dissector.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.20
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya_ebpf::{bindings::bpf_ret_code, macros::flow_dissector, programs::FlowDissectorContext};
+///
+/// #[flow_dissector]
+/// pub fn dissect(_ctx: FlowDissectorContext) -> u32 {
+///     // inspect the packet and fill in ctx.flow_keys()
+///     return bpf_ret_code::BPF_FLOW_DISSECTOR_CONTINUE
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn flow_dissector(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match FlowDissector::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog.expand(),
+        Err(err) => err.emit_as_expr_tokens(),
+    }
+    .into()
+}
+
 /// Marks a function as a cgroup device eBPF program that can be attached to a
 /// cgroup.
 ///
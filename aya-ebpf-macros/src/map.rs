@@ -4,7 +4,8 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{ItemStatic, Result};
 
-use crate::args::name_arg;
+use crate::args::{err_on_unknown_args, name_arg};
+
 pub(crate) struct Map {
     item: ItemStatic,
     name: String,
@@ -15,6 +16,7 @@ impl Map {
         let item: ItemStatic = syn::parse2(item)?;
         let mut args = syn::parse2(attrs)?;
         let name = name_arg(&mut args).unwrap_or_else(|| item.ident.to_string());
+        err_on_unknown_args(&args)?;
         Ok(Self { item, name })
     }
 
@@ -71,4 +73,17 @@ mod tests {
         );
         assert_eq!(expected.to_string(), expanded.to_string());
     }
+
+    #[test]
+    fn test_map_unknown_arg() {
+        let err = Map::parse(
+            parse_quote!(nmae = "foo"),
+            parse_quote!(
+                static BAR: HashMap<&'static str, u32> = HashMap::new();
+            ),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "invalid argument");
+    }
 }
@@ -16,7 +16,7 @@ use crate::{
         info::{FuncSecInfo, LineSecInfo},
         relocation::Relocation,
         Array, BtfEnum, BtfKind, BtfMember, BtfType, Const, Enum, FuncInfo, FuncLinkage, Int,
-        IntEncoding, LineInfo, Struct, Typedef, Union, VarLinkage,
+        IntEncoding, LineInfo, Struct, Typedef, Union, Var, VarLinkage,
     },
     generated::{btf_ext_header, btf_header},
     util::{bytes_of, HashMap},
@@ -274,7 +274,11 @@ impl Btf {
         self.types.types.len() < 2
     }
 
-    pub(crate) fn types(&self) -> impl Iterator<Item = &BtfType> {
+    /// Returns an iterator over all the types.
+    ///
+    /// The position of a type in the iterator is its type id. The first type, with id 0, is
+    /// always [`BtfType::Unknown`], which stands for `void`.
+    pub fn types(&self) -> impl Iterator<Item = &BtfType> {
         self.types.types.iter()
     }
 
@@ -298,24 +302,58 @@ impl Btf {
     }
 
     /// Loads BTF metadata from `/sys/kernel/btf/vmlinux`.
+    ///
+    /// The file contains the running kernel's types as a raw BTF blob, not wrapped in an ELF
+    /// object. To load BTF from another location, for example when the kernel BTF was extracted
+    /// to a custom path, use [`Btf::parse_file`].
     #[cfg(feature = "std")]
     pub fn from_sys_fs() -> Result<Btf, BtfError> {
         Btf::parse_file("/sys/kernel/btf/vmlinux", Endianness::default())
     }
 
     /// Loads BTF metadata from the given `path`.
+    ///
+    /// The file can either be a raw BTF blob, like `/sys/kernel/btf/vmlinux` or the files
+    /// distributed by [BTFhub](https://github.com/aquasecurity/btfhub) for kernels that don't
+    /// expose their BTF, or an ELF object with a `.BTF` section, like an uncompressed `vmlinux`
+    /// image. The endianness of ELF objects is read from their header and `endianness` is
+    /// ignored.
     #[cfg(feature = "std")]
     pub fn parse_file<P: AsRef<std::path::Path>>(
         path: P,
         endianness: Endianness,
     ) -> Result<Btf, BtfError> {
-        use std::{borrow::ToOwned, fs};
+        use std::{borrow::ToOwned, fs, io};
+
+        use object::{Object as _, ObjectSection as _};
+
         let path = path.as_ref();
+        let file_error = |error| BtfError::FileError {
+            path: path.to_owned(),
+            error,
+        };
+        let data = fs::read(path).map_err(file_error)?;
+        if !data.starts_with(b"\x7fELF") {
+            return Btf::parse(&data, endianness);
+        }
+
+        let obj =
+            object::File::parse(data.as_slice()).map_err(|e| file_error(io::Error::other(e)))?;
+        let section = obj.section_by_name(".BTF").ok_or_else(|| {
+            file_error(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "object file has no .BTF section",
+            ))
+        })?;
+        let endianness = if obj.is_little_endian() {
+            Endianness::Little
+        } else {
+            Endianness::Big
+        };
         Btf::parse(
-            &fs::read(path).map_err(|error| BtfError::FileError {
-                path: path.to_owned(),
-                error,
-            })?,
+            section
+                .data()
+                .map_err(|e| file_error(io::Error::other(e)))?,
             endianness,
         )
     }
@@ -398,15 +436,35 @@ impl Btf {
         Ok(s.to_string_lossy())
     }
 
-    pub(crate) fn type_by_id(&self, type_id: u32) -> Result<&BtfType, BtfError> {
+    /// Returns the type with the given type id.
+    pub fn type_by_id(&self, type_id: u32) -> Result<&BtfType, BtfError> {
         self.types.type_by_id(type_id)
     }
 
-    pub(crate) fn resolve_type(&self, root_type_id: u32) -> Result<u32, BtfError> {
+    /// Returns the id and the type of the first type named `name`, of any kind.
+    ///
+    /// Different kinds of types can share the same name, for example a struct and a typedef
+    /// of it. Use [`Btf::id_by_type_name_kind`] to look up a specific kind.
+    pub fn type_by_name(&self, name: &str) -> Result<(u32, &BtfType), BtfError> {
+        for (type_id, ty) in self.types().enumerate() {
+            if ty.name_offset() != 0 && self.type_name(ty)? == name {
+                return Ok((type_id as u32, ty));
+            }
+        }
+
+        Err(BtfError::UnknownBtfTypeName {
+            type_name: name.to_owned(),
+        })
+    }
+
+    /// Follows `typedef`, `const`, `volatile`, `restrict` and type tag chains starting at
+    /// `root_type_id` and returns the id of the underlying concrete type.
+    pub fn resolve_type(&self, root_type_id: u32) -> Result<u32, BtfError> {
         self.types.resolve_type(root_type_id)
     }
 
-    pub(crate) fn type_name(&self, ty: &BtfType) -> Result<Cow<'_, str>, BtfError> {
+    /// Returns the name of a type, or an empty string for anonymous types.
+    pub fn type_name(&self, ty: &BtfType) -> Result<Cow<'_, str>, BtfError> {
         self.string_at(ty.name_offset())
     }
 
@@ -431,14 +489,15 @@ impl Btf {
         })
     }
 
-    pub(crate) fn type_size(&self, root_type_id: u32) -> Result<usize, BtfError> {
+    /// Returns the size in bytes of a type, resolving modifiers, typedefs and arrays.
+    pub fn type_size(&self, root_type_id: u32) -> Result<usize, BtfError> {
         let mut type_id = root_type_id;
         let mut n_elems = 1;
         for _ in 0..MAX_RESOLVE_DEPTH {
             let ty = self.types.type_by_id(type_id)?;
             let size = match ty {
                 BtfType::Array(Array { array, .. }) => {
-                    n_elems = array.len;
+                    n_elems *= array.len;
                     type_id = array.element_type;
                     continue;
                 }
@@ -471,6 +530,68 @@ impl Btf {
         buf
     }
 
+    // Fixup DATASEC entries that point to FUNCs.
+    //
+    // `extern` kernel functions placed in a section, like the `.ksyms` section used by libbpf's
+    // `__ksym`, are emitted as FUNC entries of a DATASEC. The kernel only accepts VARs in a
+    // DATASEC so, like libbpf, point these entries to a dummy int VAR and lay out the section.
+    fn fixup_extern_func_entries(&mut self) {
+        let is_func =
+            |types: &BtfTypes, type_id| matches!(types.type_by_id(type_id), Ok(BtfType::Func(_)));
+        let datasecs: Vec<usize> = self
+            .types
+            .types
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| match t {
+                BtfType::DataSec(d)
+                    if d.entries.iter().any(|e| is_func(&self.types, e.btf_type)) =>
+                {
+                    Some(i)
+                }
+                _ => None,
+            })
+            .collect();
+        if datasecs.is_empty() {
+            return;
+        }
+
+        let int_name = self.add_string("int");
+        let int_type_id = self.add_type(BtfType::Int(Int::new(
+            int_name,
+            mem::size_of::<i32>() as u32,
+            IntEncoding::Signed,
+            0,
+        )));
+        let var_name = self.add_string("dummy_ksym");
+        let var_type_id = self.add_type(BtfType::Var(Var::new(
+            var_name,
+            int_type_id,
+            VarLinkage::Global,
+        )));
+
+        for i in datasecs {
+            let BtfType::DataSec(mut d) = self.types.types[i].clone() else {
+                continue;
+            };
+            let mut offset = 0;
+            for e in d.entries.iter_mut() {
+                if is_func(&self.types, e.btf_type) {
+                    debug!(
+                        "DATASEC: replacing FUNC entry #{} with a dummy VAR",
+                        e.btf_type
+                    );
+                    e.btf_type = var_type_id;
+                    e.size = mem::size_of::<i32>() as u32;
+                }
+                e.offset = offset;
+                offset += e.size;
+            }
+            d.size = offset;
+            self.types.types[i] = BtfType::DataSec(d);
+        }
+    }
+
     // This follows the same logic as libbpf's bpf_object__sanitize_btf() function.
     // https://github.com/libbpf/libbpf/blob/05f94ddbb837f5f4b3161e341eed21be307eaa04/src/libbpf.c#L2701
     //
@@ -498,6 +619,7 @@ impl Btf {
                 0,
             )))
         });
+        self.fixup_extern_func_entries();
         let mut types = mem::take(&mut self.types);
         for i in 0..types.types.len() {
             let t = &mut types.types[i];
@@ -652,27 +774,35 @@ impl Btf {
                     if !features.btf_func {
                         debug!("{}: not supported. replacing with TYPEDEF", kind);
                         *t = BtfType::Typedef(Typedef::new(ty.name_offset, ty.btf_type));
-                    } else if !features.btf_func_global
-                        || name == "memset"
-                        || name == "memcpy"
-                        || name == "memmove"
-                        || name == "memcmp"
-                    {
-                        // Sanitize BTF_FUNC_GLOBAL when not supported and ensure that
-                        // memory builtins are marked as static. Globals are type checked
-                        // and verified separately from their callers, while instead we
-                        // want tracking info (eg bound checks) to be propagated to the
-                        // memory builtins.
-                        if ty.linkage() == FuncLinkage::Global {
-                            if !features.btf_func_global {
-                                debug!(
-                                    "{}: BTF_FUNC_GLOBAL not supported. replacing with BTF_FUNC_STATIC",
-                                    kind
-                                );
-                            } else {
-                                debug!("changing FUNC {name} linkage to BTF_FUNC_STATIC");
+                    } else {
+                        // The kernel rejects BTF_FUNC_EXTERN. Like libbpf, make the `extern`
+                        // functions, such as the kernel functions of `.ksyms`, global.
+                        if ty.linkage() == FuncLinkage::Extern {
+                            debug!("changing FUNC {name} linkage to BTF_FUNC_GLOBAL");
+                            ty.set_linkage(FuncLinkage::Global);
+                        }
+                        if !features.btf_func_global
+                            || name == "memset"
+                            || name == "memcpy"
+                            || name == "memmove"
+                            || name == "memcmp"
+                        {
+                            // Sanitize BTF_FUNC_GLOBAL when not supported and ensure that
+                            // memory builtins are marked as static. Globals are type checked
+                            // and verified separately from their callers, while instead we
+                            // want tracking info (eg bound checks) to be propagated to the
+                            // memory builtins.
+                            if ty.linkage() == FuncLinkage::Global {
+                                if !features.btf_func_global {
+                                    debug!(
+                                        "{}: BTF_FUNC_GLOBAL not supported. replacing with BTF_FUNC_STATIC",
+                                        kind
+                                    );
+                                } else {
+                                    debug!("changing FUNC {name} linkage to BTF_FUNC_STATIC");
+                                }
+                                ty.set_linkage(FuncLinkage::Static);
                             }
-                            ty.set_linkage(FuncLinkage::Static);
                         }
                     }
                 }
@@ -1103,7 +1233,7 @@ mod tests {
     use super::*;
     use crate::btf::{
         BtfEnum64, BtfParam, DataSec, DataSecEntry, DeclTag, Enum64, Float, Func, FuncProto, Ptr,
-        TypeTag, Var,
+        TypeTag, Var, Volatile,
     };
 
     #[test]
@@ -1500,6 +1630,69 @@ mod tests {
         Btf::parse(&raw, Endianness::default()).unwrap();
     }
 
+    #[test]
+    fn test_fixup_datasec_extern_funcs() {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let func_proto_type_id =
+            btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type_id)));
+        let name_offset = btf.add_string("bpf_kfunc");
+        let func_type_id = btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            func_proto_type_id,
+            FuncLinkage::Extern,
+        )));
+
+        let name_offset = btf.add_string(".ksyms");
+        let variables = vec![DataSecEntry {
+            btf_type: func_type_id,
+            offset: 0,
+            size: 0,
+        }];
+        let datasec_type_id =
+            btf.add_type(BtfType::DataSec(DataSec::new(name_offset, variables, 0)));
+
+        let features = BtfFeatures {
+            btf_datasec: true,
+            btf_func: true,
+            btf_func_global: true,
+            ..Default::default()
+        };
+
+        // `.ksyms` has no ELF section, so this would fail if the DATASEC size was left unset
+        btf.fixup_and_sanitize(&HashMap::new(), &HashMap::new(), &features)
+            .unwrap();
+
+        assert_matches!(btf.type_by_id(datasec_type_id).unwrap(), BtfType::DataSec(fixed) => {
+            assert_eq!(fixed.size, 4);
+            assert_matches!(*fixed.entries, [
+                    DataSecEntry {
+                        btf_type,
+                        offset: 0,
+                        size: 4,
+                    },
+                ] => {
+                    assert_matches!(btf.type_by_id(btf_type).unwrap(), BtfType::Var(var) => {
+                        assert_eq!(btf.string_at(var.name_offset).unwrap(), "dummy_ksym");
+                    });
+                }
+            );
+        });
+        // the FUNC itself is kept, with a linkage the kernel accepts
+        assert_matches!(btf.type_by_id(func_type_id).unwrap(), BtfType::Func(ty) => {
+            assert_eq!(ty.linkage(), FuncLinkage::Global);
+        });
+        // Ensure we can convert to bytes and back again
+        let raw = btf.to_bytes();
+        Btf::parse(&raw, Endianness::default()).unwrap();
+    }
+
     #[test]
     fn test_sanitize_func_and_proto() {
         let mut btf = Btf::new();
@@ -1807,6 +2000,158 @@ mod tests {
         Btf::parse(&raw, Endianness::default()).unwrap();
     }
 
+    #[test]
+    fn test_id_by_type_name_kind() {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        // a struct sharing its name with the function must not be picked when resolving the
+        // attach target of fentry/fexit programs
+        let name_offset = btf.add_string("filename_lookup");
+        let struct_type_id = btf.add_type(BtfType::Struct(Struct::new(name_offset, vec![], 0)));
+        let func_proto_type_id =
+            btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type_id)));
+        let func_type_id = btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            func_proto_type_id,
+            FuncLinkage::Global,
+        )));
+
+        assert_eq!(
+            btf.id_by_type_name_kind("filename_lookup", BtfKind::Func)
+                .unwrap(),
+            func_type_id
+        );
+        assert_eq!(
+            btf.id_by_type_name_kind("filename_lookup", BtfKind::Struct)
+                .unwrap(),
+            struct_type_id
+        );
+        assert_matches!(
+            btf.id_by_type_name_kind("int", BtfKind::Func),
+            Err(BtfError::UnknownBtfTypeName { type_name }) => assert_eq!(type_name, "int")
+        );
+    }
+
+    #[test]
+    fn test_type_by_name_and_resolve_type() {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let const_type_id = btf.add_type(BtfType::Const(Const::new(int_type_id)));
+        let name_offset = btf.add_string("my_int");
+        let typedef_type_id =
+            btf.add_type(BtfType::Typedef(Typedef::new(name_offset, const_type_id)));
+
+        assert_matches!(
+            btf.type_by_name("my_int"),
+            Ok((type_id, BtfType::Typedef(_))) => assert_eq!(type_id, typedef_type_id)
+        );
+        assert_matches!(
+            btf.type_by_name("int"),
+            Ok((type_id, BtfType::Int(_))) => assert_eq!(type_id, int_type_id)
+        );
+        assert_matches!(
+            btf.type_by_name("long"),
+            Err(BtfError::UnknownBtfTypeName { type_name }) => assert_eq!(type_name, "long")
+        );
+        assert_matches!(btf.type_by_id(const_type_id), Ok(BtfType::Const(_)));
+        assert_eq!(btf.resolve_type(typedef_type_id).unwrap(), int_type_id);
+        assert_eq!(btf.type_size(typedef_type_id).unwrap(), 4);
+        assert_eq!(btf.types().count(), 4);
+    }
+
+    #[test]
+    fn test_type_size() {
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("u32");
+        let int_type_id =
+            btf.add_type(BtfType::Int(Int::new(name_offset, 4, IntEncoding::None, 0)));
+        // const volatile u32
+        let volatile_type_id = btf.add_type(BtfType::Volatile(Volatile::new(int_type_id)));
+        let const_type_id = btf.add_type(BtfType::Const(Const::new(volatile_type_id)));
+        assert_eq!(btf.type_size(const_type_id).unwrap(), 4);
+
+        // const volatile u32 [2][3]
+        let index_type_id = btf.add_type(BtfType::Int(Int::new(0, 4, IntEncoding::None, 0)));
+        let inner_type_id = btf.add_type(BtfType::Array(Array::new(
+            0,
+            const_type_id,
+            index_type_id,
+            3,
+        )));
+        let outer_type_id = btf.add_type(BtfType::Array(Array::new(
+            0,
+            inner_type_id,
+            index_type_id,
+            2,
+        )));
+        assert_eq!(btf.type_size(outer_type_id).unwrap(), 24);
+
+        // pointers have the size of a pointer, not of the pointee
+        let ptr_type_id = btf.add_type(BtfType::Ptr(Ptr::new(0, outer_type_id)));
+        assert_eq!(btf.type_size(ptr_type_id).unwrap(), mem::size_of::<usize>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_parse_file() {
+        use std::{env, fs, process};
+
+        use object::{
+            write::{self, StandardSection},
+            Architecture, BinaryFormat, SectionKind,
+        };
+
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let data = btf.to_bytes();
+
+        let path = env::temp_dir().join(format!("aya-obj-test-parse-file-{}", process::id()));
+
+        // raw BTF
+        fs::write(&path, &data).unwrap();
+        let parsed = Btf::parse_file(&path, Endianness::default()).unwrap();
+        assert_matches!(parsed.type_by_name("int"), Ok((1, BtfType::Int(_))));
+
+        // BTF in the .BTF section of an ELF object
+        let mut obj =
+            write::Object::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::default());
+        let section = obj.add_section(Vec::new(), b".BTF".to_vec(), SectionKind::Metadata);
+        obj.append_section_data(section, &data, 4);
+        fs::write(&path, obj.write().unwrap()).unwrap();
+        let parsed = Btf::parse_file(&path, Endianness::default()).unwrap();
+        assert_matches!(parsed.type_by_name("int"), Ok((1, BtfType::Int(_))));
+
+        // ELF object without BTF
+        let mut obj =
+            write::Object::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::default());
+        obj.section_id(StandardSection::Text);
+        fs::write(&path, obj.write().unwrap()).unwrap();
+        let result = Btf::parse_file(&path, Endianness::default());
+        fs::remove_file(&path).unwrap();
+        assert_matches!(result, Err(BtfError::FileError { error, .. }) => {
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData)
+        });
+    }
+
     // Not possible to emulate file system file "/sys/kernel/btf/vmlinux" as big endian, so skip
     #[test]
     #[cfg(feature = "std")]
@@ -0,0 +1,878 @@
+//! Dumping BTF as C declarations.
+
+use alloc::{
+    borrow::ToOwned as _,
+    format,
+    string::{String, ToString as _},
+    vec,
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt::Write as _};
+
+use crate::{
+    btf::{Btf, BtfMember, BtfType, MAX_RESOLVE_DEPTH},
+    util::HashMap,
+};
+
+impl Btf {
+    /// Dumps types as C declarations, like `bpftool btf dump format c`.
+    ///
+    /// The named structs, unions, enums and typedefs listed in `filter` are emitted, or all of
+    /// them if `filter` is `None`, together with the types they depend on. Declarations are
+    /// emitted in dependency order: the types a struct embeds are defined before it, while the
+    /// structs and unions it only references through pointers get a forward declaration, which
+    /// allows for mutually recursive structs. Padding that the C layout rules wouldn't add is
+    /// made explicit with unnamed bit-fields, and structs that need it are marked
+    /// `__attribute__((packed))`, so that the emitted layout matches the BTF one. Types that
+    /// share a name with an earlier type, which happens with types from different compilation
+    /// units, are renamed with a `___N` suffix.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use aya_obj::btf::Btf;
+    /// use object::Endianness;
+    ///
+    /// let data = std::fs::read("/sys/kernel/btf/vmlinux")?;
+    /// let btf = Btf::parse(&data, Endianness::default())?;
+    /// print!("{}", btf.to_c_header(Some(&["task_struct"])));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn to_c_header(&self, filter: Option<&[&str]>) -> String {
+        let mut writer = CHeaderWriter::new(self);
+        for (type_id, ty) in self.types().enumerate() {
+            if !matches!(
+                ty,
+                BtfType::Struct(_)
+                    | BtfType::Union(_)
+                    | BtfType::Enum(_)
+                    | BtfType::Enum64(_)
+                    | BtfType::Typedef(_)
+            ) {
+                continue;
+            }
+            // anonymous types are emitted inline, where they're used
+            let name = self.string_at(ty.name_offset()).unwrap_or_default();
+            if name.is_empty() {
+                continue;
+            }
+            if let Some(filter) = filter {
+                if !filter.contains(&name.as_ref()) {
+                    continue;
+                }
+            }
+            writer.emit_type(type_id as u32, true, 0);
+        }
+        writer.out
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EmitState {
+    NotEmitted,
+    Emitting,
+    Emitted,
+}
+
+struct CHeaderWriter<'a> {
+    btf: &'a Btf,
+    out: String,
+    // indexed by type id
+    states: Vec<EmitState>,
+    fwd_declared: Vec<bool>,
+    // the C name of each type
+    names: Vec<String>,
+    // the C names of the variants of each enum, keyed by type id
+    enumerators: HashMap<usize, Vec<String>>,
+    // the alignments computed so far, keyed by type id
+    aligns: RefCell<HashMap<u32, usize>>,
+}
+
+impl<'a> CHeaderWriter<'a> {
+    fn new(btf: &'a Btf) -> Self {
+        let name = |name_offset| {
+            btf.string_at(name_offset)
+                .map(|name| name.into_owned())
+                .unwrap_or_default()
+        };
+
+        // Types from different compilation units can have the same name, for example the kernel
+        // has several `enum format_state`s and a `struct format_state`. Like bpftool, give the
+        // duplicates a `___N` suffix, as structs, unions and enums share one namespace and
+        // typedefs and enumerators share another.
+        let mut tags = HashMap::new();
+        let mut idents = HashMap::new();
+        let mut names = Vec::new();
+        let mut enumerators = HashMap::new();
+        for (type_id, ty) in btf.types().enumerate() {
+            let type_name = name(ty.name_offset());
+            names.push(match ty {
+                BtfType::Struct(_) | BtfType::Union(_) | BtfType::Enum(_) | BtfType::Enum64(_)
+                    if !type_name.is_empty() =>
+                {
+                    unique_name(&mut tags, type_name)
+                }
+                BtfType::Typedef(_) => unique_name(&mut idents, type_name),
+                _ => type_name,
+            });
+            let variants: Vec<u32> = match ty {
+                BtfType::Enum(ty) => ty.variants.iter().map(|v| v.name_offset).collect(),
+                BtfType::Enum64(ty) => ty.variants.iter().map(|v| v.name_offset).collect(),
+                _ => continue,
+            };
+            let variants = variants
+                .into_iter()
+                .map(|name_offset| unique_name(&mut idents, name(name_offset)))
+                .collect();
+            enumerators.insert(type_id, variants);
+        }
+
+        Self {
+            btf,
+            out: String::new(),
+            states: vec![EmitState::NotEmitted; names.len()],
+            fwd_declared: vec![false; names.len()],
+            names,
+            enumerators,
+            aligns: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn name(&self, name_offset: u32) -> String {
+        self.btf
+            .string_at(name_offset)
+            .map(|name| name.into_owned())
+            .unwrap_or_default()
+    }
+
+    fn type_name(&self, type_id: u32) -> &str {
+        self.names
+            .get(type_id as usize)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    // Emits the declarations `type_id` depends on, then `type_id` itself if it's a named type.
+    //
+    // `strong` is true if `type_id` is used by value and must be complete, false if it's only
+    // used through a pointer and a forward declaration is enough.
+    //
+    // `depth` counts the types followed since the last named type. Named types are only emitted
+    // once, but malformed BTF can have cycles of other types, e.g. a pointer to itself.
+    fn emit_type(&mut self, type_id: u32, strong: bool, depth: u8) {
+        if depth > MAX_RESOLVE_DEPTH {
+            return;
+        }
+        let Ok(ty) = self.btf.type_by_id(type_id) else {
+            return;
+        };
+        let next = depth + 1;
+        let id = type_id as usize;
+        match ty {
+            BtfType::Ptr(ty) => self.emit_type(ty.btf_type, false, next),
+            BtfType::Const(ty) => self.emit_type(ty.btf_type, strong, next),
+            BtfType::Volatile(ty) => self.emit_type(ty.btf_type, strong, next),
+            BtfType::Restrict(ty) => self.emit_type(ty.btf_type, strong, next),
+            BtfType::TypeTag(ty) => self.emit_type(ty.btf_type, strong, next),
+            // array elements must be complete, even behind a pointer
+            BtfType::Array(ty) => self.emit_type(ty.array.element_type, true, next),
+            BtfType::FuncProto(ty) => {
+                self.emit_type(ty.return_type, false, next);
+                for param in &ty.params {
+                    self.emit_type(param.btf_type, false, next);
+                }
+            }
+            BtfType::Fwd(ty) => {
+                let keyword = if ty.is_union() { "union" } else { "struct" };
+                self.emit_fwd(id, keyword);
+            }
+            BtfType::Struct(_) | BtfType::Union(_) => {
+                let keyword = composite_keyword(ty);
+                let members: &[BtfMember] = match ty {
+                    BtfType::Struct(ty) => &ty.members,
+                    BtfType::Union(ty) => &ty.members,
+                    _ => unreachable!(),
+                };
+                if ty.name_offset() == 0 {
+                    // anonymous structs and unions are defined inline, so their members must be
+                    // complete
+                    for member in members {
+                        self.emit_type(member.btf_type, true, next);
+                    }
+                    return;
+                }
+                match self.states[id] {
+                    EmitState::Emitted => return,
+                    // a pointer cycle, e.g. a linked list
+                    EmitState::Emitting => {
+                        if !strong {
+                            self.emit_fwd(id, keyword);
+                        }
+                        return;
+                    }
+                    EmitState::NotEmitted => {
+                        if !strong {
+                            self.emit_fwd(id, keyword);
+                            return;
+                        }
+                    }
+                }
+                self.states[id] = EmitState::Emitting;
+                for member in members {
+                    self.emit_type(member.btf_type, true, 0);
+                }
+                let definition = self.composite(type_id, ty, 0, 0);
+                let _ = write!(self.out, "{definition};\n\n");
+                self.states[id] = EmitState::Emitted;
+            }
+            BtfType::Enum(_) | BtfType::Enum64(_) => {
+                // anonymous enums are emitted inline
+                if ty.name_offset() == 0 || self.states[id] != EmitState::NotEmitted {
+                    return;
+                }
+                self.states[id] = EmitState::Emitted;
+                let definition = self.enumeration(type_id, ty, 0);
+                let _ = write!(self.out, "{definition};\n\n");
+            }
+            BtfType::Typedef(ty) => {
+                if self.states[id] == EmitState::NotEmitted {
+                    // A typedef only needs the type it aliases to be declared. Emit it before
+                    // the type is defined, so that the type can refer to it through pointers.
+                    self.states[id] = EmitState::Emitting;
+                    self.emit_type(ty.btf_type, false, 0);
+                    self.states[id] = EmitState::Emitted;
+                    let definition =
+                        self.decl(ty.btf_type, self.type_name(type_id).to_owned(), 0, 0);
+                    let _ = write!(self.out, "typedef {definition};\n\n");
+                }
+                if strong {
+                    self.emit_type(ty.btf_type, true, next);
+                }
+            }
+            BtfType::Unknown
+            | BtfType::Int(_)
+            | BtfType::Float(_)
+            | BtfType::Func(_)
+            | BtfType::Var(_)
+            | BtfType::DataSec(_)
+            | BtfType::DeclTag(_) => {}
+        }
+    }
+
+    fn emit_fwd(&mut self, id: usize, keyword: &str) {
+        if self.fwd_declared[id] || self.states[id] == EmitState::Emitted {
+            return;
+        }
+        self.fwd_declared[id] = true;
+        let _ = write!(self.out, "{keyword} {};\n\n", self.names[id]);
+    }
+
+    // Returns the C declaration of `declarator` with type `type_id`, e.g. `int (*foo)[4]`.
+    //
+    // `depth` bounds the recursion like in `emit_type`, the type is `void` past it.
+    fn decl(&self, type_id: u32, declarator: String, indent: usize, depth: u8) -> String {
+        if depth > MAX_RESOLVE_DEPTH {
+            return join("void", &declarator);
+        }
+        let Ok(ty) = self.btf.type_by_id(type_id) else {
+            return join("void", &declarator);
+        };
+        let next = depth + 1;
+        match ty {
+            BtfType::Unknown => join("void", &declarator),
+            BtfType::Int(_) | BtfType::Float(_) | BtfType::Typedef(_) => {
+                join(self.type_name(type_id), &declarator)
+            }
+            BtfType::Fwd(ty) => {
+                let keyword = if ty.is_union() { "union" } else { "struct" };
+                join(
+                    &format!("{keyword} {}", self.type_name(type_id)),
+                    &declarator,
+                )
+            }
+            BtfType::Struct(_) | BtfType::Union(_) => {
+                let base = if ty.name_offset() == 0 {
+                    self.composite(type_id, ty, indent, next)
+                } else {
+                    format!("{} {}", composite_keyword(ty), self.type_name(type_id))
+                };
+                join(&base, &declarator)
+            }
+            BtfType::Enum(_) | BtfType::Enum64(_) => {
+                let base = if ty.name_offset() == 0 {
+                    self.enumeration(type_id, ty, indent)
+                } else {
+                    format!("enum {}", self.type_name(type_id))
+                };
+                join(&base, &declarator)
+            }
+            BtfType::Ptr(ty) => self.decl(ty.btf_type, format!("*{declarator}"), indent, next),
+            BtfType::Const(ty) => self.qualified("const", ty.btf_type, declarator, indent, next),
+            BtfType::Volatile(ty) => {
+                self.qualified("volatile", ty.btf_type, declarator, indent, next)
+            }
+            BtfType::Restrict(ty) => {
+                self.qualified("restrict", ty.btf_type, declarator, indent, next)
+            }
+            BtfType::TypeTag(ty) => self.decl(ty.btf_type, declarator, indent, next),
+            BtfType::Array(ty) => self.decl(
+                ty.array.element_type,
+                format!("{}[{}]", parenthesize(declarator), ty.array.len),
+                indent,
+                next,
+            ),
+            BtfType::FuncProto(ty) => {
+                let params = if ty.params.is_empty() {
+                    "void".to_owned()
+                } else {
+                    ty.params
+                        .iter()
+                        .map(|param| {
+                            // a trailing param of type void marks a variadic function
+                            if param.btf_type == 0 {
+                                "...".to_owned()
+                            } else {
+                                self.decl(
+                                    param.btf_type,
+                                    self.name(param.name_offset),
+                                    indent,
+                                    next,
+                                )
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                self.decl(
+                    ty.return_type,
+                    format!("{}({params})", parenthesize(declarator)),
+                    indent,
+                    next,
+                )
+            }
+            BtfType::Func(ty) => self.decl(ty.btf_type, self.name(ty.name_offset), indent, next),
+            BtfType::Var(ty) => self.decl(ty.btf_type, self.name(ty.name_offset), indent, next),
+            BtfType::DataSec(_) | BtfType::DeclTag(_) => join("void", &declarator),
+        }
+    }
+
+    fn qualified(
+        &self,
+        qualifier: &str,
+        type_id: u32,
+        declarator: String,
+        indent: usize,
+        depth: u8,
+    ) -> String {
+        let is_ptr = self
+            .btf
+            .type_by_id(type_id)
+            .is_ok_and(|ty| matches!(ty, BtfType::Ptr(_)));
+        if is_ptr {
+            // a qualified pointer, e.g. `int *const foo`
+            self.decl(type_id, join(qualifier, &declarator), indent, depth)
+        } else {
+            format!(
+                "{qualifier} {}",
+                self.decl(type_id, declarator, indent, depth)
+            )
+        }
+    }
+
+    // Returns the definition of a struct or union, without the trailing `;`.
+    fn composite(&self, type_id: u32, ty: &BtfType, indent: usize, depth: u8) -> String {
+        let (members, is_struct): (&[BtfMember], bool) = match ty {
+            BtfType::Struct(ty) => (&ty.members, true),
+            BtfType::Union(ty) => (&ty.members, false),
+            _ => return String::new(),
+        };
+        let keyword = composite_keyword(ty);
+        let name = self.type_name(type_id);
+        let size_bits = ty.size().unwrap_or(0) as usize * 8;
+        let packed = is_struct && self.is_packed(ty);
+        let member_indent = "\t".repeat(indent + 1);
+
+        let mut out = if name.is_empty() {
+            format!("{keyword} {{\n")
+        } else {
+            format!("{keyword} {name} {{\n")
+        };
+        let mut end_bits = 0;
+        for member in members {
+            let bit_offset = ty.member_bit_offset(member).unwrap_or(0);
+            let bit_field_size = ty.member_bit_field_size(member).unwrap_or(0);
+            if is_struct {
+                // where C would place the member right after the previous one
+                let natural_offset = if packed {
+                    if bit_field_size == 0 {
+                        align_up(end_bits, 8)
+                    } else {
+                        end_bits
+                    }
+                } else {
+                    let align_bits = self.align(member.btf_type) * 8;
+                    // a bit-field starts a new storage unit if it doesn't fit in the current one
+                    if bit_field_size > 0
+                        && end_bits / align_bits == (end_bits + bit_field_size - 1) / align_bits
+                    {
+                        end_bits
+                    } else {
+                        align_up(end_bits, align_bits)
+                    }
+                };
+                if bit_offset > natural_offset {
+                    emit_padding(&mut out, &member_indent, end_bits, bit_offset);
+                }
+            }
+
+            let decl = self.decl(
+                member.btf_type,
+                self.name(member.name_offset),
+                indent + 1,
+                depth,
+            );
+            if bit_field_size > 0 {
+                let _ = writeln!(out, "{member_indent}{decl}: {bit_field_size};");
+                end_bits = end_bits.max(bit_offset + bit_field_size);
+            } else {
+                let _ = writeln!(out, "{member_indent}{decl};");
+                let size = self.btf.type_size(member.btf_type).unwrap_or(0);
+                end_bits = end_bits.max(bit_offset + size * 8);
+            }
+        }
+        if is_struct {
+            let align_bits = if packed { 8 } else { self.align_of(ty) * 8 };
+            if size_bits > align_up(end_bits, align_bits) {
+                emit_padding(&mut out, &member_indent, end_bits, size_bits);
+            }
+        }
+
+        let _ = write!(out, "{}}}", "\t".repeat(indent));
+        if packed {
+            out.push_str(" __attribute__((packed))");
+        }
+        out
+    }
+
+    // Returns the definition of an enum, without the trailing `;`.
+    fn enumeration(&self, type_id: u32, ty: &BtfType, indent: usize) -> String {
+        let values: Vec<String> = match ty {
+            BtfType::Enum(ty) => ty
+                .variants
+                .iter()
+                .map(|v| {
+                    if ty.is_signed() {
+                        (v.value as i32).to_string()
+                    } else {
+                        v.value.to_string()
+                    }
+                })
+                .collect(),
+            BtfType::Enum64(ty) => ty
+                .variants
+                .iter()
+                .map(|v| {
+                    let value = (u64::from(v.value_high) << 32) | u64::from(v.value_low);
+                    if ty.is_signed() {
+                        format!("{}LL", value as i64)
+                    } else {
+                        format!("{value}ULL")
+                    }
+                })
+                .collect(),
+            _ => return String::new(),
+        };
+        let name = self.type_name(type_id);
+        let names = self.enumerators.get(&(type_id as usize));
+        let variant_indent = "\t".repeat(indent + 1);
+
+        let mut out = if name.is_empty() {
+            "enum {\n".to_owned()
+        } else {
+            format!("enum {name} {{\n")
+        };
+        for (name, value) in names.into_iter().flatten().zip(values) {
+            let _ = writeln!(out, "{variant_indent}{name} = {value},");
+        }
+        let _ = write!(out, "{}}}", "\t".repeat(indent));
+        // C enums are int-sized unless told otherwise
+        match ty.size() {
+            Some(1) => out.push_str(" __attribute__((mode(byte)))"),
+            Some(2) => out.push_str(" __attribute__((packed))"),
+            Some(8) => out.push_str(" __attribute__((mode(word)))"),
+            _ => {}
+        }
+        out
+    }
+
+    // Returns the alignment, in bytes, that C would give to `type_id`.
+    //
+    // Alignments are cached, and a type that contains itself, which only happens with malformed
+    // BTF, sees an alignment of 1 for the inner occurrence rather than recursing forever.
+    fn align(&self, type_id: u32) -> usize {
+        if let Some(align) = self.aligns.borrow().get(&type_id) {
+            return *align;
+        }
+        self.aligns.borrow_mut().insert(type_id, 1);
+        let align = match self
+            .btf
+            .resolve_type(type_id)
+            .and_then(|resolved| self.btf.type_by_id(resolved))
+        {
+            Ok(BtfType::Array(ty)) => self.align(ty.array.element_type),
+            Ok(ty) => self.align_of(ty),
+            Err(_) => 1,
+        };
+        self.aligns.borrow_mut().insert(type_id, align);
+        align
+    }
+
+    fn align_of(&self, ty: &BtfType) -> usize {
+        match ty {
+            BtfType::Int(_) | BtfType::Float(_) | BtfType::Enum(_) | BtfType::Enum64(_) => {
+                ty.size().unwrap_or(1).clamp(1, 8) as usize
+            }
+            // BPF pointers are always 64-bit
+            BtfType::Ptr(_) => 8,
+            BtfType::Struct(_) if self.is_packed(ty) => 1,
+            BtfType::Struct(_) | BtfType::Union(_) => ty
+                .members()
+                .into_iter()
+                .flatten()
+                .map(|member| self.align(member.btf_type))
+                .max()
+                .unwrap_or(1),
+            _ => 1,
+        }
+    }
+
+    // A struct is packed if its members or its size aren't aligned like C would align them.
+    fn is_packed(&self, ty: &BtfType) -> bool {
+        let BtfType::Struct(st) = ty else {
+            return false;
+        };
+        let mut max_align = 1;
+        for member in &st.members {
+            let align = self.align(member.btf_type);
+            max_align = max_align.max(align);
+            if st.member_bit_field_size(member) == 0
+                && !is_aligned(st.member_bit_offset(member), align * 8)
+            {
+                return true;
+            }
+        }
+        !is_aligned(st.size as usize, max_align)
+    }
+}
+
+fn composite_keyword(ty: &BtfType) -> &'static str {
+    match ty {
+        BtfType::Union(_) => "union",
+        _ => "struct",
+    }
+}
+
+fn unique_name(counts: &mut HashMap<String, usize>, name: String) -> String {
+    let count = counts.entry(name.clone()).or_insert(0);
+    *count += 1;
+    if *count == 1 {
+        name
+    } else {
+        format!("{name}___{count}")
+    }
+}
+
+fn join(base: &str, declarator: &str) -> String {
+    if declarator.is_empty() {
+        base.to_owned()
+    } else {
+        format!("{base} {declarator}")
+    }
+}
+
+// Pointers to arrays and functions need parentheses, e.g. `int (*foo)[4]`.
+fn parenthesize(declarator: String) -> String {
+    if declarator.starts_with('*') {
+        format!("({declarator})")
+    } else {
+        declarator
+    }
+}
+
+fn align_up(bits: usize, align_bits: usize) -> usize {
+    bits.div_ceil(align_bits.max(1)) * align_bits.max(1)
+}
+
+fn is_aligned(bits: usize, align_bits: usize) -> bool {
+    align_up(bits, align_bits) == bits
+}
+
+// Emits unnamed bit-fields filling the bits from `start` to `end`. Unlike named members, they
+// don't clash with the members of the enclosing struct when nested in an anonymous struct, and
+// they don't affect the alignment of the struct.
+fn emit_padding(out: &mut String, indent: &str, start: usize, end: usize) {
+    let mut start = start;
+    let partial = ((8 - start % 8) % 8).min(end - start);
+    if partial > 0 {
+        let _ = writeln!(out, "{indent}char: {partial};");
+        start += partial;
+    }
+    while start < end {
+        // a bit-field can't straddle its storage unit, so use the largest unit that's aligned
+        let (ty, bits) = [("long", 64), ("int", 32), ("short", 16), ("char", 8)]
+            .into_iter()
+            .find(|(_, bits)| is_aligned(start, *bits) && end - start >= *bits)
+            .unwrap_or(("char", end - start));
+        let _ = writeln!(out, "{indent}{ty}: {bits};");
+        start += bits;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::btf::{
+        Array, BtfEnum, BtfParam, Const, Enum, FuncProto, Int, IntEncoding, Ptr, Struct, Typedef,
+        Union,
+    };
+
+    fn add_int(btf: &mut Btf, name: &str, size: u32) -> u32 {
+        let name_offset = btf.add_string(name);
+        btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            size,
+            IntEncoding::Signed,
+            0,
+        )))
+    }
+
+    fn member(btf: &mut Btf, name: &str, btf_type: u32, offset: u32) -> BtfMember {
+        BtfMember {
+            name_offset: btf.add_string(name),
+            btf_type,
+            offset,
+        }
+    }
+
+    #[test]
+    fn test_to_c_header_linked_list() {
+        let mut btf = Btf::new();
+        let int_type_id = add_int(&mut btf, "int", 4);
+        // struct node is type 2, node_t * is type 3 and node_t is type 4
+        let members = vec![
+            member(&mut btf, "value", int_type_id, 0),
+            member(&mut btf, "next", 3, 64),
+        ];
+        let name_offset = btf.add_string("node");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 16)));
+        btf.add_type(BtfType::Ptr(Ptr::new(0, 4)));
+        let name_offset = btf.add_string("node_t");
+        btf.add_type(BtfType::Typedef(Typedef::new(name_offset, 2)));
+
+        assert_eq!(
+            btf.to_c_header(None),
+            "struct node;\n\
+             \n\
+             typedef struct node node_t;\n\
+             \n\
+             struct node {\n\
+             \tint value;\n\
+             \tnode_t *next;\n\
+             };\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_to_c_header_dependency_order() {
+        let mut btf = Btf::new();
+        let int_type_id = add_int(&mut btf, "int", 4);
+        // struct a is type 2, struct b * is type 3 and struct b is type 4
+        let members = vec![member(&mut btf, "b", 3, 0)];
+        let name_offset = btf.add_string("a");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 8)));
+        btf.add_type(BtfType::Ptr(Ptr::new(0, 4)));
+        let members = vec![
+            member(&mut btf, "a", 2, 0),
+            member(&mut btf, "p", 6, 64),
+            member(&mut btf, "cb", 8, 192),
+        ];
+        let name_offset = btf.add_string("b");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 32)));
+        let a_ptr_type_id = btf.add_type(BtfType::Ptr(Ptr::new(0, 2)));
+        btf.add_type(BtfType::Array(Array::new(0, a_ptr_type_id, int_type_id, 2)));
+        let params = vec![
+            BtfParam {
+                name_offset: 0,
+                btf_type: 3,
+            },
+            BtfParam {
+                name_offset: 0,
+                btf_type: 0,
+            },
+        ];
+        let proto_type_id = btf.add_type(BtfType::FuncProto(FuncProto::new(params, int_type_id)));
+        btf.add_type(BtfType::Ptr(Ptr::new(0, proto_type_id)));
+
+        assert_eq!(
+            btf.to_c_header(Some(&["b"])),
+            "struct b;\n\
+             \n\
+             struct a {\n\
+             \tstruct b *b;\n\
+             };\n\
+             \n\
+             struct b {\n\
+             \tstruct a a;\n\
+             \tstruct a *p[2];\n\
+             \tint (*cb)(struct b *, ...);\n\
+             };\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_to_c_header_anonymous_types() {
+        let mut btf = Btf::new();
+        let int_type_id = add_int(&mut btf, "int", 4);
+        let char_type_id = add_int(&mut btf, "char", 1);
+        let variants = vec![
+            BtfEnum::new(btf.add_string("RED"), 0),
+            BtfEnum::new(btf.add_string("GREEN"), 1),
+        ];
+        let name_offset = btf.add_string("color");
+        let enum_type_id = btf.add_type(BtfType::Enum(Enum::new(name_offset, false, variants)));
+        let members = vec![
+            member(&mut btf, "i", int_type_id, 0),
+            member(&mut btf, "ch", char_type_id, 0),
+        ];
+        let union_type_id = btf.add_type(BtfType::Union(Union::new(0, 4, members)));
+        let const_char_type_id = btf.add_type(BtfType::Const(Const::new(char_type_id)));
+        let const_char_ptr_type_id = btf.add_type(BtfType::Ptr(Ptr::new(0, const_char_type_id)));
+        let array_type_id =
+            btf.add_type(BtfType::Array(Array::new(0, int_type_id, int_type_id, 4)));
+        let array_ptr_type_id = btf.add_type(BtfType::Ptr(Ptr::new(0, array_type_id)));
+        let members = vec![
+            member(&mut btf, "c", enum_type_id, 0),
+            member(&mut btf, "", union_type_id, 32),
+            member(&mut btf, "name", const_char_ptr_type_id, 64),
+            member(&mut btf, "arr", array_ptr_type_id, 128),
+        ];
+        let name_offset = btf.add_string("t");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 24)));
+        let members = vec![member(&mut btf, "a", int_type_id, 0)];
+        let anon_type_id = btf.add_type(BtfType::Struct(Struct::new(0, members, 4)));
+        let name_offset = btf.add_string("anon_t");
+        btf.add_type(BtfType::Typedef(Typedef::new(name_offset, anon_type_id)));
+
+        assert_eq!(
+            btf.to_c_header(None),
+            "enum color {\n\
+             \tRED = 0,\n\
+             \tGREEN = 1,\n\
+             };\n\
+             \n\
+             struct t {\n\
+             \tenum color c;\n\
+             \tunion {\n\
+             \t\tint i;\n\
+             \t\tchar ch;\n\
+             \t};\n\
+             \tconst char *name;\n\
+             \tint (*arr)[4];\n\
+             };\n\
+             \n\
+             typedef struct {\n\
+             \tint a;\n\
+             } anon_t;\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_to_c_header_padding_and_packed() {
+        let mut btf = Btf::new();
+        let char_type_id = add_int(&mut btf, "char", 1);
+        let int_type_id = add_int(&mut btf, "int", 4);
+        let members = vec![
+            member(&mut btf, "c", char_type_id, 0),
+            member(&mut btf, "x", int_type_id, 64),
+        ];
+        let name_offset = btf.add_string("padded");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 16)));
+        let members = vec![
+            member(&mut btf, "c", char_type_id, 0),
+            member(&mut btf, "x", int_type_id, 8),
+        ];
+        let name_offset = btf.add_string("packed");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 5)));
+
+        assert_eq!(
+            btf.to_c_header(None),
+            "struct padded {\n\
+             \tchar c;\n\
+             \tchar: 8;\n\
+             \tshort: 16;\n\
+             \tint: 32;\n\
+             \tint x;\n\
+             \tint: 32;\n\
+             };\n\
+             \n\
+             struct packed {\n\
+             \tchar c;\n\
+             \tint x;\n\
+             } __attribute__((packed));\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_to_c_header_duplicate_names() {
+        let mut btf = Btf::new();
+        let int_type_id = add_int(&mut btf, "int", 4);
+        let variants = vec![BtfEnum::new(btf.add_string("A"), 0)];
+        let name_offset = btf.add_string("state");
+        btf.add_type(BtfType::Enum(Enum::new(name_offset, false, variants)));
+        let members = vec![member(&mut btf, "x", int_type_id, 0)];
+        let name_offset = btf.add_string("state");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 4)));
+        let variants = vec![BtfEnum::new(btf.add_string("A"), 1)];
+        let name_offset = btf.add_string("other");
+        btf.add_type(BtfType::Enum(Enum::new(name_offset, false, variants)));
+
+        assert_eq!(
+            btf.to_c_header(None),
+            "enum state {\n\
+             \tA = 0,\n\
+             };\n\
+             \n\
+             struct state___2 {\n\
+             \tint x;\n\
+             };\n\
+             \n\
+             enum other {\n\
+             \tA___2 = 1,\n\
+             };\n\
+             \n"
+        );
+    }
+
+    #[test]
+    fn test_to_c_header_cycles() {
+        let mut btf = Btf::new();
+        // loop_t is type 1 and is a pointer to a pointer to itself, through types 2 and 3
+        let name_offset = btf.add_string("loop_t");
+        btf.add_type(BtfType::Typedef(Typedef::new(name_offset, 2)));
+        btf.add_type(BtfType::Ptr(Ptr::new(0, 3)));
+        btf.add_type(BtfType::Ptr(Ptr::new(0, 2)));
+        // struct self is type 4 and contains itself
+        let members = vec![member(&mut btf, "inner", 4, 0)];
+        let name_offset = btf.add_string("self");
+        btf.add_type(BtfType::Struct(Struct::new(name_offset, members, 8)));
+
+        // malformed BTF must not overflow the stack
+        let header = btf.to_c_header(None);
+        assert!(header.contains("typedef void "), "{header}");
+        assert!(header.contains("struct self {"), "{header}");
+    }
+}
@@ -2,6 +2,7 @@
 
 #[allow(clippy::module_inception)]
 mod btf;
+mod c_header;
 mod info;
 mod relocation;
 mod types;
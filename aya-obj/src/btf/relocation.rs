@@ -8,6 +8,7 @@ use alloc::{
 };
 use core::{mem, ops::Bound::Included, ptr};
 
+use log::debug;
 use object::SectionIndex;
 
 use crate::{
@@ -214,6 +215,16 @@ impl Relocation {
 
 impl Object {
     /// Relocates programs inside this object file with loaded BTF info.
+    ///
+    /// Applies the CO-RE relocations recorded in `.BTF.ext`, resolving the local types
+    /// against `target_btf`, typically the kernel BTF.
+    ///
+    /// Like libbpf, relocations that can't be resolved in the target, for example accesses to a
+    /// field that doesn't exist in the running kernel, don't fail relocation. Instead the
+    /// relocated instruction is replaced with a call to the invalid helper `0xbad2310`, so that
+    /// the program is only rejected by the verifier (with `invalid func unknown#195896080`) if
+    /// the instruction is reachable. This lets programs guard accesses to optional fields with
+    /// field existence checks.
     pub fn relocate_btf(&mut self, target_btf: &Btf) -> Result<(), BtfRelocationError> {
         let (local_btf, btf_ext) = match (&self.btf, &self.btf_ext) {
             (Some(btf), Some(btf_ext)) => (btf, btf_ext),
@@ -385,6 +396,13 @@ fn relocate_btf_functions<'target>(
             ComputedRelocation::new(rel, &local_spec, None)?
         };
 
+        if comp_rel.target.is_none() {
+            debug!(
+                "relocation #{} ({:?}) of `{}` with access string `{}` can't be resolved in the target BTF, poisoning instruction #{}",
+                rel.number, rel.kind, local_name, access_str, ins_index
+            );
+        }
+
         comp_rel.apply(function, rel, local_btf, target_btf)?;
 
         last_function_opt = Some(function);
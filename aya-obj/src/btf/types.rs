@@ -51,6 +51,11 @@ impl Fwd {
     pub(crate) fn type_info_size(&self) -> usize {
         mem::size_of::<Self>()
     }
+
+    // the kind flag is set for forward declarations of unions
+    pub(crate) fn is_union(&self) -> bool {
+        self.info >> 31 == 1
+    }
 }
 
 #[repr(C)]
@@ -104,6 +109,16 @@ impl Volatile {
     pub(crate) fn type_info_size(&self) -> usize {
         mem::size_of::<Self>()
     }
+
+    #[cfg(test)]
+    pub(crate) fn new(btf_type: u32) -> Self {
+        let info = (BtfKind::Volatile as u32) << 24;
+        Self {
+            name_offset: 0,
+            info,
+            btf_type,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -253,6 +253,8 @@ pub const TC_H_MIN_EGRESS: u32 = 65523;
 pub const TCA_BPF_FLAG_ACT_DIRECT: u32 = 1;
 pub const SO_ATTACH_BPF: u32 = 50;
 pub const SO_DETACH_BPF: u32 = 27;
+pub const SO_ATTACH_REUSEPORT_EBPF: u32 = 52;
+pub const SO_DETACH_REUSEPORT_BPF: u32 = 68;
 pub type __u8 = ::core::ffi::c_uchar;
 pub type __s16 = ::core::ffi::c_short;
 pub type __u16 = ::core::ffi::c_ushort;
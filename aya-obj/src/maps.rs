@@ -62,6 +62,7 @@ pub struct BtfMapDef {
     pub(crate) value_size: u32,
     pub(crate) max_entries: u32,
     pub(crate) map_flags: u32,
+    pub(crate) map_extra: u64,
     pub(crate) pinning: PinningType,
     /// BTF type id of the map key
     pub btf_key_type_id: u32,
@@ -198,6 +199,54 @@ impl Map {
         }
     }
 
+    /// Sets the map flags
+    pub fn set_map_flags(&mut self, v: u32) {
+        match self {
+            Map::Legacy(m) => m.def.map_flags = v,
+            Map::Btf(m) => m.def.map_flags = v,
+        }
+    }
+
+    /// Returns the map extra value
+    ///
+    /// This is used by some map types, for example the number of hash functions of a bloom
+    /// filter. Legacy map definitions have no such field, so this is always `0` for them.
+    pub fn map_extra(&self) -> u64 {
+        match self {
+            Map::Legacy(_) => 0,
+            Map::Btf(m) => m.def.map_extra,
+        }
+    }
+
+    /// Returns the definition of the inner map of a map-in-map
+    ///
+    /// `BPF_MAP_TYPE_ARRAY_OF_MAPS` and `BPF_MAP_TYPE_HASH_OF_MAPS` maps must be created with a
+    /// template of the maps they are going to hold. This returns that template, if one was
+    /// declared.
+    pub fn inner(&self) -> Option<Map> {
+        match self {
+            Map::Legacy(m) => m.inner_def.map(|def| {
+                Map::Legacy(LegacyMap {
+                    def,
+                    inner_def: None,
+                    section_index: m.section_index,
+                    section_kind: m.section_kind,
+                    symbol_index: None,
+                    data: Vec::new(),
+                })
+            }),
+            Map::Btf(m) => m.inner_def.map(|def| {
+                Map::Btf(BtfMap {
+                    def,
+                    inner_def: None,
+                    section_index: m.section_index,
+                    symbol_index: m.symbol_index,
+                    data: Vec::new(),
+                })
+            }),
+        }
+    }
+
     /// Returns the pinning type of the map
     pub fn pinning(&self) -> PinningType {
         match self {
@@ -258,6 +307,10 @@ impl Map {
 pub struct LegacyMap {
     /// The definition of the map
     pub def: bpf_map_def,
+    /// The definition of the inner map template for map-in-map types.
+    ///
+    /// This is read from a second [`bpf_map_def`] immediately following `def`.
+    pub inner_def: Option<bpf_map_def>,
     /// The section index
     pub section_index: usize,
     /// The section kind
@@ -277,6 +330,7 @@ pub struct LegacyMap {
 pub struct BtfMap {
     /// The definition of the map
     pub def: BtfMapDef,
+    pub(crate) inner_def: Option<BtfMapDef>,
     pub(crate) section_index: usize,
     pub(crate) symbol_index: usize,
     pub(crate) data: Vec<u8>,
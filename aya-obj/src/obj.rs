@@ -22,8 +22,10 @@ use crate::{
         Array, Btf, BtfError, BtfExt, BtfFeatures, BtfType, DataSecEntry, FuncSecInfo, LineSecInfo,
     },
     generated::{
-        bpf_func_id::*, bpf_insn, bpf_map_info, bpf_map_type::BPF_MAP_TYPE_ARRAY, BPF_CALL,
-        BPF_F_RDONLY_PROG, BPF_JMP, BPF_K,
+        bpf_func_id::*,
+        bpf_insn, bpf_map_info,
+        bpf_map_type::{BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_ARRAY_OF_MAPS, BPF_MAP_TYPE_HASH_OF_MAPS},
+        BPF_CALL, BPF_F_RDONLY_PROG, BPF_JMP, BPF_K,
     },
     maps::{bpf_map_def, BtfMap, BtfMapDef, LegacyMap, Map, PinningType, MINIMUM_MAP_SIZE},
     programs::{
@@ -224,7 +226,6 @@ pub struct Function {
 /// # Unsupported Sections
 ///
 /// Currently, the following section names are not supported yet:
-/// - `flow_dissector`: `BPF_PROG_TYPE_FLOW_DISSECTOR`
 /// - `ksyscall+` or `kretsyscall+`
 /// - `usdt+`
 /// - `kprobe.multi+` or `kretprobe.multi+`: `BPF_TRACE_KPROBE_MULTI`
@@ -291,6 +292,7 @@ pub enum ProgramSection {
     Iter {
         sleepable: bool,
     },
+    FlowDissector,
 }
 
 impl FromStr for ProgramSection {
@@ -300,7 +302,9 @@ impl FromStr for ProgramSection {
         use ProgramSection::*;
 
         // parse the common case, eg "xdp/program_name" or
-        // "sk_skb/stream_verdict/program_name"
+        // "sk_skb/stream_verdict/program_name". Like libbpf, anything after the
+        // components that identify the program type is ignored: attach targets
+        // such as the function of "kprobe/do_sys_open" are passed when attaching.
         let mut pieces = section.split('/');
         let mut next = || {
             pieces
@@ -324,11 +328,7 @@ impl FromStr for ProgramSection {
                     None => XdpAttachType::Interface,
                     Some("cpumap") => XdpAttachType::CpuMap,
                     Some("devmap") => XdpAttachType::DevMap,
-                    Some(_) => {
-                        return Err(ParseError::InvalidProgramSection {
-                            section: section.to_owned(),
-                        })
-                    }
+                    Some(_) => XdpAttachType::Interface,
                 },
             },
             "tp_btf" => BtfTracePoint,
@@ -348,7 +348,7 @@ impl FromStr for ProgramSection {
                 }
             }
             "sockops" => SockOps,
-            "classifier" => SchedClassifier,
+            "classifier" | "tc" => SchedClassifier,
             "cgroup_skb" => {
                 let name = next()?;
                 match name {
@@ -444,6 +444,7 @@ impl FromStr for ProgramSection {
             "sk_lookup" => SkLookup,
             "iter" => Iter { sleepable: false },
             "iter.s" => Iter { sleepable: true },
+            "flow_dissector" => FlowDissector,
             _ => {
                 return Err(ParseError::InvalidProgramSection {
                     section: section.to_owned(),
@@ -455,7 +456,22 @@ impl FromStr for ProgramSection {
 
 impl Object {
     /// Parses the binary data as an object file into an [Object]
+    ///
+    /// The ELF structures must be naturally aligned to be parsed, so if `data` isn't 8-byte
+    /// aligned it's first copied into an aligned buffer.
     pub fn parse(data: &[u8]) -> Result<Object, ParseError> {
+        if data.as_ptr().align_offset(mem::align_of::<u64>()) != 0 {
+            let mut buf = vec![0u64; data.len().div_ceil(mem::size_of::<u64>())];
+            // SAFETY: the buffer is at least `data.len()` bytes long and any bit pattern is a
+            // valid u64.
+            let aligned = unsafe { from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, data.len()) };
+            aligned.copy_from_slice(data);
+            return Self::parse_aligned(aligned);
+        }
+        Self::parse_aligned(data)
+    }
+
+    fn parse_aligned(data: &[u8]) -> Result<Object, ParseError> {
         let obj = object::read::File::parse(data).map_err(ParseError::ElfError)?;
         let endianness = obj.endianness();
 
@@ -526,7 +542,11 @@ impl Object {
         Ok(bpf_obj)
     }
 
-    fn new(endianness: Endianness, license: CString, kernel_version: Option<u32>) -> Object {
+    pub(crate) fn new(
+        endianness: Endianness,
+        license: CString,
+        kernel_version: Option<u32>,
+    ) -> Object {
         Object {
             endianness,
             license,
@@ -558,10 +578,25 @@ impl Object {
 
         for (name, (data, must_exist)) in globals {
             if let Some(symbol) = symbols.get(name) {
-                if data.len() as u64 != symbol.size {
+                // Symbol sizes aren't always emitted by the compiler. When missing, look the
+                // variable up in the BTF DATASEC describing its section.
+                let sym_size = match symbol.size {
+                    0 => symbol
+                        .section_index
+                        .and_then(|index| {
+                            let (section, _) = self
+                                .section_infos
+                                .iter()
+                                .find(|(_, (section_index, _))| section_index.0 == index)?;
+                            btf_var_size(self.btf.as_ref()?, section, name)
+                        })
+                        .unwrap_or(0),
+                    size => size,
+                };
+                if data.len() as u64 != sym_size {
                     return Err(ParseError::InvalidGlobalData {
                         name: name.to_string(),
-                        sym_size: symbol.size,
+                        sym_size,
                         data_size: data.len(),
                     });
                 }
@@ -575,11 +610,11 @@ impl Object {
                         index: symbol.section_index.unwrap_or(0),
                     })?;
                 let start = symbol.address as usize;
-                let end = start + symbol.size as usize;
+                let end = start + sym_size as usize;
                 if start > end || end > map.data().len() {
                     return Err(ParseError::InvalidGlobalData {
                         name: name.to_string(),
-                        sym_size: symbol.size,
+                        sym_size,
                         data_size: data.len(),
                     });
                 }
@@ -773,7 +808,7 @@ impl Object {
                 if type_name == section.name {
                     // each btf_var_secinfo contains a map
                     for info in &datasec.entries {
-                        let (map_name, def) = parse_btf_map_def(btf, info)?;
+                        let (map_name, def, inner_def) = parse_btf_map_def(btf, info)?;
                         let symbol_index =
                             maps.get(&map_name)
                                 .ok_or_else(|| ParseError::SymbolNotFound {
@@ -783,6 +818,7 @@ impl Object {
                             map_name,
                             Map::Btf(BtfMap {
                                 def,
+                                inner_def,
                                 section_index: section.index.0,
                                 symbol_index: *symbol_index,
                                 data: Vec::new(),
@@ -818,6 +854,7 @@ impl Object {
                 .as_ref()
                 .ok_or(ParseError::MapSymbolNameNotFound { i: *i })?;
             let def = parse_map_def(name, data)?;
+            let inner_def = parse_inner_map_def(name, &def, data)?;
             maps.insert(
                 name.to_string(),
                 Map::Legacy(LegacyMap {
@@ -825,6 +862,7 @@ impl Object {
                     section_kind: section.kind,
                     symbol_index: Some(sym.index),
                     def,
+                    inner_def,
                     data: Vec::new(),
                 }),
             );
@@ -843,6 +881,10 @@ impl Object {
         self.section_infos
             .insert(section.name.to_owned(), (section.index, section.size));
         match section.kind {
+            // The kernel rejects maps with an empty value, and nothing can reference an empty
+            // section, so no map is created for empty sections.
+            EbpfSectionKind::Data | EbpfSectionKind::Rodata | EbpfSectionKind::Bss
+                if section.size == 0 => {}
             EbpfSectionKind::Data | EbpfSectionKind::Rodata | EbpfSectionKind::Bss => {
                 self.maps
                     .insert(section.name.to_string(), parse_data_map_section(&section)?);
@@ -1231,6 +1273,7 @@ fn parse_data_map_section(section: &Section) -> Result<Map, ParseError> {
         // Data maps don't require symbols to be relocated
         symbol_index: None,
         def,
+        inner_def: None,
         data,
     }))
 }
@@ -1255,7 +1298,52 @@ fn parse_map_def(name: &str, data: &[u8]) -> Result<bpf_map_def, ParseError> {
     }
 }
 
-fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDef), BtfError> {
+// Returns the size of the global variable `name` as recorded in the BTF DATASEC of `section`.
+fn btf_var_size(btf: &Btf, section: &str, name: &str) -> Option<u64> {
+    btf.types().find_map(|t| match t {
+        BtfType::DataSec(d) if btf.string_at(d.name_offset).ok().as_deref() == Some(section) => d
+            .entries
+            .iter()
+            .find_map(|e| match btf.type_by_id(e.btf_type) {
+                Ok(BtfType::Var(var))
+                    if btf.string_at(var.name_offset).ok().as_deref() == Some(name) =>
+                {
+                    match e.size {
+                        // the size is derived from the type of the variable, which for config
+                        // variables is usually wrapped in `const volatile`
+                        0 => btf.type_size(var.btf_type).ok().map(|size| size as u64),
+                        size => Some(size as u64),
+                    }
+                }
+                _ => None,
+            }),
+        _ => None,
+    })
+}
+
+// Map-in-map types declared in a `maps` section are followed by the definition of their inner
+// map template.
+fn parse_inner_map_def(
+    name: &str,
+    def: &bpf_map_def,
+    data: &[u8],
+) -> Result<Option<bpf_map_def>, ParseError> {
+    if def.map_type != BPF_MAP_TYPE_ARRAY_OF_MAPS as u32
+        && def.map_type != BPF_MAP_TYPE_HASH_OF_MAPS as u32
+    {
+        return Ok(None);
+    }
+    let size = mem::size_of::<bpf_map_def>();
+    if data.len() < size * 2 {
+        return Ok(None);
+    }
+    parse_map_def(name, &data[size..]).map(Some)
+}
+
+fn parse_btf_map_def(
+    btf: &Btf,
+    info: &DataSecEntry,
+) -> Result<(String, BtfMapDef, Option<BtfMapDef>), BtfError> {
     let ty = match btf.type_by_id(info.btf_type)? {
         BtfType::Var(var) => var,
         other => {
@@ -1265,10 +1353,21 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
         }
     };
     let map_name = btf.string_at(ty.name_offset)?;
+    let (map_def, inner_def) = parse_btf_map_struct(btf, ty.btf_type)?;
+    Ok((map_name.to_string(), map_def, inner_def))
+}
+
+// Parses the struct describing a BTF map. The second element of the returned tuple is the
+// definition of the inner map template of map-in-map types, if any.
+fn parse_btf_map_struct(
+    btf: &Btf,
+    type_id: u32,
+) -> Result<(BtfMapDef, Option<BtfMapDef>), BtfError> {
     let mut map_def = BtfMapDef::default();
+    let mut inner_def = None;
 
     // Safety: union
-    let root_type = btf.resolve_type(ty.btf_type)?;
+    let root_type = btf.resolve_type(type_id)?;
     let s = match btf.type_by_id(root_type)? {
         BtfType::Struct(s) => s,
         other => {
@@ -1318,6 +1417,34 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
             "map_flags" => {
                 map_def.map_flags = get_map_field(btf, m.btf_type)?;
             }
+            "map_extra" => {
+                map_def.map_extra = get_map_field(btf, m.btf_type)?.into();
+            }
+            "values" => {
+                // Map-in-map types declare their inner map with `__array(values, struct
+                // inner)`, which is an array of pointers to the inner map definition. Program
+                // arrays use the same syntax with function pointers, which we ignore.
+                let elem_type = match btf.type_by_id(m.btf_type)? {
+                    BtfType::Array(Array { array, .. }) => array.element_type,
+                    _ => {
+                        return Err(BtfError::UnexpectedBtfType {
+                            type_id: m.btf_type,
+                        })
+                    }
+                };
+                let inner_type = match btf.type_by_id(elem_type)? {
+                    BtfType::Ptr(pty) => btf.resolve_type(pty.btf_type)?,
+                    _ => {
+                        return Err(BtfError::UnexpectedBtfType { type_id: elem_type });
+                    }
+                };
+                if let BtfType::Struct(_) = btf.type_by_id(inner_type)? {
+                    let (def, _) = parse_btf_map_struct(btf, inner_type)?;
+                    inner_def = Some(def);
+                    // the values of a map-in-map are inner map file descriptors
+                    map_def.value_size = mem::size_of::<u32>() as u32;
+                }
+            }
             "pinning" => {
                 let pinning = get_map_field(btf, m.btf_type)?;
                 map_def.pinning = PinningType::try_from(pinning).unwrap_or_else(|_| {
@@ -1331,7 +1458,7 @@ fn parse_btf_map_def(btf: &Btf, info: &DataSecEntry) -> Result<(String, BtfMapDe
             }
         }
     }
-    Ok((map_name.to_string(), map_def))
+    Ok((map_def, inner_def))
 }
 
 /// Parses a [bpf_map_info] into a [Map].
@@ -1344,10 +1471,12 @@ pub fn parse_map_info(info: bpf_map_info, pinned: PinningType) -> Map {
                 value_size: info.value_size,
                 max_entries: info.max_entries,
                 map_flags: info.map_flags,
+                map_extra: info.map_extra,
                 pinning: pinned,
                 btf_key_type_id: info.btf_key_type_id,
                 btf_value_type_id: info.btf_value_type_id,
             },
+            inner_def: None,
             section_index: 0,
             symbol_index: 0,
             data: Vec::new(),
@@ -1363,6 +1492,7 @@ pub fn parse_map_info(info: bpf_map_info, pinned: PinningType) -> Map {
                 pinning: pinned,
                 id: info.id,
             },
+            inner_def: None,
             section_index: 0,
             symbol_index: None,
             section_kind: EbpfSectionKind::Undefined,
@@ -1434,7 +1564,10 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
-    use crate::generated::btf_ext_header;
+    use crate::{
+        btf::{BtfMember, Int, IntEncoding, Ptr, Struct, Var, VarLinkage},
+        generated::{bpf_map_type::BPF_MAP_TYPE_HASH, btf_ext_header, BPF_F_NO_PREALLOC},
+    };
 
     const FAKE_INS_LEN: u64 = 8;
 
@@ -1621,11 +1754,38 @@ mod tests {
                     id: 0,
                     pinning: PinningType::None,
                 },
+                inner_def: None,
                 data,
             })) if data == map_data && value_size == map_data.len() as u32
         )
     }
 
+    // An ELF64 relocatable object for BPF with no sections.
+    fn empty_elf() -> Vec<u8> {
+        let mut elf = vec![0u8; 64];
+        elf[..8].copy_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+        elf[16..18].copy_from_slice(&1u16.to_le_bytes()); // e_type: ET_REL
+        elf[18..20].copy_from_slice(&247u16.to_le_bytes()); // e_machine: EM_BPF
+        elf[20..24].copy_from_slice(&1u32.to_le_bytes()); // e_version
+        elf[52..54].copy_from_slice(&64u16.to_le_bytes()); // e_ehsize
+        elf[58..60].copy_from_slice(&64u16.to_le_bytes()); // e_shentsize
+        elf
+    }
+
+    #[test]
+    fn test_parse_unaligned() {
+        let elf = empty_elf();
+        let mut buf = vec![0u8; elf.len() + 8];
+        for offset in 0..8 {
+            let data = &mut buf[offset..][..elf.len()];
+            data.copy_from_slice(&elf);
+            assert_matches!(Object::parse(data), Ok(obj) => {
+                assert!(obj.programs.is_empty());
+                assert!(obj.maps.is_empty());
+            });
+        }
+    }
+
     fn fake_obj() -> Object {
         Object::new(Endianness::Little, CString::new("GPL").unwrap(), None)
     }
@@ -1838,6 +1998,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_section_map_of_maps() {
+        let mut obj = fake_obj();
+        fake_sym(
+            &mut obj,
+            0,
+            0,
+            "outer",
+            mem::size_of::<bpf_map_def>() as u64 * 2,
+        );
+        let outer = bpf_map_def {
+            map_type: BPF_MAP_TYPE_ARRAY_OF_MAPS as u32,
+            key_size: 4,
+            value_size: 4,
+            max_entries: 8,
+            ..Default::default()
+        };
+        let inner = bpf_map_def {
+            map_type: BPF_MAP_TYPE_ARRAY as u32,
+            key_size: 4,
+            value_size: 16,
+            max_entries: 32,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        buf.extend(bytes_of(&outer));
+        buf.extend(bytes_of(&inner));
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Maps,
+                "maps",
+                buf.as_slice(),
+                None
+            )),
+            Ok(())
+        );
+        let map = obj.maps.get("outer").unwrap();
+        assert_matches!(map, Map::Legacy(m) => {
+            assert_eq!(m.def, outer);
+            assert_eq!(m.inner_def, Some(inner));
+        });
+        assert_matches!(map.inner(), Some(Map::Legacy(m)) => {
+            assert_eq!(m.def, inner);
+            assert_eq!(m.symbol_index, None);
+        });
+    }
+
     #[test]
     fn test_parse_section_data() {
         let mut obj = fake_obj();
@@ -1897,6 +2104,22 @@ mod tests {
         assert!(obj.maps.contains_key(".data.boo"));
     }
 
+    #[test]
+    fn test_parse_section_empty_data() {
+        let mut obj = fake_obj();
+        for (kind, name) in [
+            (EbpfSectionKind::Data, ".data"),
+            (EbpfSectionKind::Rodata, ".rodata"),
+            (EbpfSectionKind::Bss, ".bss"),
+        ] {
+            assert_matches!(
+                obj.parse_section(fake_section(kind, name, &[], None)),
+                Ok(())
+            );
+            assert!(!obj.maps.contains_key(name));
+        }
+    }
+
     #[test]
     fn test_parse_section_kprobe() {
         let mut obj = fake_obj();
@@ -2082,6 +2305,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_section_flow_dissector() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "flow_dissector",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::FlowDissector,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn test_parse_section_xdp() {
         let mut obj = fake_obj();
@@ -2105,6 +2351,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_section_xdp_program_name() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "xdp/foo",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::Xdp {
+                    frags: false,
+                    attach_type: XdpAttachType::Interface
+                },
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_section_tc() {
+        let mut obj = fake_obj();
+        fake_sym(&mut obj, 0, 0, "foo", FAKE_INS_LEN);
+
+        assert_matches!(
+            obj.parse_section(fake_section(
+                EbpfSectionKind::Program,
+                "tc",
+                bytes_of(&fake_ins()),
+                None
+            )),
+            Ok(())
+        );
+        assert_matches!(
+            obj.programs.get("foo"),
+            Some(Program {
+                section: ProgramSection::SchedClassifier,
+                ..
+            })
+        );
+    }
+
     #[test]
     fn test_parse_section_xdp_frags() {
         let mut obj = fake_obj();
@@ -2599,6 +2894,7 @@ mod tests {
                     id: 1,
                     pinning: PinningType::None,
                 },
+                inner_def: None,
                 section_index: 1,
                 section_kind: EbpfSectionKind::Rodata,
                 symbol_index: Some(1),
@@ -2629,6 +2925,176 @@ mod tests {
         assert_eq!(test_data, map.data());
     }
 
+    #[test]
+    fn test_patch_map_data_btf_size() {
+        use crate::btf::{DataSec, Int, IntEncoding, Var, VarLinkage};
+
+        let mut obj = fake_obj();
+        obj.maps.insert(
+            ".rodata".to_owned(),
+            Map::Legacy(LegacyMap {
+                def: bpf_map_def {
+                    map_type: BPF_MAP_TYPE_ARRAY as u32,
+                    key_size: mem::size_of::<u32>() as u32,
+                    value_size: 8,
+                    max_entries: 1,
+                    map_flags: BPF_F_RDONLY_PROG,
+                    id: 1,
+                    pinning: PinningType::None,
+                },
+                inner_def: None,
+                section_index: 1,
+                section_kind: EbpfSectionKind::Rodata,
+                symbol_index: Some(1),
+                data: vec![0; 8],
+            }),
+        );
+        obj.section_infos
+            .insert(".rodata".to_owned(), (SectionIndex(1), 8));
+        // the symbol doesn't record its size, only BTF does
+        obj.symbol_table.insert(
+            1,
+            Symbol {
+                index: 1,
+                section_index: Some(1),
+                name: Some("my_config".to_owned()),
+                address: 4,
+                size: 0,
+                is_definition: true,
+                kind: SymbolKind::Data,
+            },
+        );
+
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let name_offset = btf.add_string("my_config");
+        let var_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            int_type_id,
+            VarLinkage::Global,
+        )));
+        // a variable with the same name but a different size in another section
+        let name_offset = btf.add_string("my_config");
+        let data_var_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            int_type_id,
+            VarLinkage::Static,
+        )));
+        let name_offset = btf.add_string(".data");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            name_offset,
+            vec![DataSecEntry {
+                btf_type: data_var_type_id,
+                offset: 0,
+                size: 8,
+            }],
+            8,
+        )));
+        obj.section_infos
+            .insert(".data".to_owned(), (SectionIndex(2), 8));
+        let name_offset = btf.add_string(".rodata");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            name_offset,
+            vec![DataSecEntry {
+                btf_type: var_type_id,
+                offset: 4,
+                size: 4,
+            }],
+            8,
+        )));
+        obj.btf = Some(btf);
+
+        assert_matches!(
+            obj.patch_map_data(HashMap::from([("my_config", (&[1u8, 2][..], true))])),
+            Err(ParseError::InvalidGlobalData {
+                sym_size: 4,
+                data_size: 2,
+                ..
+            })
+        );
+
+        obj.patch_map_data(HashMap::from([("my_config", (&[1u8, 2, 3, 4][..], true))]))
+            .unwrap();
+        let map = obj.maps.get(".rodata").unwrap();
+        assert_eq!(map.data(), &[0, 0, 0, 0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_patch_map_data_btf_const_volatile() {
+        use crate::btf::{Const, DataSec, Int, IntEncoding, Var, VarLinkage, Volatile};
+
+        let mut obj = fake_obj();
+        obj.maps.insert(
+            ".rodata".to_owned(),
+            Map::Legacy(LegacyMap {
+                def: bpf_map_def {
+                    map_type: BPF_MAP_TYPE_ARRAY as u32,
+                    key_size: mem::size_of::<u32>() as u32,
+                    value_size: 4,
+                    max_entries: 1,
+                    map_flags: BPF_F_RDONLY_PROG,
+                    id: 1,
+                    pinning: PinningType::None,
+                },
+                inner_def: None,
+                section_index: 1,
+                section_kind: EbpfSectionKind::Rodata,
+                symbol_index: Some(1),
+                data: vec![0; 4],
+            }),
+        );
+        obj.section_infos
+            .insert(".rodata".to_owned(), (SectionIndex(1), 4));
+        obj.symbol_table.insert(
+            1,
+            Symbol {
+                index: 1,
+                section_index: Some(1),
+                name: Some("target_pid".to_owned()),
+                address: 0,
+                size: 0,
+                is_definition: true,
+                kind: SymbolKind::Data,
+            },
+        );
+
+        // const volatile u32 target_pid, with no size in the DATASEC entry either
+        let mut btf = Btf::new();
+        let name_offset = btf.add_string("u32");
+        let int_type_id =
+            btf.add_type(BtfType::Int(Int::new(name_offset, 4, IntEncoding::None, 0)));
+        let volatile_type_id = btf.add_type(BtfType::Volatile(Volatile::new(int_type_id)));
+        let const_type_id = btf.add_type(BtfType::Const(Const::new(volatile_type_id)));
+        let name_offset = btf.add_string("target_pid");
+        let var_type_id = btf.add_type(BtfType::Var(Var::new(
+            name_offset,
+            const_type_id,
+            VarLinkage::Global,
+        )));
+        let name_offset = btf.add_string(".rodata");
+        btf.add_type(BtfType::DataSec(DataSec::new(
+            name_offset,
+            vec![DataSecEntry {
+                btf_type: var_type_id,
+                offset: 0,
+                size: 0,
+            }],
+            4,
+        )));
+        obj.btf = Some(btf);
+
+        obj.patch_map_data(HashMap::from([("target_pid", (&[1u8, 2, 3, 4][..], true))]))
+            .unwrap();
+        let map = obj.maps.get(".rodata").unwrap();
+        assert_eq!(map.data(), &[1, 2, 3, 4]);
+    }
+
     #[test]
     fn test_parse_btf_map_section() {
         let mut obj = fake_obj();
@@ -2797,4 +3263,72 @@ mod tests {
             assert_eq!(m.def.max_entries, 1);
         });
     }
+
+    #[test]
+    fn test_parse_btf_map_def() {
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        let u64_name = btf.add_string("u64");
+        let u64_type_id = btf.add_type(BtfType::Int(Int::new(u64_name, 8, IntEncoding::None, 0)));
+
+        // `__uint(name, value)` is encoded as a pointer to an array of `value` ints
+        let mut uint_field = |name: &str, value: u32| {
+            let array_type_id = btf.add_type(BtfType::Array(Array::new(
+                0,
+                int_type_id,
+                int_type_id,
+                value,
+            )));
+            BtfMember {
+                name_offset: btf.add_string(name),
+                btf_type: btf.add_type(BtfType::Ptr(Ptr::new(0, array_type_id))),
+                offset: 0,
+            }
+        };
+        let mut members = vec![
+            uint_field("type", BPF_MAP_TYPE_HASH as u32),
+            uint_field("max_entries", 1024),
+            uint_field("map_flags", BPF_F_NO_PREALLOC),
+            uint_field("pinning", PinningType::ByName as u32),
+        ];
+        // `__type(name, type)` is encoded as a pointer to `type`
+        members.push(BtfMember {
+            name_offset: btf.add_string("key"),
+            btf_type: btf.add_type(BtfType::Ptr(Ptr::new(0, int_type_id))),
+            offset: 0,
+        });
+        members.push(BtfMember {
+            name_offset: btf.add_string("value"),
+            btf_type: btf.add_type(BtfType::Ptr(Ptr::new(0, u64_type_id))),
+            offset: 0,
+        });
+        let struct_type_id = btf.add_type(BtfType::Struct(Struct::new(0, members, 48)));
+        let var_name = btf.add_string("my_map");
+        let var_type_id = btf.add_type(BtfType::Var(Var::new(
+            var_name,
+            struct_type_id,
+            VarLinkage::Global,
+        )));
+
+        let (name, def, inner_def) = parse_btf_map_def(
+            &btf,
+            &DataSecEntry {
+                btf_type: var_type_id,
+                offset: 0,
+                size: 48,
+            },
+        )
+        .unwrap();
+        assert_eq!(name, "my_map");
+        assert_eq!(def.map_type, BPF_MAP_TYPE_HASH as u32);
+        assert_eq!(def.key_size, 4);
+        assert_eq!(def.value_size, 8);
+        assert_eq!(def.max_entries, 1024);
+        assert_eq!(def.map_flags, BPF_F_NO_PREALLOC);
+        assert_eq!(def.pinning, PinningType::ByName);
+        assert_eq!(def.btf_key_type_id, int_type_id);
+        assert_eq!(def.btf_value_type_id, u64_type_id);
+        assert!(inner_def.is_none());
+    }
 }
@@ -7,9 +7,10 @@ use log::debug;
 use object::{SectionIndex, SymbolKind};
 
 use crate::{
+    btf::{Btf, BtfKind},
     generated::{
-        bpf_insn, BPF_CALL, BPF_JMP, BPF_K, BPF_PSEUDO_CALL, BPF_PSEUDO_FUNC, BPF_PSEUDO_MAP_FD,
-        BPF_PSEUDO_MAP_VALUE,
+        bpf_insn, BPF_CALL, BPF_JMP, BPF_K, BPF_PSEUDO_CALL, BPF_PSEUDO_FUNC,
+        BPF_PSEUDO_KFUNC_CALL, BPF_PSEUDO_MAP_FD, BPF_PSEUDO_MAP_VALUE,
     },
     maps::Map,
     obj::{Function, Object},
@@ -24,7 +25,8 @@ type RawFd = core::ffi::c_int;
 
 pub(crate) const INS_SIZE: usize = mem::size_of::<bpf_insn>();
 
-/// The error type returned by [`Object::relocate_maps`] and [`Object::relocate_calls`]
+/// The error type returned by [`Object::relocate_maps`], [`Object::relocate_kfuncs`] and
+/// [`Object::relocate_calls`]
 #[derive(thiserror::Error, Debug)]
 #[error("error relocating `{function}`")]
 pub struct EbpfRelocationError {
@@ -75,6 +77,17 @@ pub enum RelocationError {
         address: u64,
     },
 
+    /// Unsupported text relocation
+    #[error("unsupported relocation of size {size} against symbol #{symbol_index} while relocating `{caller_name}`")]
+    UnsupportedTextRelocation {
+        /// The symbol index
+        symbol_index: usize,
+        /// The relocation size
+        size: u8,
+        /// The caller name
+        caller_name: String,
+    },
+
     /// Invalid relocation offset
     #[error("invalid offset `{offset}` applying relocation #{relocation_number}")]
     InvalidRelocationOffset {
@@ -83,6 +96,20 @@ pub enum RelocationError {
         /// The relocation number
         relocation_number: usize,
     },
+
+    /// Unknown kernel function
+    #[error("kernel function `{name}` not found in the target BTF")]
+    UnknownKfunc {
+        /// The kernel function name
+        name: String,
+    },
+
+    /// Unresolved call to an extern function
+    #[error("call to extern function `{name}` was not resolved, calling kernel functions requires the target BTF")]
+    UnresolvedExternCall {
+        /// The extern function name
+        name: String,
+    },
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -115,9 +142,17 @@ impl Object {
         let mut maps_by_section = HashMap::new();
         let mut maps_by_symbol = HashMap::new();
         for (name, fd, map) in maps {
-            maps_by_section.insert(map.section_index(), (name, fd, map));
-            if let Some(index) = map.symbol_index() {
-                maps_by_symbol.insert(index, (name, fd, map));
+            // Legacy and BTF maps share their section with other maps and are always relocated
+            // by symbol. Only data maps, which have a section of their own, are relocated by
+            // section; falling back to the section for the others would silently pick whichever
+            // map of the section came last.
+            match map.symbol_index() {
+                Some(index) => {
+                    maps_by_symbol.insert(index, (name, fd, map));
+                }
+                None => {
+                    maps_by_section.insert(map.section_index(), (name, fd, map));
+                }
             }
         }
 
@@ -141,6 +176,35 @@ impl Object {
         Ok(())
     }
 
+    /// Relocates calls to kernel functions (kfuncs)
+    ///
+    /// Calls to `extern` functions are resolved to the `BTF_KIND_FUNC` of the same name in
+    /// `target_btf`, which is usually the BTF of the running kernel, and turned into
+    /// `BPF_PSEUDO_KFUNC_CALL` instructions. Only kernel functions defined in vmlinux are
+    /// supported, not those defined in kernel modules.
+    ///
+    /// This needs to run before [`Object::relocate_calls`].
+    pub fn relocate_kfuncs(&mut self, target_btf: &Btf) -> Result<(), EbpfRelocationError> {
+        let mut kfunc_ids = HashMap::new();
+        for function in self.functions.values_mut() {
+            if let Some(relocations) = self.relocations.get(&function.section_index) {
+                relocate_kfuncs(
+                    function,
+                    relocations.values(),
+                    &self.symbol_table,
+                    target_btf,
+                    &mut kfunc_ids,
+                )
+                .map_err(|error| EbpfRelocationError {
+                    function: function.name.clone(),
+                    error,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Relocates function calls
     pub fn relocate_calls(
         &mut self,
@@ -225,7 +289,25 @@ fn relocate_maps<'a, I: Iterator<Item = &'a Relocation>>(
             continue;
         }
 
-        let (_name, fd, map) = if let Some(m) = maps_by_symbol.get(&rel.symbol_index) {
+        let by_symbol = maps_by_symbol.get(&rel.symbol_index).or_else(|| {
+            // Static maps can be referenced through the symbol of their section, in which case
+            // the offset of the map within the section is stored in the instruction. This matches
+            // what libbpf does for `STT_SECTION` relocations.
+            if sym.kind != SymbolKind::Section {
+                return None;
+            }
+            let offset = sym
+                .address
+                .checked_add_signed(instructions[ins_index].imm.into())?;
+            maps_by_symbol.values().find(|(_, _, map)| {
+                map.section_index() == section_index
+                    && map
+                        .symbol_index()
+                        .and_then(|index| symbol_table.get(&index))
+                        .is_some_and(|map_sym| map_sym.address == offset)
+            })
+        });
+        let (_name, fd, map) = if let Some(m) = by_symbol {
             let map = &m.2;
             debug!(
                 "relocating map by symbol index {:?}, kind {:?} at insn {ins_index} in section {}",
@@ -233,7 +315,6 @@ fn relocate_maps<'a, I: Iterator<Item = &'a Relocation>>(
                 map.section_kind(),
                 fun.section_index.0
             );
-            debug_assert_eq!(map.symbol_index().unwrap(), rel.symbol_index);
             m
         } else {
             let Some(m) = maps_by_section.get(&section_index) else {
@@ -273,9 +354,81 @@ fn relocate_maps<'a, I: Iterator<Item = &'a Relocation>>(
     Ok(())
 }
 
+fn relocate_kfuncs<'a, I: Iterator<Item = &'a Relocation>>(
+    fun: &mut Function,
+    relocations: I,
+    symbol_table: &HashMap<usize, Symbol>,
+    target_btf: &Btf,
+    kfunc_ids: &mut HashMap<String, u32>,
+) -> Result<(), RelocationError> {
+    let section_offset = fun.section_offset;
+    let instructions = &mut fun.instructions;
+    let function_size = instructions.len() * INS_SIZE;
+
+    for (rel_n, rel) in relocations.enumerate() {
+        let rel_offset = rel.offset as usize;
+        if rel_offset < section_offset || rel_offset >= section_offset + function_size {
+            // the relocation doesn't apply to this function
+            continue;
+        }
+
+        let ins_offset = rel_offset - section_offset;
+        if ins_offset % INS_SIZE != 0 {
+            return Err(RelocationError::InvalidRelocationOffset {
+                offset: rel.offset,
+                relocation_number: rel_n,
+            });
+        }
+        let ins_index = ins_offset / INS_SIZE;
+
+        let sym = symbol_table
+            .get(&rel.symbol_index)
+            .ok_or(RelocationError::UnknownSymbol {
+                index: rel.symbol_index,
+            })?;
+
+        // kfuncs are declared `extern`, so their symbol is undefined
+        let Some(name) = extern_call_name(&instructions[ins_index], sym) else {
+            continue;
+        };
+
+        let btf_id = match kfunc_ids.get(name) {
+            Some(btf_id) => *btf_id,
+            None => {
+                let btf_id = target_btf
+                    .id_by_type_name_kind(name, BtfKind::Func)
+                    .map_err(|_| RelocationError::UnknownKfunc {
+                        name: name.to_owned(),
+                    })?;
+                kfunc_ids.insert(name.to_owned(), btf_id);
+                btf_id
+            }
+        };
+        debug!("relocating call to kfunc `{name}` with BTF id {btf_id} at insn {ins_index}");
+
+        let ins = &mut instructions[ins_index];
+        ins.set_src_reg(BPF_PSEUDO_KFUNC_CALL as u8);
+        ins.imm = btf_id as i32;
+        // 0 means the kfunc is in vmlinux, otherwise it's an index into the program's fd_array
+        ins.off = 0;
+    }
+
+    Ok(())
+}
+
+// returns the name of the extern function called by `ins`, if `ins` is a call relocated against
+// the undefined symbol `sym`
+fn extern_call_name<'a>(ins: &bpf_insn, sym: &'a Symbol) -> Option<&'a str> {
+    if sym.section_index.is_some() || sym.is_definition || !insn_is_call(ins) {
+        return None;
+    }
+    sym.name.as_deref()
+}
+
 struct FunctionLinker<'a> {
     functions: &'a BTreeMap<(usize, u64), Function>,
-    linked_functions: HashMap<u64, usize>,
+    // instruction index of each linked function, keyed by (section index, address)
+    linked_functions: HashMap<(usize, u64), usize>,
     relocations: &'a HashMap<SectionIndex, HashMap<u64, Relocation>>,
     symbol_table: &'a HashMap<usize, Symbol>,
     text_sections: &'a HashSet<usize>,
@@ -313,7 +466,7 @@ impl<'a> FunctionLinker<'a> {
         program: &mut Function,
         fun: &Function,
     ) -> Result<usize, RelocationError> {
-        if let Some(fun_ins_index) = self.linked_functions.get(&fun.address) {
+        if let Some(fun_ins_index) = self.linked_functions.get(&fun_key(fun)) {
             return Ok(*fun_ins_index);
         };
 
@@ -330,7 +483,7 @@ impl<'a> FunctionLinker<'a> {
         // the offset needs to be adjusted
         self.link_func_and_line_info(program, fun, start_ins)?;
 
-        self.linked_functions.insert(fun.address, start_ins);
+        self.linked_functions.insert(fun_key(fun), start_ins);
 
         // relocate `fun`, recursively linking in all the callees
         self.relocate(program, fun)?;
@@ -365,17 +518,24 @@ impl<'a> FunctionLinker<'a> {
                     self.symbol_table
                         .get(&rel.symbol_index)
                         .map(|sym| (rel, sym))
-                })
-                .filter(|(_rel, sym)| {
-                    // only consider text relocations, data relocations are
-                    // relocated in relocate_maps()
-                    sym.kind == SymbolKind::Text
-                        || sym
-                            .section_index
-                            .map(|section_index| self.text_sections.contains(&section_index))
-                            .unwrap_or(false)
                 });
 
+            // calls to kfuncs are relocated in relocate_kfuncs(), any extern call left here
+            // couldn't be resolved
+            if let Some(name) = rel.and_then(|(_rel, sym)| extern_call_name(&ins, sym)) {
+                return Err(RelocationError::UnresolvedExternCall {
+                    name: name.to_owned(),
+                });
+            }
+
+            let rel = rel.filter(|(_rel, sym)| {
+                // only consider text relocations, data relocations are
+                // relocated in relocate_maps()
+                sym.section_index.is_some_and(|section_index| {
+                    sym.kind == SymbolKind::Text || self.text_sections.contains(&section_index)
+                })
+            });
+
             // not a call and not a text relocation, we don't need to do anything
             if !is_call && rel.is_none() {
                 continue;
@@ -390,7 +550,13 @@ impl<'a> FunctionLinker<'a> {
                     }
                     // R_BPF_64_64 this is a ld_imm64 text relocation
                     SymbolKind::Section if rel.size == 64 => sym.address + ins.imm as u64,
-                    _ => todo!(), // FIXME: return an error here,
+                    _ => {
+                        return Err(RelocationError::UnsupportedTextRelocation {
+                            symbol_index: rel.symbol_index,
+                            size: rel.size,
+                            caller_name: fun.name.clone(),
+                        })
+                    }
                 };
                 (sym.section_index.unwrap(), address)
             } else {
@@ -485,6 +651,12 @@ impl<'a> FunctionLinker<'a> {
     }
 }
 
+// functions at the same address in different sections, e.g. `.text.foo` and `.text.bar`, are
+// distinct
+fn fun_key(fun: &Function) -> (usize, u64) {
+    (fun.section_index.0, fun.address)
+}
+
 fn insn_is_call(ins: &bpf_insn) -> bool {
     let klass = (ins.code & 0x07) as u32;
     let op = (ins.code & 0xF0) as u32;
@@ -502,8 +674,14 @@ fn insn_is_call(ins: &bpf_insn) -> bool {
 mod test {
     use alloc::{string::ToString, vec, vec::Vec};
 
+    use assert_matches::assert_matches;
+    use object::Endianness;
+
     use super::*;
-    use crate::maps::{BtfMap, LegacyMap};
+    use crate::{
+        btf::{BtfParam, BtfType, Func, FuncLinkage, FuncProto, Int, IntEncoding},
+        maps::{BtfMap, LegacyMap},
+    };
 
     fn fake_sym(index: usize, section_index: usize, address: u64, name: &str, size: u64) -> Symbol {
         Symbol {
@@ -524,6 +702,7 @@ mod test {
     fn fake_legacy_map(symbol_index: usize) -> Map {
         Map::Legacy(LegacyMap {
             def: Default::default(),
+            inner_def: None,
             section_index: 0,
             section_kind: EbpfSectionKind::Undefined,
             symbol_index: Some(symbol_index),
@@ -534,6 +713,7 @@ mod test {
     fn fake_btf_map(symbol_index: usize) -> Map {
         Map::Btf(BtfMap {
             def: Default::default(),
+            inner_def: None,
             section_index: 0,
             symbol_index,
             data: Vec::new(),
@@ -743,4 +923,414 @@ mod test {
         assert_eq!(fun.instructions[1].src_reg(), BPF_PSEUDO_MAP_FD as u8);
         assert_eq!(fun.instructions[1].imm, 2);
     }
+
+    fn ld_imm64() -> [bpf_insn; 2] {
+        [
+            ins(&[0x18, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            ins(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        ]
+    }
+
+    // An object with a function loading a legacy map, a BTF map and a `.bss` variable, each from
+    // its own section.
+    fn fake_mixed_obj() -> Object {
+        let mut obj = Object::new(Endianness::Little, c"GPL".into(), None);
+        obj.functions.insert(
+            (0, 0),
+            fake_func("test", [ld_imm64(), ld_imm64(), ld_imm64()].concat()),
+        );
+        obj.symbol_table = HashMap::from([
+            (1, fake_sym(1, 1, 0, "legacy_map", 0)),
+            (2, fake_sym(2, 2, 0, "btf_map", 0)),
+            (3, fake_sym(3, 3, 4, "counter", 4)),
+        ]);
+        obj.relocations.insert(
+            SectionIndex(0),
+            (1..=3)
+                .map(|symbol_index| {
+                    let offset = ((symbol_index - 1) * 2 * INS_SIZE) as u64;
+                    let relocation = Relocation {
+                        offset,
+                        symbol_index,
+                        size: 64,
+                    };
+                    (offset, relocation)
+                })
+                .collect(),
+        );
+        obj
+    }
+
+    #[test]
+    fn test_mixed_map_relocation() {
+        let mut obj = fake_mixed_obj();
+
+        let legacy_map = Map::Legacy(LegacyMap {
+            def: Default::default(),
+            inner_def: None,
+            section_index: 1,
+            section_kind: EbpfSectionKind::Maps,
+            symbol_index: Some(1),
+            data: Vec::new(),
+        });
+        let btf_map = Map::Btf(BtfMap {
+            def: Default::default(),
+            inner_def: None,
+            section_index: 2,
+            symbol_index: 2,
+            data: Vec::new(),
+        });
+        let bss_map = Map::Legacy(LegacyMap {
+            def: Default::default(),
+            inner_def: None,
+            section_index: 3,
+            section_kind: EbpfSectionKind::Bss,
+            symbol_index: None,
+            data: vec![0; 8],
+        });
+
+        obj.relocate_maps(
+            [
+                ("legacy_map", 10, &legacy_map),
+                ("btf_map", 20, &btf_map),
+                (".bss", 30, &bss_map),
+            ]
+            .into_iter(),
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        let instructions = &obj.functions[&(0, 0)].instructions;
+        assert_eq!(instructions[0].src_reg(), BPF_PSEUDO_MAP_FD as u8);
+        assert_eq!(instructions[0].imm, 10);
+        assert_eq!(instructions[2].src_reg(), BPF_PSEUDO_MAP_FD as u8);
+        assert_eq!(instructions[2].imm, 20);
+        // the offset of the variable within the section is stored in the second half
+        assert_eq!(instructions[4].src_reg(), BPF_PSEUDO_MAP_VALUE as u8);
+        assert_eq!(instructions[4].imm, 30);
+        assert_eq!(instructions[5].imm, 4);
+    }
+
+    #[test]
+    fn test_section_symbol_map_relocation() {
+        let mut obj = Object::new(Endianness::Little, c"GPL".into(), None);
+        // the second map of the section is referenced through the section symbol, the offset of
+        // the map within the section is in the instruction
+        let mut insns = ld_imm64();
+        insns[0].imm = 20;
+        obj.functions
+            .insert((0, 0), fake_func("test", insns.to_vec()));
+        let mut section_sym = fake_sym(1, 1, 0, "maps", 0);
+        section_sym.kind = SymbolKind::Section;
+        obj.symbol_table = HashMap::from([
+            (1, section_sym),
+            (2, fake_sym(2, 1, 0, "map_1", 20)),
+            (3, fake_sym(3, 1, 20, "map_2", 20)),
+        ]);
+        obj.relocations.insert(
+            SectionIndex(0),
+            HashMap::from([(
+                0,
+                Relocation {
+                    offset: 0,
+                    symbol_index: 1,
+                    size: 64,
+                },
+            )]),
+        );
+
+        let map = |symbol_index| {
+            Map::Legacy(LegacyMap {
+                def: Default::default(),
+                inner_def: None,
+                section_index: 1,
+                section_kind: EbpfSectionKind::Maps,
+                symbol_index: Some(symbol_index),
+                data: Vec::new(),
+            })
+        };
+        let (map_1, map_2) = (map(2), map(3));
+        obj.relocate_maps(
+            [("map_1", 1, &map_1), ("map_2", 2, &map_2)].into_iter(),
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        let instructions = &obj.functions[&(0, 0)].instructions;
+        assert_eq!(instructions[0].src_reg(), BPF_PSEUDO_MAP_FD as u8);
+        assert_eq!(instructions[0].imm, 2);
+    }
+
+    #[test]
+    fn test_missing_map_relocation() {
+        let mut obj = fake_mixed_obj();
+        obj.relocations
+            .get_mut(&SectionIndex(0))
+            .unwrap()
+            .retain(|offset, _| *offset == 0);
+
+        // `legacy_map` wasn't created, relocating against the other map in its section would be
+        // wrong.
+        let other_map = Map::Legacy(LegacyMap {
+            def: Default::default(),
+            inner_def: None,
+            section_index: 1,
+            section_kind: EbpfSectionKind::Maps,
+            symbol_index: Some(4),
+            data: Vec::new(),
+        });
+        assert_matches!(
+            obj.relocate_maps([("other_map", 10, &other_map)].into_iter(), &HashSet::new()),
+            Err(EbpfRelocationError {
+                error: RelocationError::SectionNotFound {
+                    section_index: 1,
+                    symbol_index: 1,
+                    ..
+                },
+                ..
+            })
+        );
+    }
+
+    fn call(imm: i32) -> bpf_insn {
+        let mut ins = ins(&[0x85, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        ins.imm = imm;
+        ins
+    }
+
+    fn mov_r0(imm: i32) -> bpf_insn {
+        let mut ins = ins(&[0xb7, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        ins.imm = imm;
+        ins
+    }
+
+    fn exit() -> bpf_insn {
+        ins(&[0x95, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+    }
+
+    fn text_sym(index: usize, section_index: usize, name: &str) -> Symbol {
+        Symbol {
+            kind: SymbolKind::Text,
+            ..fake_sym(index, section_index, 0, name, 0)
+        }
+    }
+
+    #[test]
+    fn test_pc_relative_call_relocation() {
+        let prog = fake_func("prog", vec![call(1), exit()]);
+        let callee = Function {
+            address: 2 * INS_SIZE as u64,
+            section_offset: 2 * INS_SIZE,
+            ..fake_func("callee", vec![mov_r0(1), exit()])
+        };
+        let functions = BTreeMap::from([((0, 0), prog.clone()), ((0, callee.address), callee)]);
+        let relocations = HashMap::new();
+        let symbol_table = HashMap::new();
+        let text_sections = HashSet::from([0]);
+
+        let linked = FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections)
+            .link(&prog)
+            .unwrap();
+
+        assert_eq!(linked.instructions.len(), 4);
+        assert_eq!(linked.instructions[0].imm, 1);
+        assert_eq!(linked.instructions[2].imm, 1);
+    }
+
+    #[test]
+    fn test_call_relocation_across_sections() {
+        let prog = fake_func("prog", vec![call(-1), call(-1), exit()]);
+        // both callees are at address 0 of their own section, like functions compiled with
+        // -ffunction-sections
+        let foo = Function {
+            section_index: SectionIndex(1),
+            ..fake_func("foo", vec![mov_r0(1), exit()])
+        };
+        let bar = Function {
+            section_index: SectionIndex(2),
+            ..fake_func("bar", vec![mov_r0(2), exit()])
+        };
+        let functions = BTreeMap::from([((0, 0), prog.clone()), ((1, 0), foo), ((2, 0), bar)]);
+        let relocations = HashMap::from([(
+            SectionIndex(0),
+            HashMap::from([
+                (
+                    0,
+                    Relocation {
+                        offset: 0,
+                        size: 32,
+                        symbol_index: 1,
+                    },
+                ),
+                (
+                    INS_SIZE as u64,
+                    Relocation {
+                        offset: INS_SIZE as u64,
+                        size: 32,
+                        symbol_index: 2,
+                    },
+                ),
+            ]),
+        )]);
+        let symbol_table = HashMap::from([(1, text_sym(1, 1, "foo")), (2, text_sym(2, 2, "bar"))]);
+        let text_sections = HashSet::from([0, 1, 2]);
+
+        let linked = FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections)
+            .link(&prog)
+            .unwrap();
+
+        assert_eq!(linked.instructions.len(), 7);
+        // foo is linked at instruction 3 and bar at instruction 5
+        assert_eq!(linked.instructions[0].imm, 2);
+        assert_eq!(linked.instructions[1].imm, 3);
+        assert_eq!(linked.instructions[3].imm, 1);
+        assert_eq!(linked.instructions[5].imm, 2);
+    }
+
+    #[test]
+    fn test_unsupported_text_relocation() {
+        let prog = fake_func("prog", vec![call(-1), exit()]);
+        let functions = BTreeMap::from([((0, 0), prog.clone())]);
+        let relocations = HashMap::from([(
+            SectionIndex(0),
+            HashMap::from([(
+                0,
+                Relocation {
+                    offset: 0,
+                    size: 16,
+                    symbol_index: 1,
+                },
+            )]),
+        )]);
+        let symbol_table = HashMap::from([(
+            1,
+            Symbol {
+                kind: SymbolKind::Section,
+                ..fake_sym(1, 1, 0, ".text", 0)
+            },
+        )]);
+        let text_sections = HashSet::from([0, 1]);
+
+        let err = FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections)
+            .link(&prog)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RelocationError::UnsupportedTextRelocation {
+                symbol_index: 1,
+                size: 16,
+                ..
+            }
+        ));
+    }
+
+    fn extern_sym(index: usize, name: &str) -> Symbol {
+        Symbol {
+            index,
+            section_index: None,
+            name: Some(name.to_string()),
+            address: 0,
+            size: 0,
+            is_definition: false,
+            kind: SymbolKind::Unknown,
+        }
+    }
+
+    fn kfunc_btf(name: &str) -> (Btf, u32) {
+        let mut btf = Btf::new();
+        let int_name = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(int_name, 4, IntEncoding::Signed, 0)));
+        let param_name = btf.add_string("x");
+        let proto_type_id = btf.add_type(BtfType::FuncProto(FuncProto::new(
+            vec![BtfParam {
+                name_offset: param_name,
+                btf_type: int_type_id,
+            }],
+            int_type_id,
+        )));
+        let func_name = btf.add_string(name);
+        let func_type_id = btf.add_type(BtfType::Func(Func::new(
+            func_name,
+            proto_type_id,
+            FuncLinkage::Global,
+        )));
+        (btf, func_type_id)
+    }
+
+    fn kfunc_call_relocations() -> HashMap<SectionIndex, HashMap<u64, Relocation>> {
+        HashMap::from([(
+            SectionIndex(0),
+            HashMap::from([(
+                INS_SIZE as u64,
+                Relocation {
+                    offset: INS_SIZE as u64,
+                    size: 32,
+                    symbol_index: 1,
+                },
+            )]),
+        )])
+    }
+
+    #[test]
+    fn test_kfunc_relocation() {
+        let mut prog = fake_func("prog", vec![mov_r0(0), call(-1), exit()]);
+        let relocations = kfunc_call_relocations();
+        let symbol_table = HashMap::from([(1, extern_sym(1, "bpf_kfunc"))]);
+        let (btf, kfunc_id) = kfunc_btf("bpf_kfunc");
+
+        relocate_kfuncs(
+            &mut prog,
+            relocations[&SectionIndex(0)].values(),
+            &symbol_table,
+            &btf,
+            &mut HashMap::new(),
+        )
+        .unwrap();
+
+        let ins = prog.instructions[1];
+        assert_eq!(ins.src_reg(), BPF_PSEUDO_KFUNC_CALL as u8);
+        assert_eq!(ins.imm, kfunc_id as i32);
+        assert_eq!(ins.off, 0);
+
+        // the kfunc call is left alone when linking
+        let functions = BTreeMap::from([((0, 0), prog.clone())]);
+        let text_sections = HashSet::from([0]);
+        let linked = FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections)
+            .link(&prog)
+            .unwrap();
+        assert_eq!(linked.instructions.len(), 3);
+        assert_eq!(linked.instructions[1].imm, kfunc_id as i32);
+    }
+
+    #[test]
+    fn test_unknown_kfunc_relocation() {
+        let mut prog = fake_func("prog", vec![mov_r0(0), call(-1), exit()]);
+        let relocations = kfunc_call_relocations();
+        let symbol_table = HashMap::from([(1, extern_sym(1, "bpf_missing_kfunc"))]);
+        let (btf, _) = kfunc_btf("bpf_kfunc");
+
+        let err = relocate_kfuncs(
+            &mut prog,
+            relocations[&SectionIndex(0)].values(),
+            &symbol_table,
+            &btf,
+            &mut HashMap::new(),
+        )
+        .unwrap_err();
+        assert_matches!(err, RelocationError::UnknownKfunc { name } if name == "bpf_missing_kfunc");
+    }
+
+    #[test]
+    fn test_unresolved_extern_call() {
+        let prog = fake_func("prog", vec![mov_r0(0), call(-1), exit()]);
+        let functions = BTreeMap::from([((0, 0), prog.clone())]);
+        let relocations = kfunc_call_relocations();
+        let symbol_table = HashMap::from([(1, extern_sym(1, "bpf_kfunc"))]);
+        let text_sections = HashSet::from([0]);
+
+        let err = FunctionLinker::new(&functions, &relocations, &symbol_table, &text_sections)
+            .link(&prog)
+            .unwrap_err();
+        assert_matches!(err, RelocationError::UnresolvedExternCall { name } if name == "bpf_kfunc");
+    }
 }
@@ -11,9 +11,11 @@ pub struct Options {
 
 #[derive(Parser)]
 enum Command {
-    /// Generate Rust bindings to Kernel types using bpftool
+    /// Generate Rust bindings to Kernel types
     #[clap(name = "generate", action)]
     Generate {
+        /// BTF to generate the bindings from, either raw BTF or an object file with a `.BTF`
+        /// section
         #[clap(long, default_value = "/sys/kernel/btf/vmlinux", action)]
         btf: PathBuf,
         #[clap(long, conflicts_with = "btf", action)]
@@ -6,6 +6,8 @@ use std::{
     str,
 };
 
+use aya_obj::btf::{Btf, BtfError};
+use object::Endianness;
 use tempfile::tempdir;
 use thiserror::Error;
 
@@ -13,11 +15,8 @@ use crate::bindgen;
 
 #[derive(Error, Debug)]
 pub enum Error {
-    #[error("error executing bpftool")]
-    BpfTool(#[source] io::Error),
-
-    #[error("{stderr}\nbpftool failed with exit code {code}")]
-    BpfToolExit { code: i32, stderr: String },
+    #[error("error parsing BTF")]
+    Btf(#[source] BtfError),
 
     #[error("bindgen failed")]
     Bindgen(#[source] io::Error),
@@ -33,7 +32,9 @@ pub enum Error {
 }
 
 pub enum InputFile {
+    /// A raw BTF file, like `/sys/kernel/btf/vmlinux`, or an object file with a `.BTF` section.
     Btf(PathBuf),
+    /// A C header file.
     Header(PathBuf),
 }
 
@@ -90,21 +91,10 @@ pub fn generate<T: AsRef<str>>(
 }
 
 fn c_header_from_btf(path: &Path) -> Result<String, Error> {
-    let output = Command::new("bpftool")
-        .args(["btf", "dump", "file"])
-        .arg(path)
-        .args(["format", "c"])
-        .output()
-        .map_err(Error::BpfTool)?;
-
-    if !output.status.success() {
-        return Err(Error::BpfToolExit {
-            code: output.status.code().unwrap(),
-            stderr: str::from_utf8(&output.stderr).unwrap().to_owned(),
-        });
-    }
+    let btf = Btf::parse_file(path, Endianness::default()).map_err(Error::Btf)?;
 
-    Ok(str::from_utf8(&output.stdout).unwrap().to_owned())
+    // bindgen picks the types to generate bindings for
+    Ok(btf.to_c_header(None))
 }
 
 fn extract_ctypes_prefix(s: &[String]) -> (Vec<String>, Option<String>) {
@@ -146,7 +136,16 @@ fn combine_flags(s1: &[String], s2: &[String]) -> Vec<String> {
 
 #[cfg(test)]
 mod test {
-    use super::{combine_flags, extract_ctypes_prefix};
+    use std::io::Write as _;
+
+    use aya_obj::btf::{Btf, BtfEnum, BtfType, Enum};
+    use object::{
+        write::{self, StandardSection},
+        Architecture, BinaryFormat, Endianness, SectionKind,
+    };
+    use tempfile::NamedTempFile;
+
+    use super::{c_header_from_btf, combine_flags, extract_ctypes_prefix};
 
     fn to_vec(s: &str) -> Vec<String> {
         s.split(' ').map(|x| x.into()).collect()
@@ -208,4 +207,51 @@ mod test {
             "a -- b c d",
         );
     }
+
+    fn test_btf() -> Vec<u8> {
+        let mut btf = Btf::new();
+        let variants = vec![BtfEnum::new(btf.add_string("FOO_BAR"), 1)];
+        let name_offset = btf.add_string("foo");
+        btf.add_type(BtfType::Enum(Enum::new(name_offset, false, variants)));
+        btf.to_bytes()
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_c_header_from_raw_btf() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&test_btf()).unwrap();
+
+        let header = c_header_from_btf(file.path()).unwrap();
+        assert!(
+            header.contains("enum foo {\n\tFOO_BAR = 1,\n};"),
+            "{header}"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_c_header_from_object_file() {
+        let mut obj = write::Object::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::Little);
+        let section = obj.add_section(Vec::new(), b".BTF".to_vec(), SectionKind::Metadata);
+        obj.append_section_data(section, &test_btf(), 4);
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&obj.write().unwrap()).unwrap();
+
+        let header = c_header_from_btf(file.path()).unwrap();
+        assert!(
+            header.contains("enum foo {\n\tFOO_BAR = 1,\n};"),
+            "{header}"
+        );
+
+        let mut obj = write::Object::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::Little);
+        obj.section_id(StandardSection::Text);
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&obj.write().unwrap()).unwrap();
+
+        assert!(matches!(
+            c_header_from_btf(file.path()),
+            Err(super::Error::Btf(_))
+        ));
+    }
 }
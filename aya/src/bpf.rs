@@ -1,5 +1,4 @@
 use std::{
-    borrow::Cow,
     collections::{HashMap, HashSet},
     fs, io,
     os::fd::{AsFd as _, AsRawFd as _},
@@ -23,9 +22,10 @@ use crate::{
     maps::{Map, MapData, MapError},
     programs::{
         BtfTracePoint, CgroupDevice, CgroupSkb, CgroupSkbAttachType, CgroupSock, CgroupSockAddr,
-        CgroupSockopt, CgroupSysctl, Extension, FEntry, FExit, Iter, KProbe, LircMode2, Lsm,
-        PerfEvent, ProbeKind, Program, ProgramData, ProgramError, RawTracePoint, SchedClassifier,
-        SkLookup, SkMsg, SkSkb, SkSkbKind, SockOps, SocketFilter, TracePoint, UProbe, Xdp,
+        CgroupSockopt, CgroupSysctl, Extension, FEntry, FExit, FlowDissector, Iter, KProbe,
+        LircMode2, Lsm, PerfEvent, ProbeKind, Program, ProgramData, ProgramError, RawTracePoint,
+        SchedClassifier, SkLookup, SkMsg, SkSkb, SkSkbKind, SockOps, SocketFilter, TracePoint,
+        UProbe, Xdp,
     },
     sys::{
         bpf_load_btf, is_bpf_cookie_supported, is_bpf_global_data_supported,
@@ -58,6 +58,16 @@ pub use aya_obj::maps::{bpf_map_def, PinningType};
 
 pub(crate) static FEATURES: LazyLock<Features> = LazyLock::new(detect_features);
 
+// Parsing the kernel BTF is expensive (it's several megabytes), so it's done at most once per
+// process and shared by all the loaders.
+static VMLINUX_BTF: LazyLock<Option<Btf>> = LazyLock::new(|| match Btf::from_sys_fs() {
+    Ok(btf) => Some(btf),
+    Err(e) => {
+        debug!("failed to load BTF from /sys/kernel/btf/vmlinux: {e}");
+        None
+    }
+});
+
 fn detect_features() -> Features {
     let btf = if is_btf_supported() {
         Some(BtfFeatures::new(
@@ -97,17 +107,21 @@ pub fn features() -> &'static Features {
 ///
 /// Loading eBPF code involves a few steps, including loading maps and applying
 /// relocations. You can use `EbpfLoader` to customize some of the loading
+/// options. [`Ebpf::load`] and [`Ebpf::load_file`] use a loader with the default
 /// options.
 ///
+/// Maps are created when the object is loaded, but programs are only loaded into
+/// the kernel when their `load()` method is called, so programs that aren't used
+/// don't need to be filtered out.
+///
 /// # Examples
 ///
 /// ```no_run
-/// use aya::{EbpfLoader, Btf};
-/// use std::fs;
+/// use aya::{EbpfLoader, Btf, Endianness};
 ///
 /// let bpf = EbpfLoader::new()
-///     // load the BTF data from /sys/kernel/btf/vmlinux
-///     .btf(Btf::from_sys_fs().ok().as_ref())
+///     // load the BTF data from a custom location instead of /sys/kernel/btf/vmlinux
+///     .btf(Btf::parse_file("/custom_btf_file", Endianness::default()).ok().as_ref())
 ///     // load pinned maps from /sys/fs/bpf/my-program
 ///     .map_pin_path("/sys/fs/bpf/my-program")
 ///     // finally load the code
@@ -116,13 +130,18 @@ pub fn features() -> &'static Features {
 /// ```
 #[derive(Debug)]
 pub struct EbpfLoader<'a> {
-    btf: Option<Cow<'a, Btf>>,
+    // `None` until `EbpfLoader::btf` is called, meaning the kernel BTF. That is only parsed in
+    // `load` so that loaders given their own BTF don't pay for it.
+    btf: Option<Option<&'a Btf>>,
     map_pin_path: Option<PathBuf>,
     globals: HashMap<&'a str, (&'a [u8], bool)>,
     max_entries: HashMap<&'a str, u32>,
+    map_flags: HashMap<&'a str, u32>,
     extensions: HashSet<&'a str>,
     verifier_log_level: VerifierLogLevel,
     allow_unsupported_maps: bool,
+    freeze_rodata: bool,
+    bump_memlock_rlimit: bool,
 }
 
 /// Builder style API for advanced loading of eBPF programs.
@@ -158,17 +177,28 @@ impl<'a> EbpfLoader<'a> {
             map_pin_path: None,
             globals: HashMap::new(),
             max_entries: HashMap::new(),
+            map_flags: HashMap::new(),
             extensions: HashSet::new(),
             verifier_log_level: VerifierLogLevel::default(),
             allow_unsupported_maps: false,
+            freeze_rodata: true,
+            bump_memlock_rlimit: false,
         }
     }
 
     /// Sets the target [BTF](Btf) info.
     ///
-    /// The loader defaults to loading `BTF` info using [Btf::from_sys_fs].
-    /// Use this method if you want to load `BTF` from a custom location or
+    /// The loader defaults to loading `BTF` info using [Btf::from_sys_fs]. The
+    /// kernel `BTF` is only parsed once per process and then shared by all
+    /// loaders. Use this method if you want to load `BTF` from a custom location or
     /// pass `None` to disable `BTF` relocations entirely.
+    ///
+    /// Kernels that don't expose `/sys/kernel/btf/vmlinux` can still run CO-RE programs with
+    /// `BTF` from another source, such as [BTFhub](https://github.com/aquasecurity/btfhub) or
+    /// the kernel's `vmlinux` image, see [Btf::parse_file]. The same `BTF` is used to relocate
+    /// calls to kernel functions, and should be passed to the `load` methods of programs which
+    /// attach by `BTF` id, like [FEntry::load](crate::programs::FEntry::load).
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -182,7 +212,7 @@ impl<'a> EbpfLoader<'a> {
     /// # Ok::<(), aya::EbpfError>(())
     /// ```
     pub fn btf(&mut self, btf: Option<&'a Btf>) -> &mut Self {
-        self.btf = btf.map(Cow::Borrowed);
+        self.btf = Some(btf);
         self
     }
 
@@ -215,6 +245,10 @@ impl<'a> EbpfLoader<'a> {
     /// Pinned maps will be loaded from `path/MAP_NAME`.
     /// The caller is responsible for ensuring the directory exists.
     ///
+    /// Maps that aren't pinned yet are created and pinned. Maps that are already pinned are
+    /// reused, and loading fails with [`MapError::IncompatiblePinnedMap`] if their type, key
+    /// size, value size, max entries or flags don't match the map definition.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -235,6 +269,10 @@ impl<'a> EbpfLoader<'a> {
     ///
     /// If the `must_exist` argument is `true`, [`EbpfLoader::load`] will fail with [`ParseError::SymbolNotFound`] if the loaded object code does not contain the variable.
     ///
+    /// The size of `value` must match the size of the variable as recorded in the ELF symbol
+    /// table, or in BTF when the symbol table doesn't include it. Otherwise [`EbpfLoader::load`]
+    /// fails with [`ParseError::InvalidGlobalData`].
+    ///
     /// From Rust eBPF, a global variable can be defined as follows:
     ///
     /// ```no_run
@@ -285,6 +323,11 @@ impl<'a> EbpfLoader<'a> {
     /// Overwrite the value of max_entries of the map that matches
     /// the provided name before the map is created.
     ///
+    /// For [`RingBuf`](crate::maps::RingBuf) maps the size is in bytes, and is rounded up to a
+    /// power-of-two multiple of the page size as the kernel requires. Storage maps (sk, inode,
+    /// task and cgroup storage) can't be resized: a non-zero value makes loading fail with
+    /// [`MapError::InvalidMaxEntries`].
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -301,6 +344,30 @@ impl<'a> EbpfLoader<'a> {
         self
     }
 
+    /// Set the map_flags for specified map.
+    ///
+    /// Overwrite the flags of the map that matches the provided name before the map is
+    /// created, for example to add `BPF_F_NO_PREALLOC` to a hash map or `BPF_F_MMAPABLE` to an
+    /// array. The flags replace the ones in the map definition rather than being added to them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use aya::EbpfLoader;
+    ///
+    /// const BPF_F_NO_PREALLOC: u32 = 1;
+    ///
+    /// let bpf = EbpfLoader::new()
+    ///     .set_map_flags("map", BPF_F_NO_PREALLOC)
+    ///     .load_file("file.o")?;
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    ///
+    pub fn set_map_flags(&mut self, name: &'a str, flags: u32) -> &mut Self {
+        self.map_flags.insert(name, flags);
+        self
+    }
+
     /// Treat the provided program as an [`Extension`]
     ///
     /// When attempting to load the program with the provided `name`
@@ -341,6 +408,51 @@ impl<'a> EbpfLoader<'a> {
         self
     }
 
+    /// Sets whether `.rodata` maps are frozen after being populated.
+    ///
+    /// By default the loader [freezes](MapData::freeze) `.rodata` maps once their initial values
+    /// have been written and before programs are loaded, which lets the verifier treat their
+    /// contents as constants. Pass `false` to keep them writable from user space.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use aya::EbpfLoader;
+    ///
+    /// let bpf = EbpfLoader::new()
+    ///     .freeze_rodata(false)
+    ///     .load_file("file.o")?;
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    ///
+    pub fn freeze_rodata(&mut self, freeze: bool) -> &mut Self {
+        self.freeze_rodata = freeze;
+        self
+    }
+
+    /// Sets whether the loader raises `RLIMIT_MEMLOCK` before creating maps and programs.
+    ///
+    /// When enabled, [`util::bump_memlock_rlimit`](crate::util::bump_memlock_rlimit) is called
+    /// at the start of loading. Failing to raise the limit, for example because the process
+    /// lacks `CAP_SYS_RESOURCE`, is logged as a warning and doesn't fail the load. Disabled by
+    /// default.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use aya::EbpfLoader;
+    ///
+    /// let bpf = EbpfLoader::new()
+    ///     .bump_memlock_rlimit(true)
+    ///     .load_file("file.o")?;
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    ///
+    pub fn bump_memlock_rlimit(&mut self, bump: bool) -> &mut Self {
+        self.bump_memlock_rlimit = bump;
+        self
+    }
+
     /// Loads eBPF bytecode from a file.
     ///
     /// # Examples
@@ -361,9 +473,10 @@ impl<'a> EbpfLoader<'a> {
 
     /// Loads eBPF bytecode from a buffer.
     ///
-    /// The buffer needs to be 4-bytes aligned. If you are bundling the bytecode statically
-    /// into your binary, it is recommended that you do so using
-    /// [`include_bytes_aligned`](crate::include_bytes_aligned).
+    /// The buffer doesn't need to be aligned, but misaligned buffers are copied before being
+    /// parsed. If you are bundling the bytecode statically into your binary, it is
+    /// recommended that you do so using [`include_bytes_aligned`](crate::include_bytes_aligned)
+    /// to avoid the copy.
     ///
     /// # Examples
     ///
@@ -375,16 +488,52 @@ impl<'a> EbpfLoader<'a> {
     /// let bpf = EbpfLoader::new().load(&data)?;
     /// # Ok::<(), aya::EbpfError>(())
     /// ```
+    ///
+    /// # Loading programs
+    ///
+    /// Only the maps are created in the kernel at this point, since they are shared by all the
+    /// programs of the object. Programs are loaded one by one when calling their `load` method,
+    /// so a program rejected by the verifier doesn't prevent the others from being loaded and
+    /// attached. When some programs are optional, their errors can be collected instead:
+    ///
+    /// ```no_run
+    /// use aya::{programs::{Program, ProgramError}, EbpfLoader};
+    ///
+    /// let mut bpf = EbpfLoader::new().load_file("file.o")?;
+    /// let mut errors: Vec<(String, ProgramError)> = Vec::new();
+    /// for (name, program) in bpf.programs_mut() {
+    ///     let result = match program {
+    ///         Program::KProbe(program) => program.load(),
+    ///         Program::Xdp(program) => program.load(),
+    ///         _ => continue,
+    ///     };
+    ///     if let Err(err) = result {
+    ///         errors.push((name.to_owned(), err));
+    ///     }
+    /// }
+    /// for (name, err) in &errors {
+    ///     eprintln!("program `{name}` failed to load: {err}");
+    /// }
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
     pub fn load(&mut self, data: &[u8]) -> Result<Ebpf, EbpfError> {
         let Self {
             btf,
             map_pin_path,
             globals,
             max_entries,
+            map_flags,
             extensions,
             verifier_log_level,
             allow_unsupported_maps,
+            freeze_rodata,
+            bump_memlock_rlimit,
         } = self;
+        if *bump_memlock_rlimit {
+            if let Err(err) = crate::util::bump_memlock_rlimit() {
+                warn!("failed to raise RLIMIT_MEMLOCK: {err}");
+            }
+        }
         let mut obj = Object::parse(data)?;
         obj.patch_map_data(globals.clone())?;
 
@@ -430,7 +579,8 @@ impl<'a> EbpfLoader<'a> {
                                 | ProgramSection::RawTracePoint
                                 | ProgramSection::SkLookup
                                 | ProgramSection::CgroupSock { attach_type: _ }
-                                | ProgramSection::CgroupDevice => {}
+                                | ProgramSection::CgroupDevice
+                                | ProgramSection::FlowDissector => {}
                             }
                         }
 
@@ -446,8 +596,10 @@ impl<'a> EbpfLoader<'a> {
             None
         };
 
-        if let Some(btf) = &btf {
+        let btf = btf.unwrap_or_else(|| VMLINUX_BTF.as_ref());
+        if let Some(btf) = btf {
             obj.relocate_btf(btf)?;
+            obj.relocate_kfuncs(btf)?;
         }
         let mut maps = HashMap::new();
         for (name, mut obj) in obj.maps.drain() {
@@ -464,6 +616,7 @@ impl<'a> EbpfLoader<'a> {
             };
             let map_type: bpf_map_type = obj.map_type().try_into().map_err(MapError::from)?;
             if let Some(max_entries) = max_entries_override(
+                &name,
                 map_type,
                 max_entries.get(name.as_str()).copied(),
                 || obj.max_entries(),
@@ -472,6 +625,9 @@ impl<'a> EbpfLoader<'a> {
             )? {
                 obj.set_max_entries(max_entries)
             }
+            if let Some(flags) = map_flags.get(name.as_str()) {
+                obj.set_map_flags(*flags)
+            }
             match obj.map_type().try_into() {
                 Ok(BPF_MAP_TYPE_CPUMAP) => {
                     obj.set_value_size(if FEATURES.cpumap_prog_id() { 8 } else { 4 })
@@ -494,7 +650,7 @@ impl<'a> EbpfLoader<'a> {
                     MapData::create_pinned_by_name(path, obj, &name, btf_fd)?
                 }
             };
-            map.finalize()?;
+            map.finalize(*freeze_rodata)?;
             maps.insert(name, map);
         }
 
@@ -687,6 +843,9 @@ impl<'a> EbpfLoader<'a> {
                             }
                             Program::Iter(Iter { data })
                         }
+                        ProgramSection::FlowDissector => Program::FlowDissector(FlowDissector {
+                            data: ProgramData::new(prog_name, obj, btf_fd, *verifier_log_level),
+                        }),
                     }
                 };
                 (name, program)
@@ -709,9 +868,11 @@ fn parse_map(
     let map_type = bpf_map_type::try_from(map.obj().map_type()).map_err(MapError::from)?;
     let map = match map_type {
         BPF_MAP_TYPE_ARRAY => Map::Array(map),
+        BPF_MAP_TYPE_ARRAY_OF_MAPS => Map::ArrayOfMaps(map),
         BPF_MAP_TYPE_PERCPU_ARRAY => Map::PerCpuArray(map),
         BPF_MAP_TYPE_PROG_ARRAY => Map::ProgramArray(map),
         BPF_MAP_TYPE_HASH => Map::HashMap(map),
+        BPF_MAP_TYPE_HASH_OF_MAPS => Map::HashOfMaps(map),
         BPF_MAP_TYPE_LRU_HASH => Map::LruHashMap(map),
         BPF_MAP_TYPE_PERCPU_HASH => Map::PerCpuHashMap(map),
         BPF_MAP_TYPE_LRU_PERCPU_HASH => Map::PerCpuLruHashMap(map),
@@ -745,13 +906,39 @@ fn parse_map(
 
 /// Computes the value which should be used to override the max_entries value of the map
 /// based on the user-provided override and the rules for that map type.
+///
+/// User overrides that the kernel would reject are reported as
+/// [`MapError::InvalidMaxEntries`].
 fn max_entries_override(
+    name: &str,
     map_type: bpf_map_type,
     user_override: Option<u32>,
     current_value: impl Fn() -> u32,
     num_cpus: impl Fn() -> Result<u32, EbpfError>,
     page_size: impl Fn() -> u32,
 ) -> Result<Option<u32>, EbpfError> {
+    if let Some(max_entries) = user_override {
+        let reason = match map_type {
+            BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED
+            | BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE_DEPRECATED
+            | BPF_MAP_TYPE_SK_STORAGE
+            | BPF_MAP_TYPE_INODE_STORAGE
+            | BPF_MAP_TYPE_TASK_STORAGE
+            | BPF_MAP_TYPE_CGRP_STORAGE
+                if max_entries != 0 =>
+            {
+                Some("storage maps must have max_entries set to 0")
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            return Err(EbpfError::MapError(MapError::InvalidMaxEntries {
+                name: name.to_owned(),
+                max_entries,
+                reason,
+            }));
+        }
+    }
     let max_entries = || user_override.unwrap_or_else(&current_value);
     Ok(match map_type {
         BPF_MAP_TYPE_PERF_EVENT_ARRAY if max_entries() == 0 => Some(num_cpus()?),
@@ -817,6 +1004,12 @@ mod tests {
             (BPF_MAP_TYPE_RINGBUF, Some(1), 1, Some(PAGE_SIZE)),
             (BPF_MAP_TYPE_RINGBUF, None, 1, Some(PAGE_SIZE)),
             (BPF_MAP_TYPE_RINGBUF, None, PAGE_SIZE, None),
+            (
+                BPF_MAP_TYPE_RINGBUF,
+                Some(3 * PAGE_SIZE),
+                1,
+                Some(4 * PAGE_SIZE),
+            ),
             (BPF_MAP_TYPE_PERF_EVENT_ARRAY, None, 1, None),
             (BPF_MAP_TYPE_PERF_EVENT_ARRAY, Some(42), 1, Some(42)),
             (BPF_MAP_TYPE_PERF_EVENT_ARRAY, Some(0), 1, Some(NUM_CPUS)),
@@ -830,6 +1023,7 @@ mod tests {
             assert_eq!(
                 exp,
                 max_entries_override(
+                    "foo",
                     map_type,
                     user_override,
                     || { current_value },
@@ -840,6 +1034,101 @@ mod tests {
             )
         })
     }
+
+    #[test]
+    fn test_max_entries_override_invalid() {
+        use assert_matches::assert_matches;
+
+        use super::max_entries_override;
+        use crate::{maps::MapError, EbpfError};
+
+        [
+            (BPF_MAP_TYPE_SK_STORAGE, 1),
+            (BPF_MAP_TYPE_CGROUP_STORAGE_DEPRECATED, 1),
+        ]
+        .into_iter()
+        .for_each(|(map_type, user_override)| {
+            assert_matches!(
+                max_entries_override(
+                    "foo",
+                    map_type,
+                    Some(user_override),
+                    || 1,
+                    || Ok(NUM_CPUS),
+                    || PAGE_SIZE
+                ),
+                Err(EbpfError::MapError(MapError::InvalidMaxEntries {
+                    name,
+                    max_entries,
+                    reason: _,
+                })) => {
+                    assert_eq!(name, "foo");
+                    assert_eq!(max_entries, user_override);
+                }
+            )
+        })
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_set_map_flags() {
+        use std::cell::Cell;
+
+        use aya_obj::generated::{bpf_cmd, BPF_F_NO_PREALLOC};
+        use object::{
+            write::{Object as WriteObject, Symbol, SymbolSection},
+            Architecture, BinaryFormat, Endianness, SectionKind, SymbolFlags, SymbolKind,
+            SymbolScope,
+        };
+
+        use super::EbpfLoader;
+        use crate::sys::{override_syscall, Syscall};
+
+        thread_local! {
+            static MAP_FLAGS: Cell<Option<u32>> = const { Cell::new(None) };
+        }
+
+        let mut obj = WriteObject::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::Little);
+        let section = obj.add_section(vec![], b"maps".to_vec(), SectionKind::Data);
+        // map_type, key_size, value_size, max_entries and map_flags of a legacy bpf_map_def.
+        let def: Vec<u8> = [BPF_MAP_TYPE_HASH as u32, 4, 8, 1, 0]
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect();
+        let offset = obj.append_section_data(section, &def, 4);
+        obj.add_symbol(Symbol {
+            name: b"foo".to_vec(),
+            value: offset,
+            size: def.len() as u64,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: SymbolSection::Section(section),
+            flags: SymbolFlags::None,
+        });
+        let data = obj.write().unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                if u.map_type == BPF_MAP_TYPE_HASH as u32 {
+                    MAP_FLAGS.set(Some(u.map_flags));
+                }
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, std::io::Error::from_raw_os_error(libc::EINVAL))),
+        });
+
+        EbpfLoader::new()
+            .btf(None)
+            .set_map_flags("foo", BPF_F_NO_PREALLOC)
+            .load(&data)
+            .unwrap();
+        assert_eq!(MAP_FLAGS.get(), Some(BPF_F_NO_PREALLOC));
+    }
 }
 
 impl Default for EbpfLoader<'_> {
@@ -877,9 +1166,7 @@ impl Ebpf {
     /// # Ok::<(), aya::EbpfError>(())
     /// ```
     pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self, EbpfError> {
-        EbpfLoader::new()
-            .btf(Btf::from_sys_fs().ok().as_ref())
-            .load_file(path)
+        EbpfLoader::new().load_file(path)
     }
 
     /// Loads eBPF bytecode from a buffer.
@@ -888,9 +1175,10 @@ impl Ebpf {
     /// [maps](crate::maps) defined in it. If the kernel supports [BTF](Btf)
     /// debug info, it is automatically loaded from `/sys/kernel/btf/vmlinux`.
     ///
-    /// The buffer needs to be 4-bytes aligned. If you are bundling the bytecode statically
-    /// into your binary, it is recommended that you do so using
-    /// [`include_bytes_aligned`](crate::include_bytes_aligned).
+    /// The buffer doesn't need to be aligned, but misaligned buffers are copied before being
+    /// parsed. If you are bundling the bytecode statically into your binary, it is
+    /// recommended that you do so using [`include_bytes_aligned`](crate::include_bytes_aligned)
+    /// to avoid the copy.
     ///
     /// For more loading options, see [EbpfLoader].
     ///
@@ -906,9 +1194,7 @@ impl Ebpf {
     /// # Ok::<(), aya::EbpfError>(())
     /// ```
     pub fn load(data: &[u8]) -> Result<Self, EbpfError> {
-        EbpfLoader::new()
-            .btf(Btf::from_sys_fs().ok().as_ref())
-            .load(data)
+        EbpfLoader::new().load(data)
     }
 
     /// Returns a reference to the map with the given name.
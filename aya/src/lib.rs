@@ -24,7 +24,8 @@
 //! * Support for function call relocation and global data maps, which
 //!   allows eBPF programs to make **function calls** and use **global variables
 //!   and initializers**.
-//! * **Async support** with both [tokio] and [async-std].
+//! * **Async support** with both [tokio] and [async-std], enabled with the `async_tokio` and
+//!   `async_std` cargo features respectively. If both features are enabled, tokio is used.
 //! * Easy to deploy and fast to build: aya doesn't require a kernel build or
 //!   compiled headers, and not even a C toolchain; a release build completes in a matter
 //!   of seconds.
@@ -77,9 +78,14 @@
     allow(unused_crate_dependencies)
 )]
 
+// Used by the `AsyncRingBuf` doctest.
+#[cfg(test)]
+use futures as _;
+
 mod bpf;
 pub mod maps;
 pub mod pin;
+pub mod probe;
 pub mod programs;
 pub mod sys;
 pub mod util;
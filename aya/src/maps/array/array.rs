@@ -1,12 +1,17 @@
 use std::{
     borrow::{Borrow, BorrowMut},
     marker::PhantomData,
+    mem,
     os::fd::AsFd as _,
 };
 
+use aya_obj::generated::{BPF_F_MMAPABLE, BPF_F_RDONLY, BPF_F_RDONLY_PROG};
+use libc::{EPERM, MAP_SHARED, PROT_READ, PROT_WRITE};
+
 use crate::{
-    maps::{check_bounds, check_kv_size, IterableMap, MapData, MapError},
+    maps::{check_bounds, check_kv_size, IterableMap, MMap, MapData, MapError},
     sys::{bpf_map_lookup_elem, bpf_map_update_elem, SyscallError},
+    util::page_size,
     Pod,
 };
 
@@ -77,6 +82,67 @@ impl<T: Borrow<MapData>, V: Pod> Array<T, V> {
     pub fn iter(&self) -> impl Iterator<Item = Result<V, MapError>> + '_ {
         (0..self.len()).map(move |i| self.get(&i, 0))
     }
+
+    /// Maps the values of the array into memory, so they can be read and written without
+    /// syscalls. See [`MmapArray`].
+    ///
+    /// The map must have been created with the `BPF_F_MMAPABLE` flag, either in its definition
+    /// or with [`EbpfLoader::set_map_flags`](crate::EbpfLoader::set_map_flags).
+    ///
+    /// Maps created with `BPF_F_RDONLY` or `BPF_F_RDONLY_PROG`, and maps that have been
+    /// [frozen](MapData::freeze), are mapped read-only.
+    ///
+    /// Introduced in kernel v5.5.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::NotMmapable`] if the map wasn't created with `BPF_F_MMAPABLE`,
+    /// [`MapError::SyscallError`] if `mmap` fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use aya::maps::Array;
+    ///
+    /// let array: Array<_, u64> = Array::try_from(bpf.map("COUNTERS").unwrap())?;
+    /// let counters = array.mmap()?;
+    /// let total: u64 = counters.iter().sum();
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    pub fn mmap(&self) -> Result<MmapArray<V>, MapError> {
+        let data = self.inner.borrow();
+        let map_flags = data.obj.map_flags();
+        if map_flags & BPF_F_MMAPABLE == 0 {
+            return Err(MapError::NotMmapable);
+        }
+        let len = data.obj.max_entries();
+        let size = len as usize * value_stride::<V>();
+        let page_size = page_size();
+        let size = size.div_ceil(page_size) * page_size;
+        let fd = data.fd().as_fd();
+        let read_only = || MMap::new(fd, size, PROT_READ, MAP_SHARED, 0);
+        let (mmap, writable) = if map_flags & (BPF_F_RDONLY | BPF_F_RDONLY_PROG) != 0 {
+            (read_only()?, false)
+        } else {
+            // The kernel refuses writable mappings of frozen maps with EPERM.
+            match MMap::new(fd, size, PROT_READ | PROT_WRITE, MAP_SHARED, 0) {
+                Ok(mmap) => (mmap, true),
+                Err(MapError::SyscallError(SyscallError { io_error, .. }))
+                    if io_error.raw_os_error() == Some(EPERM) =>
+                {
+                    (read_only()?, false)
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(MmapArray {
+            mmap,
+            len,
+            writable,
+            _v: PhantomData,
+        })
+    }
 }
 
 impl<T: BorrowMut<MapData>, V: Pod> Array<T, V> {
@@ -109,3 +175,177 @@ impl<T: Borrow<MapData>, V: Pod> IterableMap<u32, V> for Array<T, V> {
         self.get(index, 0)
     }
 }
+
+/// The values of an [`Array`] mapped into memory, created with [`Array::mmap`].
+///
+/// Reads and writes access the memory of the map directly, so they don't need a syscall, and
+/// changes are seen by eBPF programs right away. Since eBPF programs can modify the values at
+/// any time, values are copied in and out of the mapping with volatile accesses. These aren't
+/// atomic: a value that is larger than a machine word and updated concurrently can be read
+/// half-written.
+///
+/// The mapping stays valid after the [`Array`] it was created from is dropped.
+pub struct MmapArray<V: Pod> {
+    mmap: MMap,
+    len: u32,
+    writable: bool,
+    _v: PhantomData<V>,
+}
+
+impl<V: Pod> MmapArray<V> {
+    /// Returns the number of elements in the array.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns true if the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if values can be written with [`MmapArray::set`].
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    /// Returns a copy of the value at the given index, or `None` if `index` is out of bounds.
+    pub fn get(&self, index: u32) -> Option<V> {
+        let ptr = self.value_ptr(index)?;
+        // SAFETY: the pointer is in bounds and properly aligned, see `value_ptr`. Any bit pattern
+        // is a valid `Pod`.
+        Some(unsafe { ptr.read_volatile() })
+    }
+
+    /// Sets the value at the given index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds,
+    /// [`MapError::ReadOnlyMapping`] if the array is mapped read-only.
+    pub fn set(&mut self, index: u32, value: impl Borrow<V>) -> Result<(), MapError> {
+        let ptr = self.value_ptr(index).ok_or(MapError::OutOfBounds {
+            index,
+            max_entries: self.len,
+        })?;
+        if !self.writable {
+            return Err(MapError::ReadOnlyMapping);
+        }
+        // SAFETY: see `get`, and the mapping is writable.
+        unsafe { ptr.write_volatile(*value.borrow()) };
+        Ok(())
+    }
+
+    /// An iterator over copies of the values of the array.
+    pub fn iter(&self) -> impl Iterator<Item = V> + '_ {
+        (0..self.len).filter_map(move |i| self.get(i))
+    }
+
+    // The mapping covers `len` values and is page aligned, and values are 8 byte aligned within
+    // it, so the returned pointer is valid and properly aligned for `V`.
+    fn value_ptr(&self, index: u32) -> Option<*mut V> {
+        (index < self.len).then(|| {
+            let offset = index as usize * value_stride::<V>();
+            // SAFETY: the offset is within the mapping.
+            unsafe { self.mmap.ptr.as_ptr().cast::<u8>().add(offset).cast() }
+        })
+    }
+}
+
+// The kernel stores array values 8 byte aligned.
+fn value_stride<V>() -> usize {
+    mem::size_of::<V>().next_multiple_of(8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::bpf_map_type::BPF_MAP_TYPE_ARRAY;
+    use libc::{MAP_ANONYMOUS, MAP_FAILED, MAP_PRIVATE};
+
+    use super::*;
+    use crate::{
+        maps::test_utils::{self, new_map},
+        sys::TEST_MMAP_RET,
+    };
+
+    fn new_obj_map(map_flags: u32) -> aya_obj::Map {
+        let mut obj = test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_ARRAY);
+        obj.set_map_flags(map_flags);
+        obj
+    }
+
+    #[test]
+    fn test_mmap_not_mmapable() {
+        let map = new_map(new_obj_map(0));
+        let array = Array::<_, u32>::new(&map).unwrap();
+        assert_matches!(array.mmap().err(), Some(MapError::NotMmapable));
+    }
+
+    #[test]
+    fn test_mmap() {
+        let map = new_map(new_obj_map(BPF_F_MMAPABLE));
+        let array = Array::<_, u32>::new(&map).unwrap();
+
+        // 1024 u32 values, each padded to 8 bytes. The mapping is unmapped when the MmapArray is
+        // dropped.
+        let len = 1024 * 8;
+        let buf = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ | PROT_WRITE,
+                MAP_PRIVATE | MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(buf, MAP_FAILED);
+        unsafe { buf.cast::<u8>().add(8).cast::<u32>().write(42) };
+        TEST_MMAP_RET.with(|ret| *ret.borrow_mut() = buf);
+
+        let mut values = array.mmap().unwrap();
+        assert!(values.is_writable());
+        assert_eq!(values.len(), 1024);
+        assert_eq!(values.get(0), Some(0));
+        assert_eq!(values.get(1), Some(42));
+        assert_eq!(values.get(1024), None);
+
+        values.set(2, 7).unwrap();
+        assert_eq!(unsafe { buf.cast::<u8>().add(16).cast::<u32>().read() }, 7);
+        assert_eq!(values.iter().sum::<u32>(), 49);
+        assert_matches!(
+            values.set(1024, 7),
+            Err(MapError::OutOfBounds {
+                index: 1024,
+                max_entries: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_mmap_read_only() {
+        let map = new_map(new_obj_map(BPF_F_MMAPABLE | BPF_F_RDONLY_PROG));
+        let array = Array::<_, u32>::new(&map).unwrap();
+
+        let len = 1024 * 8;
+        let buf = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                PROT_READ,
+                MAP_PRIVATE | MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(buf, MAP_FAILED);
+        TEST_MMAP_RET.with(|ret| *ret.borrow_mut() = buf);
+
+        let mut values = array.mmap().unwrap();
+        assert!(!values.is_writable());
+        assert_eq!(values.get(1), Some(0));
+        assert_matches!(values.set(1, 7), Err(MapError::ReadOnlyMapping));
+    }
+}
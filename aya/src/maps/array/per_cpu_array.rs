@@ -15,6 +15,9 @@ use crate::{
 /// The size of the array is defined on the eBPF side using the `bpf_map_def::max_entries` field.
 /// All the entries are zero-initialized when the map is created.
 ///
+/// Unlike [`Array`](crate::maps::Array), per-CPU arrays can't be memory mapped: the kernel
+/// doesn't support `BPF_F_MMAPABLE` for them.
+///
 /// # Minimum kernel version
 ///
 /// The minimum kernel version required to use this feature is 4.6.
@@ -17,6 +17,16 @@ use crate::{
 /// prog_array, index)`. You can use [`ProgramArray`] to configure which
 /// programs correspond to which jump indexes.
 ///
+/// The kernel only accepts programs of the same type as the programs calling
+/// `bpf_tail_call` on the array, e.g. [`CgroupSkb`](crate::programs::CgroupSkb)
+/// programs can only jump to other `CgroupSkb` programs. The type is fixed by
+/// the first program that uses the array, and [`ProgramArray::set`] fails with
+/// `EINVAL` for programs of any other type.
+///
+/// Entries are removed by the kernel once all the user space file descriptors
+/// of the map are closed, so the [`ProgramArray`] must be kept alive for the
+/// tail calls to keep working.
+///
 /// # Minimum kernel version
 ///
 /// The minimum kernel version required to use this feature is 4.2.
@@ -70,7 +80,8 @@ impl<T: BorrowMut<MapData>> ProgramArray<T> {
     /// Sets the target program file descriptor for the given index in the jump table.
     ///
     /// When an eBPF program calls `bpf_tail_call(ctx, prog_array, index)`, control
-    /// flow will jump to `program`.
+    /// flow will jump to `program`. `program` must be loaded and its type must
+    /// match the type of the programs using the array.
     pub fn set(&mut self, index: u32, program: &ProgramFd, flags: u64) -> Result<(), MapError> {
         let data = self.inner.borrow_mut();
         check_bounds(data, index)?;
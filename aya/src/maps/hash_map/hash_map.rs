@@ -1,17 +1,28 @@
 use std::{
     borrow::{Borrow, BorrowMut},
+    io,
     marker::PhantomData,
     os::fd::AsFd as _,
 };
 
+use libc::{EINVAL, ENOENT, ENOSPC};
+
 use crate::{
     maps::{check_kv_size, hash_map, IterableMap, MapData, MapError, MapIter, MapKeys},
-    sys::{bpf_map_lookup_elem, SyscallError},
+    sys::{
+        bpf_map_delete_batch, bpf_map_delete_elem, bpf_map_get_next_key,
+        bpf_map_lookup_and_delete_batch, bpf_map_lookup_and_delete_elem, bpf_map_lookup_batch,
+        bpf_map_lookup_elem, bpf_map_update_batch, is_map_batch_supported, SysResult, SyscallError,
+    },
     Pod,
 };
 
 /// A hash map that can be shared between eBPF programs and user space.
 ///
+/// This type can be used with eBPF maps of type `BPF_MAP_TYPE_HASH` and
+/// `BPF_MAP_TYPE_LRU_HASH`. LRU maps evict the least recently used entries when they're full,
+/// otherwise they behave the same.
+///
 /// # Minimum kernel version
 ///
 /// The minimum kernel version required to use this feature is 3.19.
@@ -63,6 +74,9 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
 
     /// An iterator visiting all key-value pairs in arbitrary order. The
     /// iterator item type is `Result<(K, V), MapError>`.
+    ///
+    /// Keys can be skipped or returned more than once if the map is modified
+    /// while iterating, see [`MapKeys`].
     pub fn iter(&self) -> MapIter<'_, K, V, Self> {
         MapIter::new(self)
     }
@@ -72,6 +86,79 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
     pub fn keys(&self) -> MapKeys<'_, K> {
         MapKeys::new(self.inner.borrow())
     }
+
+    /// Returns up to `count` key-value pairs, starting from the position stored in `cursor`.
+    ///
+    /// The cursor is advanced past the returned elements. Once the end of the map is reached,
+    /// [`BatchCursor::is_done`] returns `true`.
+    ///
+    /// This uses `BPF_MAP_LOOKUP_BATCH`, falling back to looking up one element at a time on
+    /// kernels that don't support batch operations (older than 5.6). The kernel doesn't split
+    /// hash buckets across batches, so more than `count` pairs are returned when the next bucket
+    /// holds more than `count` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use aya::maps::{hash_map::BatchCursor, HashMap};
+    ///
+    /// let map = HashMap::<_, u32, u64>::try_from(bpf.map("COUNTERS").unwrap())?;
+    ///
+    /// let mut cursor = BatchCursor::default();
+    /// while !cursor.is_done() {
+    ///     for (key, value) in map.get_batch(64, &mut cursor, 0)? {
+    ///         println!("{key}: {value}");
+    ///     }
+    /// }
+    /// # Ok::<(), aya::EbpfError>(())
+    /// ```
+    pub fn get_batch(
+        &self,
+        count: u32,
+        cursor: &mut BatchCursor<K>,
+        flags: u64,
+    ) -> Result<Vec<(K, V)>, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        let mut key = match cursor.state {
+            BatchState::Done => return Ok(Vec::new()),
+            BatchState::Start | BatchState::Batch(_) => {
+                match batch("bpf_map_lookup_batch", cursor, count, |in_batch, count| {
+                    bpf_map_lookup_batch(fd, in_batch, count, flags)
+                })? {
+                    Some(items) => return Ok(items),
+                    None => None,
+                }
+            }
+            BatchState::Fallback(key) => key,
+        };
+
+        let mut items = Vec::new();
+        while items.len() < count as usize {
+            let next =
+                bpf_map_get_next_key(fd, key.as_ref()).map_err(|(_, io_error)| SyscallError {
+                    call: "bpf_map_get_next_key",
+                    io_error,
+                })?;
+            let Some(next) = next else {
+                cursor.state = BatchState::Done;
+                return Ok(items);
+            };
+            let value =
+                bpf_map_lookup_elem(fd, &next, flags).map_err(|(_, io_error)| SyscallError {
+                    call: "bpf_map_lookup_elem",
+                    io_error,
+                })?;
+            // the key may have been deleted between get_next_key and lookup
+            if let Some(value) = value {
+                items.push((next, value));
+            }
+            key = Some(next);
+        }
+        cursor.state = BatchState::Fallback(key);
+
+        Ok(items)
+    }
 }
 
 impl<T: BorrowMut<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
@@ -89,6 +176,225 @@ impl<T: BorrowMut<MapData>, K: Pod, V: Pod> HashMap<T, K, V> {
     pub fn remove(&mut self, key: &K) -> Result<(), MapError> {
         hash_map::remove(self.inner.borrow_mut(), key)
     }
+
+    /// Removes a key from the map and returns its value.
+    ///
+    /// The lookup and the removal are atomic, so a value updated concurrently by eBPF programs
+    /// can't be lost in between. For hash maps this uses kernel v5.14 features: older kernels
+    /// fail with `ENOTSUPP` (errno 524).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::KeyNotFound`] if the key doesn't exist in the map,
+    /// [`MapError::SyscallError`] if `bpf_map_lookup_and_delete_elem` fails.
+    #[doc(alias = "BPF_MAP_LOOKUP_AND_DELETE_ELEM")]
+    pub fn get_and_remove(&mut self, key: &K, flags: u64) -> Result<V, MapError> {
+        let fd = self.inner.borrow_mut().fd().as_fd();
+        let value =
+            bpf_map_lookup_and_delete_elem(fd, Some(key), flags).map_err(|(_, io_error)| {
+                SyscallError {
+                    call: "bpf_map_lookup_and_delete_elem",
+                    io_error,
+                }
+            })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// Removes and returns up to `count` key-value pairs, starting from the position stored in
+    /// `cursor`.
+    ///
+    /// Calling this repeatedly until [`BatchCursor::is_done`] returns `true` drains the map. With
+    /// `BPF_MAP_LOOKUP_AND_DELETE_BATCH` each batch is looked up and deleted atomically. On
+    /// kernels that don't support batch operations (older than 5.6), elements are looked up and
+    /// deleted one at a time instead, so values updated concurrently by eBPF programs between
+    /// the two operations may be lost, and keys deleted concurrently are skipped. As with
+    /// [`HashMap::get_batch`], more than `count` pairs are returned when the next hash bucket
+    /// holds more than `count` elements.
+    pub fn get_and_remove_batch(
+        &mut self,
+        count: u32,
+        cursor: &mut BatchCursor<K>,
+        flags: u64,
+    ) -> Result<Vec<(K, V)>, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        match cursor.state {
+            BatchState::Done => return Ok(Vec::new()),
+            BatchState::Start | BatchState::Batch(_) => {
+                if let Some(items) = batch(
+                    "bpf_map_lookup_and_delete_batch",
+                    cursor,
+                    count,
+                    |in_batch, count| bpf_map_lookup_and_delete_batch(fd, in_batch, count, flags),
+                )? {
+                    return Ok(items);
+                }
+            }
+            BatchState::Fallback(_) => {}
+        }
+
+        let mut items = Vec::new();
+        while items.len() < count as usize {
+            // removed elements are no longer part of the iteration order, so always restart
+            // from the first key
+            let next = bpf_map_get_next_key(fd, None).map_err(|(_, io_error)| SyscallError {
+                call: "bpf_map_get_next_key",
+                io_error,
+            })?;
+            let Some(next) = next else {
+                cursor.state = BatchState::Done;
+                return Ok(items);
+            };
+            let value =
+                bpf_map_lookup_elem(fd, &next, flags).map_err(|(_, io_error)| SyscallError {
+                    call: "bpf_map_lookup_elem",
+                    io_error,
+                })?;
+            // the key may have been deleted between get_next_key and lookup or delete, in which
+            // case it's skipped
+            let Some(value) = value else {
+                continue;
+            };
+            match bpf_map_delete_elem(fd, &next) {
+                Ok(_) => items.push((next, value)),
+                Err((_, io_error)) if io_error.raw_os_error() == Some(ENOENT) => {}
+                Err((_, io_error)) => {
+                    return Err(SyscallError {
+                        call: "bpf_map_delete_elem",
+                        io_error,
+                    }
+                    .into())
+                }
+            }
+        }
+        cursor.state = BatchState::Fallback(None);
+
+        Ok(items)
+    }
+
+    /// Inserts the key-value pairs `keys[i]`, `values[i]` into the map.
+    ///
+    /// This uses `BPF_MAP_UPDATE_BATCH`, falling back to inserting one element at a time on
+    /// kernels that don't support batch operations (older than 5.6).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::BatchLengthMismatch`] if `keys` and `values` have different lengths.
+    pub fn insert_batch(&mut self, keys: &[K], values: &[V], flags: u64) -> Result<(), MapError> {
+        if keys.len() != values.len() {
+            return Err(MapError::BatchLengthMismatch {
+                keys: keys.len(),
+                values: values.len(),
+            });
+        }
+        let data = self.inner.borrow_mut();
+        match bpf_map_update_batch(data.fd().as_fd(), keys, values, flags) {
+            Ok(_) => Ok(()),
+            Err((_, io_error)) if batch_unsupported(&io_error) => keys
+                .iter()
+                .zip(values)
+                .try_for_each(|(key, value)| hash_map::insert(data, key, value, flags)),
+            Err((_, io_error)) => Err(SyscallError {
+                call: "bpf_map_update_batch",
+                io_error,
+            }
+            .into()),
+        }
+    }
+
+    /// Removes `keys` from the map.
+    ///
+    /// This uses `BPF_MAP_DELETE_BATCH`, falling back to removing one element at a time on
+    /// kernels that don't support batch operations (older than 5.6).
+    pub fn remove_batch(&mut self, keys: &[K]) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        match bpf_map_delete_batch(data.fd().as_fd(), keys) {
+            Ok(_) => Ok(()),
+            Err((_, io_error)) if batch_unsupported(&io_error) => {
+                keys.iter().try_for_each(|key| hash_map::remove(data, key))
+            }
+            Err((_, io_error)) => Err(SyscallError {
+                call: "bpf_map_delete_batch",
+                io_error,
+            }
+            .into()),
+        }
+    }
+}
+
+/// The position of a batched traversal of a [`HashMap`].
+///
+/// A default-constructed cursor starts at the beginning of the map. The same cursor must be
+/// passed to successive calls of [`HashMap::get_batch`] or [`HashMap::get_and_remove_batch`].
+#[derive(Debug)]
+pub struct BatchCursor<K> {
+    state: BatchState<K>,
+}
+
+impl<K> BatchCursor<K> {
+    /// Returns `true` once the end of the map has been reached.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, BatchState::Done)
+    }
+}
+
+impl<K> Default for BatchCursor<K> {
+    fn default() -> Self {
+        Self {
+            state: BatchState::Start,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum BatchState<K> {
+    Start,
+    Batch(u32),
+    Fallback(Option<K>),
+    Done,
+}
+
+// Runs a batch lookup from the position stored in `cursor`, advancing it. Returns `Ok(None)` when
+// the cursor is at the start and the kernel doesn't support batch operations, in which case the
+// cursor is switched to the per-element fallback.
+fn batch<K: Pod, V: Pod>(
+    call: &'static str,
+    cursor: &mut BatchCursor<K>,
+    mut count: u32,
+    lookup: impl Fn(Option<&u32>, u32) -> SysResult<(Vec<K>, Vec<V>, Option<u32>)>,
+) -> Result<Option<Vec<(K, V)>>, MapError> {
+    let in_batch = match cursor.state {
+        BatchState::Batch(token) => Some(token),
+        _ => None,
+    };
+    loop {
+        match lookup(in_batch.as_ref(), count) {
+            Ok((keys, values, next)) => {
+                cursor.state = match next {
+                    Some(token) => BatchState::Batch(token),
+                    None => BatchState::Done,
+                };
+                return Ok(Some(keys.into_iter().zip(values).collect()));
+            }
+            // hash buckets aren't split across batches: the kernel fails with ENOSPC when the
+            // next bucket holds more than `count` elements, so retry with a larger batch
+            Err((_, io_error)) if io_error.raw_os_error() == Some(ENOSPC) && count < u32::MAX => {
+                count = count.saturating_mul(2).max(1);
+            }
+            Err((_, io_error))
+                if matches!(cursor.state, BatchState::Start) && batch_unsupported(&io_error) =>
+            {
+                cursor.state = BatchState::Fallback(None);
+                return Ok(None);
+            }
+            Err((_, io_error)) => return Err(SyscallError { call, io_error }.into()),
+        }
+    }
+}
+
+// Kernels without batch operations fail with EINVAL, but so do newer kernels given invalid
+// arguments such as unknown flags.
+fn batch_unsupported(io_error: &io::Error) -> bool {
+    io_error.raw_os_error() == Some(EINVAL) && !is_map_batch_supported()
 }
 
 impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<K, V> for HashMap<T, K, V> {
@@ -103,14 +409,14 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<K, V> for HashMap<T, K, V>
 
 #[cfg(test)]
 mod tests {
-    use std::{ffi::c_long, io};
+    use std::{cell::Cell, ffi::c_long};
 
     use assert_matches::assert_matches;
     use aya_obj::generated::{
         bpf_attr, bpf_cmd,
         bpf_map_type::{BPF_MAP_TYPE_HASH, BPF_MAP_TYPE_LRU_HASH},
     };
-    use libc::{EFAULT, ENOENT};
+    use libc::{EBADF, EFAULT, EINVAL, ENOENT, ENOSPC};
 
     use super::*;
     use crate::{
@@ -272,6 +578,42 @@ mod tests {
         assert!(hm.remove(&1).is_ok());
     }
 
+    #[test]
+    fn test_get_and_remove_ok() {
+        let mut map = new_map(new_obj_map());
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_ELEM,
+                attr,
+            } => {
+                assert_eq!(bpf_key(attr), Some(1u32));
+                set_ret(attr, 42u32);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(hm.get_and_remove(&1, 0), Ok(42));
+    }
+
+    #[test]
+    fn test_get_and_remove_not_found() {
+        let mut map = new_map(new_obj_map());
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_ELEM,
+                ..
+            } => sys_error(ENOENT),
+            _ => sys_error(EFAULT),
+        });
+
+        assert_matches!(hm.get_and_remove(&1, 0), Err(MapError::KeyNotFound));
+    }
+
     #[test]
     fn test_get_syscall_error() {
         let map = new_map(new_obj_map());
@@ -373,6 +715,26 @@ mod tests {
         assert_eq!(&keys, &[10, 20, 30])
     }
 
+    #[test]
+    fn test_keys_fused() {
+        let map = new_map(new_obj_map());
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => get_next_key(attr),
+            _ => sys_error(EFAULT),
+        });
+
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut keys = hm.keys();
+        assert_eq!(keys.by_ref().count(), 3);
+        // the iteration doesn't start over from the first key
+        assert_matches!(keys.next(), None);
+    }
+
     #[test]
     fn test_keys_error() {
         let map = new_map(new_obj_map());
@@ -532,4 +894,349 @@ mod tests {
         assert_matches!(iter.next(), Some(Ok((30, 300))));
         assert_matches!(iter.next(), None);
     }
+
+    fn set_batch<K: Copy, V: Copy>(attr: &mut bpf_attr, keys: &[K], values: &[V], out_batch: u32) {
+        let u = unsafe { &mut attr.batch };
+        for (i, (k, v)) in keys.iter().zip(values).enumerate() {
+            unsafe {
+                *(u.keys as *mut K).add(i) = *k;
+                *(u.values as *mut V).add(i) = *v;
+            }
+        }
+        u.count = keys.len() as u32;
+        unsafe { *(u.out_batch as *mut u32) = out_batch };
+    }
+
+    fn lookup_batch(attr: &mut bpf_attr) -> SysResult<c_long> {
+        match unsafe { attr.batch.in_batch } as *const u32 {
+            p if p.is_null() => {
+                set_batch::<u32, u32>(attr, &[10, 20], &[100, 200], 1);
+                Ok(0)
+            }
+            p if unsafe { *p } == 1 => {
+                set_batch::<u32, u32>(attr, &[30], &[300], 2);
+                sys_error(ENOENT)
+            }
+            _ => sys_error(EFAULT),
+        }
+    }
+
+    #[test]
+    fn test_get_batch() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                attr,
+            } => lookup_batch(attr),
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_eq!(
+            hm.get_batch(2, &mut cursor, 0).unwrap(),
+            &[(10, 100), (20, 200)]
+        );
+        assert!(!cursor.is_done());
+        assert_eq!(hm.get_batch(2, &mut cursor, 0).unwrap(), &[(30, 300)]);
+        assert!(cursor.is_done());
+        assert_eq!(hm.get_batch(2, &mut cursor, 0).unwrap(), &[]);
+    }
+
+    #[test]
+    fn test_get_batch_syscall_error() {
+        let map = new_map(new_obj_map());
+        override_syscall(|_| sys_error(EFAULT));
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_matches!(
+            hm.get_batch(2, &mut cursor, 0),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_lookup_batch",
+                io_error
+            })) if io_error.raw_os_error() == Some(EFAULT)
+        );
+    }
+
+    #[test]
+    fn test_get_batch_fallback() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => get_next_key(attr),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => lookup_elem(attr),
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_eq!(
+            hm.get_batch(2, &mut cursor, 0).unwrap(),
+            &[(10, 100), (20, 200)]
+        );
+        assert!(!cursor.is_done());
+        assert_eq!(hm.get_batch(2, &mut cursor, 0).unwrap(), &[(30, 300)]);
+        assert!(cursor.is_done());
+    }
+
+    #[test]
+    fn test_get_batch_bucket_too_large() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                attr,
+            } => {
+                // the first bucket holds two elements
+                if unsafe { attr.batch.count } < 2 {
+                    return sys_error(ENOSPC);
+                }
+                set_batch::<u32, u32>(attr, &[10, 20], &[100, 200], 1);
+                sys_error(ENOENT)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_eq!(
+            hm.get_batch(1, &mut cursor, 0).unwrap(),
+            &[(10, 100), (20, 200)]
+        );
+        assert!(cursor.is_done());
+    }
+
+    #[test]
+    fn test_get_batch_invalid_flags() {
+        let map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            // batch operations are supported
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                attr,
+            } if unsafe { attr.batch.map_fd } == u32::MAX => sys_error(EBADF),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            _ => sys_error(EFAULT),
+        });
+        let hm = HashMap::<_, u32, u32>::new(&map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_matches!(
+            hm.get_batch(2, &mut cursor, 0xdead),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_lookup_batch",
+                io_error
+            })) if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_get_and_remove_batch() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+                attr,
+            } => lookup_batch(attr),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        let mut items = Vec::new();
+        while !cursor.is_done() {
+            items.extend(hm.get_and_remove_batch(2, &mut cursor, 0).unwrap());
+        }
+        assert_eq!(&items, &[(10, 100), (20, 200), (30, 300)]);
+    }
+
+    #[test]
+    fn test_get_and_remove_batch_fallback() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => {
+                assert_eq!(bpf_key::<u32>(attr), None);
+                set_next_key(attr, 10);
+                Ok(1)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => lookup_elem(attr),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_ELEM,
+                attr,
+            } => {
+                assert_eq!(bpf_key::<u32>(attr), Some(10));
+                Ok(1)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_eq!(
+            hm.get_and_remove_batch(1, &mut cursor, 0).unwrap(),
+            &[(10, 100)]
+        );
+        assert!(!cursor.is_done());
+    }
+
+    #[test]
+    fn test_get_and_remove_batch_fallback_concurrent_delete() {
+        thread_local! {
+            static NEXT_KEY: Cell<u32> = const { Cell::new(10) };
+        }
+
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => {
+                set_next_key(attr, NEXT_KEY.get());
+                Ok(1)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => lookup_elem(attr),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_ELEM,
+                attr,
+            } => match bpf_key::<u32>(attr) {
+                // deleted by someone else after the lookup
+                Some(10) => {
+                    NEXT_KEY.set(20);
+                    sys_error(ENOENT)
+                }
+                Some(20) => Ok(1),
+                _ => sys_error(EFAULT),
+            },
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        let mut cursor = BatchCursor::default();
+        assert_eq!(
+            hm.get_and_remove_batch(1, &mut cursor, 0).unwrap(),
+            &[(20, 200)]
+        );
+    }
+
+    #[test]
+    fn test_insert_batch_ok() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                attr,
+            } => {
+                let u = unsafe { &attr.batch };
+                assert_eq!(u.count, 2);
+                assert_eq!(unsafe { *(u.keys as *const [u32; 2]) }, [1, 2]);
+                assert_eq!(unsafe { *(u.values as *const [u32; 2]) }, [10, 20]);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        assert_matches!(hm.insert_batch(&[1, 2], &[10, 20], 0), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_batch_fallback() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                ..
+            } => Ok(1),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        assert_matches!(hm.insert_batch(&[1, 2], &[10, 20], 0), Ok(()));
+    }
+
+    #[test]
+    fn test_insert_batch_length_mismatch() {
+        let mut map = new_map(new_obj_map());
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        assert_matches!(
+            hm.insert_batch(&[1, 2], &[10], 0),
+            Err(MapError::BatchLengthMismatch { keys: 2, values: 1 })
+        );
+    }
+
+    #[test]
+    fn test_insert_batch_invalid_flags() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_BATCH,
+                ..
+            } => sys_error(EBADF),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_BATCH,
+                ..
+            } => sys_error(EINVAL),
+            _ => sys_error(EFAULT),
+        });
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        assert_matches!(
+            hm.insert_batch(&[1, 2], &[10, 20], 0xdead),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_update_batch",
+                io_error
+            })) if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+
+    #[test]
+    fn test_remove_batch_syscall_error() {
+        let mut map = new_map(new_obj_map());
+        override_syscall(|_| sys_error(EFAULT));
+        let mut hm = HashMap::<_, u32, u32>::new(&mut map).unwrap();
+
+        assert_matches!(
+            hm.remove_batch(&[1, 2]),
+            Err(MapError::SyscallError(SyscallError {
+                call: "bpf_map_delete_batch",
+                io_error
+            })) if io_error.raw_os_error() == Some(EFAULT)
+        );
+    }
 }
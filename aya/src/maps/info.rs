@@ -11,7 +11,8 @@ use aya_obj::generated::{bpf_map_info, bpf_map_type};
 use super::{MapError, MapFd};
 use crate::{
     sys::{
-        bpf_get_object, bpf_map_get_fd_by_id, bpf_map_get_info_by_fd, iter_map_ids, SyscallError,
+        bpf_get_object, bpf_map_get_fd_by_id, bpf_map_get_info_by_fd, is_unavailable_by_id,
+        iter_map_ids, SyscallError,
     },
     util::bytes_of_bpf_name,
     FEATURES,
@@ -156,17 +157,27 @@ impl MapInfo {
 ///
 /// In cases where iteration can't be performed, for example the caller does not have the necessary
 /// privileges, a single item will be yielded containing the error that occurred.
+///
+/// Maps that are freed while iterating, or that the caller isn't allowed to access, are skipped.
 pub fn loaded_maps() -> impl Iterator<Item = Result<MapInfo, MapError>> {
-    iter_map_ids().map(|id| {
-        let id = id?;
-        MapInfo::from_id(id)
-    })
+    iter_map_ids()
+        .filter_map(|id| match id {
+            Ok(id) => match bpf_map_get_fd_by_id(id) {
+                Err(e) if is_unavailable_by_id(&e) => None,
+                fd => Some(fd),
+            },
+            Err(e) => Some(Err(e)),
+        })
+        .map(|fd| {
+            let fd = fd?;
+            MapInfo::new_from_fd(fd.as_fd())
+        })
 }
 
 /// The type of eBPF map.
 #[non_exhaustive]
 #[doc(alias = "bpf_map_type")]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MapType {
     /// An unspecified program type.
     Unspecified = bpf_map_type::BPF_MAP_TYPE_UNSPEC as isize,
@@ -233,12 +244,14 @@ pub enum MapType {
     /// Introduced in kernel v4.11.
     #[doc(alias = "BPF_MAP_TYPE_LPM_TRIE")]
     LpmTrie = bpf_map_type::BPF_MAP_TYPE_LPM_TRIE as isize,
-    /// An Array of Maps map type.
+    /// An Array of Maps map type. See [`ArrayOfMaps`](super::of_maps::ArrayOfMaps) for the map
+    /// implementation.
     ///
     /// Introduced in kernel v4.12.
     #[doc(alias = "BPF_MAP_TYPE_ARRAY_OF_MAPS")]
     ArrayOfMaps = bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS as isize,
-    /// A Hash of Maps map type.
+    /// A Hash of Maps map type. See [`HashOfMaps`](super::of_maps::HashOfMaps) for the map
+    /// implementation.
     ///
     /// Introduced in kernel v4.12.
     #[doc(alias = "BPF_MAP_TYPE_HASH_OF_MAPS")]
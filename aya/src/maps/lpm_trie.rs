@@ -54,14 +54,25 @@ pub struct LpmTrie<T, K, V> {
 
 /// A Key for an LpmTrie map.
 ///
+/// The key has the same layout as the kernel's `struct bpf_lpm_trie_key`: a `u32` prefix length
+/// in host byte order, immediately followed by the data. The kernel matches the data bit by bit
+/// starting from the most significant bit of the first byte, so the data must be in network
+/// (big-endian) byte order.
+///
 /// # Examples
 ///
 /// ```no_run
 /// use aya::maps::lpm_trie::{LpmTrie, Key};
-/// use std::net::Ipv4Addr;
+/// use std::net::{Ipv4Addr, Ipv6Addr};
 ///
 /// let ipaddr = Ipv4Addr::new(8,8,8,8);
 /// let key =  Key::new(16, u32::from(ipaddr).to_be());
+///
+/// // Alternatively, use the octets which are already in network byte order.
+/// let key = Key::new(16, ipaddr.octets());
+///
+/// let ipaddr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+/// let key = Key::new(32, ipaddr.octets());
 /// ```
 #[repr(C, packed)]
 pub struct Key<K: Pod> {
@@ -73,7 +84,8 @@ impl<K: Pod> Key<K> {
     /// Creates a new key.
     ///
     /// `prefix_len` is the number of bits in the data to match against.
-    /// `data` is the data in the key which is typically an IPv4 or IPv6 address.
+    /// `data` is the data in the key which is typically an IPv4 or IPv6 address, in network
+    /// byte order.
     /// If using a key to perform a longest prefix match on you would use a `prefix_len`
     /// of 32 for IPv4 and 128 for IPv6.
     ///
@@ -195,7 +207,11 @@ impl<T: Borrow<MapData>, K: Pod, V: Pod> IterableMap<Key<K>, V> for LpmTrie<T, K
 
 #[cfg(test)]
 mod tests {
-    use std::{ffi::c_long, io, net::Ipv4Addr};
+    use std::{
+        ffi::c_long,
+        io, mem,
+        net::{Ipv4Addr, Ipv6Addr},
+    };
 
     use assert_matches::assert_matches;
     use aya_obj::generated::{
@@ -305,6 +321,54 @@ mod tests {
         assert!(trie.insert(&key, 1, 0).is_ok());
     }
 
+    #[test]
+    fn test_key_layout() {
+        let key = Key::new(16, Ipv4Addr::new(192, 168, 0, 1).octets());
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&key as *const _ as *const u8, mem::size_of_val(&key))
+        };
+        assert_eq!(bytes[..4], 16u32.to_ne_bytes());
+        assert_eq!(bytes[4..], [192, 168, 0, 1]);
+
+        let ipaddr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let key = Key::new(32, ipaddr.octets());
+        let bytes = unsafe {
+            std::slice::from_raw_parts(&key as *const _ as *const u8, mem::size_of_val(&key))
+        };
+        assert_eq!(bytes.len(), 20);
+        assert_eq!(bytes[..4], 32u32.to_ne_bytes());
+        assert_eq!(bytes[4..], ipaddr.octets());
+    }
+
+    #[test]
+    fn test_ipv6_insert_ok() {
+        let mut map = new_map(test_utils::new_obj_map::<Key<[u8; 16]>>(
+            BPF_MAP_TYPE_LPM_TRIE,
+        ));
+        let mut trie = LpmTrie::<_, [u8; 16], u32>::new(&mut map).unwrap();
+        let ipaddr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let key = Key::new(32, ipaddr.octets());
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let key = unsafe { attr.__bindgen_anon_2.key } as *const Key<[u8; 16]>;
+                let key = unsafe { key.read_unaligned() };
+                assert_eq!(key.prefix_len(), 32);
+                assert_eq!(
+                    key.data(),
+                    Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).octets()
+                );
+                Ok(1)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert!(trie.insert(&key, 1, 0).is_ok());
+    }
+
     #[test]
     fn test_remove_syscall_error() {
         let mut map = new_map(new_obj_map());
@@ -49,26 +49,29 @@
 //! implement the [Pod] trait.
 use std::{
     borrow::Borrow,
-    ffi::{c_long, CString},
+    ffi::{c_int, c_long, c_void, CString},
     fmt, io,
+    iter::{FusedIterator, Sum},
     marker::PhantomData,
     mem,
     ops::Deref,
     os::fd::{AsFd, BorrowedFd, OwnedFd},
-    path::Path,
-    ptr,
+    path::{Path, PathBuf},
+    ptr::{self, NonNull},
+    slice,
 };
 
 use aya_obj::{generated::bpf_map_type, parse_map_info, EbpfSectionKind, InvalidTypeBinding};
-use libc::{getrlimit, rlim_t, rlimit, RLIMIT_MEMLOCK, RLIM_INFINITY};
+use libc::{getrlimit, munmap, off_t, rlim_t, rlimit, MAP_FAILED, RLIMIT_MEMLOCK, RLIM_INFINITY};
 use log::warn;
 use thiserror::Error;
 
 use crate::{
-    pin::PinError,
+    pin::{pin_object, PinError},
     sys::{
         bpf_create_map, bpf_get_object, bpf_map_freeze, bpf_map_get_fd_by_id, bpf_map_get_next_key,
-        bpf_map_update_elem_ptr, bpf_pin_object, SyscallError,
+        bpf_map_get_next_key_bytes, bpf_map_lookup_elem_bytes, bpf_map_update_elem_ptr, mmap,
+        SyscallError,
     },
     util::{nr_cpus, KernelVersion},
     PinningType, Pod,
@@ -79,6 +82,7 @@ pub mod bloom_filter;
 pub mod hash_map;
 mod info;
 pub mod lpm_trie;
+pub mod of_maps;
 pub mod perf;
 pub mod queue;
 pub mod ring_buf;
@@ -87,16 +91,20 @@ pub mod stack;
 pub mod stack_trace;
 pub mod xdp;
 
-pub use array::{Array, PerCpuArray, ProgramArray};
+pub use array::{Array, MmapArray, PerCpuArray, ProgramArray};
 pub use bloom_filter::BloomFilter;
 pub use hash_map::{HashMap, PerCpuHashMap};
 pub use info::{loaded_maps, MapInfo, MapType};
 pub use lpm_trie::LpmTrie;
+pub use of_maps::{ArrayOfMaps, HashOfMaps};
 #[cfg(any(feature = "async_tokio", feature = "async_std"))]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
 pub use perf::AsyncPerfEventArray;
 pub use perf::PerfEventArray;
 pub use queue::Queue;
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
+pub use ring_buf::AsyncRingBuf;
 pub use ring_buf::RingBuf;
 pub use sock::{SockHash, SockMap};
 pub use stack::Stack;
@@ -133,6 +141,10 @@ pub enum MapError {
     },
 
     /// Invalid key size
+    ///
+    /// Returned when converting a [`Map`] into a typed map whose key type doesn't have the key
+    /// size of the map, as declared by its BTF type or its `bpf_map_def`, or when passing a key of
+    /// the wrong length to [`MapData::lookup_bytes`].
     #[error("invalid key size {size}, expected {expected}")]
     InvalidKeySize {
         /// Size encountered
@@ -142,6 +154,9 @@ pub enum MapError {
     },
 
     /// Invalid value size
+    ///
+    /// Returned when converting a [`Map`] into a typed map whose value type doesn't have the
+    /// value size of the map, as declared by its BTF type or its `bpf_map_def`.
     #[error("invalid value size {size}, expected {expected}")]
     InvalidValueSize {
         /// Size encountered
@@ -150,6 +165,26 @@ pub enum MapError {
         expected: usize,
     },
 
+    /// Invalid max_entries override
+    #[error("invalid max_entries {max_entries} for map `{name}`: {reason}")]
+    InvalidMaxEntries {
+        /// The map name
+        name: String,
+        /// The requested max_entries
+        max_entries: u32,
+        /// Why the value was rejected
+        reason: &'static str,
+    },
+
+    /// A batch operation was called with different numbers of keys and values
+    #[error("batch has {keys} keys but {values} values")]
+    BatchLengthMismatch {
+        /// The number of keys
+        keys: usize,
+        /// The number of values
+        values: usize,
+    },
+
     /// Index is out of bounds
     #[error("the index is {index} but `max_entries` is {max_entries}")]
     OutOfBounds {
@@ -189,10 +224,44 @@ pub enum MapError {
         error: PinError,
     },
 
+    /// A map pinned by name doesn't match the definition of the map reusing it
+    #[error(
+        "pinned map `{name}` at `{}` is incompatible: {field} is {pinned}, expected {expected}",
+        path.display()
+    )]
+    IncompatiblePinnedMap {
+        /// The map name
+        name: String,
+        /// The path of the pinned map
+        path: PathBuf,
+        /// The mismatched attribute
+        field: &'static str,
+        /// The value of the attribute in the pinned map
+        pinned: u32,
+        /// The value of the attribute in the map definition
+        expected: u32,
+    },
+
     /// Program IDs are not supported
     #[error("program ids are not supported by the current kernel")]
     ProgIdNotSupported,
 
+    /// Could not freeze the map
+    #[error("failed to freeze map")]
+    Freeze {
+        /// Original io::Error
+        #[source]
+        io_error: io::Error,
+    },
+
+    /// The map can't be memory mapped
+    #[error("map was not created with BPF_F_MMAPABLE")]
+    NotMmapable,
+
+    /// The map is memory mapped read-only
+    #[error("the map is mapped read-only")]
+    ReadOnlyMapping,
+
     /// Unsupported Map type
     #[error(
         "type of {name} ({map_type:?}) is unsupported; see `EbpfLoader::allow_unsupported_maps`"
@@ -223,7 +292,8 @@ impl MapFd {
         Self { fd }
     }
 
-    fn try_clone(&self) -> io::Result<Self> {
+    /// Creates a new instance that shares the same underlying file description as [`self`].
+    pub fn try_clone(&self) -> io::Result<Self> {
         let Self { fd } = self;
         let fd = fd.try_clone()?;
         Ok(Self { fd })
@@ -265,7 +335,7 @@ fn maybe_warn_rlimit() {
         warn!(
             "RLIMIT_MEMLOCK value is {}, not RLIM_INFINITY; if experiencing problems with creating \
             maps, try raising RLIMIT_MEMLOCK either to RLIM_INFINITY or to a higher value sufficient \
-            for the size of your maps",
+            for the size of your maps, for example with `aya::util::bump_memlock_rlimit`",
             HumanSize(limit.rlim_cur)
         );
     }
@@ -276,6 +346,8 @@ fn maybe_warn_rlimit() {
 pub enum Map {
     /// An [`Array`] map.
     Array(MapData),
+    /// An [`ArrayOfMaps`] map.
+    ArrayOfMaps(MapData),
     /// A [`BloomFilter`] map.
     BloomFilter(MapData),
     /// A [`CpuMap`] map.
@@ -286,6 +358,8 @@ pub enum Map {
     DevMapHash(MapData),
     /// A [`HashMap`] map.
     HashMap(MapData),
+    /// A [`HashOfMaps`] map.
+    HashOfMaps(MapData),
     /// A [`LpmTrie`] map.
     LpmTrie(MapData),
     /// A [`HashMap`] map that uses a LRU eviction policy.
@@ -323,11 +397,13 @@ impl Map {
     fn map_type(&self) -> u32 {
         match self {
             Self::Array(map) => map.obj.map_type(),
+            Self::ArrayOfMaps(map) => map.obj.map_type(),
             Self::BloomFilter(map) => map.obj.map_type(),
             Self::CpuMap(map) => map.obj.map_type(),
             Self::DevMap(map) => map.obj.map_type(),
             Self::DevMapHash(map) => map.obj.map_type(),
             Self::HashMap(map) => map.obj.map_type(),
+            Self::HashOfMaps(map) => map.obj.map_type(),
             Self::LpmTrie(map) => map.obj.map_type(),
             Self::LruHashMap(map) => map.obj.map_type(),
             Self::PerCpuArray(map) => map.obj.map_type(),
@@ -353,11 +429,13 @@ impl Map {
     pub fn pin<P: AsRef<Path>>(&self, path: P) -> Result<(), PinError> {
         match self {
             Self::Array(map) => map.pin(path),
+            Self::ArrayOfMaps(map) => map.pin(path),
             Self::BloomFilter(map) => map.pin(path),
             Self::CpuMap(map) => map.pin(path),
             Self::DevMap(map) => map.pin(path),
             Self::DevMapHash(map) => map.pin(path),
             Self::HashMap(map) => map.pin(path),
+            Self::HashOfMaps(map) => map.pin(path),
             Self::LpmTrie(map) => map.pin(path),
             Self::LruHashMap(map) => map.pin(path),
             Self::PerCpuArray(map) => map.pin(path),
@@ -404,6 +482,7 @@ macro_rules! impl_map_pin {
 }
 
 impl_map_pin!(() {
+    ArrayOfMaps,
     ProgramArray,
     SockMap,
     StackTraceMap,
@@ -417,6 +496,7 @@ impl_map_pin!((V) {
     Array,
     PerCpuArray,
     SockHash,
+    HashOfMaps,
     BloomFilter,
     Queue,
     Stack,
@@ -478,6 +558,7 @@ macro_rules! impl_try_from_map {
 }
 
 impl_try_from_map!(() {
+    ArrayOfMaps,
     CpuMap,
     DevMap,
     DevMapHash,
@@ -493,11 +574,13 @@ impl_try_from_map!(() {
 #[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
 impl_try_from_map!(() {
     AsyncPerfEventArray from PerfEventArray,
+    AsyncRingBuf from RingBuf,
 });
 
 impl_try_from_map!((V) {
     Array,
     BloomFilter,
+    HashOfMaps,
     PerCpuArray,
     Queue,
     SockHash,
@@ -578,22 +661,36 @@ impl MapData {
             }
         };
 
+        // Map-in-map types need a template of the maps they are going to hold at creation time.
+        // The kernel only keeps the template's metadata, so it can be dropped right after the
+        // outer map is created.
+        let inner = obj
+            .inner()
+            .map(|inner| Self::create(inner, &format!("{name}.inner"), btf_fd))
+            .transpose()?;
+
         #[cfg(not(test))]
         let kernel_version = KernelVersion::current().unwrap();
         #[cfg(test)]
         let kernel_version = KernelVersion::new(0xff, 0xff, 0xff);
-        let fd =
-            bpf_create_map(&c_name, &obj, btf_fd, kernel_version).map_err(|(code, io_error)| {
-                if kernel_version < KernelVersion::new(5, 11, 0) {
-                    maybe_warn_rlimit();
-                }
+        let fd = bpf_create_map(
+            &c_name,
+            &obj,
+            btf_fd,
+            inner.as_ref().map(|inner| inner.fd().as_fd()),
+            kernel_version,
+        )
+        .map_err(|(code, io_error)| {
+            if kernel_version < KernelVersion::new(5, 11, 0) {
+                maybe_warn_rlimit();
+            }
 
-                MapError::CreateError {
-                    name: name.into(),
-                    code,
-                    io_error,
-                }
-            })?;
+            MapError::CreateError {
+                name: name.into(),
+                code,
+                io_error,
+            }
+        })?;
         Ok(Self {
             obj,
             fd: MapFd::from_fd(fd),
@@ -623,10 +720,30 @@ impl MapData {
             call: "BPF_OBJ_GET",
             io_error,
         }) {
-            Ok(fd) => Ok(Self {
-                obj,
-                fd: MapFd::from_fd(fd),
-            }),
+            Ok(fd) => {
+                let MapInfo(info) = MapInfo::new_from_fd(fd.as_fd())?;
+                for (field, pinned, expected) in [
+                    ("map type", info.type_, obj.map_type()),
+                    ("key size", info.key_size, obj.key_size()),
+                    ("value size", info.value_size, obj.value_size()),
+                    ("max entries", info.max_entries, obj.max_entries()),
+                    ("map flags", info.map_flags, obj.map_flags()),
+                ] {
+                    if pinned != expected {
+                        return Err(MapError::IncompatiblePinnedMap {
+                            name: name.into(),
+                            path,
+                            field,
+                            pinned,
+                            expected,
+                        });
+                    }
+                }
+                Ok(Self {
+                    obj,
+                    fd: MapFd::from_fd(fd),
+                })
+            }
             Err(_) => {
                 let map = Self::create(obj, name, btf_fd)?;
                 map.pin(&path).map_err(|error| MapError::PinError {
@@ -638,7 +755,7 @@ impl MapData {
         }
     }
 
-    pub(crate) fn finalize(&mut self) -> Result<(), MapError> {
+    pub(crate) fn finalize(&mut self, freeze_rodata: bool) -> Result<(), MapError> {
         let Self { obj, fd } = self;
         if !obj.data().is_empty() {
             bpf_map_update_elem_ptr(fd.as_fd(), &0 as *const _, obj.data_mut().as_mut_ptr(), 0)
@@ -648,17 +765,33 @@ impl MapData {
                 })
                 .map_err(MapError::from)?;
         }
-        if obj.section_kind() == EbpfSectionKind::Rodata {
-            bpf_map_freeze(fd.as_fd())
-                .map_err(|(_, io_error)| SyscallError {
-                    call: "bpf_map_freeze",
-                    io_error,
-                })
-                .map_err(MapError::from)?;
+        if freeze_rodata && obj.section_kind() == EbpfSectionKind::Rodata {
+            self.freeze()?;
         }
         Ok(())
     }
 
+    /// Freezes the map, making it read-only from user space.
+    ///
+    /// Once frozen, the kernel rejects any further updates made through the `bpf` syscall.
+    /// eBPF programs can still write to the map unless it was created with
+    /// `BPF_F_RDONLY_PROG`. Freezing cannot be undone.
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 5.2.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::Freeze`] if `BPF_MAP_FREEZE` fails, for example with `EPERM` if the
+    /// caller lacks the required privileges, `EBUSY` if the map is already frozen or has
+    /// writable memory mappings, or `EINVAL` if the kernel doesn't support freezing.
+    pub fn freeze(&mut self) -> Result<(), MapError> {
+        bpf_map_freeze(self.fd.as_fd())
+            .map(|_| ())
+            .map_err(|(_, io_error)| MapError::Freeze { io_error })
+    }
+
     /// Loads a map from a pinned path in bpffs.
     pub fn from_pin<P: AsRef<Path>>(path: P) -> Result<Self, MapError> {
         use std::os::unix::ffi::OsStrExt as _;
@@ -699,7 +832,28 @@ impl MapData {
     ///
     /// If loading from a BPF Filesystem (bpffs) you should use [`Map::from_pin`](crate::maps::MapData::from_pin).
     /// This API is intended for cases where you have received a valid BPF FD from some other means.
-    /// For example, you received an FD over Unix Domain Socket.
+    /// For example, you received an FD over Unix Domain Socket, or the map was created by another
+    /// library such as libbpf.
+    ///
+    /// The type, key size, value size, max entries and flags of the map are queried from the
+    /// kernel with `BPF_OBJ_GET_INFO_BY_FD`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::os::fd::AsFd as _;
+    ///
+    /// use aya::maps::{HashMap, Map, MapData};
+    ///
+    /// // Any valid map FD works here; one is duplicated from a pinned map for illustration.
+    /// let fd = MapData::from_pin("/sys/fs/bpf/my_map")?
+    ///     .fd()
+    ///     .as_fd()
+    ///     .try_clone_to_owned()?;
+    /// let map_data = MapData::from_fd(fd)?;
+    /// let map: HashMap<_, u32, u64> = HashMap::try_from(Map::HashMap(map_data))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
     pub fn from_fd(fd: OwnedFd) -> Result<Self, MapError> {
         let fd = crate::MockableFd::from_fd(fd);
         Self::from_fd_inner(fd)
@@ -717,6 +871,8 @@ impl MapData {
     /// [`std::io::Error`] kind will be [`std::io::ErrorKind::AlreadyExists`].
     /// Returns a [`PinError::InvalidPinPath`] if the path provided cannot be
     /// converted to a [`CString`].
+    /// Returns a [`PinError::NotBpfFs`] if the path provided is not on a BPF
+    /// filesystem.
     ///
     /// # Example
     ///
@@ -730,24 +886,15 @@ impl MapData {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn pin<P: AsRef<Path>>(&self, path: P) -> Result<(), PinError> {
-        use std::os::unix::ffi::OsStrExt as _;
-
         let Self { fd, obj: _ } = self;
-        let path = path.as_ref();
-        let path_string = CString::new(path.as_os_str().as_bytes()).map_err(|error| {
-            PinError::InvalidPinPath {
-                path: path.to_path_buf(),
-                error,
-            }
-        })?;
-        bpf_pin_object(fd.as_fd(), &path_string).map_err(|(_, io_error)| SyscallError {
-            call: "BPF_OBJ_PIN",
-            io_error,
-        })?;
-        Ok(())
+        pin_object(fd.as_fd(), path.as_ref())
     }
 
     /// Returns the file descriptor of the map.
+    ///
+    /// The file descriptor is borrowed and stays owned by the map. It can be passed to other
+    /// libraries with [`AsFd::as_fd`], or duplicated with [`MapFd::try_clone`] when the other side
+    /// needs to own it.
     pub fn fd(&self) -> &MapFd {
         let Self { obj: _, fd } = self;
         fd
@@ -762,8 +909,119 @@ impl MapData {
     pub fn info(&self) -> Result<MapInfo, MapError> {
         MapInfo::new_from_fd(self.fd.as_fd())
     }
+
+    /// Returns the value stored at `key` as raw bytes, or `None` if the key doesn't exist.
+    ///
+    /// This works with any map without knowing its key and value types at compile time;
+    /// `key` must be exactly as long as the key size of the map. For per-CPU maps the returned
+    /// buffer holds one value per possible CPU, each padded to a multiple of 8 bytes as done by
+    /// the kernel.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use aya::maps::MapData;
+    ///
+    /// let map = MapData::from_pin("/sys/fs/bpf/my_map")?;
+    /// for key in map.keys_bytes() {
+    ///     let key = key?;
+    ///     if let Some(value) = map.lookup_bytes(&key)? {
+    ///         println!("{key:02x?}: {value:02x?}");
+    ///     }
+    /// }
+    /// # Ok::<(), aya::maps::MapError>(())
+    /// ```
+    pub fn lookup_bytes(&self, key: &[u8]) -> Result<Option<Vec<u8>>, MapError> {
+        let expected = self.obj.key_size() as usize;
+        if key.len() != expected {
+            return Err(MapError::InvalidKeySize {
+                size: key.len(),
+                expected,
+            });
+        }
+        let mut value = vec![0u8; self.value_buf_size()?];
+        let found = bpf_map_lookup_elem_bytes(self.fd.as_fd(), key, &mut value, 0).map_err(
+            |(_, io_error)| SyscallError {
+                call: "bpf_map_lookup_elem",
+                io_error,
+            },
+        )?;
+        Ok(found.map(|()| value))
+    }
+
+    /// Returns an iterator over the keys of the map as raw bytes.
+    ///
+    /// Each key is as long as the key size of the map. See [`MapKeys`] for how the iterator
+    /// behaves when the map is modified.
+    pub fn keys_bytes(&self) -> MapKeysBytes<'_> {
+        MapKeysBytes {
+            map: self,
+            done: false,
+            key: None,
+        }
+    }
+
+    fn value_buf_size(&self) -> Result<usize, MapError> {
+        let value_size = self.obj.value_size() as usize;
+        let map_type = self.obj.map_type();
+        if map_type == bpf_map_type::BPF_MAP_TYPE_PERCPU_HASH as u32
+            || map_type == bpf_map_type::BPF_MAP_TYPE_PERCPU_ARRAY as u32
+            || map_type == bpf_map_type::BPF_MAP_TYPE_LRU_PERCPU_HASH as u32
+            || map_type == bpf_map_type::BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE as u32
+        {
+            let nr_cpus = nr_cpus().map_err(|(_, error)| MapError::IoError(error))?;
+            Ok(((value_size + 7) & !7) * nr_cpus)
+        } else {
+            Ok(value_size)
+        }
+    }
+}
+
+/// Iterator returned by [`MapData::keys_bytes`].
+///
+/// Behaves like [`MapKeys`], but returns each key as raw bytes.
+pub struct MapKeysBytes<'coll> {
+    map: &'coll MapData,
+    done: bool,
+    key: Option<Vec<u8>>,
+}
+
+impl Iterator for MapKeysBytes<'_> {
+    type Item = Result<Vec<u8>, MapError>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, MapError>> {
+        if self.done {
+            return None;
+        }
+
+        let fd = self.map.fd().as_fd();
+        let mut next_key = vec![0u8; self.map.obj.key_size() as usize];
+        let found = bpf_map_get_next_key_bytes(fd, self.key.as_deref(), &mut next_key).map_err(
+            |(_, io_error)| SyscallError {
+                call: "bpf_map_get_next_key",
+                io_error,
+            },
+        );
+        match found {
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            }
+            Ok(None) => {
+                // don't start over from the first key once the end has been reached
+                self.done = true;
+                None
+            }
+            Ok(Some(())) => {
+                self.key = Some(next_key.clone());
+                Some(Ok(next_key))
+            }
+        }
+    }
 }
 
+impl FusedIterator for MapKeysBytes<'_> {}
+
 /// An iterable map
 pub trait IterableMap<K: Pod, V> {
     /// Get a generic map handle
@@ -774,9 +1032,14 @@ pub trait IterableMap<K: Pod, V> {
 }
 
 /// Iterator returned by `map.keys()`.
+///
+/// The keys are read one at a time with `BPF_MAP_GET_NEXT_KEY`, so the iterator doesn't see a
+/// snapshot of the map. If the map is modified while iterating, keys can be skipped or returned
+/// more than once: when the last returned key is deleted, for example, hash maps restart the
+/// iteration from the first key. The iterator stops after the last key or after the first error.
 pub struct MapKeys<'coll, K: Pod> {
     map: &'coll MapData,
-    err: bool,
+    done: bool,
     key: Option<K>,
 }
 
@@ -784,7 +1047,7 @@ impl<'coll, K: Pod> MapKeys<'coll, K> {
     fn new(map: &'coll MapData) -> Self {
         Self {
             map,
-            err: false,
+            done: false,
             key: None,
         }
     }
@@ -794,7 +1057,7 @@ impl<K: Pod> Iterator for MapKeys<'_, K> {
     type Item = Result<K, MapError>;
 
     fn next(&mut self) -> Option<Result<K, MapError>> {
-        if self.err {
+        if self.done {
             return None;
         }
 
@@ -806,10 +1069,12 @@ impl<K: Pod> Iterator for MapKeys<'_, K> {
             });
         match key {
             Err(err) => {
-                self.err = true;
+                self.done = true;
                 Some(Err(err.into()))
             }
             Ok(key) => {
+                // don't start over from the first key once the end has been reached
+                self.done = key.is_none();
                 self.key = key;
                 key.map(Ok)
             }
@@ -817,7 +1082,12 @@ impl<K: Pod> Iterator for MapKeys<'_, K> {
     }
 }
 
+impl<K: Pod> FusedIterator for MapKeys<'_, K> {}
+
 /// Iterator returned by `map.iter()`.
+///
+/// Keys that are deleted between being returned by the kernel and being looked up are skipped.
+/// See [`MapKeys`] for how the iterator behaves when the map is modified.
 pub struct MapIter<'coll, K: Pod, V, I: IterableMap<K, V>> {
     keys: MapKeys<'coll, K>,
     map: &'coll I,
@@ -852,6 +1122,8 @@ impl<K: Pod, V, I: IterableMap<K, V>> Iterator for MapIter<'_, K, V, I> {
     }
 }
 
+impl<K: Pod, V, I: IterableMap<K, V>> FusedIterator for MapIter<'_, K, V, I> {}
+
 pub(crate) struct PerCpuKernelMem {
     bytes: Vec<u8>,
 }
@@ -944,6 +1216,38 @@ impl<T: Pod> PerCpuValues<T> {
     }
 }
 
+impl<T: Pod> PerCpuValues<T> {
+    /// Returns the number of CPUs, which is the number of values.
+    ///
+    /// The value of a given CPU can be accessed by indexing, as in `values[cpu]`.
+    pub fn nr_cpus(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Folds the values of all the CPUs into a single value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let values = aya::maps::PerCpuValues::try_from(vec![0u64; 4])?;
+    /// let max = values.reduce(0, |max, value| max.max(value));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn reduce<B, F: FnMut(B, T) -> B>(&self, init: B, f: F) -> B {
+        self.values.iter().copied().fold(init, f)
+    }
+
+    /// Returns the sum of the values of all the CPUs.
+    ///
+    /// This is the usual way to read counters that eBPF programs keep per CPU.
+    pub fn sum(&self) -> T
+    where
+        T: Sum<T>,
+    {
+        self.values.iter().copied().sum()
+    }
+}
+
 impl<T: Pod> Deref for PerCpuValues<T> {
     type Target = Box<[T]>;
 
@@ -952,6 +1256,68 @@ impl<T: Pod> Deref for PerCpuValues<T> {
     }
 }
 
+// MMap corresponds to a memory-mapped region.
+//
+// The data is unmapped in Drop.
+pub(crate) struct MMap {
+    pub(crate) ptr: NonNull<c_void>,
+    pub(crate) len: usize,
+}
+
+// Needed because NonNull<T> is !Send and !Sync out of caution that the data
+// might be aliased unsafely.
+unsafe impl Send for MMap {}
+unsafe impl Sync for MMap {}
+
+impl MMap {
+    pub(crate) fn new(
+        fd: BorrowedFd<'_>,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        offset: off_t,
+    ) -> Result<Self, MapError> {
+        match unsafe { mmap(ptr::null_mut(), len, prot, flags, fd, offset) } {
+            MAP_FAILED => Err(MapError::SyscallError(SyscallError {
+                call: "mmap",
+                io_error: io::Error::last_os_error(),
+            })),
+            ptr => Ok(Self {
+                ptr: NonNull::new(ptr).ok_or(
+                    // This should never happen, but to be paranoid, and so we never need to talk
+                    // about a null pointer, we check it anyway.
+                    MapError::SyscallError(SyscallError {
+                        call: "mmap",
+                        io_error: io::Error::other("mmap returned null pointer"),
+                    }),
+                )?,
+                len,
+            }),
+        }
+    }
+}
+
+impl AsRef<[u8]> for MMap {
+    fn as_ref(&self) -> &[u8] {
+        let Self { ptr, len } = self;
+        unsafe { slice::from_raw_parts(ptr.as_ptr().cast(), *len) }
+    }
+}
+
+impl AsMut<[u8]> for MMap {
+    fn as_mut(&mut self) -> &mut [u8] {
+        let Self { ptr, len } = self;
+        unsafe { slice::from_raw_parts_mut(ptr.as_ptr().cast(), *len) }
+    }
+}
+
+impl Drop for MMap {
+    fn drop(&mut self) {
+        let Self { ptr, len } = *self;
+        unsafe { munmap(ptr.as_ptr(), len) };
+    }
+}
+
 #[cfg(test)]
 mod test_utils {
     use aya_obj::{
@@ -986,6 +1352,7 @@ mod test_utils {
                 max_entries: 1024,
                 ..Default::default()
             },
+            inner_def: None,
             section_index: 0,
             section_kind: EbpfSectionKind::Maps,
             data: Vec::new(),
@@ -1005,6 +1372,7 @@ mod test_utils {
                 max_entries,
                 ..Default::default()
             },
+            inner_def: None,
             section_index: 0,
             section_kind: EbpfSectionKind::Maps,
             data: Vec::new(),
@@ -1015,14 +1383,17 @@ mod test_utils {
 
 #[cfg(test)]
 mod tests {
-    use std::{ffi::c_char, os::fd::AsRawFd as _};
+    use std::{
+        ffi::c_char,
+        os::fd::{AsRawFd as _, FromRawFd as _},
+    };
 
     use assert_matches::assert_matches;
     use aya_obj::generated::{bpf_cmd, bpf_map_info, bpf_map_type};
-    use libc::EFAULT;
+    use libc::{EFAULT, ENOENT, EPERM};
 
     use super::*;
-    use crate::sys::{override_syscall, Syscall};
+    use crate::sys::{override_syscall, SysResult, Syscall};
 
     fn new_obj_map() -> aya_obj::Map {
         test_utils::new_obj_map::<u32>(bpf_map_type::BPF_MAP_TYPE_HASH)
@@ -1063,6 +1434,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_map_id_failed() {
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(ENOENT))));
+
+        let err = MapData::from_id(1234).unwrap_err();
+        assert_eq!(err.to_string(), "`bpf_map_get_fd_by_id` failed");
+        // the io::Error is the source, so callers can still match on the errno
+        let source = std::error::Error::source(&err).unwrap();
+        assert_matches!(
+            source.downcast_ref::<io::Error>(),
+            Some(io_error) if io_error.raw_os_error() == Some(ENOENT)
+        );
+    }
+
+    #[test]
+    fn test_from_fd() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET_INFO_BY_FD,
+                attr,
+            } => {
+                assert_eq!(
+                    unsafe { attr.info.bpf_fd },
+                    crate::MockableFd::mock_unsigned_fd(),
+                );
+                let map_info = unsafe { &mut *(attr.info.info as *mut bpf_map_info) };
+                map_info.type_ = bpf_map_type::BPF_MAP_TYPE_HASH as u32;
+                map_info.key_size = 4;
+                map_info.value_size = 8;
+                map_info.max_entries = 16;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let fd = unsafe { OwnedFd::from_raw_fd(crate::MockableFd::mock_signed_fd()) };
+        let map = MapData::from_fd(fd).unwrap();
+        assert_eq!(
+            map.fd().as_fd().as_raw_fd(),
+            crate::MockableFd::mock_signed_fd()
+        );
+        let obj = map.obj();
+        assert_eq!(obj.map_type(), bpf_map_type::BPF_MAP_TYPE_HASH as u32);
+        assert_eq!(obj.key_size(), 4);
+        assert_eq!(obj.value_size(), 8);
+        assert_eq!(obj.max_entries(), 16);
+    }
+
     #[test]
     fn test_create() {
         override_syscall(|call| match call {
@@ -1082,6 +1501,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_map_of_maps() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                if u.map_type == bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS as u32 {
+                    assert_eq!(u.inner_map_fd, crate::MockableFd::mock_unsigned_fd());
+                } else {
+                    assert_eq!(u.map_type, bpf_map_type::BPF_MAP_TYPE_HASH as u32);
+                    assert_eq!(u.inner_map_fd, 0);
+                }
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let mut obj = test_utils::new_obj_map::<u32>(bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS);
+        let inner = test_utils::new_obj_map::<u32>(bpf_map_type::BPF_MAP_TYPE_HASH);
+        match (&mut obj, inner) {
+            (aya_obj::Map::Legacy(outer), aya_obj::Map::Legacy(inner)) => {
+                outer.inner_def = Some(inner.def)
+            }
+            _ => unreachable!(),
+        }
+
+        assert_matches!(
+            MapData::create(obj, "foo", None),
+            Ok(MapData {
+                obj,
+                fd,
+            }) => {
+                assert_eq!(fd.as_fd().as_raw_fd(), crate::MockableFd::mock_signed_fd());
+                assert_matches!(obj.inner(), Some(inner) => {
+                    assert_eq!(inner.map_type(), bpf_map_type::BPF_MAP_TYPE_HASH as u32)
+                });
+            }
+        );
+    }
+
     #[test]
     #[cfg_attr(miri, ignore = "nr_cpus() opens a file on procfs that upsets miri")]
     fn test_create_perf_event_array() {
@@ -1242,6 +1703,125 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "`let map_info = unsafe { &mut *(attr.info.info as *mut bpf_map_info) }` is trying to retag from <wildcard> for Unique permission, but no exposed tags have suitable permission in the borrow stack for this location"
+    )]
+    fn test_loaded_maps_skips_unavailable() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_ID,
+                attr,
+            } => unsafe {
+                let id = attr.__bindgen_anon_6.__bindgen_anon_1.start_id;
+                if id < 3 {
+                    attr.__bindgen_anon_6.next_id = id + 1;
+                    Ok(0)
+                } else {
+                    Err((-1, io::Error::from_raw_os_error(libc::ENOENT)))
+                }
+            },
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_FD_BY_ID,
+                attr,
+            } => match unsafe { attr.__bindgen_anon_6.__bindgen_anon_1.map_id } {
+                // access to map 1 is denied
+                1 => Err((-1, io::Error::from_raw_os_error(EPERM))),
+                // map 2 was freed after its id was returned
+                2 => Err((-1, io::Error::from_raw_os_error(libc::ENOENT))),
+                _ => Ok(crate::MockableFd::mock_signed_fd().into()),
+            },
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET_INFO_BY_FD,
+                attr,
+            } => {
+                let map_info = unsafe { &mut *(attr.info.info as *mut bpf_map_info) };
+                map_info.id = 3;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        assert_eq!(
+            loaded_maps()
+                .map(|map_info| map_info.unwrap().id())
+                .collect::<Vec<_>>(),
+            [3]
+        );
+    }
+
+    #[test]
+    fn test_loaded_maps_not_permitted() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_ID,
+                ..
+            } => Err((-1, io::Error::from_raw_os_error(EPERM))),
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let mut maps = loaded_maps();
+        assert_matches!(
+            maps.next(),
+            Some(Err(MapError::SyscallError(SyscallError { call: "bpf_map_get_next_id", io_error })))
+                if io_error.raw_os_error() == Some(EPERM)
+        );
+        assert_matches!(maps.next(), None);
+    }
+
+    fn pinned_map_syscall(call: Syscall<'_>, key_size: u32) -> SysResult<c_long> {
+        match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET,
+                ..
+            } => Ok(crate::MockableFd::mock_signed_fd().into()),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET_INFO_BY_FD,
+                attr,
+            } => {
+                let map_info = unsafe { &mut *(attr.info.info as *mut bpf_map_info) };
+                map_info.type_ = bpf_map_type::BPF_MAP_TYPE_HASH as u32;
+                map_info.key_size = key_size;
+                map_info.value_size = 4;
+                map_info.max_entries = 1024;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        }
+    }
+
+    #[test]
+    fn test_create_pinned_by_name_reuse() {
+        override_syscall(|call| pinned_map_syscall(call, 4));
+
+        assert_matches!(
+            MapData::create_pinned_by_name("/sys/fs/bpf", new_obj_map(), "foo", None),
+            Ok(MapData { obj: _, fd }) => {
+                assert_eq!(fd.as_fd().as_raw_fd(), crate::MockableFd::mock_signed_fd())
+            }
+        );
+    }
+
+    #[test]
+    fn test_create_pinned_by_name_incompatible() {
+        override_syscall(|call| pinned_map_syscall(call, 8));
+
+        assert_matches!(
+            MapData::create_pinned_by_name("/sys/fs/bpf", new_obj_map(), "foo", None),
+            Err(MapError::IncompatiblePinnedMap {
+                name,
+                path,
+                field: "key size",
+                pinned: 8,
+                expected: 4,
+            }) => {
+                assert_eq!(name, "foo");
+                assert_eq!(path, Path::new("/sys/fs/bpf/foo"));
+            }
+        );
+    }
+
     #[test]
     fn test_create_failed() {
         override_syscall(|_| Err((-42, io::Error::from_raw_os_error(EFAULT))));
@@ -1255,4 +1835,111 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_freeze_failed() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                ..
+            } => Ok(crate::MockableFd::mock_signed_fd().into()),
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_FREEZE,
+                ..
+            } => Err((-1, io::Error::from_raw_os_error(EPERM))),
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let mut map = MapData::create(new_obj_map(), "foo", None).unwrap();
+        assert_matches!(
+            map.freeze(),
+            Err(MapError::Freeze { io_error }) => assert_eq!(io_error.raw_os_error(), Some(EPERM))
+        );
+    }
+
+    #[test]
+    fn test_keys_bytes_and_lookup_bytes() {
+        let map = test_utils::new_map(new_obj_map());
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_GET_NEXT_KEY,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_2 };
+                let next = match u.key as *const u32 {
+                    p if p.is_null() => 10u32,
+                    p if unsafe { *p } == 10 => 20,
+                    _ => return Err((-1, io::Error::from_raw_os_error(ENOENT))),
+                };
+                unsafe { *(u.__bindgen_anon_1.next_key as *mut u32) = next };
+                Ok(0)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_LOOKUP_ELEM,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_2 };
+                let key = unsafe { *(u.key as *const u32) };
+                unsafe { *(u.__bindgen_anon_1.value as *mut u32) = key * 10 };
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let keys = map.keys_bytes().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(keys, [10u32.to_ne_bytes(), 20u32.to_ne_bytes()]);
+        assert_matches!(
+            map.lookup_bytes(&keys[1]),
+            Ok(Some(value)) => assert_eq!(value, 200u32.to_ne_bytes())
+        );
+    }
+
+    #[test]
+    fn test_lookup_bytes_not_found() {
+        let map = test_utils::new_map(new_obj_map());
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(ENOENT))));
+
+        assert_matches!(map.lookup_bytes(&[0; 4]), Ok(None));
+    }
+
+    #[test]
+    fn test_lookup_bytes_invalid_key_size() {
+        let map = test_utils::new_map(new_obj_map());
+
+        assert_matches!(
+            map.lookup_bytes(&[0; 8]),
+            Err(MapError::InvalidKeySize {
+                size: 8,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "nr_cpus() opens a file on procfs that upsets miri")]
+    fn test_lookup_bytes_per_cpu() {
+        let map = test_utils::new_map(test_utils::new_obj_map::<u32>(
+            bpf_map_type::BPF_MAP_TYPE_PERCPU_HASH,
+        ));
+        override_syscall(|_| Ok(0));
+
+        let nr_cpus = crate::util::nr_cpus().unwrap();
+        // 4 byte values are padded to 8 bytes for each CPU
+        assert_matches!(
+            map.lookup_bytes(&[0; 4]),
+            Ok(Some(value)) => assert_eq!(value.len(), nr_cpus * 8)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "nr_cpus() opens a file on procfs that upsets miri")]
+    fn test_per_cpu_values_reduce() {
+        let nr_cpus = crate::util::nr_cpus().unwrap();
+        let values = PerCpuValues::try_from((1..=nr_cpus as u64).collect::<Vec<_>>()).unwrap();
+
+        assert_eq!(values.nr_cpus(), nr_cpus);
+        assert_eq!(values[0], 1);
+        assert_eq!(values.sum(), (nr_cpus * (nr_cpus + 1) / 2) as u64);
+        assert_eq!(values.reduce(0, u64::max), nr_cpus as u64);
+    }
 }
@@ -0,0 +1,126 @@
+//! An array of eBPF maps.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    os::fd::{AsFd as _, AsRawFd as _},
+};
+
+use crate::{
+    maps::{check_bounds, check_kv_size, MapData, MapError, MapFd, MapKeys},
+    sys::{bpf_map_delete_elem, bpf_map_lookup_elem, bpf_map_update_elem, SyscallError},
+};
+
+/// An array of eBPF maps.
+///
+/// An `ArrayOfMaps` holds references to other maps, all compatible with the inner map template
+/// the array was created with. eBPF programs can look up an inner map and then use it like any
+/// other map, which makes it possible to atomically swap the map a program is using at runtime.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.12.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::{ArrayOfMaps, MapData};
+///
+/// let mut outer = ArrayOfMaps::try_from(bpf.map_mut("OUTER").unwrap())?;
+/// let inner = MapData::from_pin("/sys/fs/bpf/inner")?;
+/// let flags = 0;
+///
+/// // OUTER.get(0) on the eBPF side now returns the pinned map
+/// outer.set(0, inner.fd(), flags)?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_ARRAY_OF_MAPS")]
+pub struct ArrayOfMaps<T> {
+    pub(crate) inner: T,
+}
+
+impl<T: Borrow<MapData>> ArrayOfMaps<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<u32, u32>(data)?;
+
+        Ok(Self { inner: map })
+    }
+
+    /// Returns the number of elements in the array.
+    pub fn len(&self) -> u32 {
+        self.inner.borrow().obj.max_entries()
+    }
+
+    /// Returns the ID of the map stored at the given index.
+    ///
+    /// The kernel returns map IDs rather than file descriptors when looking up an inner map from
+    /// userspace. Use [`MapData::from_id`] to get a handle to the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds, [`MapError::KeyNotFound`] if
+    /// no map is stored at `index` and [`MapError::SyscallError`] if `bpf_map_lookup_elem` fails.
+    pub fn get(&self, index: &u32, flags: u64) -> Result<u32, MapError> {
+        let data = self.inner.borrow();
+        check_bounds(data, *index)?;
+        let fd = data.fd().as_fd();
+
+        let value =
+            bpf_map_lookup_elem(fd, index, flags).map_err(|(_, io_error)| SyscallError {
+                call: "bpf_map_lookup_elem",
+                io_error,
+            })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator over the indices of the array that point to a map. The iterator item type
+    /// is `Result<u32, MapError>`.
+    pub fn indices(&self) -> MapKeys<'_, u32> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>> ArrayOfMaps<T> {
+    /// Stores the map referenced by `value` at the given index.
+    ///
+    /// The map must be compatible with the inner map template of the array, i.e. have the same
+    /// type, key size, value size and flags.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds, [`MapError::SyscallError`]
+    /// if `bpf_map_update_elem` fails.
+    pub fn set(&mut self, index: u32, value: &MapFd, flags: u64) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        check_bounds(data, index)?;
+        let fd = data.fd().as_fd();
+        let map_fd = value.as_fd().as_raw_fd();
+
+        bpf_map_update_elem(fd, Some(&index), &map_fd, flags).map_err(|(_, io_error)| {
+            SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Clears the value at the given index.
+    ///
+    /// Looking up a cleared index from an eBPF program returns `NULL`.
+    pub fn clear_index(&mut self, index: &u32) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        check_bounds(data, *index)?;
+        let fd = data.fd().as_fd();
+
+        bpf_map_delete_elem(fd, index)
+            .map(|_| ())
+            .map_err(|(_, io_error)| {
+                SyscallError {
+                    call: "bpf_map_delete_elem",
+                    io_error,
+                }
+                .into()
+            })
+    }
+}
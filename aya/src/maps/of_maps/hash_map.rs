@@ -0,0 +1,113 @@
+//! A hash map of eBPF maps.
+
+use std::{
+    borrow::{Borrow, BorrowMut},
+    marker::PhantomData,
+    os::fd::{AsFd as _, AsRawFd as _},
+};
+
+use crate::{
+    maps::{check_kv_size, hash_map, IterableMap, MapData, MapError, MapFd, MapIter, MapKeys},
+    sys::{bpf_map_lookup_elem, SyscallError},
+    Pod,
+};
+
+/// A hash map of eBPF maps.
+///
+/// A `HashOfMaps` holds references to other maps, all compatible with the inner map template
+/// the map was created with. eBPF programs can look up an inner map and then use it like any
+/// other map, which makes it possible to atomically swap the map a program is using at runtime.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.12.
+///
+/// # Examples
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::{HashOfMaps, MapData};
+///
+/// let mut outer = HashOfMaps::<_, u32>::try_from(bpf.map_mut("OUTER").unwrap())?;
+/// let inner = MapData::from_pin("/sys/fs/bpf/inner")?;
+///
+/// // OUTER.get(&42) on the eBPF side now returns the pinned map
+/// outer.insert(42, inner.fd(), 0)?;
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[doc(alias = "BPF_MAP_TYPE_HASH_OF_MAPS")]
+pub struct HashOfMaps<T, K> {
+    pub(crate) inner: T,
+    _k: PhantomData<K>,
+}
+
+impl<T: Borrow<MapData>, K: Pod> HashOfMaps<T, K> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let data = map.borrow();
+        check_kv_size::<K, u32>(data)?;
+
+        Ok(Self {
+            inner: map,
+            _k: PhantomData,
+        })
+    }
+
+    /// Returns the ID of the map stored at the given key.
+    ///
+    /// The kernel returns map IDs rather than file descriptors when looking up an inner map from
+    /// userspace. Use [`MapData::from_id`] to get a handle to the map.
+    pub fn get(&self, key: &K, flags: u64) -> Result<u32, MapError> {
+        let fd = self.inner.borrow().fd().as_fd();
+        let value = bpf_map_lookup_elem(fd, key, flags).map_err(|(_, io_error)| SyscallError {
+            call: "bpf_map_lookup_elem",
+            io_error,
+        })?;
+        value.ok_or(MapError::KeyNotFound)
+    }
+
+    /// An iterator visiting all key-value pairs in arbitrary order. The
+    /// iterator item type is `Result<(K, u32), MapError>`.
+    pub fn iter(&self) -> MapIter<'_, K, u32, Self> {
+        MapIter::new(self)
+    }
+
+    /// An iterator visiting all keys in arbitrary order. The iterator element
+    /// type is `Result<K, MapError>`.
+    pub fn keys(&self) -> MapKeys<'_, K> {
+        MapKeys::new(self.inner.borrow())
+    }
+}
+
+impl<T: BorrowMut<MapData>, K: Pod> HashOfMaps<T, K> {
+    /// Inserts the map referenced by `value` under the given key.
+    ///
+    /// The map must be compatible with the inner map template of the map, i.e. have the same
+    /// type, key size, value size and flags.
+    pub fn insert(
+        &mut self,
+        key: impl Borrow<K>,
+        value: &MapFd,
+        flags: u64,
+    ) -> Result<(), MapError> {
+        hash_map::insert(
+            self.inner.borrow_mut(),
+            key.borrow(),
+            &value.as_fd().as_raw_fd(),
+            flags,
+        )
+    }
+
+    /// Removes a map from the map.
+    pub fn remove(&mut self, key: &K) -> Result<(), MapError> {
+        hash_map::remove(self.inner.borrow_mut(), key)
+    }
+}
+
+impl<T: Borrow<MapData>, K: Pod> IterableMap<K, u32> for HashOfMaps<T, K> {
+    fn map(&self) -> &MapData {
+        self.inner.borrow()
+    }
+
+    fn get(&self, key: &K) -> Result<u32, MapError> {
+        Self::get(self, key, 0)
+    }
+}
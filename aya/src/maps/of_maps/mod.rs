@@ -0,0 +1,6 @@
+//! Map of maps types.
+mod array;
+mod hash_map;
+
+pub use array::ArrayOfMaps;
+pub use hash_map::HashOfMaps;
@@ -22,7 +22,8 @@ use crate::maps::{
 /// [`perf`](https://perf.wiki.kernel.org/index.php/Main_Page) API.
 ///
 /// This is the async version of [`PerfEventArray`], which provides integration
-/// with [tokio](https://docs.rs/tokio) and [async-std](https:/docs.rs/async-std) and a nice `Future` based API.
+/// with [tokio](https://docs.rs/tokio) and [async-std](https://docs.rs/async-std) and a nice `Future` based API. The runtime is selected
+/// with the `async_tokio` and `async_std` cargo features.
 ///
 /// To receive events you need to:
 /// * call [`AsyncPerfEventArray::open`]
@@ -95,6 +96,10 @@ impl<T: BorrowMut<MapData>> AsyncPerfEventArray<T> {
     /// Opens the perf buffer at the given index.
     ///
     /// The returned buffer will receive all the events eBPF programs send at the given index.
+    ///
+    /// `page_count` is the number of pages of the ring buffer, which must be a power of two and
+    /// defaults to 2. A larger buffer makes it less likely that events are lost when userspace
+    /// doesn't read them fast enough; lost events are reported by `read_events`.
     pub fn open(
         &mut self,
         index: u32,
@@ -154,6 +159,12 @@ impl<T: BorrowMut<MapData>> AsyncPerfEventArrayBuffer<T> {
     /// Returns the number of events read and the number of events lost. Events
     /// are lost when user space doesn't read events fast enough and the ring
     /// buffer fills up.
+    ///
+    /// The buffers are cleared and reused: the contents of a filled buffer are replaced on the
+    /// next call, and it's only reallocated when an event doesn't fit in its capacity. Reserving
+    /// enough capacity up front for the largest event makes reading allocation-free; splitting or
+    /// freezing a buffer gives away its memory, so the next event read into it allocates again.
+    /// The number of buffers filled is [`Events::read`].
     pub async fn read_events(
         &mut self,
         buffers: &mut [BytesMut],
@@ -15,7 +15,7 @@ use bytes::BytesMut;
 use libc::{munmap, MAP_FAILED, MAP_SHARED, PROT_READ, PROT_WRITE};
 use thiserror::Error;
 
-use crate::sys::{mmap, perf_event_ioctl, perf_event_open_bpf, SysResult};
+use crate::sys::{mmap, perf_event_ioctl, perf_event_open_bpf, SysResult, SyscallError};
 
 /// Perf buffer error.
 #[derive(Error, Debug)]
@@ -70,6 +70,10 @@ pub enum PerfBufferError {
     /// An IO error occurred.
     #[error(transparent)]
     IOError(#[from] io::Error),
+
+    /// A syscall failed.
+    #[error(transparent)]
+    SyscallError(#[from] SyscallError),
 }
 
 /// Return type of `read_events()`.
@@ -154,10 +158,11 @@ impl PerfBuffer {
         let fill_buf = |start_off, base, mmap_size, out_buf: &mut [u8]| {
             let len = out_buf.len();
 
-            let end = (start_off + len) % mmap_size;
             let start = start_off % mmap_size;
 
-            if start < end {
+            // The record straddles the end of the ring when it doesn't fit before the end of the
+            // mmap. Comparing `start` and `end` isn't enough: they're equal when `len` is 0.
+            if start + len <= mmap_size {
                 out_buf.copy_from_slice(unsafe {
                     slice::from_raw_parts((base + start) as *const u8, len)
                 });
@@ -228,6 +233,8 @@ impl PerfBuffer {
             let buf = &mut buffers[buf_n];
 
             let event_start = tail % self.size;
+            // Records are 8-byte aligned and the ring size is a multiple of the page size, so the
+            // header itself never wraps around. Its payload can, see `fill_buf`.
             let event =
                 unsafe { ptr::read_unaligned((base + event_start) as *const perf_event_header) };
             let event_size = event.size as usize;
@@ -323,6 +330,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_open_offline_cpu() {
+        override_syscall(|call| match call {
+            Syscall::PerfEventOpen { cpu, .. } => {
+                assert_eq!(cpu, 3);
+                Err((-1, io::Error::from_raw_os_error(libc::ENODEV)))
+            }
+            call => panic!("unexpected syscall: {:?}", call),
+        });
+        assert_matches!(
+            PerfBuffer::open(3, PAGE_SIZE, 1),
+            Err(PerfBufferError::OpenError { io_error }) if io_error.raw_os_error() == Some(libc::ENODEV)
+        );
+    }
+
     #[test]
     fn test_no_out_bufs() {
         let mmapped_buf = MMappedBuf {
@@ -364,24 +386,7 @@ mod tests {
         };
         fake_mmap(&mmapped_buf);
 
-        #[repr(C)]
-        #[derive(Debug)]
-        struct LostSamples {
-            header: perf_event_header,
-            id: u64,
-            count: u64,
-        }
-
-        let evt = LostSamples {
-            header: perf_event_header {
-                type_: PERF_RECORD_LOST as u32,
-                misc: 0,
-                size: mem::size_of::<LostSamples>() as u16,
-            },
-            id: 1,
-            count: 0xCAFEBABE,
-        };
-        write(&mut mmapped_buf, 0, evt);
+        write_lost(&mut mmapped_buf, 0, 0xCAFEBABE);
 
         let mut buf = PerfBuffer::open(1, PAGE_SIZE, 1).unwrap();
         let out_buf = BytesMut::with_capacity(0);
@@ -421,6 +426,27 @@ mod tests {
         write(mmapped_buf, offset, sample)
     }
 
+    #[repr(C)]
+    #[derive(Debug)]
+    struct LostSamples {
+        header: perf_event_header,
+        id: u64,
+        count: u64,
+    }
+
+    fn write_lost(mmapped_buf: &mut MMappedBuf, offset: usize, count: u64) -> usize {
+        let lost = LostSamples {
+            header: perf_event_header {
+                type_: PERF_RECORD_LOST as u32,
+                misc: 0,
+                size: mem::size_of::<LostSamples>() as u16,
+            },
+            id: 1,
+            count,
+        };
+        write(mmapped_buf, offset, lost)
+    }
+
     fn u32_from_buf(buf: &[u8]) -> u32 {
         u32::from_ne_bytes(buf[..4].try_into().unwrap())
     }
@@ -450,6 +476,67 @@ mod tests {
         assert_eq!(u32_from_buf(&out_bufs[0]), 0xCAFEBABE);
     }
 
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "`ptr::write_unaligned(dst, value)` is attempting a write access but no exposed tags have suitable permission in the borrow stack for this location"
+    )]
+    fn test_read_reuses_buffers() {
+        let mut mmapped_buf = MMappedBuf {
+            data: [0; PAGE_SIZE * 2],
+        };
+        fake_mmap(&mmapped_buf);
+        let mut buf = PerfBuffer::open(1, PAGE_SIZE, 1).unwrap();
+
+        let mut out_bufs = [BytesMut::with_capacity(8)];
+        let ptr = out_bufs[0].as_ptr();
+
+        let next = write_sample(&mut mmapped_buf, 0, 0xCAFEBABEu32);
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 0, read: 1 });
+        assert_eq!(u32_from_buf(&out_bufs[0]), 0xCAFEBABE);
+
+        write_sample(&mut mmapped_buf, next, 0xBADCAFEu32);
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 0, read: 1 });
+        assert_eq!(u32_from_buf(&out_bufs[0]), 0xBADCAFE);
+
+        // the buffer was cleared and refilled in place
+        assert_eq!(out_bufs[0].len(), 4);
+        assert_eq!(out_bufs[0].as_ptr(), ptr);
+    }
+
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "`ptr::write_unaligned(dst, value)` is attempting a write access but no exposed tags have suitable permission in the borrow stack for this location"
+    )]
+    fn test_read_lost_between_samples() {
+        let mut mmapped_buf = MMappedBuf {
+            data: [0; PAGE_SIZE * 2],
+        };
+        fake_mmap(&mmapped_buf);
+        let mut buf = PerfBuffer::open(1, PAGE_SIZE, 1).unwrap();
+
+        let next = write_sample(&mut mmapped_buf, 0, 0xCAFEBABEu32);
+        let next = write_lost(&mut mmapped_buf, next, 3);
+        let next = write_sample(&mut mmapped_buf, next, 0xBADCAFEu32);
+        write_lost(&mut mmapped_buf, next, 5);
+
+        let mut out_bufs = [BytesMut::with_capacity(4), BytesMut::with_capacity(4)];
+
+        // lost records don't use an output buffer
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 3, read: 2 });
+        assert_eq!(u32_from_buf(&out_bufs[0]), 0xCAFEBABE);
+        assert_eq!(u32_from_buf(&out_bufs[1]), 0xBADCAFE);
+
+        // reading stops once all the buffers are full, so the last record is reported by the
+        // next read
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 5, read: 0 });
+    }
+
     #[test]
     #[cfg_attr(
         miri,
@@ -606,4 +693,67 @@ mod tests {
         assert_eq!(events, Events { lost: 0, read: 1 });
         assert_eq!(u64_from_buf(&out_bufs[0]), 0xBAADCAFECAFEBABE);
     }
+
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "`ptr::write_unaligned(dst, value)` is attempting a write access but no exposed tags have suitable permission in the borrow stack for this location"
+    )]
+    fn test_read_wrapping_lost() {
+        let mut mmapped_buf = MMappedBuf {
+            data: [0; PAGE_SIZE * 2],
+        };
+        fake_mmap(&mmapped_buf);
+        let mut buf = PerfBuffer::open(1, PAGE_SIZE, 1).unwrap();
+
+        // the header is at the end of the ring, and the id and count wrap around to the start
+        let offset = PAGE_SIZE - mem::size_of::<perf_event_header>();
+        mmapped_buf.mmap_page.data_tail = offset as u64;
+        write(
+            &mut mmapped_buf,
+            offset,
+            perf_event_header {
+                type_: PERF_RECORD_LOST as u32,
+                misc: 0,
+                size: mem::size_of::<LostSamples>() as u16,
+            },
+        );
+        write(&mut mmapped_buf, 0, 1u64);
+        write(&mut mmapped_buf, mem::size_of::<u64>(), 42u64);
+        let next = write_sample(&mut mmapped_buf, 2 * mem::size_of::<u64>(), 0xCAFEBABEu32);
+        // the head keeps increasing past the end of the ring
+        unsafe {
+            ptr::write_volatile(
+                &mut mmapped_buf.mmap_page.data_head,
+                (PAGE_SIZE + next) as u64,
+            )
+        };
+
+        let mut out_bufs = [BytesMut::with_capacity(4)];
+
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 42, read: 1 });
+        assert_eq!(u32_from_buf(&out_bufs[0]), 0xCAFEBABE);
+    }
+
+    #[test]
+    #[cfg_attr(
+        miri,
+        ignore = "`ptr::write_unaligned(dst, value)` is attempting a write access but no exposed tags have suitable permission in the borrow stack for this location"
+    )]
+    fn test_read_empty_sample() {
+        let mut mmapped_buf = MMappedBuf {
+            data: [0; PAGE_SIZE * 2],
+        };
+        fake_mmap(&mmapped_buf);
+        let mut buf = PerfBuffer::open(1, PAGE_SIZE, 1).unwrap();
+
+        write_sample(&mut mmapped_buf, 0, ());
+
+        let mut out_bufs = [BytesMut::with_capacity(4)];
+
+        let events = buf.read_events(&mut out_bufs).unwrap();
+        assert_eq!(events, Events { lost: 0, read: 1 });
+        assert!(out_bufs[0].is_empty());
+    }
 }
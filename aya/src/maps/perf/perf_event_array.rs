@@ -16,7 +16,7 @@ use crate::{
         perf::{Events, PerfBuffer, PerfBufferError},
         MapData, MapError, PinError,
     },
-    sys::bpf_map_update_elem,
+    sys::{bpf_map_update_elem, SyscallError},
     util::page_size,
 };
 
@@ -48,6 +48,12 @@ impl<T: BorrowMut<MapData>> PerfEventArrayBuffer<T> {
     /// are lost when user space doesn't read events fast enough and the ring
     /// buffer fills up.
     ///
+    /// The buffers are cleared and reused: the contents of a filled buffer are replaced on the
+    /// next call, and it's only reallocated when an event doesn't fit in its capacity. Reserving
+    /// enough capacity up front for the largest event makes reading allocation-free; splitting or
+    /// freezing a buffer gives away its memory, so the next event read into it allocates again.
+    /// The number of buffers filled is [`Events::read`].
+    ///
     /// # Errors
     ///
     /// [`PerfBufferError::NoBuffers`] is returned when `out_bufs` is empty.
@@ -189,6 +195,24 @@ impl<T: BorrowMut<MapData>> PerfEventArray<T> {
     /// Opens the perf buffer at the given index.
     ///
     /// The returned buffer will receive all the events eBPF programs send at the given index.
+    ///
+    /// `page_count` is the number of pages of the ring buffer, which must be a power of two and
+    /// defaults to 2. A larger buffer makes it less likely that events are lost when userspace
+    /// doesn't read them fast enough; lost events are reported by `read_events`.
+    ///
+    /// eBPF programs usually send events at the index of the CPU they run on, in which case
+    /// `index` is a CPU id. Buffers can be opened for a subset of the CPUs only, for example the
+    /// ones returned by [`online_cpus`](crate::util::online_cpus). The returned buffer owns its
+    /// file descriptor, which can be registered with any event loop to be notified when events
+    /// are available.
+    ///
+    /// # Errors
+    ///
+    /// - [`PerfBufferError::InvalidPageCount`] if `page_count` isn't a power of two.
+    /// - [`PerfBufferError::OpenError`] if the perf event can't be opened on the CPU `index`,
+    ///   for example with `ENODEV` if the CPU is offline or `EINVAL` if it doesn't exist.
+    /// - [`PerfBufferError::SyscallError`] if the buffer can't be stored in the map, for example
+    ///   with `E2BIG` if `index` is out of the bounds of the map.
     pub fn open(
         &mut self,
         index: u32,
@@ -199,8 +223,12 @@ impl<T: BorrowMut<MapData>> PerfEventArray<T> {
         let map_data: &MapData = self.map.deref().borrow();
         let map_fd = map_data.fd().as_fd();
         let buf = PerfBuffer::open(index, self.page_size, page_count.unwrap_or(2))?;
-        bpf_map_update_elem(map_fd, Some(&index), &buf.as_fd().as_raw_fd(), 0)
-            .map_err(|(_, io_error)| io_error)?;
+        bpf_map_update_elem(map_fd, Some(&index), &buf.as_fd().as_raw_fd(), 0).map_err(
+            |(_, io_error)| SyscallError {
+                call: "bpf_map_update_elem",
+                io_error,
+            },
+        )?;
 
         Ok(PerfEventArrayBuffer {
             buf,
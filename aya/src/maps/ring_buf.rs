@@ -6,23 +6,29 @@
 
 use std::{
     borrow::Borrow,
-    ffi::{c_int, c_void},
     fmt::{self, Debug, Formatter},
-    io, mem,
+    mem,
     ops::Deref,
-    os::fd::{AsFd as _, AsRawFd, BorrowedFd, RawFd},
-    ptr,
-    ptr::NonNull,
-    slice,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
     sync::atomic::{AtomicU32, AtomicUsize, Ordering},
 };
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
+// See the note in `async_perf_event_array.rs`: tokio wins when both runtimes are requested.
+#[cfg(all(not(feature = "async_tokio"), feature = "async_std"))]
+use async_io::Async;
 use aya_obj::generated::{BPF_RINGBUF_BUSY_BIT, BPF_RINGBUF_DISCARD_BIT, BPF_RINGBUF_HDR_SZ};
-use libc::{munmap, off_t, MAP_FAILED, MAP_SHARED, PROT_READ, PROT_WRITE};
+use libc::{MAP_SHARED, PROT_READ, PROT_WRITE};
+#[cfg(feature = "async_tokio")]
+use tokio::io::unix::AsyncFd;
 
 use crate::{
-    maps::{MapData, MapError},
-    sys::{mmap, SyscallError},
+    maps::{MMap, MapData, MapError},
     util::page_size,
 };
 
@@ -47,6 +53,8 @@ use crate::{
 ///
 /// To receive async notifications of data availability, you may construct an
 /// [`tokio::io::unix::AsyncFd`] from the [`RingBuf`]'s file descriptor and poll it for readiness.
+/// With the `async_tokio` or `async_std` cargo features, `AsyncRingBuf` wraps this for you and
+/// implements `Stream`.
 ///
 /// # Minimum kernel version
 ///
@@ -88,9 +96,9 @@ use crate::{
 /// # Polling
 ///
 /// In the example above the implementations of poll(), poll.readable(), guard.inner_mut(), and
-/// guard.clear_ready() are not given. RingBuf implements the AsRawFd trait, so you can implement
-/// polling using any crate that can poll file descriptors, like epoll, mio etc. The above example
-/// API is motivated by that of [`tokio::io::unix::AsyncFd`].
+/// guard.clear_ready() are not given. RingBuf implements the AsFd and AsRawFd traits, so you can
+/// implement polling using any crate that can poll file descriptors, like epoll, mio etc. The above
+/// example API is motivated by that of [`tokio::io::unix::AsyncFd`].
 #[doc(alias = "BPF_MAP_TYPE_RINGBUF")]
 pub struct RingBuf<T> {
     map: T,
@@ -135,15 +143,140 @@ impl<T> RingBuf<T> {
     }
 }
 
-/// Access to the RawFd can be used to construct an AsyncFd for use with epoll.
-impl<T: Borrow<MapData>> AsRawFd for RingBuf<T> {
-    fn as_raw_fd(&self) -> RawFd {
+/// Access to the fd can be used to construct an AsyncFd for use with epoll.
+impl<T: Borrow<MapData>> AsFd for RingBuf<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
         let Self {
             map,
             consumer: _,
             producer: _,
         } = self;
-        map.borrow().fd().as_fd().as_raw_fd()
+        map.borrow().fd().as_fd()
+    }
+}
+
+/// Access to the RawFd can be used to construct an AsyncFd for use with epoll.
+impl<T: Borrow<MapData>> AsRawFd for RingBuf<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_fd().as_raw_fd()
+    }
+}
+
+/// A [`Stream`] based [`RingBuf`].
+///
+/// This is the async version of [`RingBuf`], which provides integration with
+/// [tokio](https://docs.rs/tokio) and [async-std](https://docs.rs/async-std). The runtime is
+/// selected with the `async_tokio` and `async_std` cargo features.
+///
+/// The stream waits for the kernel to notify the map's file descriptor and then yields every
+/// committed record, skipping discarded ones. Each record is copied out of the ring before it's
+/// yielded, so the space it occupied is handed back to the eBPF program right away. The stream
+/// never ends; it only yields an error if polling the file descriptor fails.
+///
+/// With the `async_tokio` feature, an [`AsyncRingBuf`] must be created from within a tokio
+/// runtime.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 5.8.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(thiserror::Error, Debug)]
+/// # enum Error {
+/// #    #[error(transparent)]
+/// #    IO(#[from] std::io::Error),
+/// #    #[error(transparent)]
+/// #    Map(#[from] aya::maps::MapError),
+/// #    #[error(transparent)]
+/// #    Ebpf(#[from] aya::EbpfError),
+/// # }
+/// # #[cfg(any(feature = "async_tokio", feature = "async_std"))]
+/// # async fn try_main() -> Result<(), Error> {
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::maps::AsyncRingBuf;
+/// use futures::StreamExt as _;
+///
+/// let mut ring_buf = AsyncRingBuf::try_from(bpf.take_map("EVENTS").unwrap())?;
+/// while let Some(item) = ring_buf.next().await {
+///     let item = item?;
+///     println!("Received: {:?}", item);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`Stream`]: futures_core::Stream
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "async_tokio", feature = "async_std"))))]
+#[doc(alias = "BPF_MAP_TYPE_RINGBUF")]
+pub struct AsyncRingBuf<T: Borrow<MapData>> {
+    #[cfg(feature = "async_tokio")]
+    ring_buf: AsyncFd<RingBuf<T>>,
+
+    #[cfg(all(not(feature = "async_tokio"), feature = "async_std"))]
+    ring_buf: Async<RingBuf<T>>,
+}
+
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+impl<T: Borrow<MapData>> AsyncRingBuf<T> {
+    pub(crate) fn new(map: T) -> Result<Self, MapError> {
+        let ring_buf = RingBuf::new(map)?;
+        #[cfg(feature = "async_tokio")]
+        let ring_buf = AsyncFd::new(ring_buf)?;
+        #[cfg(all(not(feature = "async_tokio"), feature = "async_std"))]
+        let ring_buf = Async::new(ring_buf)?;
+        Ok(Self { ring_buf })
+    }
+}
+
+// The ring buffer is never pinned structurally, the stream only needs `&mut self`.
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+impl<T: Borrow<MapData>> Unpin for AsyncRingBuf<T> {}
+
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+impl<T: Borrow<MapData>> futures_core::Stream for AsyncRingBuf<T> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Self { ring_buf } = self.get_mut();
+        loop {
+            #[cfg(feature = "async_tokio")]
+            {
+                let mut guard = match ring_buf.poll_read_ready_mut(cx) {
+                    Poll::Ready(Ok(guard)) => guard,
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => return Poll::Pending,
+                };
+                if let Some(item) = guard.get_inner_mut().next() {
+                    return Poll::Ready(Some(Ok(item.to_vec())));
+                }
+                // The guard only clears the readiness it observed, so a notification that raced
+                // with the read above wakes us up again.
+                guard.clear_ready();
+            }
+
+            #[cfg(all(not(feature = "async_tokio"), feature = "async_std"))]
+            {
+                if let Some(item) = unsafe { ring_buf.get_mut() }.next() {
+                    return Poll::Ready(Some(Ok(item.to_vec())));
+                }
+                match ring_buf.poll_readable(cx) {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(err)) => return Poll::Ready(Some(Err(err))),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(any(feature = "async_tokio", feature = "async_std"))]
+impl<T: Borrow<MapData>> AsFd for AsyncRingBuf<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        let Self { ring_buf } = self;
+        ring_buf.get_ref().as_fd()
     }
 }
 
@@ -403,61 +536,3 @@ impl ProducerData {
         }
     }
 }
-
-// MMap corresponds to a memory-mapped region.
-//
-// The data is unmapped in Drop.
-struct MMap {
-    ptr: NonNull<c_void>,
-    len: usize,
-}
-
-// Needed because NonNull<T> is !Send and !Sync out of caution that the data
-// might be aliased unsafely.
-unsafe impl Send for MMap {}
-unsafe impl Sync for MMap {}
-
-impl MMap {
-    fn new(
-        fd: BorrowedFd<'_>,
-        len: usize,
-        prot: c_int,
-        flags: c_int,
-        offset: off_t,
-    ) -> Result<Self, MapError> {
-        match unsafe { mmap(ptr::null_mut(), len, prot, flags, fd, offset) } {
-            MAP_FAILED => Err(MapError::SyscallError(SyscallError {
-                call: "mmap",
-                io_error: io::Error::last_os_error(),
-            })),
-            ptr => Ok(Self {
-                ptr: NonNull::new(ptr).ok_or(
-                    // This should never happen, but to be paranoid, and so we never need to talk
-                    // about a null pointer, we check it anyway.
-                    MapError::SyscallError(SyscallError {
-                        call: "mmap",
-                        io_error: io::Error::new(
-                            io::ErrorKind::Other,
-                            "mmap returned null pointer",
-                        ),
-                    }),
-                )?,
-                len,
-            }),
-        }
-    }
-}
-
-impl AsRef<[u8]> for MMap {
-    fn as_ref(&self) -> &[u8] {
-        let Self { ptr, len } = self;
-        unsafe { slice::from_raw_parts(ptr.as_ptr().cast(), *len) }
-    }
-}
-
-impl Drop for MMap {
-    fn drop(&mut self) {
-        let Self { ptr, len } = *self;
-        unsafe { munmap(ptr.as_ptr(), len) };
-    }
-}
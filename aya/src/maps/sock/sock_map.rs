@@ -1,4 +1,4 @@
-//! An array of eBPF program file descriptors used as a jump table.
+//! An array of sockets.
 
 use std::{
     borrow::{Borrow, BorrowMut},
@@ -63,7 +63,7 @@ impl<T: Borrow<MapData>> SockMap<T> {
         Ok(Self { inner: map })
     }
 
-    /// An iterator over the indices of the array that point to a program. The iterator item type
+    /// An iterator over the indices of the array that point to a socket. The iterator item type
     /// is `Result<u32, MapError>`.
     pub fn indices(&self) -> MapKeys<'_, u32> {
         MapKeys::new(self.inner.borrow())
@@ -112,3 +112,101 @@ impl<T: BorrowMut<MapData>> SockMap<T> {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::c_long, io, os::fd::BorrowedFd};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{bpf_cmd, bpf_map_type::BPF_MAP_TYPE_SOCKMAP};
+    use libc::{EFAULT, EOPNOTSUPP};
+
+    use super::*;
+    use crate::{
+        maps::test_utils::{self, new_map},
+        sys::{override_syscall, SysResult, Syscall},
+    };
+
+    fn new_obj_map() -> aya_obj::Map {
+        test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_SOCKMAP)
+    }
+
+    fn sys_error(value: i32) -> SysResult<c_long> {
+        Err((-1, io::Error::from_raw_os_error(value)))
+    }
+
+    #[test]
+    fn test_set() {
+        let mut map = new_map(new_obj_map());
+        let mut sock_map = SockMap::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_2 };
+                assert_eq!(unsafe { *(u.key as *const u32) }, 3);
+                assert_eq!(unsafe { *(u.__bindgen_anon_1.value as *const RawFd) }, 42);
+                assert_eq!(u.flags, 0);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        let socket = unsafe { BorrowedFd::borrow_raw(42) };
+        assert!(sock_map.set(3, &socket, 0).is_ok());
+    }
+
+    #[test]
+    fn test_set_out_of_bounds() {
+        let mut map = new_map(new_obj_map());
+        let mut sock_map = SockMap::new(&mut map).unwrap();
+
+        override_syscall(|_| sys_error(EFAULT));
+
+        let socket = unsafe { BorrowedFd::borrow_raw(42) };
+        assert_matches!(
+            sock_map.set(1024, &socket, 0),
+            Err(MapError::OutOfBounds {
+                index: 1024,
+                max_entries: 1024
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_syscall_error() {
+        let mut map = new_map(new_obj_map());
+        let mut sock_map = SockMap::new(&mut map).unwrap();
+
+        // the kernel rejects sockets that can't be stored in a sockmap
+        override_syscall(|_| sys_error(EOPNOTSUPP));
+
+        let socket = unsafe { BorrowedFd::borrow_raw(42) };
+        assert_matches!(
+            sock_map.set(0, &socket, 0),
+            Err(MapError::SyscallError(SyscallError { call: "bpf_map_update_elem", io_error }))
+                if io_error.raw_os_error() == Some(EOPNOTSUPP)
+        );
+    }
+
+    #[test]
+    fn test_clear_index() {
+        let mut map = new_map(new_obj_map());
+        let mut sock_map = SockMap::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_DELETE_ELEM,
+                attr,
+            } => {
+                assert_eq!(unsafe { *(attr.__bindgen_anon_2.key as *const u32) }, 5);
+                Ok(0)
+            }
+            _ => sys_error(EFAULT),
+        });
+
+        assert!(sock_map.clear_index(&5).is_ok());
+    }
+}
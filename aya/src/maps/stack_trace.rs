@@ -39,11 +39,11 @@ use crate::{
 /// # }
 /// # let bpf = aya::Ebpf::load(&[])?;
 /// use aya::maps::StackTraceMap;
-/// use aya::util::kernel_symbols;
+/// use aya::util::SymbolResolver;
 ///
 /// let mut stack_traces = StackTraceMap::try_from(bpf.map("STACK_TRACES").unwrap())?;
 /// // load kernel symbols from /proc/kallsyms
-/// let ksyms = kernel_symbols()?;
+/// let ksyms = SymbolResolver::kernel()?;
 ///
 /// // NOTE: you typically send stack_ids from eBPF to user space using other maps
 /// let stack_id = 1234;
@@ -52,18 +52,10 @@ use crate::{
 /// // here we resolve symbol names using kernel symbols. If this was a user space stack (for
 /// // example captured from a uprobe), you'd have to load the symbols using some other mechanism
 /// // (eg loading the target binary debuginfo)
-/// for frame in stack_trace.frames() {
-///     if let Some(sym) = ksyms.range(..=frame.ip).next_back().map(|(_, s)| s) {
-///         println!(
-///             "{:#x} {}",
-///             frame.ip,
-///             sym
-///         );
-///     } else {
-///         println!(
-///             "{:#x}",
-///             frame.ip
-///         );
+/// for ip in stack_trace.ips() {
+///     match ksyms.resolve(ip) {
+///         Some((sym, offset)) => println!("{ip:#x} {sym}+{offset:#x}"),
+///         None => println!("{ip:#x}"),
 ///     }
 /// }
 ///
@@ -92,7 +84,8 @@ impl<T: Borrow<MapData>> StackTraceMap<T> {
                 io_error,
             })?;
         let size = data.obj.value_size() as usize;
-        if size > max_stack_depth * mem::size_of::<u64>() {
+        let expected = max_stack_depth * mem::size_of::<u64>();
+        if size > expected {
             return Err(MapError::InvalidValueSize { size, expected });
         }
 
@@ -193,6 +186,12 @@ impl StackTrace {
     pub fn frames(&self) -> &[StackFrame] {
         &self.frames
     }
+
+    /// An iterator visiting the instruction pointers of the frames in this stack trace, from the
+    /// innermost frame outwards.
+    pub fn ips(&self) -> impl Iterator<Item = u64> + '_ {
+        self.frames.iter().map(|frame| frame.ip)
+    }
 }
 
 /// A stack frame.
@@ -207,3 +206,56 @@ fn sysctl<T: FromStr>(key: &str) -> Result<T, io::Error> {
         .parse::<T>()
         .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, val))
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use aya_obj::{
+        generated::bpf_map_type::BPF_MAP_TYPE_STACK_TRACE, maps::LegacyMap, EbpfSectionKind,
+    };
+
+    use super::*;
+    use crate::{bpf_map_def, maps::test_utils::new_map};
+
+    fn new_obj_map(key_size: u32, value_size: u32) -> aya_obj::Map {
+        aya_obj::Map::Legacy(LegacyMap {
+            def: bpf_map_def {
+                map_type: BPF_MAP_TYPE_STACK_TRACE as u32,
+                key_size,
+                value_size,
+                max_entries: 1024,
+                ..Default::default()
+            },
+            inner_def: None,
+            section_index: 0,
+            section_kind: EbpfSectionKind::Maps,
+            data: Vec::new(),
+            symbol_index: None,
+        })
+    }
+
+    #[test]
+    fn test_wrong_key_size() {
+        let map = new_map(new_obj_map(8, 127 * 8));
+        assert_matches!(
+            StackTraceMap::new(&map),
+            Err(MapError::InvalidKeySize {
+                size: 8,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "sysctl opens a file on procfs that upsets miri")]
+    fn test_wrong_value_size() {
+        let max_stack_depth = sysctl::<u32>("kernel/perf_event_max_stack").unwrap();
+        let map = new_map(new_obj_map(4, (max_stack_depth + 1) * 8));
+        assert_matches!(
+            StackTraceMap::new(&map),
+            Err(MapError::InvalidValueSize { size, expected })
+                if size == (max_stack_depth as usize + 1) * 8
+                    && expected == max_stack_depth as usize * 8
+        );
+    }
+}
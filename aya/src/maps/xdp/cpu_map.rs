@@ -2,6 +2,7 @@
 
 use std::{
     borrow::{Borrow, BorrowMut},
+    mem,
     num::NonZeroU32,
     os::fd::{AsFd, AsRawFd},
 };
@@ -13,7 +14,7 @@ use crate::{
     maps::{check_bounds, check_kv_size, IterableMap, MapData, MapError},
     programs::ProgramFd,
     sys::{bpf_map_lookup_elem, bpf_map_update_elem, SyscallError},
-    Pod, FEATURES,
+    Pod,
 };
 
 /// An array of available CPUs.
@@ -52,19 +53,27 @@ use crate::{
 #[doc(alias = "BPF_MAP_TYPE_CPUMAP")]
 pub struct CpuMap<T> {
     pub(crate) inner: T,
+    prog_value: bool,
 }
 
 impl<T: Borrow<MapData>> CpuMap<T> {
     pub(crate) fn new(map: T) -> Result<Self, MapError> {
         let data = map.borrow();
 
-        if FEATURES.cpumap_prog_id() {
-            check_kv_size::<u32, bpf_cpumap_val>(data)?;
-        } else {
+        // CPU maps hold either bare queue sizes or, starting from kernel 5.9, `bpf_cpumap_val`s
+        // which can also reference a chained program.
+        let prog_value = if data.obj.value_size() as usize == mem::size_of::<u32>() {
             check_kv_size::<u32, u32>(data)?;
-        }
+            false
+        } else {
+            check_kv_size::<u32, bpf_cpumap_val>(data)?;
+            true
+        };
 
-        Ok(Self { inner: map })
+        Ok(Self {
+            inner: map,
+            prog_value,
+        })
     }
 
     /// Returns the number of elements in the array.
@@ -85,7 +94,7 @@ impl<T: Borrow<MapData>> CpuMap<T> {
         check_bounds(data, cpu_index)?;
         let fd = data.fd().as_fd();
 
-        let value = if FEATURES.cpumap_prog_id() {
+        let value = if self.prog_value {
             bpf_map_lookup_elem::<_, bpf_cpumap_val>(fd, &cpu_index, flags).map(|value| {
                 value.map(|value| CpuMapValue {
                     queue_size: value.qsize,
@@ -133,9 +142,10 @@ impl<T: BorrowMut<MapData>> CpuMap<T> {
     ///
     /// # Errors
     ///
-    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds, [`MapError::SyscallError`]
-    /// if `bpf_map_update_elem` fails, [`XdpMapError::ChainedProgramNotSupported`] if the kernel
-    /// does not support chained programs and one is provided.
+    /// Returns [`MapError::OutOfBounds`] if `cpu_index` is out of bounds,
+    /// [`MapError::SyscallError`] if `bpf_map_update_elem` fails,
+    /// [`XdpMapError::ChainedProgramNotSupported`] if the map only holds queue sizes and a program
+    /// is provided.
     pub fn set(
         &mut self,
         cpu_index: u32,
@@ -147,7 +157,7 @@ impl<T: BorrowMut<MapData>> CpuMap<T> {
         check_bounds(data, cpu_index)?;
         let fd = data.fd().as_fd();
 
-        let res = if FEATURES.cpumap_prog_id() {
+        let res = if self.prog_value {
             let mut value = unsafe { std::mem::zeroed::<bpf_cpumap_val>() };
             value.qsize = queue_size;
             // Default is valid as the kernel will only consider fd > 0:
@@ -193,3 +203,85 @@ pub struct CpuMapValue {
     /// Chained XDP program ID.
     pub prog_id: Option<NonZeroU32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{bpf_cmd, bpf_map_type::BPF_MAP_TYPE_CPUMAP};
+    use libc::EFAULT;
+
+    use super::*;
+    use crate::{
+        maps::test_utils::{self, new_map},
+        sys::{override_syscall, Syscall},
+    };
+
+    fn new_obj_map(value_size: u32) -> aya_obj::Map {
+        let mut obj = test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_CPUMAP);
+        obj.set_value_size(value_size);
+        obj
+    }
+
+    #[test]
+    fn test_queue_size_value() {
+        let map = new_map(new_obj_map(4));
+        assert!(!CpuMap::new(&map).unwrap().prog_value);
+    }
+
+    #[test]
+    fn test_cpumap_val_value() {
+        let map = new_map(new_obj_map(mem::size_of::<bpf_cpumap_val>() as u32));
+        assert!(CpuMap::new(&map).unwrap().prog_value);
+    }
+
+    #[test]
+    fn test_wrong_value_size() {
+        let map = new_map(new_obj_map(12));
+        assert_matches!(
+            CpuMap::new(&map).err(),
+            Some(MapError::InvalidValueSize {
+                size: 8,
+                expected: 12
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_out_of_bounds() {
+        let mut map = new_map(new_obj_map(mem::size_of::<bpf_cpumap_val>() as u32));
+        let mut cpu_map = CpuMap::new(&mut map).unwrap();
+
+        assert_matches!(
+            cpu_map.set(1024, 2048, None, 0),
+            Err(XdpMapError::MapError(MapError::OutOfBounds {
+                index: 1024,
+                max_entries: 1024
+            }))
+        );
+    }
+
+    #[test]
+    fn test_set_ok() {
+        let mut map = new_map(new_obj_map(mem::size_of::<bpf_cpumap_val>() as u32));
+        let mut cpu_map = CpuMap::new(&mut map).unwrap();
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_UPDATE_ELEM,
+                attr,
+            } => {
+                let value = unsafe { attr.__bindgen_anon_2.__bindgen_anon_1.value }
+                    as *const bpf_cpumap_val;
+                let value = unsafe { value.read_unaligned() };
+                assert_eq!(value.qsize, 2048);
+                assert_eq!(unsafe { value.bpf_prog.fd }, 0);
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        assert!(cpu_map.set(0, 2048, None, 0).is_ok());
+    }
+}
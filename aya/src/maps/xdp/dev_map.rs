@@ -2,6 +2,7 @@
 
 use std::{
     borrow::{Borrow, BorrowMut},
+    mem,
     num::NonZeroU32,
     os::fd::{AsFd, AsRawFd},
 };
@@ -13,7 +14,7 @@ use crate::{
     maps::{check_bounds, check_kv_size, IterableMap, MapData, MapError},
     programs::ProgramFd,
     sys::{bpf_map_lookup_elem, bpf_map_update_elem, SyscallError},
-    Pod, FEATURES,
+    Pod,
 };
 
 /// An array of network devices.
@@ -43,19 +44,17 @@ use crate::{
 #[doc(alias = "BPF_MAP_TYPE_DEVMAP")]
 pub struct DevMap<T> {
     pub(crate) inner: T,
+    prog_value: bool,
 }
 
 impl<T: Borrow<MapData>> DevMap<T> {
     pub(crate) fn new(map: T) -> Result<Self, MapError> {
-        let data = map.borrow();
+        let prog_value = check_devmap_kv_size(map.borrow())?;
 
-        if FEATURES.devmap_prog_id() {
-            check_kv_size::<u32, bpf_devmap_val>(data)?;
-        } else {
-            check_kv_size::<u32, u32>(data)?;
-        }
-
-        Ok(Self { inner: map })
+        Ok(Self {
+            inner: map,
+            prog_value,
+        })
     }
 
     /// Returns the number of elements in the array.
@@ -76,7 +75,7 @@ impl<T: Borrow<MapData>> DevMap<T> {
         check_bounds(data, index)?;
         let fd = data.fd().as_fd();
 
-        let value = if FEATURES.devmap_prog_id() {
+        let value = if self.prog_value {
             bpf_map_lookup_elem::<_, bpf_devmap_val>(fd, &index, flags).map(|value| {
                 value.map(|value| DevMapValue {
                     if_index: value.ifindex,
@@ -124,8 +123,8 @@ impl<T: BorrowMut<MapData>> DevMap<T> {
     /// # Errors
     ///
     /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds, [`MapError::SyscallError`]
-    /// if `bpf_map_update_elem` fails, [`MapError::ProgIdNotSupported`] if the kernel does not
-    /// support chained programs and one is provided.
+    /// if `bpf_map_update_elem` fails, [`XdpMapError::ChainedProgramNotSupported`] if the map
+    /// only holds interface indices and a program is provided.
     pub fn set(
         &mut self,
         index: u32,
@@ -137,7 +136,7 @@ impl<T: BorrowMut<MapData>> DevMap<T> {
         check_bounds(data, index)?;
         let fd = data.fd().as_fd();
 
-        let res = if FEATURES.devmap_prog_id() {
+        let res = if self.prog_value {
             let mut value = unsafe { std::mem::zeroed::<bpf_devmap_val>() };
             value.ifindex = target_if_index;
             // Default is valid as the kernel will only consider fd > 0:
@@ -176,6 +175,18 @@ impl<T: Borrow<MapData>> IterableMap<u32, DevMapValue> for DevMap<T> {
 
 unsafe impl Pod for bpf_devmap_val {}
 
+// Device maps hold either bare interface indices or, starting from kernel 5.8, `bpf_devmap_val`s
+// which can also reference a chained program. Returns whether the map holds the latter.
+pub(super) fn check_devmap_kv_size(data: &MapData) -> Result<bool, MapError> {
+    if data.obj.value_size() as usize == mem::size_of::<u32>() {
+        check_kv_size::<u32, u32>(data)?;
+        Ok(false)
+    } else {
+        check_kv_size::<u32, bpf_devmap_val>(data)?;
+        Ok(true)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 /// The value of a device map.
 pub struct DevMapValue {
@@ -184,3 +195,42 @@ pub struct DevMapValue {
     /// Chained XDP program ID.
     pub prog_id: Option<NonZeroU32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use aya_obj::generated::bpf_map_type::BPF_MAP_TYPE_DEVMAP;
+
+    use super::*;
+    use crate::maps::test_utils::{self, new_map};
+
+    fn new_obj_map(value_size: u32) -> aya_obj::Map {
+        let mut obj = test_utils::new_obj_map::<u32>(BPF_MAP_TYPE_DEVMAP);
+        obj.set_value_size(value_size);
+        obj
+    }
+
+    #[test]
+    fn test_ifindex_value() {
+        let map = new_map(new_obj_map(4));
+        assert!(!DevMap::new(&map).unwrap().prog_value);
+    }
+
+    #[test]
+    fn test_devmap_val_value() {
+        let map = new_map(new_obj_map(mem::size_of::<bpf_devmap_val>() as u32));
+        assert!(DevMap::new(&map).unwrap().prog_value);
+    }
+
+    #[test]
+    fn test_wrong_value_size() {
+        let map = new_map(new_obj_map(12));
+        assert_matches!(
+            DevMap::new(&map).err(),
+            Some(MapError::InvalidValueSize {
+                size: 8,
+                expected: 12
+            })
+        );
+    }
+}
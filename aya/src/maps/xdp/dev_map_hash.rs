@@ -8,12 +8,14 @@ use std::{
 
 use aya_obj::generated::bpf_devmap_val;
 
-use super::{dev_map::DevMapValue, XdpMapError};
+use super::{
+    dev_map::{check_devmap_kv_size, DevMapValue},
+    XdpMapError,
+};
 use crate::{
-    maps::{check_kv_size, hash_map, IterableMap, MapData, MapError, MapIter, MapKeys},
+    maps::{hash_map, IterableMap, MapData, MapError, MapIter, MapKeys},
     programs::ProgramFd,
     sys::{bpf_map_lookup_elem, SyscallError},
-    FEATURES,
 };
 
 /// An hashmap of network devices.
@@ -43,19 +45,17 @@ use crate::{
 #[doc(alias = "BPF_MAP_TYPE_DEVMAP_HASH")]
 pub struct DevMapHash<T> {
     pub(crate) inner: T,
+    prog_value: bool,
 }
 
 impl<T: Borrow<MapData>> DevMapHash<T> {
     pub(crate) fn new(map: T) -> Result<Self, MapError> {
-        let data = map.borrow();
-
-        if FEATURES.devmap_prog_id() {
-            check_kv_size::<u32, bpf_devmap_val>(data)?;
-        } else {
-            check_kv_size::<u32, u32>(data)?;
-        }
+        let prog_value = check_devmap_kv_size(map.borrow())?;
 
-        Ok(Self { inner: map })
+        Ok(Self {
+            inner: map,
+            prog_value,
+        })
     }
 
     /// Returns the target interface index and optional program for a given key.
@@ -66,7 +66,7 @@ impl<T: Borrow<MapData>> DevMapHash<T> {
     pub fn get(&self, key: u32, flags: u64) -> Result<DevMapValue, MapError> {
         let fd = self.inner.borrow().fd().as_fd();
 
-        let value = if FEATURES.devmap_prog_id() {
+        let value = if self.prog_value {
             bpf_map_lookup_elem::<_, bpf_devmap_val>(fd, &key, flags).map(|value| {
                 value.map(|value| DevMapValue {
                     if_index: value.ifindex,
@@ -118,8 +118,8 @@ impl<T: BorrowMut<MapData>> DevMapHash<T> {
     /// # Errors
     ///
     /// Returns [`MapError::SyscallError`] if `bpf_map_update_elem` fails,
-    /// [`MapError::ProgIdNotSupported`] if the kernel does not support chained programs and one is
-    /// provided.
+    /// [`XdpMapError::ChainedProgramNotSupported`] if the map only holds interface indices and a
+    /// program is provided.
     pub fn insert(
         &mut self,
         key: u32,
@@ -127,7 +127,7 @@ impl<T: BorrowMut<MapData>> DevMapHash<T> {
         program: Option<&ProgramFd>,
         flags: u64,
     ) -> Result<(), XdpMapError> {
-        if FEATURES.devmap_prog_id() {
+        if self.prog_value {
             let mut value = unsafe { std::mem::zeroed::<bpf_devmap_val>() };
             value.ifindex = target_if_index;
             // Default is valid as the kernel will only consider fd > 0:
@@ -15,8 +15,8 @@ use super::MapError;
 #[derive(Error, Debug)]
 /// Errors occuring from working with XDP maps.
 pub enum XdpMapError {
-    /// Chained programs are not supported.
-    #[error("chained programs are not supported by the current kernel")]
+    /// Chained programs are not supported by the kernel, or the map only holds interface indices.
+    #[error("chained programs are not supported by the current kernel or map")]
     ChainedProgramNotSupported,
 
     /// Map operation failed.
@@ -7,7 +7,7 @@ use std::{
 
 use crate::{
     maps::{check_bounds, check_kv_size, MapData, MapError},
-    sys::{bpf_map_update_elem, SyscallError},
+    sys::{bpf_map_delete_elem, bpf_map_update_elem, SyscallError},
 };
 
 /// An array of AF_XDP sockets.
@@ -27,7 +27,9 @@ use crate::{
 ///
 /// let mut xskmap = XskMap::try_from(bpf.map_mut("SOCKETS").unwrap())?;
 /// // socket_fd is the RawFd of an AF_XDP socket
-/// xskmap.set(0, socket_fd, 0);
+/// xskmap.set(0, socket_fd, 0)?;
+/// // packets redirected to index 0 are now dropped
+/// xskmap.unset(0)?;
 /// # Ok::<(), aya::EbpfError>(())
 /// ```
 ///
@@ -78,4 +80,27 @@ impl<T: BorrowMut<MapData>> XskMap<T> {
         )?;
         Ok(())
     }
+
+    /// Removes the `AF_XDP` socket at a given index.
+    ///
+    /// Packets redirected to `index` will be dropped once the socket has been removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MapError::OutOfBounds`] if `index` is out of bounds, [`MapError::SyscallError`]
+    /// if `bpf_map_delete_elem` fails.
+    pub fn unset(&mut self, index: u32) -> Result<(), MapError> {
+        let data = self.inner.borrow_mut();
+        check_bounds(data, index)?;
+        let fd = data.fd().as_fd();
+        bpf_map_delete_elem(fd, &index)
+            .map(|_| ())
+            .map_err(|(_, io_error)| {
+                SyscallError {
+                    call: "bpf_map_delete_elem",
+                    io_error,
+                }
+                .into()
+            })
+    }
 }
@@ -1,8 +1,18 @@
 //! Pinning BPF objects to the BPF filesystem.
 
+use std::{
+    ffi::CString,
+    io, mem,
+    os::{fd::BorrowedFd, unix::ffi::OsStrExt as _},
+    path::{Path, PathBuf},
+};
+
 use thiserror::Error;
 
-use crate::sys::SyscallError;
+use crate::sys::{bpf_pin_object, SyscallError};
+
+/// The magic number of the BPF filesystem, as reported by `statfs(2)`.
+const BPF_FS_MAGIC: u32 = 0xcafe4a11;
 
 /// An error ocurred working with a pinned BPF object.
 #[derive(Error, Debug)]
@@ -23,7 +33,88 @@ pub enum PinError {
         /// The source error.
         error: std::ffi::NulError,
     },
+    /// The path for the BPF object is not on a BPF filesystem.
+    #[error("pin path `{}` is not on a BPF filesystem (bpffs)", path.display())]
+    NotBpfFs {
+        /// The path.
+        path: PathBuf,
+    },
     /// An error ocurred making a syscall.
     #[error(transparent)]
     SyscallError(#[from] SyscallError),
 }
+
+/// Pins `fd` to `path`.
+///
+/// If pinning fails because the parent directory of `path` is not on a BPF filesystem,
+/// [`PinError::NotBpfFs`] is returned instead of the kernel's less descriptive error.
+pub(crate) fn pin_object(fd: BorrowedFd<'_>, path: &Path) -> Result<(), PinError> {
+    let path_string =
+        CString::new(path.as_os_str().as_bytes()).map_err(|error| PinError::InvalidPinPath {
+            path: path.into(),
+            error,
+        })?;
+    bpf_pin_object(fd, &path_string).map_err(|(_, io_error)| {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        match is_bpf_fs(parent) {
+            Ok(false) => PinError::NotBpfFs { path: path.into() },
+            Ok(true) | Err(_) => SyscallError {
+                call: "BPF_OBJ_PIN",
+                io_error,
+            }
+            .into(),
+        }
+    })?;
+    Ok(())
+}
+
+fn is_bpf_fs(path: &Path) -> io::Result<bool> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stat = unsafe { mem::zeroed::<libc::statfs>() };
+    if unsafe { libc::statfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // The type and signedness of `f_type` differ between architectures and libcs.
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_type as u32 == BPF_FS_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::BorrowedFd;
+
+    use assert_matches::assert_matches;
+    use libc::EPERM;
+
+    use super::*;
+    use crate::sys::override_syscall;
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`statfs` not available when isolation is enabled")]
+    fn test_pin_object_not_bpf_fs() {
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(EPERM))));
+
+        let fd = unsafe { BorrowedFd::borrow_raw(crate::MockableFd::mock_signed_fd()) };
+        let path = std::env::temp_dir().join("aya-pin-test");
+        assert_matches!(
+            pin_object(fd, &path),
+            Err(PinError::NotBpfFs { path: p }) if p == path
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`statfs` not available when isolation is enabled")]
+    fn test_pin_object_missing_parent() {
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(EPERM))));
+
+        let fd = unsafe { BorrowedFd::borrow_raw(crate::MockableFd::mock_signed_fd()) };
+        assert_matches!(
+            pin_object(fd, Path::new("/aya/does/not/exist")),
+            Err(PinError::SyscallError(SyscallError { call: "BPF_OBJ_PIN", io_error }))
+                if io_error.raw_os_error() == Some(EPERM)
+        );
+    }
+}
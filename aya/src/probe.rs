@@ -0,0 +1,94 @@
+//! Probing the running kernel for eBPF features.
+//!
+//! Loaders that must run on a range of kernels can use these functions to check whether a
+//! program type, a map type or a helper is available before relying on it, and fall back to
+//! something older otherwise. Each probe loads a throwaway program or creates a throwaway map
+//! and inspects the error returned by the kernel, the same way libbpf's `libbpf_probe_bpf_*`
+//! functions do. Results are cached for the lifetime of the process.
+//!
+//! The probes need the same privileges as loading programs and creating maps, so they report
+//! every feature as unsupported when run without `CAP_BPF` (or `CAP_SYS_ADMIN` on older
+//! kernels).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use aya::{probe::probe_map_type, maps::MapType};
+//!
+//! if probe_map_type(MapType::RingBuf) {
+//!     // load the version of the program that uses a ring buffer
+//! } else {
+//!     // fall back to a perf event array
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use crate::{
+    maps::MapType,
+    programs::ProgramType,
+    sys::{is_helper_supported, is_map_type_supported, is_prog_type_supported},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Probe {
+    ProgType(ProgramType),
+    MapType(MapType),
+    Helper(ProgramType, u32),
+}
+
+static PROBES: LazyLock<Mutex<HashMap<Probe, bool>>> = LazyLock::new(Default::default);
+
+fn cached(probe: Probe, run: impl FnOnce() -> bool) -> bool {
+    if let Some(supported) = PROBES.lock().unwrap().get(&probe) {
+        return *supported;
+    }
+    // The lock isn't held while probing since helper probes first probe their program type.
+    let supported = run();
+    *PROBES.lock().unwrap().entry(probe).or_insert(supported)
+}
+
+/// Returns `true` if the kernel supports programs of type `prog_type`.
+///
+/// For [`ProgramType::Tracing`], [`ProgramType::Lsm`], [`ProgramType::Extension`] and
+/// [`ProgramType::StructOps`] this also requires the kernel to expose its BTF.
+#[doc(alias = "libbpf_probe_bpf_prog_type")]
+pub fn probe_prog_type(prog_type: ProgramType) -> bool {
+    cached(Probe::ProgType(prog_type), || {
+        is_prog_type_supported(prog_type)
+    })
+}
+
+/// Returns `true` if the kernel supports maps of type `map_type`.
+#[doc(alias = "libbpf_probe_bpf_map_type")]
+pub fn probe_map_type(map_type: MapType) -> bool {
+    cached(Probe::MapType(map_type), || is_map_type_supported(map_type))
+}
+
+/// Returns `true` if programs of type `prog_type` can call the helper `helper_id`.
+///
+/// `helper_id` is a `bpf_func_id` value, for example
+/// `aya_obj::generated::bpf_func_id::BPF_FUNC_ringbuf_output as u32`.
+///
+/// Returns `false` if the program type itself isn't supported. Helpers can't be probed for
+/// [`ProgramType::Tracing`], [`ProgramType::Lsm`], [`ProgramType::Extension`] and
+/// [`ProgramType::StructOps`] programs, since those must be attached to a kernel function or
+/// program to be loaded, so `false` is returned for them too.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya::{probe::probe_bpf_helper, programs::ProgramType};
+/// use aya_obj::generated::bpf_func_id::BPF_FUNC_probe_read_kernel;
+///
+/// let supported = probe_bpf_helper(ProgramType::KProbe, BPF_FUNC_probe_read_kernel as u32);
+/// ```
+#[doc(alias = "libbpf_probe_bpf_helper")]
+pub fn probe_bpf_helper(prog_type: ProgramType, helper_id: u32) -> bool {
+    cached(Probe::Helper(prog_type, helper_id), || {
+        probe_prog_type(prog_type) && is_helper_supported(prog_type, helper_id)
+    })
+}
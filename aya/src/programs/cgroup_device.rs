@@ -9,7 +9,8 @@ use aya_obj::generated::{
 use crate::{
     programs::{
         bpf_prog_get_fd_by_id, define_link_wrapper, id_as_key, load_program, query,
-        CgroupAttachMode, FdLink, Link, ProgAttachLink, ProgramData, ProgramError, ProgramFd,
+        CgroupAttachMode, FdLink, Link, LinkError, ProgAttachLink, ProgramData, ProgramError,
+        ProgramFd,
     },
     sys::{bpf_link_create, LinkTarget, ProgQueryTarget, SyscallError},
     util::KernelVersion,
@@ -158,6 +159,18 @@ impl Link for CgroupDeviceLinkInner {
 
 id_as_key!(CgroupDeviceLinkInner, CgroupDeviceLinkIdInner);
 
+impl TryFrom<CgroupDeviceLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(value: CgroupDeviceLink) -> Result<Self, Self::Error> {
+        if let CgroupDeviceLinkInner::Fd(fd) = value.into_inner() {
+            Ok(fd)
+        } else {
+            Err(LinkError::InvalidLink)
+        }
+    }
+}
+
 define_link_wrapper!(
     /// The link used by [CgroupDevice] programs.
     CgroupDeviceLink,
@@ -9,8 +9,8 @@ use aya_obj::generated::{
 
 use crate::{
     programs::{
-        define_link_wrapper, id_as_key, load_program, CgroupAttachMode, FdLink, Link,
-        ProgAttachLink, ProgramData, ProgramError,
+        define_link_wrapper, id_as_key, load_program, query_cgroup, CgroupAttachMode, FdLink, Link,
+        LinkError, ProgAttachLink, ProgramData, ProgramError, ProgramInfo,
     },
     sys::{bpf_link_create, LinkTarget, SyscallError},
     util::KernelVersion,
@@ -125,6 +125,41 @@ impl CgroupSkb {
         }
     }
 
+    /// Queries the cgroup for programs attached with `attach_type`.
+    ///
+    /// Returns the mode the programs were attached with and the programs, in the order they run.
+    /// Programs inherited from ancestor cgroups aren't included.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(Debug, thiserror::Error)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Program(#[from] aya::programs::ProgramError),
+    /// # }
+    /// use aya::programs::{CgroupSkb, CgroupSkbAttachType};
+    ///
+    /// let cgroup = std::fs::File::open("/sys/fs/cgroup/unified")?;
+    /// let (mode, programs) = CgroupSkb::query(cgroup, CgroupSkbAttachType::Egress)?;
+    /// for program in programs {
+    ///     println!("{} attached with {mode:?}", program.id());
+    /// }
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn query<T: AsFd>(
+        cgroup: T,
+        attach_type: CgroupSkbAttachType,
+    ) -> Result<(CgroupAttachMode, Vec<ProgramInfo>), ProgramError> {
+        let attach_type = match attach_type {
+            CgroupSkbAttachType::Ingress => BPF_CGROUP_INET_INGRESS,
+            CgroupSkbAttachType::Egress => BPF_CGROUP_INET_EGRESS,
+        };
+        query_cgroup(cgroup.as_fd(), attach_type)
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -175,6 +210,18 @@ impl Link for CgroupSkbLinkInner {
 
 id_as_key!(CgroupSkbLinkInner, CgroupSkbLinkIdInner);
 
+impl TryFrom<CgroupSkbLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(value: CgroupSkbLink) -> Result<Self, Self::Error> {
+        if let CgroupSkbLinkInner::Fd(fd) = value.into_inner() {
+            Ok(fd)
+        } else {
+            Err(LinkError::InvalidLink)
+        }
+    }
+}
+
 define_link_wrapper!(
     /// The link used by [CgroupSkb] programs.
     CgroupSkbLink,
@@ -7,8 +7,8 @@ pub use aya_obj::programs::CgroupSockAttachType;
 
 use crate::{
     programs::{
-        define_link_wrapper, id_as_key, load_program, CgroupAttachMode, FdLink, Link,
-        ProgAttachLink, ProgramData, ProgramError,
+        define_link_wrapper, id_as_key, load_program, query_cgroup, CgroupAttachMode, FdLink, Link,
+        LinkError, ProgAttachLink, ProgramData, ProgramError, ProgramInfo,
     },
     sys::{bpf_link_create, LinkTarget, SyscallError},
     util::KernelVersion,
@@ -102,6 +102,17 @@ impl CgroupSock {
         }
     }
 
+    /// Queries the cgroup for programs attached with `attach_type`.
+    ///
+    /// Returns the mode the programs were attached with and the programs, in the order they run.
+    /// Programs inherited from ancestor cgroups aren't included.
+    pub fn query<T: AsFd>(
+        cgroup: T,
+        attach_type: CgroupSockAttachType,
+    ) -> Result<(CgroupAttachMode, Vec<ProgramInfo>), ProgramError> {
+        query_cgroup(cgroup.as_fd(), attach_type.into())
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -149,6 +160,18 @@ impl Link for CgroupSockLinkInner {
 
 id_as_key!(CgroupSockLinkInner, CgroupSockLinkIdInner);
 
+impl TryFrom<CgroupSockLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(value: CgroupSockLink) -> Result<Self, Self::Error> {
+        if let CgroupSockLinkInner::Fd(fd) = value.into_inner() {
+            Ok(fd)
+        } else {
+            Err(LinkError::InvalidLink)
+        }
+    }
+}
+
 define_link_wrapper!(
     /// The link used by [CgroupSock] programs.
     CgroupSockLink,
@@ -8,8 +8,8 @@ use aya_obj::generated::{
 
 use crate::{
     programs::{
-        define_link_wrapper, id_as_key, load_program, CgroupAttachMode, FdLink, Link,
-        ProgAttachLink, ProgramData, ProgramError,
+        define_link_wrapper, id_as_key, load_program, query_cgroup, CgroupAttachMode, FdLink, Link,
+        LinkError, ProgAttachLink, ProgramData, ProgramError, ProgramInfo,
     },
     sys::{bpf_link_create, LinkTarget, SyscallError},
     util::KernelVersion,
@@ -99,6 +99,14 @@ impl CgroupSysctl {
                 )))
         }
     }
+
+    /// Queries the cgroup for attached programs.
+    ///
+    /// Returns the mode the programs were attached with and the programs, in the order they run.
+    /// Programs inherited from ancestor cgroups aren't included.
+    pub fn query<T: AsFd>(cgroup: T) -> Result<(CgroupAttachMode, Vec<ProgramInfo>), ProgramError> {
+        query_cgroup(cgroup.as_fd(), BPF_CGROUP_SYSCTL)
+    }
 }
 
 #[derive(Debug, Hash, Eq, PartialEq)]
@@ -133,6 +141,18 @@ impl Link for CgroupSysctlLinkInner {
 
 id_as_key!(CgroupSysctlLinkInner, CgroupSysctlLinkIdInner);
 
+impl TryFrom<CgroupSysctlLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(value: CgroupSysctlLink) -> Result<Self, Self::Error> {
+        if let CgroupSysctlLinkInner::Fd(fd) = value.into_inner() {
+            Ok(fd)
+        } else {
+            Err(LinkError::InvalidLink)
+        }
+    }
+}
+
 define_link_wrapper!(
     /// The link used by [CgroupSysctl] programs.
     CgroupSysctlLink,
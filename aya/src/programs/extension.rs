@@ -202,3 +202,144 @@ define_link_wrapper!(
     FdLinkId,
     Extension,
 );
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        io,
+        os::fd::{AsRawFd as _, BorrowedFd},
+        slice,
+    };
+
+    use assert_matches::assert_matches;
+    use aya_obj::{
+        btf::{BtfError, BtfType, Func, FuncLinkage, FuncProto, Int, IntEncoding},
+        generated::{bpf_btf_info, bpf_cmd, bpf_prog_info},
+    };
+    use libc::EFAULT;
+
+    use super::*;
+    use crate::sys::{override_syscall, Syscall};
+
+    const BTF_FD: u32 = crate::MockableFd::mock_unsigned_fd() + 1;
+
+    fn prog_fd() -> BorrowedFd<'static> {
+        unsafe { BorrowedFd::borrow_raw(crate::MockableFd::mock_signed_fd()) }
+    }
+
+    // BTF with `padding` unrelated types followed by `int func_to_replace(void)`.
+    fn fake_btf(padding: usize) -> Vec<u8> {
+        let mut btf = Btf::new();
+        for i in 0..padding {
+            let name_offset = btf.add_string(&format!("pad{i}"));
+            btf.add_type(BtfType::Int(Int::new(
+                name_offset,
+                4,
+                IntEncoding::Signed,
+                0,
+            )));
+        }
+        let name_offset = btf.add_string("int");
+        let int_type_id = btf.add_type(BtfType::Int(Int::new(
+            name_offset,
+            4,
+            IntEncoding::Signed,
+            0,
+        )));
+        let func_proto_type_id =
+            btf.add_type(BtfType::FuncProto(FuncProto::new(vec![], int_type_id)));
+        let name_offset = btf.add_string("func_to_replace");
+        btf.add_type(BtfType::Func(Func::new(
+            name_offset,
+            func_proto_type_id,
+            FuncLinkage::Global,
+        )));
+        btf.to_bytes()
+    }
+
+    thread_local! {
+        // The BTF id and the BTF of the program returned by the fake syscalls.
+        static PROG_BTF: RefCell<(u32, Vec<u8>)> = const { RefCell::new((0, Vec::new())) };
+    }
+
+    fn override_prog_btf(btf_id: u32, btf: Vec<u8>) {
+        PROG_BTF.with(|prog_btf| *prog_btf.borrow_mut() = (btf_id, btf));
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET_INFO_BY_FD,
+                attr,
+            } => {
+                let info = unsafe { &mut attr.info };
+                let (btf_id, btf) = PROG_BTF.with(|prog_btf| prog_btf.borrow().clone());
+                if info.bpf_fd == crate::MockableFd::mock_unsigned_fd() {
+                    let prog_info = unsafe { &mut *(info.info as *mut bpf_prog_info) };
+                    prog_info.btf_id = btf_id;
+                } else {
+                    assert_eq!(info.bpf_fd, BTF_FD);
+                    let btf_info = unsafe { &mut *(info.info as *mut bpf_btf_info) };
+                    if btf_info.btf_size as usize >= btf.len() {
+                        let buf = unsafe {
+                            slice::from_raw_parts_mut(btf_info.btf as *mut u8, btf.len())
+                        };
+                        buf.copy_from_slice(&btf);
+                    }
+                    btf_info.btf_size = btf.len() as u32;
+                }
+                Ok(0)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_BTF_GET_FD_BY_ID,
+                attr,
+            } => {
+                let (btf_id, _) = PROG_BTF.with(|prog_btf| prog_btf.borrow().clone());
+                assert_eq!(
+                    unsafe { attr.__bindgen_anon_6.__bindgen_anon_1.btf_id },
+                    btf_id
+                );
+                Ok(BTF_FD.into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+    }
+
+    #[test]
+    fn test_get_btf_info() {
+        override_prog_btf(42, fake_btf(0));
+
+        let (btf_fd, btf_id) = get_btf_info(prog_fd(), "func_to_replace").unwrap();
+        assert_eq!(btf_fd.as_raw_fd(), BTF_FD as i32);
+        assert_eq!(btf_id, 3);
+    }
+
+    #[test]
+    fn test_get_btf_info_large_btf() {
+        // BTF larger than the initial buffer is read again with a buffer of the right size.
+        let btf = fake_btf(1024);
+        assert!(btf.len() > 4096);
+        override_prog_btf(42, btf);
+
+        let (_, btf_id) = get_btf_info(prog_fd(), "func_to_replace").unwrap();
+        assert_eq!(btf_id, 1024 + 3);
+    }
+
+    #[test]
+    fn test_get_btf_info_unknown_function() {
+        override_prog_btf(42, fake_btf(0));
+
+        assert_matches!(
+            get_btf_info(prog_fd(), "unknown"),
+            Err(ProgramError::Btf(BtfError::UnknownBtfTypeName { type_name })) if type_name == "unknown"
+        );
+    }
+
+    #[test]
+    fn test_get_btf_info_no_btf() {
+        override_prog_btf(0, Vec::new());
+
+        assert_matches!(
+            get_btf_info(prog_fd(), "func_to_replace"),
+            Err(ProgramError::ExtensionError(ExtensionError::NoBTF))
+        );
+    }
+}
@@ -56,6 +56,10 @@ impl FEntry {
     /// Loads the program so it's executed when the kernel function `fn_name`
     /// is entered. The `btf` argument must contain the BTF info for the
     /// running kernel.
+    ///
+    /// The program's attach target is the BTF id of the `fn_name` function, which is typically
+    /// resolved from [`Btf::from_sys_fs`]. Returns [`ProgramError::Btf`] if `btf` doesn't
+    /// contain a function named `fn_name`.
     pub fn load(&mut self, fn_name: &str, btf: &Btf) -> Result<(), ProgramError> {
         self.data.expected_attach_type = Some(BPF_TRACE_FENTRY);
         self.data.attach_btf_id = Some(btf.id_by_type_name_kind(fn_name, BtfKind::Func)?);
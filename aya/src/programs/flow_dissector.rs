@@ -0,0 +1,162 @@
+//! Flow dissector programs.
+
+use std::os::fd::AsFd;
+
+use aya_obj::generated::{
+    bpf_attach_type::BPF_FLOW_DISSECTOR, bpf_prog_type::BPF_PROG_TYPE_FLOW_DISSECTOR,
+};
+
+use crate::{
+    programs::{
+        define_link_wrapper, id_as_key, load_program, CgroupAttachMode, FdLink, Link, LinkError,
+        ProgAttachLink, ProgramData, ProgramError,
+    },
+    sys::{bpf_link_create, LinkTarget, SyscallError},
+    util::KernelVersion,
+};
+
+/// A program that implements custom flow dissection.
+///
+/// [`FlowDissector`] programs replace the kernel's built-in flow dissector for a network
+/// namespace. They parse packet headers and fill in the flow keys used for RPS/RFS, flow
+/// hashing and classification.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.20.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[derive(Debug, thiserror::Error)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     IO(#[from] std::io::Error),
+/// #     #[error(transparent)]
+/// #     Map(#[from] aya::maps::MapError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// #     #[error(transparent)]
+/// #     Ebpf(#[from] aya::EbpfError)
+/// # }
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::programs::FlowDissector;
+///
+/// let netns = std::fs::File::open("/proc/self/ns/net")?;
+/// let program: &mut FlowDissector = bpf.program_mut("dissect").unwrap().try_into()?;
+/// program.load()?;
+/// program.attach(netns)?;
+/// # Ok::<(), Error>(())
+/// ```
+#[derive(Debug)]
+#[doc(alias = "BPF_PROG_TYPE_FLOW_DISSECTOR")]
+pub struct FlowDissector {
+    pub(crate) data: ProgramData<FlowDissectorLink>,
+}
+
+impl FlowDissector {
+    /// Loads the program inside the kernel.
+    pub fn load(&mut self) -> Result<(), ProgramError> {
+        self.data.expected_attach_type = Some(BPF_FLOW_DISSECTOR);
+        load_program(BPF_PROG_TYPE_FLOW_DISSECTOR, &mut self.data)
+    }
+
+    /// Attaches the program to the given network namespace.
+    ///
+    /// On kernels older than 5.8, which don't support flow dissector links, the program is
+    /// attached with `BPF_PROG_ATTACH` instead. Those kernels ignore `netns` and attach the
+    /// program to the network namespace of the calling thread.
+    ///
+    /// The returned value can be used to detach, see [FlowDissector::detach].
+    pub fn attach<T: AsFd>(&mut self, netns: T) -> Result<FlowDissectorLinkId, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
+        let netns_fd = netns.as_fd();
+
+        if KernelVersion::current().unwrap() >= KernelVersion::new(5, 8, 0) {
+            let link_fd = bpf_link_create(
+                prog_fd,
+                LinkTarget::Fd(netns_fd),
+                BPF_FLOW_DISSECTOR,
+                0,
+                None,
+            )
+            .map_err(|(_, io_error)| SyscallError {
+                call: "bpf_link_create",
+                io_error,
+            })?;
+            self.data
+                .links
+                .insert(FlowDissectorLink::new(FlowDissectorLinkInner::Fd(
+                    FdLink::new(link_fd),
+                )))
+        } else {
+            let link = ProgAttachLink::attach(
+                prog_fd,
+                netns_fd,
+                BPF_FLOW_DISSECTOR,
+                CgroupAttachMode::Single,
+            )?;
+
+            self.data
+                .links
+                .insert(FlowDissectorLink::new(FlowDissectorLinkInner::ProgAttach(
+                    link,
+                )))
+        }
+    }
+}
+
+#[derive(Debug, Hash, Eq, PartialEq)]
+enum FlowDissectorLinkIdInner {
+    Fd(<FdLink as Link>::Id),
+    ProgAttach(<ProgAttachLink as Link>::Id),
+}
+
+#[derive(Debug)]
+enum FlowDissectorLinkInner {
+    Fd(FdLink),
+    ProgAttach(ProgAttachLink),
+}
+
+impl Link for FlowDissectorLinkInner {
+    type Id = FlowDissectorLinkIdInner;
+
+    fn id(&self) -> Self::Id {
+        match self {
+            Self::Fd(fd) => FlowDissectorLinkIdInner::Fd(fd.id()),
+            Self::ProgAttach(p) => FlowDissectorLinkIdInner::ProgAttach(p.id()),
+        }
+    }
+
+    fn detach(self) -> Result<(), ProgramError> {
+        match self {
+            Self::Fd(fd) => fd.detach(),
+            Self::ProgAttach(p) => p.detach(),
+        }
+    }
+}
+
+id_as_key!(FlowDissectorLinkInner, FlowDissectorLinkIdInner);
+
+impl TryFrom<FlowDissectorLink> for FdLink {
+    type Error = LinkError;
+
+    fn try_from(value: FlowDissectorLink) -> Result<Self, Self::Error> {
+        if let FlowDissectorLinkInner::Fd(fd) = value.into_inner() {
+            Ok(fd)
+        } else {
+            Err(LinkError::InvalidLink)
+        }
+    }
+}
+
+define_link_wrapper!(
+    /// The link used by [FlowDissector] programs.
+    FlowDissectorLink,
+    /// The type returned by [FlowDissector::attach]. Can be passed to [FlowDissector::detach].
+    FlowDissectorLinkId,
+    FlowDissectorLinkInner,
+    FlowDissectorLinkIdInner,
+    FlowDissector,
+);
@@ -2,12 +2,14 @@
 
 use std::{
     ffi::CString,
+    io, mem,
     os::fd::{AsFd as _, BorrowedFd},
     path::Path,
     time::{Duration, SystemTime},
 };
 
-use aya_obj::generated::{bpf_prog_info, bpf_prog_type};
+use aya_obj::generated::{bpf_insn, bpf_prog_info, bpf_prog_type};
+use libc::EPERM;
 
 use super::{
     utils::{boot_time, get_fdinfo},
@@ -15,7 +17,8 @@ use super::{
 };
 use crate::{
     sys::{
-        bpf_get_object, bpf_prog_get_fd_by_id, bpf_prog_get_info_by_fd, iter_prog_ids, SyscallError,
+        bpf_get_object, bpf_prog_get_fd_by_id, bpf_prog_get_info_by_fd, bpf_prog_get_insns_by_fd,
+        is_unavailable_by_id, iter_prog_ids, SyscallError,
     },
     util::bytes_of_bpf_name,
     FEATURES,
@@ -34,6 +37,17 @@ impl ProgramInfo {
         Ok(Self(info))
     }
 
+    /// Loads program info from a program ID.
+    ///
+    /// Ids are returned by queries such as [`Xdp::query`](crate::programs::Xdp::query) and
+    /// [`CgroupSkb::query`](crate::programs::CgroupSkb::query).
+    ///
+    /// Uses kernel v4.13 features.
+    pub fn from_id(id: u32) -> Result<Self, ProgramError> {
+        let fd = bpf_prog_get_fd_by_id(id)?;
+        Self::new_from_fd(fd.as_fd())
+    }
+
     /// The type of program.
     ///
     /// Introduced in kernel v4.13.
@@ -83,6 +97,56 @@ impl ProgramInfo {
         (self.0.xlated_prog_len > 0).then_some(self.0.xlated_prog_len)
     }
 
+    /// The program's translated eBPF bytecode.
+    ///
+    /// These are the instructions run by the kernel, after the verifier rewrote them, for
+    /// example to inline helper calls or to replace map references with map addresses.
+    ///
+    /// `None` is returned if the field is not available, or if the caller doesn't have
+    /// `CAP_BPF` or `CAP_SYS_ADMIN`.
+    ///
+    /// # Errors
+    ///
+    /// [`ProgramError::SyscallError`] with `EPERM` is returned if the kernel refuses to dump
+    /// the instructions, which contain kernel addresses, because of `kernel.kptr_restrict`.
+    ///
+    /// Introduced in kernel v4.13.
+    pub fn translated_instructions(&self) -> Result<Option<Vec<bpf_insn>>, ProgramError> {
+        if self.0.xlated_prog_len == 0 {
+            return Ok(None);
+        }
+        let len = self.0.xlated_prog_len as usize / mem::size_of::<bpf_insn>();
+        let mut insns = vec![unsafe { mem::zeroed::<bpf_insn>() }; len];
+        let info = bpf_prog_get_insns_by_fd(self.fd()?.as_fd(), &mut insns, &mut [])?;
+        if info.xlated_prog_insns == 0 {
+            return Err(dump_denied_error());
+        }
+        Ok(Some(insns))
+    }
+
+    /// The program's JIT-compiled machine code.
+    ///
+    /// `None` is returned if the program wasn't JIT-compiled, or if the caller doesn't have
+    /// `CAP_BPF` or `CAP_SYS_ADMIN`.
+    ///
+    /// # Errors
+    ///
+    /// [`ProgramError::SyscallError`] with `EPERM` is returned if the kernel refuses to dump
+    /// the machine code, which contains kernel addresses, because of `kernel.kptr_restrict`.
+    ///
+    /// Introduced in kernel v4.13.
+    pub fn jitted_instructions(&self) -> Result<Option<Vec<u8>>, ProgramError> {
+        if self.0.jited_prog_len == 0 {
+            return Ok(None);
+        }
+        let mut code = vec![0u8; self.0.jited_prog_len as usize];
+        let info = bpf_prog_get_insns_by_fd(self.fd()?.as_fd(), &mut [], &mut code)?;
+        if info.jited_prog_insns == 0 {
+            return Err(dump_denied_error());
+        }
+        Ok(Some(code))
+    }
+
     /// The time when the program was loaded.
     ///
     /// `None` is returned if the field is not available.
@@ -272,11 +336,17 @@ pub(crate) use impl_info;
 ///
 /// In cases where iteration can't be performed, for example the caller does not have the necessary
 /// privileges, a single item will be yielded containing the error that occurred.
+///
+/// Programs that are unloaded while iterating, or that the caller isn't allowed to access, are
+/// skipped.
 pub fn loaded_programs() -> impl Iterator<Item = Result<ProgramInfo, ProgramError>> {
     iter_prog_ids()
-        .map(|id| {
-            let id = id?;
-            bpf_prog_get_fd_by_id(id)
+        .filter_map(|id| match id {
+            Ok(id) => match bpf_prog_get_fd_by_id(id) {
+                Err(e) if is_unavailable_by_id(&e) => None,
+                fd => Some(fd),
+            },
+            Err(e) => Some(Err(e)),
         })
         .map(|fd| {
             let fd = fd?;
@@ -285,10 +355,18 @@ pub fn loaded_programs() -> impl Iterator<Item = Result<ProgramInfo, ProgramErro
         .map(|result| result.map(ProgramInfo).map_err(Into::into))
 }
 
+fn dump_denied_error() -> ProgramError {
+    SyscallError {
+        call: "bpf_prog_get_info_by_fd",
+        io_error: io::Error::from_raw_os_error(EPERM),
+    }
+    .into()
+}
+
 /// The type of eBPF program.
 #[non_exhaustive]
 #[doc(alias = "bpf_prog_type")]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum ProgramType {
     /// An unspecified program type.
     Unspecified = bpf_prog_type::BPF_PROG_TYPE_UNSPEC as isize,
@@ -72,6 +72,13 @@ impl KProbe {
     /// Conversely if the program is a `kretprobe`, it is attached to the return address of the
     /// target function.
     ///
+    /// If the kernel has a `kprobe` PMU in `/sys/bus/event_source/devices`, which was added in
+    /// kernel 4.17, the probe is created with `perf_event_open`, setting the PMU's `retprobe` bit
+    /// for `kretprobe` programs. Otherwise, an event with a unique name is added to `kprobe_events`
+    /// in tracefs and removed again when the program is detached. tracefs is looked up at
+    /// `/sys/kernel/tracing`, `/sys/kernel/debug/tracing` and in `/proc/mounts`, unless the
+    /// `AYA_TRACEFS` environment variable is set to its path.
+    ///
     /// The returned value can be used to detach from the given function, see [KProbe::detach].
     pub fn attach<T: AsRef<OsStr>>(
         &mut self,
@@ -14,9 +14,9 @@ use hashbrown::hash_set::{Entry, HashSet};
 use thiserror::Error;
 
 use crate::{
-    pin::PinError,
+    pin::{pin_object, PinError},
     programs::{MultiProgLink, MultiProgram, ProgramError, ProgramFd, ProgramId},
-    sys::{bpf_get_object, bpf_pin_object, bpf_prog_attach, bpf_prog_detach, SyscallError},
+    sys::{bpf_get_object, bpf_link_update, bpf_prog_attach, bpf_prog_detach, SyscallError},
 };
 
 /// A Link.
@@ -27,24 +27,49 @@ pub trait Link: std::fmt::Debug + Eq + std::hash::Hash + 'static {
     /// Returns the link id
     fn id(&self) -> Self::Id;
 
-    /// Detaches the LinkOwnedLink is gone... but this doesn't work :(
+    /// Detaches the link.
+    ///
+    /// Detaching a link doesn't unload the program it belongs to, which can be attached again.
     fn detach(self) -> Result<(), ProgramError>;
 }
 
 /// Program attachment mode.
-#[derive(Clone, Copy, Debug, Default)]
+///
+/// The mode controls whether other programs can be attached to the same cgroup, or to its
+/// descendants, with the same attach type. All the programs attached to a cgroup must use
+/// compatible modes: attaching with [`AllowMultiple`](Self::AllowMultiple) fails if a program
+/// was attached with another mode, and the other way around.
+///
+/// Use [`AllowMultiple`](Self::AllowMultiple) to coexist with programs attached by other
+/// applications. Since attaching to cgroups uses BPF links on kernels 5.7 and newer, detaching
+/// only removes the program that was attached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum CgroupAttachMode {
     /// Allows only one BPF program in the cgroup subtree.
     #[default]
     Single,
 
     /// Allows the program to be overridden by one in a sub-cgroup.
+    #[doc(alias = "BPF_F_ALLOW_OVERRIDE")]
     AllowOverride,
 
     /// Allows multiple programs to be run in the cgroup subtree.
+    #[doc(alias = "BPF_F_ALLOW_MULTI")]
     AllowMultiple,
 }
 
+impl CgroupAttachMode {
+    pub(crate) fn from_flags(flags: u32) -> Self {
+        if flags & BPF_F_ALLOW_MULTI != 0 {
+            Self::AllowMultiple
+        } else if flags & BPF_F_ALLOW_OVERRIDE != 0 {
+            Self::AllowOverride
+        } else {
+            Self::Single
+        }
+    }
+}
+
 impl From<CgroupAttachMode> for u32 {
     fn from(mode: CgroupAttachMode) -> Self {
         match mode {
@@ -113,7 +138,10 @@ pub struct FdLinkId(pub(crate) RawFd);
 ///
 /// Fd links are returned directly when attaching some program types (for
 /// instance [`crate::programs::cgroup_skb::CgroupSkb`]), or can be obtained by
-/// converting other link types (see the `TryFrom` implementations).
+/// converting other link types (see the `TryFrom` implementations). Converting
+/// fails with [`LinkError::InvalidLink`] if the program was attached with a
+/// mechanism that isn't backed by a `bpf_link`, for example `BPF_PROG_ATTACH`
+/// on kernels that don't support `BPF_LINK_CREATE` for the attach type.
 ///
 /// An important property of fd links is that they can be pinned. Pinning
 /// can be used keep a link attached "in background" even after the program
@@ -174,20 +202,54 @@ impl FdLink {
     /// # Ok::<(), Error>(())
     /// ```
     pub fn pin<P: AsRef<Path>>(self, path: P) -> Result<PinnedLink, PinError> {
-        use std::os::unix::ffi::OsStrExt as _;
-
         let path = path.as_ref();
-        let path_string = CString::new(path.as_os_str().as_bytes()).map_err(|error| {
-            PinError::InvalidPinPath {
-                path: path.into(),
-                error,
+        pin_object(self.fd.as_fd(), path)?;
+        Ok(PinnedLink::new(path.into(), self))
+    }
+
+    /// Atomically replaces the program attached through the link with `program`.
+    ///
+    /// The hook keeps running the old program until the new one takes its place, so no events
+    /// are missed. This works for the links of cgroup, XDP, TCX, netns and sk_lookup programs,
+    /// among others; the syscall fails with `EOPNOTSUPP` for links whose program can't be
+    /// replaced, for example those of tracing programs. `program` must be loaded with the same
+    /// type and expected attach type as the program it replaces.
+    ///
+    /// Programs attached without a BPF link can't be replaced with this method. XDP and TC
+    /// programs attached through netlink can be replaced atomically with
+    /// [`Xdp::attach_to_link`](crate::programs::Xdp::attach_to_link) and
+    /// [`SchedClassifier::attach_to_link`](crate::programs::SchedClassifier::attach_to_link).
+    /// Other programs have to be detached and the new program attached, leaving a gap during
+    /// which neither program runs.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// # let mut new_bpf = aya::Ebpf::load(&[])?;
+    /// use aya::programs::{links::FdLink, CgroupAttachMode, CgroupSkb, CgroupSkbAttachType};
+    ///
+    /// let file = std::fs::File::open("/sys/fs/cgroup/unified")?;
+    /// let program: &mut CgroupSkb = bpf.program_mut("filter").unwrap().try_into()?;
+    /// program.load()?;
+    /// let link_id =
+    ///     program.attach(&file, CgroupSkbAttachType::Egress, CgroupAttachMode::Single)?;
+    /// let mut link: FdLink = program.take_link(link_id)?.try_into()?;
+    ///
+    /// // later, after loading an upgraded version of the program
+    /// let new_program: &mut CgroupSkb = new_bpf.program_mut("filter").unwrap().try_into()?;
+    /// new_program.load()?;
+    /// link.replace_program(new_program.fd()?)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn replace_program(&mut self, program: &ProgramFd) -> Result<(), LinkError> {
+        bpf_link_update(self.fd.as_fd(), program.as_fd(), None, 0).map_err(|(_, io_error)| {
+            SyscallError {
+                call: "bpf_link_update",
+                io_error,
             }
         })?;
-        bpf_pin_object(self.fd.as_fd(), &path_string).map_err(|(_, io_error)| SyscallError {
-            call: "BPF_OBJ_PIN",
-            io_error,
-        })?;
-        Ok(PinnedLink::new(path.into(), self))
+        Ok(())
     }
 }
 
@@ -575,16 +637,23 @@ impl LinkOrder {
 
 #[cfg(test)]
 mod tests {
-    use std::{cell::RefCell, fs::File, rc::Rc};
+    use std::{
+        cell::{Cell, RefCell},
+        fs::File,
+        io,
+        os::fd::AsRawFd as _,
+        rc::Rc,
+    };
 
     use assert_matches::assert_matches;
-    use aya_obj::generated::{BPF_F_ALLOW_MULTI, BPF_F_ALLOW_OVERRIDE};
+    use aya_obj::generated::{bpf_cmd, BPF_F_ALLOW_MULTI, BPF_F_ALLOW_OVERRIDE};
+    use libc::{EFAULT, EOPNOTSUPP};
     use tempfile::tempdir;
 
-    use super::{FdLink, Link, Links};
+    use super::{FdLink, Link, LinkError, Links};
     use crate::{
-        programs::{CgroupAttachMode, ProgramError},
-        sys::override_syscall,
+        programs::{CgroupAttachMode, ProgramError, ProgramFd},
+        sys::{override_syscall, Syscall, SyscallError},
     };
 
     #[derive(Debug, Hash, Eq, PartialEq)]
@@ -734,6 +803,42 @@ mod tests {
         assert!(!pin.exists());
     }
 
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_replace_program() {
+        let link_file = tempfile::tempfile().unwrap();
+        let prog_file = tempfile::tempfile().unwrap();
+        thread_local! {
+            static FDS: Cell<(u32, u32)> = const { Cell::new((0, 0)) };
+        }
+        FDS.set((link_file.as_raw_fd() as u32, prog_file.as_raw_fd() as u32));
+        let mut link = FdLink::new(link_file.into());
+        let program = ProgramFd(prog_file.into());
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_LINK_UPDATE,
+                attr,
+            } => {
+                let update = unsafe { &attr.link_update };
+                let new_prog_fd = unsafe { update.__bindgen_anon_1.new_prog_fd };
+                assert_eq!((update.link_fd, new_prog_fd), FDS.get());
+                assert_eq!(update.flags, 0);
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+        link.replace_program(&program).unwrap();
+
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(EOPNOTSUPP))));
+        assert_matches!(
+            link.replace_program(&program),
+            Err(LinkError::SyscallError(SyscallError { call: "bpf_link_update", io_error })) => {
+                assert_eq!(io_error.raw_os_error(), Some(EOPNOTSUPP))
+            }
+        );
+    }
+
     #[test]
     fn test_cgroup_attach_flag() {
         assert_eq!(u32::from(CgroupAttachMode::Single), 0);
@@ -746,4 +851,20 @@ mod tests {
             BPF_F_ALLOW_MULTI
         );
     }
+
+    #[test]
+    fn test_cgroup_attach_mode_from_flags() {
+        for mode in [
+            CgroupAttachMode::Single,
+            CgroupAttachMode::AllowOverride,
+            CgroupAttachMode::AllowMultiple,
+        ] {
+            assert_eq!(CgroupAttachMode::from_flags(mode.into()), mode);
+        }
+        // BPF_F_ALLOW_MULTI implies BPF_F_ALLOW_OVERRIDE
+        assert_eq!(
+            CgroupAttachMode::from_flags(BPF_F_ALLOW_MULTI | BPF_F_ALLOW_OVERRIDE),
+            CgroupAttachMode::AllowMultiple
+        );
+    }
 }
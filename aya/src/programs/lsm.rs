@@ -1,15 +1,31 @@
 //! LSM probes.
 
+use std::fs;
+
 use aya_obj::{
     btf::{Btf, BtfKind},
     generated::{bpf_attach_type::BPF_LSM_MAC, bpf_prog_type::BPF_PROG_TYPE_LSM},
 };
+use thiserror::Error;
 
 use crate::programs::{
     define_link_wrapper, load_program, utils::attach_raw_tracepoint, FdLink, FdLinkId, ProgramData,
     ProgramError,
 };
 
+const ACTIVE_LSMS_PATH: &str = "/sys/kernel/security/lsm";
+
+/// The type returned when attaching an [`Lsm`] program fails.
+#[derive(Debug, Error)]
+pub enum LsmError {
+    /// The BPF LSM is not in the list of active LSMs.
+    #[error("the BPF LSM is not enabled, active LSMs are `{active}`; boot with `lsm=...,bpf`")]
+    BpfLsmNotEnabled {
+        /// The active LSMs, as read from `/sys/kernel/security/lsm`.
+        active: String,
+    },
+}
+
 /// A program that attaches to Linux LSM hooks. Used to implement security policy and
 /// audit logging.
 ///
@@ -71,11 +87,29 @@ impl Lsm {
     /// Attaches the program.
     ///
     /// The returned value can be used to detach, see [Lsm::detach].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LsmError::BpfLsmNotEnabled`] if `/sys/kernel/security/lsm` shows that the BPF
+    /// LSM is not active. The check is skipped if the active LSMs can't be read, for example
+    /// because securityfs is not mounted.
     pub fn attach(&mut self) -> Result<LsmLinkId, ProgramError> {
+        if let Ok(active) = fs::read_to_string(ACTIVE_LSMS_PATH) {
+            if !bpf_lsm_enabled(&active) {
+                return Err(LsmError::BpfLsmNotEnabled {
+                    active: active.trim().to_owned(),
+                }
+                .into());
+            }
+        }
         attach_raw_tracepoint(&mut self.data, None)
     }
 }
 
+fn bpf_lsm_enabled(active: &str) -> bool {
+    active.trim().split(',').any(|lsm| lsm == "bpf")
+}
+
 define_link_wrapper!(
     /// The link used by [Lsm] programs.
     LsmLink,
@@ -85,3 +119,17 @@ define_link_wrapper!(
     FdLinkId,
     Lsm,
 );
+
+#[cfg(test)]
+mod tests {
+    use super::bpf_lsm_enabled;
+
+    #[test]
+    fn test_bpf_lsm_enabled() {
+        assert!(bpf_lsm_enabled("lockdown,capability,yama,bpf\n"));
+        assert!(bpf_lsm_enabled("bpf"));
+        assert!(!bpf_lsm_enabled("lockdown,capability,yama,apparmor\n"));
+        assert!(!bpf_lsm_enabled("lockdown,bpffoo"));
+        assert!(!bpf_lsm_enabled(""));
+    }
+}
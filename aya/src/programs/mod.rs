@@ -29,6 +29,28 @@
 //! The signature of the `attach()` method varies depending on what kind of
 //! program you're trying to attach.
 //!
+//! # Detaching and re-attaching programs
+//!
+//! `attach()` returns a link id that can be passed to `detach()` to detach the
+//! program, or to `take_link()` to take ownership of the link, which is detached
+//! when dropped. Detaching doesn't unload the program, which stays loaded in the
+//! kernel for as long as it's held, so `attach()` can be called again to attach
+//! it to the same or another hook point:
+//!
+//! ```no_run
+//! use aya::{Ebpf, programs::KProbe};
+//!
+//! let mut bpf = Ebpf::load_file("ebpf_programs.o")?;
+//! let program: &mut KProbe = bpf.program_mut("intercept_wakeups").unwrap().try_into()?;
+//! program.load()?;
+//! let link_id = program.attach("try_to_wake_up", 0)?;
+//! // ...
+//! program.detach(link_id)?;
+//! // ...
+//! program.attach("try_to_wake_up", 0)?;
+//! # Ok::<(), aya::EbpfError>(())
+//! ```
+//!
 //! [`Ebpf::load_file`]: crate::Ebpf::load_file
 //! [`Ebpf::load`]: crate::Ebpf::load
 //! [`Ebpf::programs`]: crate::Ebpf::programs
@@ -39,6 +61,7 @@
 // modules we don't export
 mod info;
 mod probe;
+mod test_run;
 mod utils;
 
 // modules we explicitly export so their pub items (Links etc) get exported too
@@ -51,6 +74,7 @@ pub mod cgroup_sysctl;
 pub mod extension;
 pub mod fentry;
 pub mod fexit;
+pub mod flow_dissector;
 pub mod iter;
 pub mod kprobe;
 pub mod links;
@@ -85,8 +109,10 @@ use aya_obj::{
 };
 use info::impl_info;
 pub use info::{loaded_programs, ProgramInfo, ProgramType};
-use libc::ENOSPC;
+use libc::{ENOENT, ENOSPC};
 use tc::SchedClassifierLink;
+use test_run::impl_test_run;
+pub use test_run::{TestRunOptions, TestRunResult};
 use thiserror::Error;
 
 // re-export the main items needed to load and attach
@@ -100,37 +126,38 @@ pub use crate::programs::{
     extension::{Extension, ExtensionError},
     fentry::FEntry,
     fexit::FExit,
+    flow_dissector::FlowDissector,
     iter::Iter,
     kprobe::{KProbe, KProbeError},
     links::{CgroupAttachMode, Link, LinkOrder},
     lirc_mode2::LircMode2,
-    lsm::Lsm,
+    lsm::{Lsm, LsmError},
     perf_event::{PerfEvent, PerfEventScope, PerfTypeId, SamplePolicy},
     probe::ProbeKind,
     raw_trace_point::RawTracePoint,
     sk_lookup::SkLookup,
     sk_msg::SkMsg,
-    sk_skb::{SkSkb, SkSkbKind},
+    sk_skb::{SkSkb, SkSkbKind, SkSkbStreamLinks},
     sock_ops::SockOps,
     socket_filter::{SocketFilter, SocketFilterError},
     tc::{SchedClassifier, TcAttachType, TcError},
     tp_btf::BtfTracePoint,
-    trace_point::{TracePoint, TracePointError},
+    trace_point::{TracePoint, TracePointError, TracePointField},
     uprobe::{UProbe, UProbeError},
-    xdp::{Xdp, XdpError, XdpFlags},
+    xdp::{Xdp, XdpAttachedPrograms, XdpError, XdpFlags},
 };
 use crate::{
     maps::MapError,
-    pin::PinError,
+    pin::{pin_object, PinError},
     programs::{links::*, perf_attach::*},
     sys::{
         bpf_btf_get_fd_by_id, bpf_get_object, bpf_link_get_fd_by_id, bpf_link_get_info_by_fd,
-        bpf_load_program, bpf_pin_object, bpf_prog_get_fd_by_id, bpf_prog_query, iter_link_ids,
+        bpf_load_program, bpf_prog_get_fd_by_id, bpf_prog_query, iter_link_ids,
         retry_with_verifier_logs, EbpfLoadProgramAttrs, NetlinkError, ProgQueryTarget,
         SyscallError,
     },
     util::KernelVersion,
-    VerifierLogLevel,
+    VerifierLogLevel, FEATURES,
 };
 
 /// Error type returned when working with programs.
@@ -162,6 +189,26 @@ pub enum ProgramError {
         verifier_log: VerifierLog,
     },
 
+    /// Loading the program failed because it calls a helper that the kernel doesn't support for
+    /// programs of its type.
+    #[error(
+        "the `{helper}` helper (id {helper_id}) is not supported by {prog_type:?} programs. Verifier output: {verifier_log}"
+    )]
+    HelperNotSupported {
+        /// The name of the helper as reported by the verifier. Helpers that the kernel doesn't
+        /// know about are reported as `unknown`.
+        helper: String,
+        /// The id of the helper.
+        helper_id: u32,
+        /// The type of the program.
+        prog_type: ProgramType,
+        /// The [`io::Error`] returned by the `BPF_PROG_LOAD` syscall.
+        #[source]
+        io_error: io::Error,
+        /// The error log produced by the kernel verifier.
+        verifier_log: VerifierLog,
+    },
+
     /// A syscall failed.
     #[error(transparent)]
     SyscallError(#[from] SyscallError),
@@ -209,6 +256,10 @@ pub enum ProgramError {
     #[error(transparent)]
     ExtensionError(#[from] ExtensionError),
 
+    /// An error occurred while working with an [`Lsm`] program.
+    #[error(transparent)]
+    LsmError(#[from] LsmError),
+
     /// An error occurred while working with BTF.
     #[error(transparent)]
     Btf(#[from] BtfError),
@@ -319,6 +370,8 @@ pub enum Program {
     CgroupDevice(CgroupDevice),
     /// An [`Iter`] program
     Iter(Iter),
+    /// A [`FlowDissector`] program
+    FlowDissector(FlowDissector),
 }
 
 impl Program {
@@ -357,6 +410,7 @@ impl Program {
             Self::SkLookup(_) => ProgramType::SkLookup,
             Self::CgroupSock(_) => ProgramType::CgroupSock,
             Self::CgroupDevice(_) => ProgramType::CgroupDevice,
+            Self::FlowDissector(_) => ProgramType::FlowDissector,
         }
     }
 
@@ -388,6 +442,7 @@ impl Program {
             Self::CgroupSock(p) => p.pin(path),
             Self::CgroupDevice(p) => p.pin(path),
             Self::Iter(p) => p.pin(path),
+            Self::FlowDissector(p) => p.pin(path),
         }
     }
 
@@ -419,12 +474,17 @@ impl Program {
             Self::CgroupSock(mut p) => p.unload(),
             Self::CgroupDevice(mut p) => p.unload(),
             Self::Iter(mut p) => p.unload(),
+            Self::FlowDissector(mut p) => p.unload(),
         }
     }
 
     /// Returns the file descriptor of a program.
     ///
     /// Can be used to add a program to a [`crate::maps::ProgramArray`] or attach an [`Extension`] program.
+    ///
+    /// The file descriptor is borrowed and stays owned by the program. It can be passed to other
+    /// libraries with [`AsFd::as_fd`], or duplicated with [`ProgramFd::try_clone`] when the other
+    /// side needs to own it.
     pub fn fd(&self) -> Result<&ProgramFd, ProgramError> {
         match self {
             Self::KProbe(p) => p.fd(),
@@ -452,6 +512,7 @@ impl Program {
             Self::CgroupSock(p) => p.fd(),
             Self::CgroupDevice(p) => p.fd(),
             Self::Iter(p) => p.fd(),
+            Self::FlowDissector(p) => p.fd(),
         }
     }
 
@@ -459,6 +520,10 @@ impl Program {
     ///
     /// This information is populated at load time by the kernel and can be used
     /// to get kernel details for a given [`Program`].
+    ///
+    /// The run statistics, [`ProgramInfo::run_count`] and [`ProgramInfo::run_time`], are read
+    /// when this is called, and are only updated while
+    /// [`enable_stats`](crate::sys::enable_stats) is in effect.
     pub fn info(&self) -> Result<ProgramInfo, ProgramError> {
         match self {
             Self::KProbe(p) => p.info(),
@@ -486,6 +551,7 @@ impl Program {
             Self::CgroupSock(p) => p.info(),
             Self::CgroupDevice(p) => p.info(),
             Self::Iter(p) => p.info(),
+            Self::FlowDissector(p) => p.info(),
         }
     }
 }
@@ -595,8 +661,6 @@ fn unload_program<T: Link>(data: &mut ProgramData<T>) -> Result<(), ProgramError
 }
 
 fn pin_program<T: Link, P: AsRef<Path>>(data: &ProgramData<T>, path: P) -> Result<(), PinError> {
-    use std::os::unix::ffi::OsStrExt as _;
-
     let fd = data.fd.as_ref().ok_or(PinError::NoFd {
         name: data
             .name
@@ -604,17 +668,7 @@ fn pin_program<T: Link, P: AsRef<Path>>(data: &ProgramData<T>, path: P) -> Resul
             .unwrap_or("<unknown program>")
             .to_string(),
     })?;
-    let path = path.as_ref();
-    let path_string =
-        CString::new(path.as_os_str().as_bytes()).map_err(|error| PinError::InvalidPinPath {
-            path: path.into(),
-            error,
-        })?;
-    bpf_pin_object(fd.as_fd(), &path_string).map_err(|(_, io_error)| SyscallError {
-        call: "BPF_OBJ_PIN",
-        io_error,
-    })?;
-    Ok(())
+    pin_object(fd.as_fd(), path.as_ref())
 }
 
 fn load_program<T: Link>(
@@ -662,17 +716,28 @@ fn load_program<T: Link>(
     let target_kernel_version =
         kernel_version.unwrap_or_else(|| KernelVersion::current().unwrap().code());
 
-    let prog_name = if let Some(name) = name {
-        let mut name = name.clone();
-        if name.len() > 15 {
-            name.truncate(15);
-        }
-        let prog_name = CString::new(name.clone())
-            .map_err(|_| ProgramError::InvalidName { name: name.clone() })?;
-        Some(prog_name)
-    } else {
-        None
-    };
+    // The name is truncated to fit the kernel's limit when the program is loaded.
+    let prog_name = name
+        .as_ref()
+        .map(|name| {
+            CString::new(name.as_str())
+                .map_err(|_| ProgramError::InvalidName { name: name.clone() })
+        })
+        .transpose()?;
+
+    // func_info and line_info were introduced together with BTF_KIND_FUNC, kernels that support
+    // BTF but not functions reject them. The verifier log then doesn't reference source lines.
+    let (func_info_rec_size, func_info, line_info_rec_size, line_info) =
+        if FEATURES.btf().is_some_and(|btf| btf.btf_func()) {
+            (
+                *func_info_rec_size,
+                func_info.clone(),
+                *line_info_rec_size,
+                line_info.clone(),
+            )
+        } else {
+            (0, Default::default(), 0, Default::default())
+        };
 
     let attr = EbpfLoadProgramAttrs {
         name: prog_name,
@@ -685,10 +750,10 @@ fn load_program<T: Link>(
         attach_btf_obj_fd: attach_btf_obj_fd.as_ref().map(|fd| fd.as_fd()),
         attach_btf_id: *attach_btf_id,
         attach_prog_fd: attach_prog_fd.as_ref().map(|fd| fd.as_fd()),
-        func_info_rec_size: *func_info_rec_size,
-        func_info: func_info.clone(),
-        line_info_rec_size: *line_info_rec_size,
-        line_info: line_info.clone(),
+        func_info_rec_size,
+        func_info,
+        line_info_rec_size,
+        line_info,
         flags: *flags,
     };
 
@@ -701,13 +766,47 @@ fn load_program<T: Link>(
             *fd = Some(ProgramFd(prog_fd));
             Ok(())
         }
-        Err((_, io_error)) => Err(ProgramError::LoadError {
-            io_error,
-            verifier_log,
-        }),
+        Err((_, io_error)) => {
+            let log = verifier_log.to_string();
+            match (unsupported_helper(&log), ProgramType::try_from(prog_type)) {
+                (Some((helper, helper_id)), Ok(prog_type)) => {
+                    Err(ProgramError::HelperNotSupported {
+                        helper: helper.to_owned(),
+                        helper_id,
+                        prog_type,
+                        io_error,
+                        verifier_log,
+                    })
+                }
+                _ => Err(ProgramError::LoadError {
+                    io_error,
+                    verifier_log,
+                }),
+            }
+        }
     }
 }
 
+/// Returns the name and id of the helper the verifier rejected the program for, if any.
+///
+/// The verifier reports helpers it doesn't know about as `invalid func unknown#212`, and older
+/// kernels report helpers that can't be used by the program type as `unknown func
+/// bpf_ringbuf_output#130` instead of `program of this type cannot use helper
+/// bpf_ringbuf_output#130`.
+fn unsupported_helper(verifier_log: &str) -> Option<(&str, u32)> {
+    verifier_log.lines().find_map(|line| {
+        let (_, helper) = [
+            "invalid func ",
+            "unknown func ",
+            "program of this type cannot use helper ",
+        ]
+        .iter()
+        .find_map(|msg| line.split_once(msg))?;
+        let (name, id) = helper.trim_end().rsplit_once('#')?;
+        Some((name, id.parse().ok()?))
+    })
+}
+
 pub(crate) fn query(
     target: ProgQueryTarget<'_>,
     attach_type: bpf_attach_type,
@@ -750,6 +849,36 @@ pub(crate) fn query(
     }
 }
 
+// Queries the programs attached directly to a cgroup and looks up their info. Programs that are
+// detached and unloaded before they can be looked up are skipped.
+pub(crate) fn query_cgroup(
+    cgroup_fd: BorrowedFd<'_>,
+    attach_type: bpf_attach_type,
+) -> Result<(CgroupAttachMode, Vec<ProgramInfo>), ProgramError> {
+    let mut attach_flags = Some(0);
+    let (_, prog_ids) = query(
+        ProgQueryTarget::Fd(cgroup_fd),
+        attach_type,
+        0,
+        &mut attach_flags,
+    )?;
+    let mode = CgroupAttachMode::from_flags(attach_flags.unwrap_or_default());
+
+    let prog_infos = prog_ids
+        .into_iter()
+        .filter_map(|prog_id| match ProgramInfo::from_id(prog_id) {
+            Err(ProgramError::SyscallError(SyscallError { io_error, .. }))
+                if io_error.raw_os_error() == Some(ENOENT) =>
+            {
+                None
+            }
+            result => Some(result),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((mode, prog_infos))
+}
+
 macro_rules! impl_program_unload {
     ($($struct_name:ident),+ $(,)?) => {
         $(
@@ -799,6 +928,7 @@ impl_program_unload!(
     CgroupSock,
     CgroupDevice,
     Iter,
+    FlowDissector,
 );
 
 macro_rules! impl_fd {
@@ -840,6 +970,7 @@ impl_fd!(
     CgroupSock,
     CgroupDevice,
     Iter,
+    FlowDissector,
 );
 
 /// Trait implemented by the [`Program`] types which support the kernel's
@@ -903,6 +1034,7 @@ macro_rules! impl_program_pin{
                 /// Aya has unloaded the program.
                 /// To remove the program, the file on the BPF filesystem must be removed.
                 /// Any directories in the the path provided should have been created by the caller.
+                /// [`PinError::NotBpfFs`] is returned if the path is not on a BPF filesystem.
                 pub fn pin<P: AsRef<Path>>(&mut self, path: P) -> Result<(), PinError> {
                     self.data.path = Some(path.as_ref().to_path_buf());
                     pin_program(&self.data, path)
@@ -946,6 +1078,7 @@ impl_program_pin!(
     CgroupSock,
     CgroupDevice,
     Iter,
+    FlowDissector,
 );
 
 macro_rules! impl_from_pin {
@@ -985,6 +1118,7 @@ impl_from_pin!(
     SockOps,
     CgroupDevice,
     Iter,
+    FlowDissector,
 );
 
 macro_rules! impl_try_from_program {
@@ -1041,6 +1175,7 @@ impl_try_from_program!(
     CgroupSock,
     CgroupDevice,
     Iter,
+    FlowDissector,
 );
 
 impl_info!(
@@ -1069,6 +1204,19 @@ impl_info!(
     CgroupSock,
     CgroupDevice,
     Iter,
+    FlowDissector,
+);
+
+impl_test_run!(
+    SocketFilter,
+    Xdp,
+    SchedClassifier,
+    CgroupSkb,
+    RawTracePoint,
+    FEntry,
+    FExit,
+    SkLookup,
+    FlowDissector,
 );
 
 // TODO(https://github.com/aya-rs/aya/issues/645): this API is currently used in tests. Stabilize
@@ -1086,3 +1234,141 @@ pub fn loaded_links() -> impl Iterator<Item = Result<bpf_link_info, ProgramError
         })
         .map(|result| result.map_err(Into::into))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{io, os::fd::BorrowedFd};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{bpf_attach_type::BPF_CGROUP_INET_EGRESS, bpf_cmd, BPF_F_ALLOW_MULTI};
+    use libc::EFAULT;
+
+    use super::*;
+    use crate::sys::{override_syscall, Syscall};
+
+    #[test]
+    fn test_query_cgroup_skips_unloaded_programs() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_QUERY,
+                attr,
+            } => {
+                let query = unsafe { &mut attr.query };
+                assert_eq!(query.attach_type, BPF_CGROUP_INET_EGRESS as u32);
+                let prog_ids =
+                    unsafe { std::slice::from_raw_parts_mut(query.prog_ids as *mut u32, 2) };
+                prog_ids.copy_from_slice(&[1, 2]);
+                query.__bindgen_anon_2.prog_cnt = 2;
+                query.attach_flags = BPF_F_ALLOW_MULTI;
+                Ok(0)
+            }
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_GET_FD_BY_ID,
+                attr,
+            } => match unsafe { attr.__bindgen_anon_6.__bindgen_anon_1.prog_id } {
+                // program 1 was unloaded after the query
+                1 => Err((-1, io::Error::from_raw_os_error(ENOENT))),
+                _ => Ok(crate::MockableFd::mock_signed_fd().into()),
+            },
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_OBJ_GET_INFO_BY_FD,
+                attr,
+            } => {
+                let info = unsafe { &mut *(attr.info.info as *mut bpf_prog_info) };
+                info.id = 2;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let cgroup_fd = unsafe { BorrowedFd::borrow_raw(42) };
+        let (mode, programs) = query_cgroup(cgroup_fd, BPF_CGROUP_INET_EGRESS).unwrap();
+        assert_matches!(mode, CgroupAttachMode::AllowMultiple);
+        assert_eq!(
+            programs.iter().map(ProgramInfo::id).collect::<Vec<_>>(),
+            [2]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_helper() {
+        assert_eq!(
+            unsupported_helper(
+                "0: R1=ctx() R10=fp0\n0: (85) call bpf_ringbuf_output#130\nunknown func bpf_ringbuf_output#130\nprocessed 1 insns"
+            ),
+            Some(("bpf_ringbuf_output", 130))
+        );
+        assert_eq!(
+            unsupported_helper(
+                "0: (85) call bpf_get_current_task#35\nprogram of this type cannot use helper bpf_get_current_task#35\n"
+            ),
+            Some(("bpf_get_current_task", 35))
+        );
+        assert_eq!(
+            unsupported_helper("0: (85) call unknown#212\ninvalid func unknown#212\n"),
+            Some(("unknown", 212))
+        );
+        assert_eq!(
+            unsupported_helper("0: (b7) r0 = 0\n1: (95) exit\nR0 !read_ok\n"),
+            None
+        );
+        assert_eq!(unsupported_helper(""), None);
+    }
+
+    #[test]
+    fn test_load_program_helper_not_supported() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                let u = unsafe { &mut attr.__bindgen_anon_3 };
+                if u.log_size != 0 {
+                    let log = b"unknown func bpf_ringbuf_output#130\n\0";
+                    let log_buf = unsafe {
+                        std::slice::from_raw_parts_mut(u.log_buf as *mut u8, u.log_size as usize)
+                    };
+                    log_buf[..log.len()].copy_from_slice(log);
+                }
+                Err((-1, io::Error::from_raw_os_error(libc::EINVAL)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let obj = (
+            aya_obj::Program {
+                license: c"GPL".to_owned(),
+                kernel_version: Some(0),
+                section: aya_obj::ProgramSection::KProbe,
+                section_index: 0,
+                address: 0,
+            },
+            aya_obj::Function {
+                address: 0,
+                name: "test".to_owned(),
+                section_index: object::SectionIndex(0),
+                section_offset: 0,
+                instructions: Vec::new(),
+                func_info: Default::default(),
+                line_info: Default::default(),
+                func_info_rec_size: 0,
+                line_info_rec_size: 0,
+            },
+        );
+        let mut data = ProgramData::<kprobe::KProbeLink>::new(
+            Some("test".to_owned()),
+            obj,
+            None,
+            VerifierLogLevel::default(),
+        );
+        assert_matches!(
+            load_program(bpf_prog_type::BPF_PROG_TYPE_KPROBE, &mut data),
+            Err(ProgramError::HelperNotSupported {
+                helper,
+                helper_id: 130,
+                prog_type: ProgramType::KProbe,
+                ..
+            }) => assert_eq!(helper, "bpf_ringbuf_output")
+        );
+    }
+}
@@ -72,9 +72,10 @@ impl Link for PerfLink {
     fn detach(self) -> Result<(), ProgramError> {
         let Self { perf_fd, event } = self;
         let _: SysResult<_> = perf_event_ioctl(perf_fd.as_fd(), PERF_EVENT_IOC_DISABLE, 0);
-        if let Some(event) = event {
-            let _: Result<_, _> = detach_debug_fs(event);
-        }
+        // The kernel refuses to remove a probe event that is still in use, so the perf event must
+        // be closed first.
+        drop(perf_fd);
+        event.map(detach_debug_fs).transpose()?;
 
         Ok(())
     }
@@ -121,18 +122,29 @@ fn perf_attach_either(
     fd: crate::MockableFd,
     event: Option<ProbeEvent>,
 ) -> Result<PerfLinkInner, ProgramError> {
-    perf_event_ioctl(fd.as_fd(), PERF_EVENT_IOC_SET_BPF, prog_fd.as_raw_fd()).map_err(
-        |(_, io_error)| SyscallError {
-            call: "PERF_EVENT_IOC_SET_BPF",
-            io_error,
-        },
-    )?;
-    perf_event_ioctl(fd.as_fd(), PERF_EVENT_IOC_ENABLE, 0).map_err(|(_, io_error)| {
-        SyscallError {
-            call: "PERF_EVENT_IOC_ENABLE",
-            io_error,
-        }
-    })?;
+    let link = PerfLink { perf_fd: fd, event };
+    let attached = perf_event_ioctl(
+        link.perf_fd.as_fd(),
+        PERF_EVENT_IOC_SET_BPF,
+        prog_fd.as_raw_fd(),
+    )
+    .map_err(|(_, io_error)| SyscallError {
+        call: "PERF_EVENT_IOC_SET_BPF",
+        io_error,
+    })
+    .and_then(|_| {
+        perf_event_ioctl(link.perf_fd.as_fd(), PERF_EVENT_IOC_ENABLE, 0).map_err(|(_, io_error)| {
+            SyscallError {
+                call: "PERF_EVENT_IOC_ENABLE",
+                io_error,
+            }
+        })
+    });
+    if let Err(err) = attached {
+        // Don't leak the probe event if attaching fails.
+        let _: Result<_, _> = link.detach();
+        return Err(err.into());
+    }
 
-    Ok(PerfLinkInner::PerfLink(PerfLink { perf_fd: fd, event }))
+    Ok(PerfLinkInner::PerfLink(link))
 }
@@ -45,9 +45,10 @@ pub enum PerfTypeId {
 /// Sample Policy
 #[derive(Debug, Clone)]
 pub enum SamplePolicy {
-    /// Period
+    /// Period: the program runs every `n` occurrences of the event
     Period(u64),
-    /// Frequency
+    /// Frequency: the kernel adjusts the period so that the program runs about `n` times per
+    /// second, which is what CPU profilers usually want
     Frequency(u64),
 }
 
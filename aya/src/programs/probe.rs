@@ -18,11 +18,13 @@ use crate::{
         Link, ProgramData, ProgramError,
     },
     sys::{perf_event_open_probe, perf_event_open_trace_point, SyscallError},
-    util::KernelVersion,
 };
 
 static PROBE_NAME_INDEX: AtomicUsize = AtomicUsize::new(0);
 
+// See MAX_EVENT_NAME_LEN in kernel/trace/trace.h.
+const MAX_EVENT_NAME_LEN: usize = 64;
+
 /// Kind of probe program
 #[derive(Debug, Copy, Clone)]
 pub enum ProbeKind {
@@ -114,26 +116,30 @@ pub(crate) fn attach<T: Link + From<PerfLinkInner>>(
     pid: Option<pid_t>,
     cookie: Option<u64>,
 ) -> Result<T::Id, ProgramError> {
-    // https://github.com/torvalds/linux/commit/e12f03d7031a977356e3d7b75a68c2185ff8d155
-    // Use debugfs to create probe
     let prog_fd = program_data.fd()?;
     let prog_fd = prog_fd.as_fd();
-    let link = if KernelVersion::current().unwrap() < KernelVersion::new(4, 17, 0) {
-        if cookie.is_some() {
-            return Err(ProgramError::AttachCookieNotSupported);
+    // https://github.com/torvalds/linux/commit/e12f03d7031a977356e3d7b75a68c2185ff8d155
+    // The kprobe and uprobe PMUs were added in 4.17, and can be used to create probes with
+    // perf_event_open. Without them, probes are created by writing to `{k,u}probe_events` in
+    // tracefs. Backports make the kernel version unreliable, so check for the PMU instead.
+    let link = match read_sys_fs_perf_type(kind.pmu()) {
+        Ok(perf_ty) => {
+            let fd = create_as_probe(kind, perf_ty, fn_name, offset, pid)?;
+            perf_attach(prog_fd, fd, cookie)
+        }
+        Err((_, io_error)) if io_error.kind() == io::ErrorKind::NotFound => {
+            if cookie.is_some() {
+                return Err(ProgramError::AttachCookieNotSupported);
+            }
+            let (fd, event_alias) = create_as_trace_point(kind, fn_name, offset, pid)?;
+            perf_attach_debugfs(prog_fd, fd, ProbeEvent { kind, event_alias })
         }
-        let (fd, event_alias) = create_as_trace_point(kind, fn_name, offset, pid)?;
-        perf_attach_debugfs(prog_fd, fd, ProbeEvent { kind, event_alias })
-    } else {
-        let fd = create_as_probe(kind, fn_name, offset, pid)?;
-        perf_attach(prog_fd, fd, cookie)
+        Err((filename, io_error)) => Err(probe_file_error(kind, filename, io_error)),
     }?;
     program_data.links.insert(T::from(link))
 }
 
 pub(crate) fn detach_debug_fs(event: ProbeEvent) -> Result<(), ProgramError> {
-    use ProbeKind::*;
-
     let tracefs = find_tracefs_path()?;
 
     let ProbeEvent {
@@ -143,37 +149,33 @@ pub(crate) fn detach_debug_fs(event: ProbeEvent) -> Result<(), ProgramError> {
     let kind = *kind;
     let result = delete_probe_event(tracefs, event);
 
-    result.map_err(|(filename, io_error)| match kind {
+    result.map_err(|(filename, io_error)| probe_file_error(kind, filename, io_error))
+}
+
+fn probe_file_error(kind: ProbeKind, filename: PathBuf, io_error: io::Error) -> ProgramError {
+    use ProbeKind::*;
+
+    match kind {
         KProbe | KRetProbe => KProbeError::FileError { filename, io_error }.into(),
         UProbe | URetProbe => UProbeError::FileError { filename, io_error }.into(),
-    })
+    }
 }
 
 fn create_as_probe(
     kind: ProbeKind,
+    perf_ty: u32,
     fn_name: &OsStr,
     offset: u64,
     pid: Option<pid_t>,
 ) -> Result<crate::MockableFd, ProgramError> {
     use ProbeKind::*;
 
-    let perf_ty = match kind {
-        KProbe | KRetProbe => read_sys_fs_perf_type(kind.pmu())
-            .map_err(|(filename, io_error)| KProbeError::FileError { filename, io_error })?,
-        UProbe | URetProbe => read_sys_fs_perf_type(kind.pmu())
-            .map_err(|(filename, io_error)| UProbeError::FileError { filename, io_error })?,
-    };
-
     let ret_bit = match kind {
-        KRetProbe => Some(
-            read_sys_fs_perf_ret_probe(kind.pmu())
-                .map_err(|(filename, io_error)| KProbeError::FileError { filename, io_error })?,
-        ),
-        URetProbe => Some(
+        KRetProbe | URetProbe => Some(
             read_sys_fs_perf_ret_probe(kind.pmu())
-                .map_err(|(filename, io_error)| UProbeError::FileError { filename, io_error })?,
+                .map_err(|(filename, io_error)| probe_file_error(kind, filename, io_error))?,
         ),
-        _ => None,
+        KProbe | UProbe => None,
     };
 
     perf_event_open_probe(perf_ty, ret_bit, fn_name, offset, pid).map_err(|(_code, io_error)| {
@@ -191,25 +193,29 @@ fn create_as_trace_point(
     offset: u64,
     pid: Option<pid_t>,
 ) -> Result<(crate::MockableFd, OsString), ProgramError> {
-    use ProbeKind::*;
-
     let tracefs = find_tracefs_path()?;
 
-    let event_alias = match kind {
-        KProbe | KRetProbe => create_probe_event(tracefs, kind, name, offset)
-            .map_err(|(filename, io_error)| KProbeError::FileError { filename, io_error })?,
-        UProbe | URetProbe => create_probe_event(tracefs, kind, name, offset)
-            .map_err(|(filename, io_error)| UProbeError::FileError { filename, io_error })?,
-    };
+    let event_alias = create_probe_event(tracefs, kind, name, offset)
+        .map_err(|(filename, io_error)| probe_file_error(kind, filename, io_error))?;
 
     let category = format!("{}s", kind.pmu());
-    let tpid = read_sys_fs_trace_point_id(tracefs, &category, event_alias.as_ref())?;
-    let fd = perf_event_open_trace_point(tpid, pid).map_err(|(_code, io_error)| SyscallError {
-        call: "perf_event_open",
-        io_error,
-    })?;
-
-    Ok((fd, event_alias))
+    let open = || -> Result<_, ProgramError> {
+        let tpid = read_sys_fs_trace_point_id(tracefs, &category, event_alias.as_ref())?;
+        let fd =
+            perf_event_open_trace_point(tpid, pid).map_err(|(_code, io_error)| SyscallError {
+                call: "perf_event_open",
+                io_error,
+            })?;
+        Ok(fd)
+    };
+    match open() {
+        Ok(fd) => Ok((fd, event_alias)),
+        Err(err) => {
+            // Don't leak the probe event if it can't be opened.
+            let _: Result<_, _> = delete_probe_event(tracefs, ProbeEvent { kind, event_alias });
+            Err(err)
+        }
+    }
 }
 
 fn create_probe_event(
@@ -228,28 +234,28 @@ fn create_probe_event(
         KRetProbe | URetProbe => 'r',
     };
 
-    let mut event_alias = OsString::new();
-    write!(
-        &mut event_alias,
-        "aya_{}_{}_",
-        process::id(),
-        probe_type_prefix,
-    )
-    .unwrap();
-    for b in fn_name.as_bytes() {
-        let b = match *b {
-            b'.' | b'/' | b'-' => b'_',
-            b => b,
-        };
-        event_alias.push(OsStr::from_bytes(&[b]));
-    }
-    write!(
-        &mut event_alias,
+    // The kernel only accepts event names shorter than MAX_EVENT_NAME_LEN made of alphanumerics
+    // and underscores. The pid and the counter keep the names unique, so when the name of the
+    // target doesn't fit only its end, which is the most specific part of a path, is kept.
+    let prefix = format!("aya_{}_{}_", process::id(), probe_type_prefix);
+    let suffix = format!(
         "_{:#x}_{}",
         offset,
         PROBE_NAME_INDEX.fetch_add(1, Ordering::AcqRel)
-    )
-    .unwrap();
+    );
+    let name = fn_name.as_bytes();
+    let max_len = (MAX_EVENT_NAME_LEN - 1).saturating_sub(prefix.len() + suffix.len());
+    let name = &name[name.len().saturating_sub(max_len)..];
+    let mut event_alias = prefix;
+    event_alias.extend(name.iter().map(|&b| {
+        if b.is_ascii_alphanumeric() {
+            char::from(b)
+        } else {
+            '_'
+        }
+    }));
+    event_alias.push_str(&suffix);
+    let event_alias = OsString::from(event_alias);
 
     let mut probe = OsString::new();
     write!(&mut probe, "{}:{}s/", probe_type_prefix, kind.pmu(),).unwrap();
@@ -293,7 +299,11 @@ fn delete_probe_event(tracefs: &Path, event: ProbeEvent) -> Result<(), (PathBuf,
                         Some((b, rest)) => {
                             line = rest;
                             if *b == b'/' {
-                                break line.starts_with(event_alias.as_bytes());
+                                // The alias is followed by the probe location, don't match
+                                // another event it's a prefix of.
+                                break line
+                                    .strip_prefix(event_alias.as_bytes())
+                                    .is_some_and(|rest| matches!(rest.first(), None | Some(b' ')));
                             }
                         }
                     }
@@ -352,3 +362,137 @@ fn read_sys_fs_perf_ret_probe(pmu: &str) -> Result<u32, (PathBuf, io::Error)> {
         })
         .map_err(|e| (file, e))
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_create_and_delete_probe_event() {
+        let tracefs = tempdir().unwrap();
+        let events_file = tracefs.path().join("kprobe_events");
+        fs::write(&events_file, "").unwrap();
+
+        let entry = create_probe_event(
+            tracefs.path(),
+            ProbeKind::KProbe,
+            OsStr::new("do_sys.open"),
+            8,
+        )
+        .unwrap();
+        let ret = create_probe_event(
+            tracefs.path(),
+            ProbeKind::KRetProbe,
+            OsStr::new("do_sys.open"),
+            0,
+        )
+        .unwrap();
+        assert_ne!(entry, ret);
+
+        let pid = process::id();
+        let entry_prefix = format!("aya_{pid}_p_do_sys_open_0x8_");
+        let ret_prefix = format!("aya_{pid}_r_do_sys_open_0x0_");
+        assert!(entry.starts_with(OsStr::new(&entry_prefix)), "{entry:?}");
+        assert!(ret.starts_with(OsStr::new(&ret_prefix)), "{ret:?}");
+
+        // the return probe is created with the `r` type and without an offset
+        let events = fs::read_to_string(&events_file).unwrap();
+        assert_eq!(
+            events,
+            format!(
+                "p:kprobes/{} do_sys.open+8\nr:kprobes/{} do_sys.open\n",
+                entry.to_str().unwrap(),
+                ret.to_str().unwrap()
+            )
+        );
+
+        delete_probe_event(
+            tracefs.path(),
+            ProbeEvent {
+                kind: ProbeKind::KRetProbe,
+                event_alias: ret.clone(),
+            },
+        )
+        .unwrap();
+        let removed = fs::read_to_string(&events_file).unwrap();
+        assert_eq!(removed, format!("{events}-:{}\n", ret.to_str().unwrap()));
+
+        // events that don't exist anymore aren't removed again
+        delete_probe_event(
+            tracefs.path(),
+            ProbeEvent {
+                kind: ProbeKind::KProbe,
+                event_alias: OsString::from("aya_unknown"),
+            },
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&events_file).unwrap(), removed);
+
+        // an event whose alias the removed one is a prefix of isn't mistaken for it
+        let entry_prefix = OsString::from(entry.to_str().unwrap().rsplit_once('_').unwrap().0);
+        delete_probe_event(
+            tracefs.path(),
+            ProbeEvent {
+                kind: ProbeKind::KProbe,
+                event_alias: entry_prefix,
+            },
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&events_file).unwrap(), removed);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_create_probe_event_long_name() {
+        let tracefs = tempdir().unwrap();
+        let events_file = tracefs.path().join("uprobe_events");
+        fs::write(&events_file, "").unwrap();
+
+        let path = "/opt/some-vendor/product@1.2/lib/x86_64-linux-gnu/libverylongname.so.1";
+        let alias =
+            create_probe_event(tracefs.path(), ProbeKind::UProbe, OsStr::new(path), 0x10).unwrap();
+        let alias = alias.to_str().unwrap();
+
+        assert!(alias.len() < MAX_EVENT_NAME_LEN, "{alias}");
+        assert!(
+            alias
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_'),
+            "{alias}"
+        );
+        // the end of the path is kept
+        assert!(alias.contains("_libverylongname_so_1_0x10_"), "{alias}");
+        assert_eq!(
+            fs::read_to_string(&events_file).unwrap(),
+            format!("p:uprobes/{alias} {path}:0x10\n")
+        );
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "`open` not available when isolation is enabled")]
+    fn test_create_uretprobe_event() {
+        let tracefs = tempdir().unwrap();
+        let events_file = tracefs.path().join("uprobe_events");
+        fs::write(&events_file, "").unwrap();
+
+        let alias = create_probe_event(
+            tracefs.path(),
+            ProbeKind::URetProbe,
+            OsStr::new("/usr/lib/libc.so.6"),
+            0x1234,
+        )
+        .unwrap();
+
+        // uprobes and uretprobes both take an offset into the binary
+        assert_eq!(
+            fs::read_to_string(&events_file).unwrap(),
+            format!(
+                "r:uprobes/{} /usr/lib/libc.so.6:0x1234\n",
+                alias.to_str().unwrap()
+            )
+        );
+    }
+}
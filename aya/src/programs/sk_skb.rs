@@ -10,8 +10,8 @@ use aya_obj::generated::{
 use crate::{
     maps::sock::SockMapFd,
     programs::{
-        define_link_wrapper, load_program, CgroupAttachMode, ProgAttachLink, ProgAttachLinkId,
-        ProgramData, ProgramError,
+        define_link_wrapper, load_program, CgroupAttachMode, Link as _, ProgAttachLink,
+        ProgAttachLinkId, ProgramData, ProgramError,
     },
     VerifierLogLevel,
 };
@@ -97,6 +97,85 @@ impl SkSkb {
         self.data.links.insert(SkSkbLink::new(link))
     }
 
+    /// Attaches a stream parser and a stream verdict program to the given socket map.
+    ///
+    /// The parser splits the incoming byte stream of each socket into messages, and the verdict
+    /// program then decides what to do with every message. The parser is attached first with
+    /// `BPF_SK_SKB_STREAM_PARSER`, followed by the verdict with `BPF_SK_SKB_STREAM_VERDICT`. If
+    /// attaching the verdict fails, the parser is detached again.
+    ///
+    /// The kernel only sets up stream parsing for sockets inserted into the map while both
+    /// programs are attached, so this should be called before the map is populated.
+    ///
+    /// Both links are owned by the returned [`SkSkbStreamLinks`] rather than by the programs.
+    /// Dropping it detaches the verdict, then the parser.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProgramError::UnexpectedProgramType`] if `parser` isn't a
+    /// [`SkSkbKind::StreamParser`] or `verdict` isn't a [`SkSkbKind::StreamVerdict`] program.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(Debug, thiserror::Error)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     IO(#[from] std::io::Error),
+    /// #     #[error(transparent)]
+    /// #     Map(#[from] aya::maps::MapError),
+    /// #     #[error(transparent)]
+    /// #     Program(#[from] aya::programs::ProgramError),
+    /// #     #[error(transparent)]
+    /// #     Ebpf(#[from] aya::EbpfError)
+    /// # }
+    /// # let mut bpf = aya::Ebpf::load(&[])?;
+    /// use aya::maps::SockMap;
+    /// use aya::programs::SkSkb;
+    ///
+    /// let sock_map: SockMap<_> = bpf.take_map("SOCK_MAP").unwrap().try_into()?;
+    /// let map_fd = sock_map.fd().try_clone()?;
+    ///
+    /// let (mut parser, mut verdict) = (None, None);
+    /// for (name, program) in bpf.programs_mut() {
+    ///     match name {
+    ///         "parser" => parser = Some(program),
+    ///         "verdict" => verdict = Some(program),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// let parser: &mut SkSkb = parser.unwrap().try_into()?;
+    /// let verdict: &mut SkSkb = verdict.unwrap().try_into()?;
+    /// parser.load()?;
+    /// verdict.load()?;
+    ///
+    /// let links = SkSkb::attach_stream(parser, verdict, &map_fd)?;
+    /// // insert sockets into `sock_map`
+    /// # Ok::<(), Error>(())
+    /// ```
+    pub fn attach_stream(
+        parser: &mut Self,
+        verdict: &mut Self,
+        map: &SockMapFd,
+    ) -> Result<SkSkbStreamLinks, ProgramError> {
+        if !matches!(parser.kind, SkSkbKind::StreamParser)
+            || !matches!(verdict.kind, SkSkbKind::StreamVerdict)
+        {
+            return Err(ProgramError::UnexpectedProgramType);
+        }
+
+        let parser_id = parser.attach(map)?;
+        let parser_link = parser.take_link(parser_id)?;
+        // If this fails `parser_link` is dropped, which detaches the parser.
+        let verdict_id = verdict.attach(map)?;
+        let verdict_link = verdict.take_link(verdict_id)?;
+
+        Ok(SkSkbStreamLinks {
+            verdict: verdict_link,
+            parser: parser_link,
+        })
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -109,6 +188,29 @@ impl SkSkb {
     }
 }
 
+/// The links returned by [`SkSkb::attach_stream`].
+///
+/// Dropping this detaches the verdict program, then the parser.
+#[derive(Debug)]
+pub struct SkSkbStreamLinks {
+    // Fields are dropped in declaration order, so the verdict goes first.
+    verdict: SkSkbLink,
+    parser: SkSkbLink,
+}
+
+impl SkSkbStreamLinks {
+    /// Detaches the verdict program, then the parser.
+    ///
+    /// The parser is detached even if detaching the verdict fails, in which case the verdict
+    /// error is returned.
+    pub fn detach(self) -> Result<(), ProgramError> {
+        let Self { verdict, parser } = self;
+        let verdict = verdict.detach();
+        let parser = parser.detach();
+        verdict.and(parser)
+    }
+}
+
 define_link_wrapper!(
     /// The link used by [SkSkb] programs.
     SkSkbLink,
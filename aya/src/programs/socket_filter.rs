@@ -5,7 +5,8 @@ use std::{
 };
 
 use aya_obj::generated::{
-    bpf_prog_type::BPF_PROG_TYPE_SOCKET_FILTER, SO_ATTACH_BPF, SO_DETACH_BPF,
+    bpf_prog_type::BPF_PROG_TYPE_SOCKET_FILTER, SO_ATTACH_BPF, SO_ATTACH_REUSEPORT_EBPF,
+    SO_DETACH_BPF, SO_DETACH_REUSEPORT_BPF,
 };
 use libc::{setsockopt, SOL_SOCKET};
 use thiserror::Error;
@@ -22,6 +23,14 @@ pub enum SocketFilterError {
         #[source]
         io_error: io::Error,
     },
+
+    /// Setting the `SO_ATTACH_REUSEPORT_EBPF` socket option failed.
+    #[error("setsockopt SO_ATTACH_REUSEPORT_EBPF failed")]
+    SoAttachReuseportEbpfError {
+        /// original [`io::Error`]
+        #[source]
+        io_error: io::Error,
+    },
 }
 
 /// A program used to inspect and filter incoming packets on a socket.
@@ -71,36 +80,73 @@ impl SocketFilter {
 
     /// Attaches the filter on the given socket.
     ///
+    /// Any socket can be used, including raw `AF_PACKET` sockets created with [`libc::socket`];
+    /// a raw file descriptor can be passed with [`BorrowedFd::borrow_raw`]. The program is
+    /// attached with the `SO_ATTACH_BPF` socket option and detached with `SO_DETACH_BPF`. The
+    /// link doesn't own the socket: the socket must outlive the link for the detach to succeed.
+    ///
     /// The returned value can be used to detach from the socket, see [SocketFilter::detach].
+    ///
+    /// [`BorrowedFd::borrow_raw`]: std::os::fd::BorrowedFd::borrow_raw
     pub fn attach<T: AsFd>(&mut self, socket: T) -> Result<SocketFilterLinkId, ProgramError> {
+        self.attach_with(socket, SocketFilterAttachType::Filter)
+    }
+
+    /// Attaches the program to the `SO_REUSEPORT` group of the given socket.
+    ///
+    /// The program selects which socket of the group receives each incoming packet or
+    /// connection by returning its index in the group. Returning an index that is out of range
+    /// falls back to the default hash-based selection. The program is attached with the
+    /// `SO_ATTACH_REUSEPORT_EBPF` socket option and applies to the whole group, not just to
+    /// `socket`. The socket must have `SO_REUSEPORT` set and be bound.
+    ///
+    /// Detaching uses `SO_DETACH_REUSEPORT_BPF`, which requires kernel 5.3. On older kernels
+    /// the program stays attached until it's replaced or all the sockets of the group are closed.
+    ///
+    /// The returned value can be used to detach from the socket, see [SocketFilter::detach].
+    ///
+    /// # Minimum kernel version
+    ///
+    /// The minimum kernel version required to use this feature is 4.5.
+    pub fn attach_reuseport<T: AsFd>(
+        &mut self,
+        socket: T,
+    ) -> Result<SocketFilterLinkId, ProgramError> {
+        self.attach_with(socket, SocketFilterAttachType::Reuseport)
+    }
+
+    fn attach_with<T: AsFd>(
+        &mut self,
+        socket: T,
+        attach_type: SocketFilterAttachType,
+    ) -> Result<SocketFilterLinkId, ProgramError> {
         let prog_fd = self.fd()?;
         let prog_fd = prog_fd.as_fd();
         let prog_fd = prog_fd.as_raw_fd();
         let socket = socket.as_fd();
         let socket = socket.as_raw_fd();
 
-        let ret = unsafe {
-            setsockopt(
-                socket,
-                SOL_SOCKET,
-                SO_ATTACH_BPF as i32,
-                &prog_fd as *const _ as *const _,
-                mem::size_of::<RawFd>() as u32,
-            )
+        let optname = match attach_type {
+            SocketFilterAttachType::Filter => SO_ATTACH_BPF,
+            SocketFilterAttachType::Reuseport => SO_ATTACH_REUSEPORT_EBPF,
         };
-        if ret < 0 {
-            return Err(SocketFilterError::SoAttachEbpfError {
-                io_error: io::Error::last_os_error(),
+        set_socket_option(socket, optname, prog_fd).map_err(|io_error| match attach_type {
+            SocketFilterAttachType::Filter => SocketFilterError::SoAttachEbpfError { io_error },
+            SocketFilterAttachType::Reuseport => {
+                SocketFilterError::SoAttachReuseportEbpfError { io_error }
             }
-            .into());
-        }
+        })?;
 
-        self.data.links.insert(SocketFilterLink { socket, prog_fd })
+        self.data.links.insert(SocketFilterLink {
+            socket,
+            prog_fd,
+            attach_type,
+        })
     }
 
     /// Detaches the program.
     ///
-    /// See [`Self::attach``].
+    /// See [`Self::attach`] and [`Self::attach_reuseport`].
     pub fn detach(&mut self, link_id: SocketFilterLinkId) -> Result<(), ProgramError> {
         self.data.links.remove(link_id)
     }
@@ -117,36 +163,56 @@ impl SocketFilter {
     }
 }
 
-/// The type returned by [SocketFilter::attach]. Can be passed to [SocketFilter::detach].
+/// The type returned by [SocketFilter::attach] and [SocketFilter::attach_reuseport]. Can be
+/// passed to [SocketFilter::detach].
 #[derive(Debug, Hash, Eq, PartialEq)]
-pub struct SocketFilterLinkId(RawFd, RawFd);
+pub struct SocketFilterLinkId(RawFd, RawFd, SocketFilterAttachType);
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+enum SocketFilterAttachType {
+    Filter,
+    Reuseport,
+}
 
 /// A SocketFilter Link
 #[derive(Debug)]
 pub struct SocketFilterLink {
     socket: RawFd,
     prog_fd: RawFd,
+    attach_type: SocketFilterAttachType,
 }
 
 impl Link for SocketFilterLink {
     type Id = SocketFilterLinkId;
 
     fn id(&self) -> Self::Id {
-        SocketFilterLinkId(self.socket, self.prog_fd)
+        SocketFilterLinkId(self.socket, self.prog_fd, self.attach_type)
     }
 
     fn detach(self) -> Result<(), ProgramError> {
-        unsafe {
-            setsockopt(
-                self.socket,
-                SOL_SOCKET,
-                SO_DETACH_BPF as i32,
-                &self.prog_fd as *const _ as *const _,
-                mem::size_of::<RawFd>() as u32,
-            );
-        }
+        let optname = match self.attach_type {
+            SocketFilterAttachType::Filter => SO_DETACH_BPF,
+            SocketFilterAttachType::Reuseport => SO_DETACH_REUSEPORT_BPF,
+        };
+        let _: Result<(), io::Error> = set_socket_option(self.socket, optname, self.prog_fd);
         Ok(())
     }
 }
 
+fn set_socket_option(socket: RawFd, optname: u32, prog_fd: RawFd) -> Result<(), io::Error> {
+    let ret = unsafe {
+        setsockopt(
+            socket,
+            SOL_SOCKET,
+            optname as i32,
+            &prog_fd as *const _ as *const _,
+            mem::size_of::<RawFd>() as u32,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 id_as_key!(SocketFilterLink, SocketFilterLinkId);
@@ -48,6 +48,11 @@ pub enum TcAttachType {
 /// linux network traffic control system. See
 /// [https://man7.org/linux/man-pages/man8/tc-bpf.8.html](https://man7.org/linux/man-pages/man8/tc-bpf.8.html).
 ///
+/// Programs are attached in direct-action mode (`TCA_BPF_FLAG_ACT_DIRECT`): the value they return
+/// is the `TC_ACT_*` verdict for the packet rather than a class id. Multiple programs can be
+/// attached to the same hook, ordered with [`LinkOrder`] on kernels supporting TCX or with the
+/// priority of [`NlOptions`] otherwise, see [`SchedClassifier::attach_with_options`].
+///
 /// # Examples
 ///
 /// # Minimum kernel version
@@ -69,11 +74,7 @@ pub enum TcAttachType {
 /// #     Ebpf(#[from] aya::EbpfError)
 /// # }
 /// # let mut bpf = aya::Ebpf::load(&[])?;
-/// use aya::programs::{tc, SchedClassifier, TcAttachType};
-///
-/// // the clsact qdisc needs to be added before SchedClassifier programs can be
-/// // attached
-/// tc::qdisc_add_clsact("eth0")?;
+/// use aya::programs::{SchedClassifier, TcAttachType};
 ///
 /// let prog: &mut SchedClassifier = bpf.program_mut("redirect_ingress").unwrap().try_into()?;
 /// prog.load()?;
@@ -173,9 +174,7 @@ impl SchedClassifier {
     ///
     /// When attaching fails, [`ProgramError::SyscallError`] is returned for
     /// kernels `>= 6.6.0`, and [`TcError::NetlinkError`] is returned for
-    /// older kernels. A common cause of netlink attachment failure is not having added
-    /// the `clsact` qdisc to the given interface, see [`qdisc_add_clsact`]
-    ///
+    /// older kernels.
     pub fn attach(
         &mut self,
         interface: &str,
@@ -200,14 +199,17 @@ impl SchedClassifier {
 
     /// Attaches the program to the given `interface` with options defined in [`TcAttachOptions`].
     ///
+    /// When attaching with [`TcAttachOptions::Netlink`] to [`TcAttachType::Ingress`] or
+    /// [`TcAttachType::Egress`], the `clsact` qdisc is added to the interface if it doesn't
+    /// exist yet. The qdisc is left in place when the program is detached, since other programs
+    /// may still be using it. Use [`NlOptions`] to choose the priority and handle of the filter
+    /// so that multiple programs can be attached to the same interface.
+    ///
     /// The returned value can be used to detach, see [SchedClassifier::detach].
     ///
     /// # Errors
     ///
-    /// [`TcError::NetlinkError`] is returned if attaching fails. A common cause
-    /// of failure is not having added the `clsact` qdisc to the given
-    /// interface, see [`qdisc_add_clsact`]
-    ///
+    /// [`TcError::NetlinkError`] is returned if attaching fails.
     pub fn attach_with_options(
         &mut self,
         interface: &str,
@@ -271,6 +273,14 @@ impl SchedClassifier {
 
         match options {
             TcAttachOptions::Netlink(options) => {
+                if create && !matches!(attach_type, TcAttachType::Custom(_)) {
+                    match unsafe { netlink_qdisc_add_clsact(if_index as i32) } {
+                        Ok(()) => {}
+                        Err(e) if e.raw_os_error() == Some(libc::EEXIST) => {}
+                        Err(e) => return Err(TcError::NetlinkError(e).into()),
+                    }
+                }
+
                 let name = self.data.name.as_deref().unwrap_or_default();
                 // TODO: avoid this unwrap by adding a new error variant.
                 let name = CString::new(name).unwrap();
@@ -559,7 +569,10 @@ impl SchedClassifierLink {
 /// Add the `clasct` qdisc to the given interface.
 ///
 /// The `clsact` qdisc must be added to an interface before [`SchedClassifier`]
-/// programs can be attached.
+/// programs can be attached with netlink. [`SchedClassifier::attach`] adds it
+/// automatically when attaching to [`TcAttachType::Ingress`] or
+/// [`TcAttachType::Egress`], so this is only needed when attaching to a
+/// [`TcAttachType::Custom`] parent or to set up the qdisc ahead of time.
 pub fn qdisc_add_clsact(if_name: &str) -> Result<(), TcError> {
     let if_index = ifindex_from_ifname(if_name)?;
     unsafe { netlink_qdisc_add_clsact(if_index as i32).map_err(TcError::NetlinkError) }
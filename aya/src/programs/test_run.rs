@@ -0,0 +1,292 @@
+//! Running programs with `BPF_PROG_TEST_RUN`.
+
+use std::{os::fd::BorrowedFd, time::Duration};
+
+use aya_obj::generated::BPF_F_TEST_RUN_ON_CPU;
+
+use crate::{
+    programs::ProgramError,
+    sys::{bpf_prog_test_run, EbpfTestRunAttrs, SyscallError},
+};
+
+// Programs can grow packets, for example with `bpf_xdp_adjust_head` or `bpf_skb_adjust_room`, so
+// by default the output buffer leaves some room on top of the input size. This matches
+// XDP_PACKET_HEADROOM.
+const DEFAULT_DATA_OUT_ROOM: usize = 256;
+
+/// Input for [`test_run`](crate::programs::Xdp::test_run).
+///
+/// # Examples
+///
+/// ```no_run
+/// # let mut bpf = aya::Ebpf::load(&[])?;
+/// use aya::programs::{TestRunOptions, Xdp};
+///
+/// let program: &mut Xdp = bpf.program_mut("xdp_prog").unwrap().try_into()?;
+/// program.load()?;
+///
+/// let packet = [0u8; 64];
+/// let result = program.test_run(&TestRunOptions::new(&packet).repeat(1000))?;
+/// println!(
+///     "returned {} in {:?}, output {:?}",
+///     result.return_value, result.duration, result.data_out
+/// );
+/// # Ok::<(), aya::EbpfError>(())
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TestRunOptions<'a> {
+    data_in: &'a [u8],
+    data_out_size: Option<usize>,
+    ctx_in: &'a [u8],
+    ctx_out_size: usize,
+    repeat: u32,
+    cpu: Option<u32>,
+}
+
+impl<'a> TestRunOptions<'a> {
+    /// Creates options to run a program with `data_in` as its input packet.
+    ///
+    /// Program types that don't process packets, like [`RawTracePoint`](crate::programs::RawTracePoint),
+    /// must be run with an empty `data_in`.
+    pub fn new(data_in: &'a [u8]) -> Self {
+        Self {
+            data_in,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the size of the buffer the output packet is copied into.
+    ///
+    /// Defaults to the size of the input plus 256 bytes of headroom, or to 0 if the input is
+    /// empty. If the output packet doesn't fit, [`test_run`](crate::programs::Xdp::test_run)
+    /// fails with `ENOSPC`.
+    pub fn data_out_size(mut self, size: usize) -> Self {
+        self.data_out_size = Some(size);
+        self
+    }
+
+    /// Sets the context passed to the program, for example a `struct __sk_buff` or the
+    /// arguments of a raw tracepoint.
+    ///
+    /// Introduced in kernel v5.2.
+    pub fn ctx_in(mut self, ctx_in: &'a [u8]) -> Self {
+        self.ctx_in = ctx_in;
+        self
+    }
+
+    /// Sets the size of the buffer the output context is copied into. Defaults to 0, which
+    /// doesn't copy the context out.
+    pub fn ctx_out_size(mut self, size: usize) -> Self {
+        self.ctx_out_size = size;
+        self
+    }
+
+    /// Runs the program `repeat` times. The reported duration is the average of all the runs.
+    ///
+    /// Defaults to 0, which runs the program once. Not supported by tracing programs.
+    pub fn repeat(mut self, repeat: u32) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Runs the program on the given CPU.
+    ///
+    /// Only supported by [`RawTracePoint`](crate::programs::RawTracePoint) programs.
+    pub fn cpu(mut self, cpu: u32) -> Self {
+        self.cpu = Some(cpu);
+        self
+    }
+}
+
+/// The output of [`test_run`](crate::programs::Xdp::test_run).
+#[derive(Debug)]
+pub struct TestRunResult {
+    /// The value returned by the program.
+    pub return_value: u32,
+    /// The packet after the program ran.
+    pub data_out: Vec<u8>,
+    /// The context after the program ran.
+    pub ctx_out: Vec<u8>,
+    /// The average duration of a run.
+    pub duration: Duration,
+}
+
+pub(crate) fn test_run(
+    prog_fd: BorrowedFd<'_>,
+    opts: &TestRunOptions<'_>,
+) -> Result<TestRunResult, ProgramError> {
+    let TestRunOptions {
+        data_in,
+        data_out_size,
+        ctx_in,
+        ctx_out_size,
+        repeat,
+        cpu,
+    } = *opts;
+
+    let data_out_size = data_out_size.unwrap_or(if data_in.is_empty() {
+        0
+    } else {
+        data_in.len() + DEFAULT_DATA_OUT_ROOM
+    });
+    let mut data_out = vec![0; data_out_size];
+    let mut ctx_out = vec![0; ctx_out_size];
+
+    let output = bpf_prog_test_run(
+        prog_fd,
+        EbpfTestRunAttrs {
+            data_in,
+            data_out: &mut data_out,
+            ctx_in,
+            ctx_out: &mut ctx_out,
+            repeat,
+            flags: if cpu.is_some() {
+                BPF_F_TEST_RUN_ON_CPU
+            } else {
+                0
+            },
+            cpu: cpu.unwrap_or(0),
+        },
+    )
+    .map_err(|(_, io_error)| SyscallError {
+        call: "bpf_prog_test_run",
+        io_error,
+    })?;
+
+    data_out.truncate(output.data_size_out as usize);
+    ctx_out.truncate(output.ctx_size_out as usize);
+
+    Ok(TestRunResult {
+        return_value: output.retval,
+        data_out,
+        ctx_out,
+        duration: Duration::from_nanos(output.duration.into()),
+    })
+}
+
+macro_rules! impl_test_run {
+    ($($struct_name:ident),+ $(,)?) => {
+        $(
+            impl $struct_name {
+                /// Runs the program once or more in the kernel, without attaching it, and returns
+                /// its output. See [`TestRunOptions`].
+                ///
+                /// The program must be loaded.
+                ///
+                /// Uses kernel v4.12 features.
+                #[doc(alias = "BPF_PROG_TEST_RUN")]
+                #[doc(alias = "BPF_PROG_RUN")]
+                pub fn test_run(
+                    &self,
+                    opts: &TestRunOptions<'_>,
+                ) -> Result<TestRunResult, ProgramError> {
+                    let ProgramFd(fd) = self.fd()?;
+                    crate::programs::test_run::test_run(fd.as_fd(), opts)
+                }
+            }
+        )+
+    }
+}
+
+pub(crate) use impl_test_run;
+
+#[cfg(test)]
+mod tests {
+    use std::{io, os::fd::BorrowedFd, slice, time::Duration};
+
+    use assert_matches::assert_matches;
+    use aya_obj::generated::{bpf_cmd, BPF_F_TEST_RUN_ON_CPU};
+    use libc::{EFAULT, ENOSPC};
+
+    use super::*;
+    use crate::sys::{override_syscall, Syscall};
+
+    fn prog_fd() -> BorrowedFd<'static> {
+        unsafe { BorrowedFd::borrow_raw(crate::MockableFd::mock_signed_fd()) }
+    }
+
+    #[test]
+    fn test_test_run() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_TEST_RUN,
+                attr,
+            } => {
+                let test = unsafe { &mut attr.test };
+                assert_eq!(test.prog_fd, crate::MockableFd::mock_unsigned_fd());
+                assert_eq!(test.data_size_in, 4);
+                assert_eq!(test.data_size_out, 4 + 256);
+                assert_eq!(test.ctx_in, 0);
+                assert_eq!(test.ctx_out, 0);
+                assert_eq!(test.repeat, 10);
+                assert_eq!(test.flags, 0);
+
+                let data_in = unsafe { slice::from_raw_parts(test.data_in as *const u8, 4) };
+                let data_out = unsafe {
+                    slice::from_raw_parts_mut(test.data_out as *mut u8, test.data_size_out as usize)
+                };
+                // strip the first byte of the packet
+                data_out[..3].copy_from_slice(&data_in[1..]);
+                test.data_size_out = 3;
+                test.retval = 2;
+                test.duration = 42;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let result = test_run(prog_fd(), &TestRunOptions::new(&[1, 2, 3, 4]).repeat(10)).unwrap();
+        assert_eq!(result.return_value, 2);
+        assert_eq!(result.data_out, [2, 3, 4]);
+        assert!(result.ctx_out.is_empty());
+        assert_eq!(result.duration, Duration::from_nanos(42));
+    }
+
+    #[test]
+    fn test_test_run_no_data() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_TEST_RUN,
+                attr,
+            } => {
+                let test = unsafe { &mut attr.test };
+                assert_eq!(test.data_in, 0);
+                assert_eq!(test.data_size_in, 0);
+                assert_eq!(test.data_out, 0);
+                assert_eq!(test.data_size_out, 0);
+                assert_ne!(test.ctx_in, 0);
+                assert_eq!(test.ctx_size_in, 8);
+                assert_eq!(test.flags, BPF_F_TEST_RUN_ON_CPU);
+                assert_eq!(test.cpu, 3);
+                test.retval = 1;
+                Ok(0)
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        let ctx = 1u64.to_ne_bytes();
+        let result = test_run(prog_fd(), &TestRunOptions::new(&[]).ctx_in(&ctx).cpu(3)).unwrap();
+        assert_eq!(result.return_value, 1);
+        assert!(result.data_out.is_empty());
+    }
+
+    #[test]
+    fn test_test_run_no_space() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_TEST_RUN,
+                attr,
+            } => {
+                assert_eq!(unsafe { attr.test.data_size_out }, 2);
+                Err((-1, io::Error::from_raw_os_error(ENOSPC)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        assert_matches!(
+            test_run(prog_fd(), &TestRunOptions::new(&[1, 2, 3, 4]).data_out_size(2)),
+            Err(ProgramError::SyscallError(SyscallError { call: "bpf_prog_test_run", io_error }))
+                if io_error.raw_os_error() == Some(ENOSPC)
+        );
+    }
+}
@@ -66,12 +66,27 @@ impl TracePoint {
     /// For a list of the available event categories and names, see
     /// `/sys/kernel/debug/tracing/events`.
     ///
+    /// The id of the trace point is read from tracefs, which is looked up at `/sys/kernel/tracing`,
+    /// `/sys/kernel/debug/tracing` and in `/proc/mounts`, unless the `AYA_TRACEFS` environment
+    /// variable is set to its path.
+    ///
     /// The returned value can be used to detach, see [TracePoint::detach].
     pub fn attach(&mut self, category: &str, name: &str) -> Result<TracePointLinkId, ProgramError> {
-        let prog_fd = self.fd()?;
-        let prog_fd = prog_fd.as_fd();
         let tracefs = find_tracefs_path()?;
         let id = read_sys_fs_trace_point_id(tracefs, category, name.as_ref())?;
+        self.attach_by_id(id)
+    }
+
+    /// Attaches to the trace point with the given id.
+    ///
+    /// The id is the one found in `events/<category>/<name>/id` in tracefs. Unlike
+    /// [TracePoint::attach], tracefs isn't accessed, so ids can be looked up or cached ahead of
+    /// time.
+    ///
+    /// The returned value can be used to detach, see [TracePoint::detach].
+    pub fn attach_by_id(&mut self, id: u32) -> Result<TracePointLinkId, ProgramError> {
+        let prog_fd = self.fd()?;
+        let prog_fd = prog_fd.as_fd();
         let fd =
             perf_event_open_trace_point(id, None).map_err(|(_code, io_error)| SyscallError {
                 call: "perf_event_open_trace_point",
@@ -81,6 +96,63 @@ impl TracePoint {
         let link = perf_attach(prog_fd, fd, None /* cookie */)?;
         self.data.links.insert(TracePointLink::new(link))
     }
+
+    /// Returns the fields of the context passed to programs attached to the given trace point.
+    ///
+    /// The fields are read from `events/<category>/<name>/format` in tracefs, and can be used to
+    /// generate or validate the struct the program reads its context into. The first fields,
+    /// prefixed with `common_`, are shared by all the trace points.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use aya::programs::TracePoint;
+    ///
+    /// for field in TracePoint::fields("syscalls", "sys_enter_openat")? {
+    ///     println!("{} {}: offset {} size {}", field.ty, field.name, field.offset, field.size);
+    /// }
+    /// # Ok::<(), aya::programs::ProgramError>(())
+    /// ```
+    pub fn fields(category: &str, name: &str) -> Result<Vec<TracePointField>, ProgramError> {
+        let tracefs = find_tracefs_path()?;
+        let file = tracefs
+            .join("events")
+            .join(category)
+            .join(name)
+            .join("format");
+        let format = fs::read_to_string(&file).map_err(|io_error| TracePointError::FileError {
+            filename: file.display().to_string(),
+            io_error,
+        })?;
+        parse_trace_point_format(&format).map_err(|io_error| {
+            TracePointError::FileError {
+                filename: file.display().to_string(),
+                io_error,
+            }
+            .into()
+        })
+    }
+}
+
+/// A field of a trace point context, see [`TracePoint::fields`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracePointField {
+    /// The name of the field.
+    pub name: String,
+    /// The C type of the field, for example `unsigned long`, `const char *` or `char[16]`.
+    pub ty: String,
+    /// The offset of the field in the context, in bytes.
+    pub offset: usize,
+    /// The size of the field, in bytes.
+    pub size: usize,
+    /// Whether the field is a signed integer.
+    pub signed: bool,
+    /// Whether the field is a dynamically sized `__data_loc` field.
+    ///
+    /// The value of these fields isn't stored at `offset`. Instead, `offset` holds a `u32`
+    /// whose lower 16 bits are the offset of the data in the context and whose upper 16 bits are
+    /// its length.
+    pub data_loc: bool,
 }
 
 define_link_wrapper!(
@@ -138,3 +210,116 @@ pub(crate) fn read_sys_fs_trace_point_id(
 
     Ok(id)
 }
+
+fn parse_trace_point_format(format: &str) -> Result<Vec<TracePointField>, io::Error> {
+    format
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("field:"))
+        .map(|line| {
+            parse_trace_point_field(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid field `{line}`"),
+                )
+            })
+        })
+        .collect()
+}
+
+// Parses a line like `field:unsigned short common_type;	offset:0;	size:2;	signed:0;`, without
+// the `field:` prefix.
+fn parse_trace_point_field(line: &str) -> Option<TracePointField> {
+    let mut parts = line.split(';').map(str::trim);
+    let decl = parts.next()?;
+
+    let (mut offset, mut size, mut signed) = (None, None, false);
+    for part in parts {
+        match part.split_once(':') {
+            Some(("offset", value)) => offset = Some(value.parse().ok()?),
+            Some(("size", value)) => size = Some(value.parse().ok()?),
+            Some(("signed", value)) => signed = value == "1",
+            _ => {}
+        }
+    }
+
+    let (decl, data_loc) = match decl.strip_prefix("__data_loc ") {
+        Some(decl) => (decl, true),
+        None => (decl, false),
+    };
+    // the name is the last token, with the array length, if any, moved to the type:
+    // `char comm[16]` is `comm` of type `char[16]`
+    let (ty, name) = decl.rsplit_once(char::is_whitespace)?;
+    let (name, array) = match name.find('[') {
+        Some(i) => name.split_at(i),
+        None => (name, ""),
+    };
+
+    Some(TracePointField {
+        name: name.to_owned(),
+        ty: format!("{}{array}", ty.trim_end()),
+        offset: offset?,
+        size: size?,
+        signed,
+        data_loc,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(
+        name: &str,
+        ty: &str,
+        offset: usize,
+        size: usize,
+        signed: bool,
+        data_loc: bool,
+    ) -> TracePointField {
+        TracePointField {
+            name: name.to_owned(),
+            ty: ty.to_owned(),
+            offset,
+            size,
+            signed,
+            data_loc,
+        }
+    }
+
+    #[test]
+    fn test_parse_trace_point_format() {
+        let format = "name: sched_process_exec
+ID: 311
+format:
+\tfield:unsigned short common_type;\toffset:0;\tsize:2;\tsigned:0;
+\tfield:unsigned char common_flags;\toffset:2;\tsize:1;\tsigned:0;
+\tfield:int common_pid;\toffset:4;\tsize:4;\tsigned:1;
+
+\tfield:__data_loc char[] filename;\toffset:8;\tsize:4;\tsigned:0;
+\tfield:pid_t pid;\toffset:12;\tsize:4;\tsigned:1;
+\tfield:char comm[16];\toffset:16;\tsize:16;\tsigned:0;
+\tfield:const char * name;\toffset:32;\tsize:8;\tsigned:0;
+
+print fmt: \"filename=%s pid=%d\", __get_str(filename), REC->pid
+";
+        assert_eq!(
+            parse_trace_point_format(format).unwrap(),
+            [
+                field("common_type", "unsigned short", 0, 2, false, false),
+                field("common_flags", "unsigned char", 2, 1, false, false),
+                field("common_pid", "int", 4, 4, true, false),
+                field("filename", "char[]", 8, 4, false, true),
+                field("pid", "pid_t", 12, 4, true, false),
+                field("comm", "char[16]", 16, 16, false, false),
+                field("name", "const char *", 32, 8, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_trace_point_format_invalid() {
+        let format = "format:\n\tfield:int pid;\toffset:x;\tsize:4;\tsigned:1;\n";
+        let err = parse_trace_point_format(format).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -1,6 +1,7 @@
 //! User space probes.
 use std::{
     borrow::Cow,
+    collections::HashSet,
     error::Error,
     ffi::{c_char, CStr, OsStr, OsString},
     fs,
@@ -13,7 +14,10 @@ use std::{
 
 use aya_obj::generated::{bpf_link_type, bpf_prog_type::BPF_PROG_TYPE_KPROBE};
 use libc::pid_t;
-use object::{Object, ObjectSection, ObjectSymbol, Symbol};
+use object::{
+    read::elf::{ElfFile, FileHeader},
+    Object, ObjectSection, ObjectSymbol, ReadRef, Symbol, SymbolIndex,
+};
 use thiserror::Error;
 
 use crate::{
@@ -98,6 +102,13 @@ impl UProbe {
     /// the target function.  Instead if the program is a `uretprobe`, it is
     /// attached to the return address of the target function.
     ///
+    /// If the kernel has a `uprobe` PMU in `/sys/bus/event_source/devices`, which was added in
+    /// kernel 4.17, the probe is created with `perf_event_open`, setting the PMU's `retprobe` bit
+    /// for `uretprobe` programs. Otherwise, an event with a unique name is added to `uprobe_events`
+    /// in tracefs and removed again when the program is detached. tracefs is looked up at
+    /// `/sys/kernel/tracing`, `/sys/kernel/debug/tracing` and in `/proc/mounts`, unless the
+    /// `AYA_TRACEFS` environment variable is set to its path.
+    ///
     /// The returned value can be used to detach, see [UProbe::detach].
     ///
     /// The cookie is supported since kernel 5.15, and it is made available to
@@ -439,8 +450,14 @@ enum ResolveSymbolError {
     #[error("error parsing ELF")]
     Object(#[from] object::Error),
 
-    #[error("unknown symbol `{0}`")]
-    Unknown(String),
+    #[error("unknown symbol `{name}`{}", format_candidates(candidates))]
+    Unknown {
+        name: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("symbol `{name}` is defined more than once, at addresses {addresses:#x?}")]
+    Ambiguous { name: String, addresses: Vec<u64> },
 
     #[error("symbol `{0}` does not appear in section")]
     NotInSection(String),
@@ -455,6 +472,35 @@ enum ResolveSymbolError {
     BuildIdMismatch(String),
 }
 
+fn format_candidates(candidates: &[String]) -> String {
+    if candidates.is_empty() {
+        String::new()
+    } else {
+        format!(", similar symbols: {}", candidates.join(", "))
+    }
+}
+
+// The maximum number of similar symbols listed when a symbol isn't found.
+const MAX_CANDIDATES: usize = 10;
+
+fn unknown_symbol(obj: &object::File<'_>, symbol: &str) -> ResolveSymbolError {
+    let mut candidates: Vec<String> = obj
+        .dynamic_symbols()
+        .chain(obj.symbols())
+        .filter(|sym| !sym.is_undefined())
+        .filter_map(|sym| sym.name().ok())
+        .filter(|name| name.contains(symbol))
+        .map(str::to_owned)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates.truncate(MAX_CANDIDATES);
+    ResolveSymbolError::Unknown {
+        name: symbol.to_owned(),
+        candidates,
+    }
+}
+
 fn construct_debuglink_path(
     filename: &[u8],
     main_path: &Path,
@@ -503,15 +549,62 @@ fn find_debug_path_in_object<'a>(
 ) -> Result<PathBuf, ResolveSymbolError> {
     match obj.gnu_debuglink() {
         Ok(Some((filename, _))) => construct_debuglink_path(filename, main_path),
-        Ok(None) => Err(ResolveSymbolError::Unknown(symbol.to_string())),
+        Ok(None) => Err(unknown_symbol(obj, symbol)),
         Err(err) => Err(ResolveSymbolError::Object(err)),
     }
 }
 
-fn find_symbol_in_object<'a>(obj: &'a object::File<'a>, symbol: &str) -> Option<Symbol<'a, 'a>> {
-    obj.dynamic_symbols()
-        .chain(obj.symbols())
-        .find(|sym| sym.name().map(|name| name == symbol).unwrap_or(false))
+// Returns the `.dynsym` entries that are non-default versions of their symbol, for example the
+// `memcpy@GLIBC_2.2.5` compatibility symbol of glibc next to the default `memcpy@@GLIBC_2.14`.
+fn hidden_dynamic_symbols(obj: &object::File<'_>) -> HashSet<SymbolIndex> {
+    fn hidden<'data, Elf: FileHeader, R: ReadRef<'data>>(
+        file: &ElfFile<'data, Elf, R>,
+    ) -> HashSet<SymbolIndex> {
+        let endian = file.endian();
+        let Ok(Some(versions)) = file.elf_section_table().versions(endian, file.data()) else {
+            return HashSet::new();
+        };
+        file.dynamic_symbols()
+            .map(|sym| sym.index())
+            .filter(|index| versions.version_index(endian, *index).is_hidden())
+            .collect()
+    }
+
+    match obj {
+        object::File::Elf32(file) => hidden(file),
+        object::File::Elf64(file) => hidden(file),
+        _ => HashSet::new(),
+    }
+}
+
+// Symbols usually appear in both `.dynsym` and `.symtab`, so a symbol is only ambiguous if its
+// definitions have different addresses, for example static functions with the same name in
+// different compilation units. `Symbol::name` doesn't include the version of dynamic symbols, so
+// the non-default versions of a symbol are only considered when it has no default version.
+fn find_symbol_in_object<'a>(
+    obj: &'a object::File<'a>,
+    symbol: &str,
+) -> Result<Option<Symbol<'a, 'a>>, ResolveSymbolError> {
+    let hidden = hidden_dynamic_symbols(obj);
+    let mut syms: Vec<_> = obj
+        .dynamic_symbols()
+        .map(|sym| (hidden.contains(&sym.index()), sym))
+        .chain(obj.symbols().map(|sym| (false, sym)))
+        .filter(|(_, sym)| !sym.is_undefined() && sym.name() == Ok(symbol))
+        .collect();
+    if syms.iter().any(|(hidden, _)| !hidden) {
+        syms.retain(|(hidden, _)| !hidden);
+    }
+    let mut addresses: Vec<u64> = syms.iter().map(|(_, sym)| sym.address()).collect();
+    addresses.sort_unstable();
+    addresses.dedup();
+    if addresses.len() > 1 {
+        return Err(ResolveSymbolError::Ambiguous {
+            name: symbol.to_owned(),
+            addresses,
+        });
+    }
+    Ok(syms.into_iter().next().map(|(_, sym)| sym))
 }
 
 fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError> {
@@ -521,7 +614,7 @@ fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError>
     let mut debug_data = Vec::default();
     let mut debug_obj_keeper = None;
 
-    let sym = find_symbol_in_object(&obj, symbol).map_or_else(
+    let sym = find_symbol_in_object(&obj, symbol)?.map_or_else(
         || {
             // Only search in the debug object if the symbol was not found in the main object
             let debug_path = find_debug_path_in_object(&obj, path, symbol)?;
@@ -538,9 +631,9 @@ fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError>
 
             verify_build_ids(&obj, &debug_obj, symbol)?;
 
-            debug_obj_keeper = Some(debug_obj);
-            find_symbol_in_object(debug_obj_keeper.as_ref().unwrap(), symbol)
-                .ok_or_else(|| ResolveSymbolError::Unknown(symbol.to_string()))
+            let debug_obj = debug_obj_keeper.insert(debug_obj);
+            find_symbol_in_object(debug_obj, symbol)?
+                .ok_or_else(|| unknown_symbol(debug_obj, symbol))
         },
         Ok,
     )?;
@@ -569,7 +662,11 @@ fn resolve_symbol(path: &Path, symbol: &str) -> Result<u64, ResolveSymbolError>
 #[cfg(test)]
 mod tests {
 
-    use object::{write::SectionKind, Architecture, BinaryFormat, Endianness};
+    use assert_matches::assert_matches;
+    use object::{
+        write::{SectionKind, Symbol as WriteSymbol, SymbolSection},
+        Architecture, BinaryFormat, Endianness, SymbolFlags, SymbolKind, SymbolScope,
+    };
 
     use super::*;
 
@@ -663,6 +760,29 @@ mod tests {
         obj.write()
     }
 
+    fn create_elf_with_symbols(symbols: &[(&str, u64)]) -> Result<Vec<u8>, object::write::Error> {
+        let mut obj =
+            object::write::Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
+
+        let section_id = obj.add_section(vec![], b".text".to_vec(), SectionKind::Text);
+        obj.append_section_data(section_id, &[0; 64], 8 /* align */);
+
+        for (name, value) in symbols {
+            obj.add_symbol(WriteSymbol {
+                name: name.as_bytes().to_vec(),
+                value: *value,
+                size: 8,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Compilation,
+                weak: false,
+                section: SymbolSection::Section(section_id),
+                flags: SymbolFlags::None,
+            });
+        }
+
+        obj.write()
+    }
+
     fn aligned_slice(vec: &mut Vec<u8>) -> &mut [u8] {
         let alignment = 8;
 
@@ -735,4 +855,46 @@ mod tests {
             Err(ResolveSymbolError::BuildIdMismatch(_))
         ));
     }
+
+    #[test]
+    fn test_find_symbol_ambiguous() {
+        let mut bytes =
+            create_elf_with_symbols(&[("foo", 0), ("bar", 8), ("foo", 16)]).expect("got elf bytes");
+        let align_bytes = aligned_slice(&mut bytes);
+        let obj = object::File::parse(&*align_bytes).expect("got obj");
+
+        assert_eq!(
+            find_symbol_in_object(&obj, "bar")
+                .unwrap()
+                .map(|sym| sym.address()),
+            Some(8)
+        );
+        assert_matches!(
+            find_symbol_in_object(&obj, "foo"),
+            Err(ResolveSymbolError::Ambiguous { name, addresses }) => {
+                assert_eq!(name, "foo");
+                assert_eq!(addresses, [0, 16]);
+            }
+        );
+    }
+
+    #[test]
+    fn test_unknown_symbol_candidates() {
+        let mut bytes =
+            create_elf_with_symbols(&[("do_read", 0), ("do_read_fast", 8), ("write", 16)])
+                .expect("got elf bytes");
+        let align_bytes = aligned_slice(&mut bytes);
+        let obj = object::File::parse(&*align_bytes).expect("got obj");
+
+        assert_matches!(find_symbol_in_object(&obj, "read"), Ok(None));
+        let err = unknown_symbol(&obj, "read");
+        assert_eq!(
+            err.to_string(),
+            "unknown symbol `read`, similar symbols: do_read, do_read_fast"
+        );
+        assert_eq!(
+            unknown_symbol(&obj, "open").to_string(),
+            "unknown symbol `open`"
+        );
+    }
 }
@@ -1,10 +1,11 @@
 //! Common functions shared between multiple eBPF program types.
 use std::{
+    env,
     ffi::CStr,
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader},
     os::fd::{AsFd as _, AsRawFd as _, BorrowedFd},
-    path::Path,
+    path::{Path, PathBuf},
     sync::LazyLock,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -30,9 +31,19 @@ pub(crate) fn attach_raw_tracepoint<T: Link + From<FdLink>>(
     program_data.links.insert(FdLink::new(pfd).into())
 }
 
+/// The environment variable that overrides where tracefs is looked up.
+const TRACEFS_ENV: &str = "AYA_TRACEFS";
+
 /// Find tracefs filesystem path.
+///
+/// The path in `AYA_TRACEFS` is used if the variable is set. Otherwise tracefs is looked up at
+/// `/sys/kernel/tracing` and `/sys/kernel/debug/tracing`, then anywhere in `/proc/mounts`.
 pub(crate) fn find_tracefs_path() -> Result<&'static Path, ProgramError> {
-    static TRACE_FS: LazyLock<Option<&'static Path>> = LazyLock::new(|| {
+    static TRACE_FS: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+        if let Some(path) = env::var_os(TRACEFS_ENV) {
+            return Some(path.into());
+        }
+
         [
             Path::new("/sys/kernel/tracing"),
             Path::new("/sys/kernel/debug/tracing"),
@@ -51,11 +62,52 @@ pub(crate) fn find_tracefs_path() -> Result<&'static Path, ProgramError> {
                     Err(io::Error { .. }) => false,
                 }
         })
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            let mounts = fs::read_to_string("/proc/mounts").ok()?;
+            tracefs_from_mounts(&mounts)
+        })
     });
 
-    TRACE_FS
-        .as_deref()
-        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "tracefs not found").into())
+    TRACE_FS.as_deref().ok_or_else(|| {
+        io::Error::other(format!(
+            "tracefs not found, mount it or set {TRACEFS_ENV} to its path"
+        ))
+        .into()
+    })
+}
+
+/// Returns the mount point of the first tracefs entry in the contents of `/proc/mounts`.
+fn tracefs_from_mounts(mounts: &str) -> Option<PathBuf> {
+    mounts.lines().find_map(|line| {
+        let mut fields = line.split_ascii_whitespace();
+        let _source = fields.next()?;
+        let mount_point = fields.next()?;
+        let fs_type = fields.next()?;
+        (fs_type == "tracefs").then(|| unescape_mount_point(mount_point).into())
+    })
+}
+
+/// Decodes the octal escapes (`\040` for a space, ...) the kernel uses in `/proc/mounts`.
+fn unescape_mount_point(mount_point: &str) -> String {
+    let mut out = String::with_capacity(mount_point.len());
+    let mut rest = mount_point;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let escape = rest.get(i + 1..i + 4);
+        match escape.and_then(|digits| u8::from_str_radix(digits, 8).ok()) {
+            Some(c) => {
+                out.push(char::from(c));
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
 }
 
 /// The time at which the system is booted.
@@ -94,3 +146,24 @@ pub(crate) fn get_fdinfo(fd: BorrowedFd<'_>, key: &str) -> Result<u32, ProgramEr
 
     Ok(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracefs_from_mounts() {
+        let mounts = "\
+sysfs /sys sysfs rw,nosuid,nodev,noexec,relatime 0 0
+proc /proc proc rw,nosuid,nodev,noexec,relatime 0 0
+tracefs /mnt/trace\\040fs tracefs rw,nosuid,nodev,noexec,relatime 0 0
+tracefs /sys/kernel/tracing tracefs rw,nosuid,nodev,noexec,relatime 0 0
+";
+        assert_eq!(
+            tracefs_from_mounts(mounts),
+            Some(PathBuf::from("/mnt/trace fs"))
+        );
+        assert_eq!(tracefs_from_mounts("proc /proc proc rw 0 0\n"), None);
+        assert_eq!(unescape_mount_point(r"/a\b\0"), r"/a\b\0");
+    }
+}
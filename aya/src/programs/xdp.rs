@@ -23,8 +23,8 @@ use crate::{
         ProgramError,
     },
     sys::{
-        bpf_link_create, bpf_link_get_info_by_fd, bpf_link_update, netlink_set_xdp_fd, LinkTarget,
-        NetlinkError, SyscallError,
+        bpf_link_create, bpf_link_get_info_by_fd, bpf_link_update, netlink_get_xdp_programs,
+        netlink_set_xdp_fd, LinkTarget, NetlinkError, SyscallError,
     },
     util::KernelVersion,
     VerifierLogLevel,
@@ -36,6 +36,14 @@ pub enum XdpError {
     /// A netlink error occurred.
     #[error(transparent)]
     NetlinkError(#[from] NetlinkError),
+
+    /// The program was attached with [`XdpFlags::DRV_MODE`], but the driver of the interface
+    /// doesn't support native XDP. [`XdpFlags::SKB_MODE`] works with any driver.
+    #[error("the driver of interface {if_index} doesn't support XDP in driver mode")]
+    DriverModeNotSupported {
+        /// The interface index.
+        if_index: u32,
+    },
 }
 
 bitflags::bitflags! {
@@ -55,6 +63,39 @@ bitflags::bitflags! {
     }
 }
 
+/// The XDP programs attached to a network interface, as returned by [`Xdp::query`].
+///
+/// An interface can have a program attached in each mode at the same time. The program ids can be
+/// passed to [`ProgramInfo::from_id`](crate::programs::ProgramInfo::from_id) to inspect the
+/// programs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct XdpAttachedPrograms {
+    /// The id of the program attached in [`XdpFlags::SKB_MODE`].
+    pub skb_mode: Option<u32>,
+    /// The id of the program attached in [`XdpFlags::DRV_MODE`].
+    pub drv_mode: Option<u32>,
+    /// The id of the program attached in [`XdpFlags::HW_MODE`].
+    pub hw_mode: Option<u32>,
+}
+
+impl XdpAttachedPrograms {
+    /// Returns `true` if no program is attached.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Returns an iterator over the attach mode and id of each attached program.
+    pub fn iter(&self) -> impl Iterator<Item = (XdpFlags, u32)> {
+        [
+            (XdpFlags::SKB_MODE, self.skb_mode),
+            (XdpFlags::DRV_MODE, self.drv_mode),
+            (XdpFlags::HW_MODE, self.hw_mode),
+        ]
+        .into_iter()
+        .filter_map(|(mode, id)| id.map(|id| (mode, id)))
+    }
+}
+
 /// An XDP program.
 ///
 /// eXpress Data Path (XDP) programs can be attached to the very early stages of network
@@ -102,16 +143,11 @@ impl Xdp {
     /// When attaching fails, [`ProgramError::SyscallError`] is returned for
     /// kernels `>= 5.9.0`, and instead
     /// [`XdpError::NetlinkError`] is returned for older
-    /// kernels.
+    /// kernels. If `flags` contains [`XdpFlags::DRV_MODE`] and the driver
+    /// doesn't support native XDP, [`XdpError::DriverModeNotSupported`] is
+    /// returned.
     pub fn attach(&mut self, interface: &str, flags: XdpFlags) -> Result<XdpLinkId, ProgramError> {
-        // TODO: avoid this unwrap by adding a new error variant.
-        let c_interface = CString::new(interface).unwrap();
-        let if_index = unsafe { if_nametoindex(c_interface.as_ptr()) };
-        if if_index == 0 {
-            return Err(ProgramError::UnknownInterface {
-                name: interface.to_string(),
-            });
-        }
+        let if_index = if_index_from_name(interface)?;
         self.attach_to_if_index(if_index, flags)
     }
 
@@ -124,7 +160,9 @@ impl Xdp {
     /// When attaching fails, [`ProgramError::SyscallError`] is returned for
     /// kernels `>= 5.9.0`, and instead
     /// [`XdpError::NetlinkError`] is returned for older
-    /// kernels.
+    /// kernels. If `flags` contains [`XdpFlags::DRV_MODE`] and the driver
+    /// doesn't support native XDP, [`XdpError::DriverModeNotSupported`] is
+    /// returned.
     pub fn attach_to_if_index(
         &mut self,
         if_index: u32,
@@ -149,17 +187,31 @@ impl Xdp {
                 flags.bits(),
                 None,
             )
-            .map_err(|(_, io_error)| SyscallError {
-                call: "bpf_link_create",
-                io_error,
+            .map_err(|(_, io_error)| {
+                if is_drv_mode_unsupported(flags, io_error.raw_os_error()) {
+                    ProgramError::from(XdpError::DriverModeNotSupported { if_index })
+                } else {
+                    SyscallError {
+                        call: "bpf_link_create",
+                        io_error,
+                    }
+                    .into()
+                }
             })?;
             self.data
                 .links
                 .insert(XdpLink::new(XdpLinkInner::FdLink(FdLink::new(link_fd))))
         } else {
+            unsafe { netlink_set_xdp_fd(if_index as i32, Some(prog_fd), None, flags.bits()) }
+                .map_err(|e| {
+                    if is_drv_mode_unsupported(flags, e.raw_os_error()) {
+                        XdpError::DriverModeNotSupported { if_index }
+                    } else {
+                        XdpError::NetlinkError(e)
+                    }
+                })?;
+
             let if_index = if_index as i32;
-            unsafe { netlink_set_xdp_fd(if_index, Some(prog_fd), None, flags.bits()) }
-                .map_err(XdpError::NetlinkError)?;
 
             let prog_fd = prog_fd.as_raw_fd();
             self.data
@@ -172,6 +224,46 @@ impl Xdp {
         }
     }
 
+    /// Queries the given `interface` for attached XDP programs.
+    ///
+    /// This works regardless of how the programs were attached, including by other tools, so it
+    /// can be used to avoid replacing a program that is already attached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[derive(Debug, thiserror::Error)]
+    /// # enum Error {
+    /// #     #[error(transparent)]
+    /// #     Program(#[from] aya::programs::ProgramError),
+    /// # }
+    /// use aya::programs::{ProgramInfo, Xdp};
+    ///
+    /// for (mode, id) in Xdp::query("eth0")?.iter() {
+    ///     let info = ProgramInfo::from_id(id)?;
+    ///     println!("{:?} attached in {mode:?}", info.name_as_str());
+    /// }
+    /// # Ok::<(), Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the given `interface` does not exist [`ProgramError::UnknownInterface`] is returned.
+    /// If the query fails, [`XdpError::NetlinkError`] is returned.
+    pub fn query(interface: &str) -> Result<XdpAttachedPrograms, ProgramError> {
+        let if_index = if_index_from_name(interface)?;
+        Self::query_if_index(if_index)
+    }
+
+    /// Queries the given interface index for attached XDP programs.
+    ///
+    /// See [`Xdp::query`].
+    pub fn query_if_index(if_index: u32) -> Result<XdpAttachedPrograms, ProgramError> {
+        let programs =
+            unsafe { netlink_get_xdp_programs(if_index as i32) }.map_err(XdpError::NetlinkError)?;
+        Ok(programs)
+    }
+
     /// Creates a program from a pinned entry on a bpffs.
     ///
     /// Existing links will not be populated. To work with existing links you should use [`crate::programs::links::PinnedLink`].
@@ -237,6 +329,24 @@ impl Xdp {
     }
 }
 
+fn if_index_from_name(interface: &str) -> Result<u32, ProgramError> {
+    // TODO: avoid this unwrap by adding a new error variant.
+    let c_interface = CString::new(interface).unwrap();
+    let if_index = unsafe { if_nametoindex(c_interface.as_ptr()) };
+    if if_index == 0 {
+        return Err(ProgramError::UnknownInterface {
+            name: interface.to_string(),
+        });
+    }
+    Ok(if_index)
+}
+
+// The kernel fails with EOPNOTSUPP when native mode is requested but the driver doesn't
+// implement `ndo_bpf`.
+fn is_drv_mode_unsupported(flags: XdpFlags, errno: Option<i32>) -> bool {
+    flags.contains(XdpFlags::DRV_MODE) && errno == Some(libc::EOPNOTSUPP)
+}
+
 #[derive(Debug)]
 pub(crate) struct NlLink {
     if_index: i32,
@@ -1,5 +1,4 @@
 use std::{
-    cmp,
     ffi::{c_char, c_long, CStr, CString},
     io, iter,
     mem::{self, MaybeUninit},
@@ -16,19 +15,20 @@ use aya_obj::{
     generated::{
         bpf_attach_type, bpf_attr, bpf_btf_info, bpf_cmd, bpf_func_id::*, bpf_insn, bpf_link_info,
         bpf_map_info, bpf_map_type, bpf_prog_info, bpf_prog_type, bpf_stats_type, BPF_ALU64,
-        BPF_CALL, BPF_DW, BPF_EXIT, BPF_F_REPLACE, BPF_IMM, BPF_JMP, BPF_K, BPF_LD, BPF_MEM,
-        BPF_MOV, BPF_PSEUDO_MAP_VALUE, BPF_ST, BPF_SUB, BPF_X,
+        BPF_CALL, BPF_DW, BPF_EXIT, BPF_F_MMAPABLE, BPF_F_NO_PREALLOC, BPF_F_REPLACE,
+        BPF_F_SLEEPABLE, BPF_IMM, BPF_JMP, BPF_K, BPF_LD, BPF_MEM, BPF_MOV, BPF_PSEUDO_MAP_VALUE,
+        BPF_ST, BPF_SUB, BPF_X,
     },
     maps::{bpf_map_def, LegacyMap},
     EbpfSectionKind, VerifierLog,
 };
-use libc::{ENOENT, ENOSPC};
+use libc::{EACCES, ENOENT, ENOSPC, EPERM};
 
 use crate::{
-    maps::{MapData, PerCpuValues},
-    programs::links::LinkRef,
+    maps::{MapData, MapType, PerCpuValues},
+    programs::{links::LinkRef, ProgramType},
     sys::{syscall, SysResult, Syscall, SyscallError},
-    util::KernelVersion,
+    util::{page_size, KernelVersion},
     Btf, Pod, VerifierLogLevel, FEATURES,
 };
 
@@ -46,6 +46,7 @@ pub(crate) fn bpf_create_map(
     name: &CStr,
     def: &aya_obj::Map,
     btf_fd: Option<BorrowedFd<'_>>,
+    inner_map_fd: Option<BorrowedFd<'_>>,
     kernel_version: KernelVersion,
 ) -> SysResult<crate::MockableFd> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -56,6 +57,10 @@ pub(crate) fn bpf_create_map(
     u.value_size = def.value_size();
     u.max_entries = def.max_entries();
     u.map_flags = def.map_flags();
+    u.map_extra = def.map_extra();
+    if let Some(fd) = inner_map_fd {
+        u.inner_map_fd = fd.as_raw_fd() as u32;
+    }
 
     if let aya_obj::Map::Btf(m) = def {
         use bpf_map_type::*;
@@ -94,17 +99,31 @@ pub(crate) fn bpf_create_map(
     // The map name was added as a parameter in kernel 4.15+ so we skip adding it on
     // older kernels for compatibility
     if kernel_version >= KernelVersion::new(4, 15, 0) {
-        // u.map_name is 16 bytes max and must be NULL terminated
-        let name_bytes = name.to_bytes_with_nul();
-        let len = cmp::min(name_bytes.len(), u.map_name.len());
-        u.map_name[..len]
-            .copy_from_slice(unsafe { mem::transmute::<&[u8], &[c_char]>(&name_bytes[..len]) });
+        copy_obj_name(&mut u.map_name, name);
     }
 
     // SAFETY: BPF_MAP_CREATE returns a new file descriptor.
     unsafe { fd_sys_bpf(bpf_cmd::BPF_MAP_CREATE, &mut attr) }
 }
 
+// Copies `name` into the `map_name` or `prog_name` field of `bpf_attr`, which must be zeroed.
+//
+// The kernel rejects names that don't fit in the field with their NUL terminator, and names with
+// characters other than alphanumerics, `_` and `.`. Longer names are truncated, keeping their
+// prefix, and other characters are replaced with `_`. The kernel doesn't require names to be
+// unique, so names that are the same after truncation are fine.
+fn copy_obj_name(dst: &mut [c_char], name: &CStr) {
+    let len = dst.len() - 1;
+    for (dst, &b) in dst[..len].iter_mut().zip(name.to_bytes()) {
+        let b = if b.is_ascii_alphanumeric() || b == b'_' || b == b'.' {
+            b
+        } else {
+            b'_'
+        };
+        *dst = b as c_char;
+    }
+}
+
 pub(crate) fn bpf_pin_object(fd: BorrowedFd<'_>, path: &CStr) -> SysResult<c_long> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
     let u = unsafe { &mut attr.__bindgen_anon_4 };
@@ -150,10 +169,7 @@ pub(crate) fn bpf_load_program(
     let u = unsafe { &mut attr.__bindgen_anon_3 };
 
     if let Some(name) = &aya_attr.name {
-        let name_bytes = name.to_bytes();
-        let len = cmp::min(name_bytes.len(), u.prog_name.len());
-        u.prog_name[..len]
-            .copy_from_slice(unsafe { mem::transmute::<&[u8], &[c_char]>(&name_bytes[..len]) });
+        copy_obj_name(&mut u.prog_name, name);
     }
 
     u.prog_flags = aya_attr.flags;
@@ -370,6 +386,145 @@ pub(crate) fn bpf_map_get_next_key<K: Pod>(
     }
 }
 
+pub(crate) fn bpf_map_lookup_elem_bytes(
+    fd: BorrowedFd<'_>,
+    key: &[u8],
+    value: &mut [u8],
+    flags: u64,
+) -> SysResult<Option<()>> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.__bindgen_anon_2 };
+    u.map_fd = fd.as_raw_fd() as u32;
+    u.key = key.as_ptr() as u64;
+    u.__bindgen_anon_1.value = value.as_mut_ptr() as u64;
+    u.flags = flags;
+
+    match sys_bpf(bpf_cmd::BPF_MAP_LOOKUP_ELEM, &mut attr) {
+        Ok(_) => Ok(Some(())),
+        Err((_, io_error)) if io_error.raw_os_error() == Some(ENOENT) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn bpf_map_get_next_key_bytes(
+    fd: BorrowedFd<'_>,
+    key: Option<&[u8]>,
+    next_key: &mut [u8],
+) -> SysResult<Option<()>> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.__bindgen_anon_2 };
+    u.map_fd = fd.as_raw_fd() as u32;
+    if let Some(key) = key {
+        u.key = key.as_ptr() as u64;
+    }
+    u.__bindgen_anon_1.next_key = next_key.as_mut_ptr() as u64;
+
+    match sys_bpf(bpf_cmd::BPF_MAP_GET_NEXT_KEY, &mut attr) {
+        Ok(_) => Ok(Some(())),
+        Err((_, io_error)) if io_error.raw_os_error() == Some(ENOENT) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn lookup_batch<K: Pod, V: Pod>(
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&u32>,
+    count: u32,
+    flags: u64,
+    cmd: bpf_cmd,
+) -> SysResult<(Vec<K>, Vec<V>, Option<u32>)> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let mut keys = Vec::<K>::with_capacity(count as usize);
+    let mut values = Vec::<V>::with_capacity(count as usize);
+    let mut out_batch = 0u32;
+
+    let u = unsafe { &mut attr.batch };
+    u.map_fd = fd.as_raw_fd() as u32;
+    if let Some(in_batch) = in_batch {
+        u.in_batch = in_batch as *const _ as u64;
+    }
+    u.out_batch = &mut out_batch as *mut _ as u64;
+    u.keys = keys.as_mut_ptr() as u64;
+    u.values = values.as_mut_ptr() as u64;
+    u.count = count;
+    u.elem_flags = flags;
+
+    let next = match sys_bpf(cmd, &mut attr) {
+        Ok(_) => Some(out_batch),
+        // ENOENT signals that the end of the map was reached; `count` still holds the number of
+        // elements copied by this call.
+        Err((_, io_error)) if io_error.raw_os_error() == Some(ENOENT) => None,
+        Err(e) => return Err(e),
+    };
+
+    let len = unsafe { attr.batch.count }.min(count) as usize;
+    unsafe {
+        keys.set_len(len);
+        values.set_len(len);
+    }
+
+    Ok((keys, values, next))
+}
+
+// since kernel 5.6
+pub(crate) fn bpf_map_lookup_batch<K: Pod, V: Pod>(
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&u32>,
+    count: u32,
+    flags: u64,
+) -> SysResult<(Vec<K>, Vec<V>, Option<u32>)> {
+    lookup_batch(fd, in_batch, count, flags, bpf_cmd::BPF_MAP_LOOKUP_BATCH)
+}
+
+// since kernel 5.6
+pub(crate) fn bpf_map_lookup_and_delete_batch<K: Pod, V: Pod>(
+    fd: BorrowedFd<'_>,
+    in_batch: Option<&u32>,
+    count: u32,
+    flags: u64,
+) -> SysResult<(Vec<K>, Vec<V>, Option<u32>)> {
+    lookup_batch(
+        fd,
+        in_batch,
+        count,
+        flags,
+        bpf_cmd::BPF_MAP_LOOKUP_AND_DELETE_BATCH,
+    )
+}
+
+// since kernel 5.6
+pub(crate) fn bpf_map_update_batch<K: Pod, V: Pod>(
+    fd: BorrowedFd<'_>,
+    keys: &[K],
+    values: &[V],
+    flags: u64,
+) -> SysResult<c_long> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.batch };
+    u.map_fd = fd.as_raw_fd() as u32;
+    u.keys = keys.as_ptr() as u64;
+    u.values = values.as_ptr() as u64;
+    u.count = keys.len().min(values.len()) as u32;
+    u.elem_flags = flags;
+
+    sys_bpf(bpf_cmd::BPF_MAP_UPDATE_BATCH, &mut attr)
+}
+
+// since kernel 5.6
+pub(crate) fn bpf_map_delete_batch<K: Pod>(fd: BorrowedFd<'_>, keys: &[K]) -> SysResult<c_long> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.batch };
+    u.map_fd = fd.as_raw_fd() as u32;
+    u.keys = keys.as_ptr() as u64;
+    u.count = keys.len() as u32;
+
+    sys_bpf(bpf_cmd::BPF_MAP_DELETE_BATCH, &mut attr)
+}
+
 // since kernel 5.2
 pub(crate) fn bpf_map_freeze(fd: BorrowedFd<'_>) -> SysResult<c_long> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -560,6 +715,64 @@ pub(crate) fn bpf_prog_query(
     ret
 }
 
+pub(crate) struct EbpfTestRunAttrs<'a> {
+    pub(crate) data_in: &'a [u8],
+    pub(crate) data_out: &'a mut [u8],
+    pub(crate) ctx_in: &'a [u8],
+    pub(crate) ctx_out: &'a mut [u8],
+    pub(crate) repeat: u32,
+    pub(crate) flags: u32,
+    pub(crate) cpu: u32,
+}
+
+pub(crate) struct EbpfTestRunOutput {
+    pub(crate) retval: u32,
+    pub(crate) data_size_out: u32,
+    pub(crate) ctx_size_out: u32,
+    pub(crate) duration: u32,
+}
+
+/// Introduced in kernel v4.12.
+pub(crate) fn bpf_prog_test_run(
+    prog_fd: BorrowedFd<'_>,
+    aya_attr: EbpfTestRunAttrs<'_>,
+) -> SysResult<EbpfTestRunOutput> {
+    // Some program types reject any non-null buffer, so empty buffers are passed as null.
+    fn buf_ptr(buf: &[u8]) -> u64 {
+        if buf.is_empty() {
+            0
+        } else {
+            buf.as_ptr() as u64
+        }
+    }
+
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+
+    let u = unsafe { &mut attr.test };
+    u.prog_fd = prog_fd.as_raw_fd() as u32;
+    u.data_in = buf_ptr(aya_attr.data_in);
+    u.data_size_in = aya_attr.data_in.len() as u32;
+    u.data_out = buf_ptr(aya_attr.data_out);
+    u.data_size_out = aya_attr.data_out.len() as u32;
+    u.ctx_in = buf_ptr(aya_attr.ctx_in);
+    u.ctx_size_in = aya_attr.ctx_in.len() as u32;
+    u.ctx_out = buf_ptr(aya_attr.ctx_out);
+    u.ctx_size_out = aya_attr.ctx_out.len() as u32;
+    u.repeat = aya_attr.repeat;
+    u.flags = aya_attr.flags;
+    u.cpu = aya_attr.cpu;
+
+    sys_bpf(bpf_cmd::BPF_PROG_TEST_RUN, &mut attr)?;
+
+    let u = unsafe { &attr.test };
+    Ok(EbpfTestRunOutput {
+        retval: u.retval,
+        data_size_out: u.data_size_out,
+        ctx_size_out: u.ctx_size_out,
+        duration: u.duration,
+    })
+}
+
 /// Introduced in kernel v4.13.
 pub(crate) fn bpf_prog_get_fd_by_id(prog_id: u32) -> Result<crate::MockableFd, SyscallError> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -619,6 +832,25 @@ pub(crate) fn bpf_prog_get_info_by_fd(
     })
 }
 
+/// Reads the translated and JIT-compiled instructions of a program into the given buffers.
+///
+/// The kernel clears `xlated_prog_insns` or `jited_prog_insns` in the returned info when it
+/// refuses to dump the instructions of the program.
+///
+/// Introduced in kernel v4.13.
+pub(crate) fn bpf_prog_get_insns_by_fd(
+    fd: BorrowedFd<'_>,
+    xlated: &mut [bpf_insn],
+    jited: &mut [u8],
+) -> Result<bpf_prog_info, SyscallError> {
+    bpf_obj_get_info_by_fd(fd, |info: &mut bpf_prog_info| {
+        info.xlated_prog_len = mem::size_of_val(xlated) as _;
+        info.xlated_prog_insns = xlated.as_mut_ptr() as _;
+        info.jited_prog_len = jited.len() as _;
+        info.jited_prog_insns = jited.as_mut_ptr() as _;
+    })
+}
+
 /// Introduced in kernel v4.13.
 pub(crate) fn bpf_map_get_fd_by_id(map_id: u32) -> Result<crate::MockableFd, SyscallError> {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
@@ -733,11 +965,7 @@ pub(crate) fn bpf_btf_get_fd_by_id(id: u32) -> Result<crate::MockableFd, Syscall
 pub(crate) fn is_prog_name_supported() -> bool {
     with_trivial_prog(|attr| {
         let u = unsafe { &mut attr.__bindgen_anon_3 };
-        let name = c"aya_name_check";
-        let name_bytes = name.to_bytes();
-        let len = cmp::min(name_bytes.len(), u.prog_name.len());
-        u.prog_name[..len]
-            .copy_from_slice(unsafe { mem::transmute::<&[u8], &[c_char]>(&name_bytes[..len]) });
+        copy_obj_name(&mut u.prog_name, c"aya_name_check");
         bpf_prog_load(attr).is_ok()
     })
 }
@@ -849,6 +1077,21 @@ pub(crate) fn is_perf_link_supported() -> bool {
     })
 }
 
+// Batch operations return EINVAL both when the kernel doesn't know the command and when it
+// rejects the arguments, so they're probed separately to tell the two apart.
+pub(crate) fn is_map_batch_supported() -> bool {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.batch };
+    // Uses an invalid map FD so we get EBADF if supported.
+    u.map_fd = u32::MAX;
+
+    // Returns EINVAL if unsupported. EBADF if supported.
+    matches!(
+        sys_bpf(bpf_cmd::BPF_MAP_LOOKUP_BATCH, &mut attr),
+        Err((_, e)) if e.raw_os_error() == Some(libc::EBADF)
+    )
+}
+
 pub(crate) fn is_bpf_global_data_supported() -> bool {
     let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
     let u = unsafe { &mut attr.__bindgen_anon_3 };
@@ -862,6 +1105,7 @@ pub(crate) fn is_bpf_global_data_supported() -> bool {
                 max_entries: 1,
                 ..Default::default()
             },
+            inner_def: None,
             section_index: 0,
             section_kind: EbpfSectionKind::Maps,
             symbol_index: None,
@@ -1098,6 +1342,227 @@ pub(crate) fn is_btf_type_tag_supported() -> bool {
     bpf_load_btf(btf_bytes.as_slice(), &mut [], Default::default()).is_ok()
 }
 
+/// Tests whether programs of the given type can be loaded.
+///
+/// Mirrors libbpf's `libbpf_probe_bpf_prog_type`: a trivial program is loaded and, for the
+/// program types that must be attached to a BTF type, the verifier log is checked for the error
+/// the kernel reports when the type itself is supported.
+pub(crate) fn is_prog_type_supported(prog_type: ProgramType) -> bool {
+    let mov64_imm = (BPF_ALU64 | BPF_MOV | BPF_K) as _;
+    let exit = (BPF_JMP | BPF_EXIT) as _;
+    let insns = [new_insn(mov64_imm, 0, 0, 0, 0), new_insn(exit, 0, 0, 0, 0)];
+
+    let expected_error = match prog_type {
+        ProgramType::Unspecified => return false,
+        ProgramType::Tracing | ProgramType::Lsm => Some("attach_btf_id 1 is not a function"),
+        ProgramType::Extension => Some("Cannot replace kernel functions"),
+        ProgramType::StructOps => Some("attach_btf_id 1 is not a supported struct"),
+        _ => None,
+    };
+
+    let mut log_buf = [0; 256];
+    match bpf_prog_load_probe(prog_type, &insns, &mut log_buf) {
+        Ok(_) => expected_error.is_none(),
+        Err(_) => expected_error.is_some_and(|msg| probe_log_contains(&log_buf, msg)),
+    }
+}
+
+/// Tests whether programs of the given type can call the helper with id `helper_id`.
+///
+/// Mirrors libbpf's `libbpf_probe_bpf_helper`: a program calling the helper is loaded and the
+/// verifier log is checked for the error reported for unknown helpers. Any other verifier error,
+/// for example about the helper arguments, means that the helper is available.
+pub(crate) fn is_helper_supported(prog_type: ProgramType, helper_id: u32) -> bool {
+    // Programs of these types fail to load before the verifier looks at their instructions.
+    if let ProgramType::Unspecified
+    | ProgramType::Tracing
+    | ProgramType::Lsm
+    | ProgramType::Extension
+    | ProgramType::StructOps = prog_type
+    {
+        return false;
+    }
+
+    let call = (BPF_JMP | BPF_CALL) as _;
+    let exit = (BPF_JMP | BPF_EXIT) as _;
+    let insns = [
+        new_insn(call, 0, 0, 0, helper_id as i32),
+        new_insn(exit, 0, 0, 0, 0),
+    ];
+
+    let mut log_buf = vec![0; 4096];
+    match bpf_prog_load_probe(prog_type, &insns, &mut log_buf) {
+        Ok(_) => true,
+        Err(_) => {
+            !probe_log_contains(&log_buf, "invalid func ")
+                && !probe_log_contains(&log_buf, "unknown func ")
+        }
+    }
+}
+
+fn bpf_prog_load_probe(
+    prog_type: ProgramType,
+    insns: &[bpf_insn],
+    log_buf: &mut [u8],
+) -> SysResult<crate::MockableFd> {
+    use bpf_attach_type::*;
+
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_3 };
+
+    let gpl = c"GPL";
+    u.license = gpl.as_ptr() as u64;
+
+    u.insn_cnt = insns.len() as u32;
+    u.insns = insns.as_ptr() as u64;
+    u.prog_type = prog_type as u32;
+
+    let expected_attach_type = match prog_type {
+        ProgramType::CgroupSockAddr => Some(BPF_CGROUP_INET4_CONNECT),
+        ProgramType::CgroupSockopt => Some(BPF_CGROUP_GETSOCKOPT),
+        ProgramType::SkLookup => Some(BPF_SK_LOOKUP),
+        ProgramType::Tracing => Some(BPF_TRACE_FENTRY),
+        ProgramType::Lsm => Some(BPF_LSM_MAC),
+        ProgramType::Netfilter => Some(BPF_NETFILTER),
+        _ => None,
+    };
+    if let Some(expected_attach_type) = expected_attach_type {
+        u.expected_attach_type = expected_attach_type as u32;
+    }
+
+    match prog_type {
+        // BTF type 1 is never a function or a struct_ops struct in vmlinux, so these are
+        // expected to fail with a message specific to the program type.
+        ProgramType::Tracing
+        | ProgramType::Lsm
+        | ProgramType::Extension
+        | ProgramType::StructOps => u.attach_btf_id = 1,
+        ProgramType::Syscall => u.prog_flags = BPF_F_SLEEPABLE,
+        // Kernels before 5.0 refuse to load kprobes built for a different kernel version.
+        ProgramType::KProbe => {
+            if let Ok(version) = KernelVersion::current() {
+                u.kern_version = version.code();
+            }
+        }
+        _ => {}
+    }
+
+    if !log_buf.is_empty() {
+        u.log_level = 1;
+        u.log_buf = log_buf.as_mut_ptr() as u64;
+        u.log_size = log_buf.len() as u32;
+    }
+
+    bpf_prog_load(&mut attr)
+}
+
+fn probe_log_contains(log_buf: &[u8], msg: &str) -> bool {
+    let len = log_buf
+        .iter()
+        .position(|b| *b == 0)
+        .unwrap_or(log_buf.len());
+    String::from_utf8_lossy(&log_buf[..len]).contains(msg)
+}
+
+// ENOTSUPP is internal to the kernel and not exported by libc.
+const ENOTSUPP: i32 = 524;
+
+/// Tests whether maps of the given type can be created.
+///
+/// Mirrors libbpf's `libbpf_probe_bpf_map_type`: the smallest map the kernel accepts for the
+/// type is created and immediately closed.
+pub(crate) fn is_map_type_supported(map_type: MapType) -> bool {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_1 };
+
+    u.map_type = map_type as u32;
+    u.key_size = 4;
+    u.value_size = 4;
+    u.max_entries = 1;
+
+    // Kept alive until the probed map has been created.
+    let mut _inner_map_fd = None;
+    let mut _btf_fd = None;
+
+    match map_type {
+        MapType::Unspecified => return false,
+        MapType::LpmTrie => {
+            u.key_size = 8;
+            u.value_size = 8;
+            u.map_flags = BPF_F_NO_PREALLOC;
+        }
+        MapType::StackTrace => u.value_size = 8,
+        MapType::Queue | MapType::Stack | MapType::BloomFilter => u.key_size = 0,
+        MapType::RingBuf | MapType::UserRingBuf => {
+            u.key_size = 0;
+            u.value_size = 0;
+            u.max_entries = page_size() as u32;
+        }
+        MapType::Arena => {
+            u.key_size = 0;
+            u.value_size = 0;
+            u.map_flags = BPF_F_MMAPABLE;
+        }
+        MapType::ArrayOfMaps | MapType::HashOfMaps => {
+            let mut inner_attr = unsafe { mem::zeroed::<bpf_attr>() };
+            let inner = unsafe { &mut inner_attr.__bindgen_anon_1 };
+            inner.map_type = bpf_map_type::BPF_MAP_TYPE_ARRAY as u32;
+            inner.key_size = 4;
+            inner.value_size = 4;
+            inner.max_entries = 1;
+            // SAFETY: BPF_MAP_CREATE returns a new file descriptor.
+            let Ok(fd) = (unsafe { fd_sys_bpf(bpf_cmd::BPF_MAP_CREATE, &mut inner_attr) }) else {
+                return false;
+            };
+            u.inner_map_fd = fd.as_raw_fd() as u32;
+            _inner_map_fd = Some(fd);
+        }
+        MapType::CgroupStorage | MapType::PerCpuCgroupStorage => {
+            // sizeof(struct bpf_cgroup_storage_key)
+            u.key_size = 16;
+            u.max_entries = 0;
+        }
+        MapType::SkStorage
+        | MapType::InodeStorage
+        | MapType::TaskStorage
+        | MapType::CgrpStorage => {
+            // Local storage maps require BTF for their key, which must be an int.
+            let mut btf = Btf::new();
+            let name_offset = btf.add_string("int");
+            let int_type_id = btf.add_type(BtfType::Int(Int::new(
+                name_offset,
+                4,
+                IntEncoding::Signed,
+                0,
+            )));
+            let Ok(fd) = bpf_load_btf(btf.to_bytes().as_slice(), &mut [], Default::default())
+            else {
+                return false;
+            };
+            u.btf_fd = fd.as_raw_fd() as u32;
+            u.btf_key_type_id = int_type_id;
+            u.btf_value_type_id = int_type_id;
+            u.map_flags = BPF_F_NO_PREALLOC;
+            u.max_entries = 0;
+            _btf_fd = Some(fd);
+        }
+        MapType::StructOps => {
+            u.btf_vmlinux_value_type_id = 1;
+        }
+        _ => {}
+    }
+
+    // SAFETY: BPF_MAP_CREATE returns a new file descriptor.
+    match unsafe { fd_sys_bpf(bpf_cmd::BPF_MAP_CREATE, &mut attr) } {
+        Ok(_) => true,
+        // BTF type 1 is never a struct_ops value type. Kernels that support struct_ops maps
+        // report ENOTSUPP for it, older kernels report EINVAL.
+        Err((_, io_error)) => {
+            map_type == MapType::StructOps && io_error.raw_os_error() == Some(ENOTSUPP)
+        }
+    }
+}
+
 fn bpf_prog_load(attr: &mut bpf_attr) -> SysResult<crate::MockableFd> {
     // SAFETY: BPF_PROG_LOAD returns a new file descriptor.
     unsafe { fd_sys_bpf(bpf_cmd::BPF_PROG_LOAD, attr) }
@@ -1152,6 +1617,13 @@ fn iter_obj_ids(
     })
 }
 
+// Looking up an object by id fails with ENOENT if the object was freed after its id was returned,
+// and with EPERM or EACCES if the caller isn't allowed to access that object, for example because
+// of an LSM policy. Iterating over all the objects on the host skips those objects.
+pub(crate) fn is_unavailable_by_id(error: &SyscallError) -> bool {
+    matches!(error.io_error.raw_os_error(), Some(ENOENT | EPERM | EACCES))
+}
+
 /// Introduced in kernel v4.13.
 pub(crate) fn iter_prog_ids() -> impl Iterator<Item = Result<u32, SyscallError>> {
     iter_obj_ids(bpf_cmd::BPF_PROG_GET_NEXT_ID, "bpf_prog_get_next_id")
@@ -1209,7 +1681,10 @@ pub(crate) fn retry_with_verifier_logs<T>(
         if let Some(pos) = log_buf.iter().position(|b| *b == 0) {
             log_buf.truncate(pos);
         }
-        let log_buf = String::from_utf8(log_buf).unwrap();
+        // The kernel truncates the log at an arbitrary byte when it runs out of space, which might
+        // split a multi-byte character.
+        let log_buf = String::from_utf8(log_buf)
+            .unwrap_or_else(|err| String::from_utf8_lossy(err.as_bytes()).into_owned());
 
         break (ret, VerifierLog::new(log_buf));
     }
@@ -1217,11 +1692,101 @@ pub(crate) fn retry_with_verifier_logs<T>(
 
 #[cfg(test)]
 mod tests {
-    use libc::{EBADF, EINVAL};
+    use std::cmp;
+
+    use libc::{EACCES, EBADF, EINVAL};
 
     use super::*;
     use crate::sys::override_syscall;
 
+    fn write_log(log_buf: &mut [u8], log: &[u8]) {
+        let len = cmp::min(log.len(), log_buf.len() - 1);
+        log_buf[..len].copy_from_slice(&log[..len]);
+        log_buf[len] = 0;
+    }
+
+    #[test]
+    fn test_copy_obj_name() {
+        let copy = |name: &CStr| {
+            let mut dst = [0; 16];
+            copy_obj_name(&mut dst, name);
+            dst.map(|c| c as u8)
+        };
+        assert_eq!(&copy(c"map.name_1"), b"map.name_1\0\0\0\0\0\0");
+        // the name is truncated to keep the NUL terminator
+        assert_eq!(&copy(c"a_very_long_map_name"), b"a_very_long_map\0");
+        // characters the kernel rejects are replaced
+        assert_eq!(&copy(c"maps/foo-bar"), b"maps_foo_bar\0\0\0\0");
+    }
+
+    #[test]
+    fn test_retry_with_verifier_logs() {
+        let attempts = std::cell::RefCell::new(Vec::new());
+        let (ret, log) = retry_with_verifier_logs(10, |log_buf| {
+            attempts.borrow_mut().push(log_buf.len());
+            match log_buf.len() {
+                // the first attempt is made without a log
+                0 => Err::<(), _>((-1, io::Error::from_raw_os_error(EACCES))),
+                // the log doesn't fit, the kernel truncates it and fails with ENOSPC
+                len if len < 20 * 1024 => {
+                    write_log(log_buf, &[b'x'; 20 * 1024]);
+                    Err((-1, io::Error::from_raw_os_error(ENOSPC)))
+                }
+                _ => {
+                    write_log(log_buf, b"R1 invalid mem access");
+                    Err((-1, io::Error::from_raw_os_error(EACCES)))
+                }
+            }
+        });
+        assert_eq!(*attempts.borrow(), [0, 10 * 1024, 100 * 1024]);
+        assert_matches!(ret, Err((_, io_error)) => assert_eq!(io_error.raw_os_error(), Some(EACCES)));
+        assert_eq!(log.to_string(), "R1 invalid mem access");
+    }
+
+    #[test]
+    fn test_retry_with_verifier_logs_truncated_utf8() {
+        let (ret, log) = retry_with_verifier_logs(1, |log_buf| {
+            if !log_buf.is_empty() {
+                // a truncated multi-byte character
+                write_log(log_buf, &[b'o', b'k', 0xe2, 0x82]);
+            }
+            Err::<(), _>((-1, io::Error::from_raw_os_error(ENOSPC)))
+        });
+        assert_matches!(ret, Err(_));
+        assert_eq!(log.to_string(), "ok\u{fffd}");
+    }
+
+    #[test]
+    fn test_bpf_enable_stats() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_ENABLE_STATS,
+                attr,
+            } => {
+                assert_eq!(
+                    unsafe { attr.enable_stats.type_ },
+                    bpf_stats_type::BPF_STATS_RUN_TIME as u32
+                );
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+
+        let fd = bpf_enable_stats(bpf_stats_type::BPF_STATS_RUN_TIME).unwrap();
+        assert_eq!(fd.as_raw_fd(), crate::MockableFd::mock_signed_fd());
+    }
+
+    #[test]
+    fn test_bpf_enable_stats_not_supported() {
+        override_syscall(|_| Err((-1, io::Error::from_raw_os_error(EINVAL))));
+
+        assert_matches!(
+            bpf_enable_stats(bpf_stats_type::BPF_STATS_RUN_TIME),
+            Err(SyscallError { call: "bpf_enable_stats", io_error })
+                if io_error.raw_os_error() == Some(EINVAL)
+        );
+    }
+
     #[test]
     fn test_attach_with_attributes() {
         const FAKE_FLAGS: u32 = 1234;
@@ -1305,6 +1870,113 @@ mod tests {
         assert!(!supported);
     }
 
+    fn load_log(attr: &mut bpf_attr) -> &mut [u8] {
+        let u = unsafe { &attr.__bindgen_anon_3 };
+        unsafe { std::slice::from_raw_parts_mut(u.log_buf as *mut u8, u.log_size as usize) }
+    }
+
+    #[test]
+    fn test_prog_type_supported() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_3 };
+                assert_eq!(
+                    u.expected_attach_type,
+                    bpf_attach_type::BPF_CGROUP_INET4_CONNECT as u32
+                );
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(is_prog_type_supported(ProgramType::CgroupSockAddr));
+
+        override_syscall(|_call| Err((-1, io::Error::from_raw_os_error(EINVAL))));
+        assert!(!is_prog_type_supported(ProgramType::CgroupSockAddr));
+    }
+
+    #[test]
+    fn test_prog_type_supported_btf() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                assert_eq!(unsafe { attr.__bindgen_anon_3.attach_btf_id }, 1);
+                write_log(load_log(attr), b"attach_btf_id 1 is not a function\n");
+                Err((-1, io::Error::from_raw_os_error(EINVAL)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(is_prog_type_supported(ProgramType::Lsm));
+
+        override_syscall(|_call| Err((-1, io::Error::from_raw_os_error(EINVAL))));
+        assert!(!is_prog_type_supported(ProgramType::Lsm));
+    }
+
+    #[test]
+    fn test_helper_supported() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                write_log(load_log(attr), b"0: R1=ctx() R10=fp0\n0: (85) call bpf_probe_read_kernel#113\nR1 type=ctx expected=fp\n");
+                Err((-1, io::Error::from_raw_os_error(EACCES)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(is_helper_supported(
+            ProgramType::KProbe,
+            BPF_FUNC_probe_read_kernel as u32
+        ));
+
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_PROG_LOAD,
+                attr,
+            } => {
+                write_log(
+                    load_log(attr),
+                    b"0: (85) call unknown#113\ninvalid func unknown#113\n",
+                );
+                Err((-1, io::Error::from_raw_os_error(EINVAL)))
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(!is_helper_supported(
+            ProgramType::KProbe,
+            BPF_FUNC_probe_read_kernel as u32
+        ));
+    }
+
+    #[test]
+    fn test_map_type_supported() {
+        override_syscall(|call| match call {
+            Syscall::Ebpf {
+                cmd: bpf_cmd::BPF_MAP_CREATE,
+                attr,
+            } => {
+                let u = unsafe { &attr.__bindgen_anon_1 };
+                assert_eq!(u.key_size, 0);
+                assert_eq!(u.value_size, 0);
+                assert_eq!(u.max_entries, page_size() as u32);
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EINVAL))),
+        });
+        assert!(is_map_type_supported(MapType::RingBuf));
+
+        override_syscall(|_call| Err((-1, io::Error::from_raw_os_error(EINVAL))));
+        assert!(!is_map_type_supported(MapType::RingBuf));
+        assert!(!is_map_type_supported(MapType::StructOps));
+
+        override_syscall(|_call| Err((-1, io::Error::from_raw_os_error(ENOTSUPP))));
+        assert!(is_map_type_supported(MapType::StructOps));
+    }
+
     #[test]
     #[should_panic = "assertion failed: `BPF_MAP_TYPE_HASH` does not match `bpf_map_type::BPF_MAP_TYPE_CPUMAP | bpf_map_type::BPF_MAP_TYPE_DEVMAP |
 bpf_map_type::BPF_MAP_TYPE_DEVMAP_HASH`"]
@@ -170,16 +170,38 @@ impl From<Stats> for aya_obj::generated::bpf_stats_type {
 /// 4. Record the statistic of interest again, and calculate the difference.
 /// 5. Close/release fd automatically or manually.
 ///
+/// Statistics are tracked for all the programs on the host, as long as at least one such file
+/// descriptor is open. Tracking them adds some overhead to every program run. On kernels older
+/// than v5.8, they can instead be enabled by setting the `kernel.bpf_stats_enabled` sysctl.
+///
 /// Introduced in kernel v5.8.
 ///
 /// # Examples
 ///
 /// ```no_run
-/// # use aya::sys::{SyscallError};
+/// # #[derive(Debug, thiserror::Error)]
+/// # enum Error {
+/// #     #[error(transparent)]
+/// #     Syscall(#[from] aya::sys::SyscallError),
+/// #     #[error(transparent)]
+/// #     Program(#[from] aya::programs::ProgramError),
+/// # }
+/// # let bpf = aya::Ebpf::load(&[]).unwrap();
+/// use std::{thread, time::Duration};
+///
 /// use aya::sys::{enable_stats, Stats};
 ///
-/// let _fd = enable_stats(Stats::RunTime)?;
-/// # Ok::<(), SyscallError>(())
+/// let program = bpf.program("counter").unwrap();
+/// let _stats = enable_stats(Stats::RunTime)?;
+///
+/// let before = program.info()?;
+/// thread::sleep(Duration::from_secs(1));
+/// let after = program.info()?;
+///
+/// let runs = after.run_count() - before.run_count();
+/// let run_time = after.run_time() - before.run_time();
+/// println!("{runs} runs in the last second, {run_time:?} in total");
+/// # Ok::<(), Error>(())
 /// ```
 #[doc(alias = "BPF_ENABLE_STATS")]
 pub fn enable_stats(stats_type: Stats) -> Result<OwnedFd, SyscallError> {
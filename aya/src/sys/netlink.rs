@@ -7,21 +7,26 @@ use std::{
 };
 
 use aya_obj::generated::{
-    ifinfomsg, nlmsgerr_attrs::NLMSGERR_ATTR_MSG, tcmsg, IFLA_XDP_EXPECTED_FD, IFLA_XDP_FD,
-    IFLA_XDP_FLAGS, NLMSG_ALIGNTO, TCA_BPF_FD, TCA_BPF_FLAGS, TCA_BPF_FLAG_ACT_DIRECT,
+    ifinfomsg, nlmsgerr_attrs::NLMSGERR_ATTR_MSG, tcmsg, IFLA_XDP_ATTACHED, IFLA_XDP_DRV_PROG_ID,
+    IFLA_XDP_EXPECTED_FD, IFLA_XDP_FD, IFLA_XDP_FLAGS, IFLA_XDP_HW_PROG_ID, IFLA_XDP_PROG_ID,
+    IFLA_XDP_SKB_PROG_ID, NLMSG_ALIGNTO, TCA_BPF_FD, TCA_BPF_FLAGS, TCA_BPF_FLAG_ACT_DIRECT,
     TCA_BPF_NAME, TCA_KIND, TCA_OPTIONS, TC_H_CLSACT, TC_H_INGRESS, TC_H_MAJ_MASK, TC_H_UNSPEC,
     XDP_FLAGS_REPLACE,
 };
 use libc::{
     getsockname, nlattr, nlmsgerr, nlmsghdr, recv, send, setsockopt, sockaddr_nl, socket,
-    AF_NETLINK, AF_UNSPEC, ETH_P_ALL, IFF_UP, IFLA_XDP, NETLINK_CAP_ACK, NETLINK_EXT_ACK,
-    NETLINK_ROUTE, NLA_ALIGNTO, NLA_F_NESTED, NLA_TYPE_MASK, NLMSG_DONE, NLMSG_ERROR, NLM_F_ACK,
-    NLM_F_CREATE, NLM_F_DUMP, NLM_F_ECHO, NLM_F_EXCL, NLM_F_MULTI, NLM_F_REQUEST, RTM_DELTFILTER,
-    RTM_GETTFILTER, RTM_NEWQDISC, RTM_NEWTFILTER, RTM_SETLINK, SOCK_RAW, SOL_NETLINK,
+    AF_NETLINK, AF_UNSPEC, ETH_P_ALL, IFF_UP, IFLA_XDP, MSG_PEEK, MSG_TRUNC, NETLINK_CAP_ACK,
+    NETLINK_EXT_ACK, NETLINK_ROUTE, NLA_ALIGNTO, NLA_F_NESTED, NLA_TYPE_MASK, NLMSG_DONE,
+    NLMSG_ERROR, NLM_F_ACK, NLM_F_CREATE, NLM_F_DUMP, NLM_F_ECHO, NLM_F_EXCL, NLM_F_MULTI,
+    NLM_F_REQUEST, RTM_DELTFILTER, RTM_GETLINK, RTM_GETTFILTER, RTM_NEWLINK, RTM_NEWQDISC,
+    RTM_NEWTFILTER, RTM_SETLINK, SOCK_RAW, SOL_NETLINK,
 };
 use thiserror::Error;
 
-use crate::{programs::TcAttachType, util::tc_handler_make};
+use crate::{
+    programs::{TcAttachType, XdpAttachedPrograms},
+    util::tc_handler_make,
+};
 
 const NLA_HDR_LEN: usize = align_to(mem::size_of::<nlattr>(), NLA_ALIGNTO as usize);
 
@@ -47,6 +52,17 @@ pub(crate) enum NetlinkErrorInternal {
 #[error(transparent)]
 pub struct NetlinkError(#[from] NetlinkErrorInternal);
 
+impl NetlinkError {
+    pub(crate) fn raw_os_error(&self) -> Option<i32> {
+        match &self.0 {
+            NetlinkErrorInternal::Error { source, .. } | NetlinkErrorInternal::IoError(source) => {
+                source.raw_os_error()
+            }
+            NetlinkErrorInternal::NulError(_) | NetlinkErrorInternal::NlAttrError(_) => None,
+        }
+    }
+}
+
 // Safety: marking this as unsafe overall because of all the pointer math required to comply with
 // netlink alignments
 pub(crate) unsafe fn netlink_set_xdp_fd(
@@ -106,6 +122,81 @@ pub(crate) unsafe fn netlink_set_xdp_fd(
     Ok(())
 }
 
+pub(crate) unsafe fn netlink_get_xdp_programs(
+    if_index: i32,
+) -> Result<XdpAttachedPrograms, NetlinkError> {
+    let sock = NetlinkSocket::open()?;
+
+    // Safety: Request is POD so this is safe
+    let mut req = mem::zeroed::<Request>();
+
+    let nlmsg_len = mem::size_of::<nlmsghdr>() + mem::size_of::<ifinfomsg>();
+    req.header = nlmsghdr {
+        nlmsg_len: nlmsg_len as u32,
+        nlmsg_flags: NLM_F_REQUEST as u16,
+        nlmsg_type: RTM_GETLINK,
+        nlmsg_pid: 0,
+        nlmsg_seq: 1,
+    };
+    req.if_info.ifi_family = AF_UNSPEC as u8;
+    req.if_info.ifi_index = if_index;
+
+    sock.send(&bytes_of(&req)[..req.header.nlmsg_len as usize])?;
+
+    for msg in sock.recv()? {
+        if msg.header.nlmsg_type != RTM_NEWLINK || msg.data.len() < mem::size_of::<ifinfomsg>() {
+            continue;
+        }
+        let if_info = ptr::read_unaligned(msg.data.as_ptr() as *const ifinfomsg);
+        if if_info.ifi_index != if_index {
+            continue;
+        }
+        let attrs = msg
+            .data
+            .get(align_to(mem::size_of::<ifinfomsg>(), NLMSG_ALIGNTO as usize)..)
+            .unwrap_or_default();
+        return parse_xdp_programs(attrs)
+            .map_err(|e| NetlinkError(NetlinkErrorInternal::NlAttrError(e)));
+    }
+
+    Ok(XdpAttachedPrograms::default())
+}
+
+// Parses the IFLA_XDP attribute out of the attributes of a RTM_NEWLINK message.
+fn parse_xdp_programs(attrs: &[u8]) -> Result<XdpAttachedPrograms, NlAttrError> {
+    let attrs = parse_attrs(attrs)?;
+    let Some(xdp) = attrs.get(&IFLA_XDP) else {
+        return Ok(XdpAttachedPrograms::default());
+    };
+    let xdp = parse_attrs(xdp.data)?;
+    let prog_id = |attr: u32| {
+        xdp.get(&(attr as u16))
+            .and_then(|attr| attr.data.get(..mem::size_of::<u32>()))
+            .map(|data| u32::from_ne_bytes(data.try_into().unwrap()))
+            .filter(|id| *id != 0)
+    };
+    let mut programs = XdpAttachedPrograms {
+        skb_mode: prog_id(IFLA_XDP_SKB_PROG_ID),
+        drv_mode: prog_id(IFLA_XDP_DRV_PROG_ID),
+        hw_mode: prog_id(IFLA_XDP_HW_PROG_ID),
+    };
+    // Kernels before 4.19 don't report the program of each mode, only the id of the single
+    // attached program and the mode it's attached in.
+    if programs.is_empty() {
+        let attached = xdp
+            .get(&(IFLA_XDP_ATTACHED as u16))
+            .and_then(|attr| attr.data.first().copied());
+        let id = prog_id(IFLA_XDP_PROG_ID);
+        match attached {
+            Some(XDP_ATTACHED_SKB) => programs.skb_mode = id,
+            Some(XDP_ATTACHED_DRV) => programs.drv_mode = id,
+            Some(XDP_ATTACHED_HW) => programs.hw_mode = id,
+            _ => {}
+        }
+    }
+    Ok(programs)
+}
+
 pub(crate) unsafe fn netlink_qdisc_add_clsact(if_index: i32) -> Result<(), NetlinkError> {
     let sock = NetlinkSocket::open()?;
 
@@ -425,11 +516,29 @@ impl NetlinkSocket {
     }
 
     fn recv(&self) -> Result<Vec<NetlinkMessage>, NetlinkErrorInternal> {
-        let mut buf = [0u8; 4096];
+        let mut buf = vec![0u8; 4096];
         let mut messages = Vec::new();
         let mut multipart = true;
         'out: while multipart {
             multipart = false;
+            // Messages that don't fit in the buffer are truncated, so peek at the size of the
+            // next one first. A RTM_NEWLINK message can be larger than a page on interfaces with
+            // many VFs for example.
+            // Safety: libc wrapper
+            let len = unsafe {
+                recv(
+                    self.sock.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut _,
+                    buf.len(),
+                    MSG_PEEK | MSG_TRUNC,
+                )
+            };
+            if len < 0 {
+                return Err(NetlinkErrorInternal::IoError(io::Error::last_os_error()));
+            }
+            if len as usize > buf.len() {
+                buf.resize(len as usize, 0);
+            }
             // Safety: libc wrapper
             let len = unsafe {
                 recv(
@@ -459,11 +568,13 @@ impl NetlinkSocket {
                             // this is an ACK
                             continue;
                         }
-                        let attrs = parse_attrs(&message.data)?;
-                        let err_msg = attrs.get(&(NLMSGERR_ATTR_MSG as u16)).and_then(|msg| {
-                            CStr::from_bytes_with_nul(msg.data)
-                                .ok()
-                                .map(|s| s.to_string_lossy().into_owned())
+                        // Don't let malformed extended ACK attributes hide the errno.
+                        let err_msg = parse_attrs(&message.data).ok().and_then(|attrs| {
+                            attrs.get(&(NLMSGERR_ATTR_MSG as u16)).and_then(|msg| {
+                                CStr::from_bytes_until_nul(msg.data)
+                                    .ok()
+                                    .map(|s| s.to_string_lossy().into_owned())
+                            })
                         });
                         let e = match err_msg {
                             Some(err_msg) => NetlinkErrorInternal::Error {
@@ -514,19 +625,32 @@ impl NetlinkMessage {
         }
 
         let (rest, error) = if header.nlmsg_type == NLMSG_ERROR as u16 {
-            if data_offset + mem::size_of::<nlmsgerr>() > buf.len() {
+            let err_end = data_offset + mem::size_of::<nlmsgerr>();
+            if err_end > msg_len {
                 return Err(io::Error::new(
                     io::ErrorKind::Other,
                     "NLMSG_ERROR but not enough space for nlmsgerr",
                 ));
             }
-            (
-                &buf[data_offset + mem::size_of::<nlmsgerr>()..msg_len],
-                // Safety: nlmsgerr is POD so read is safe
-                Some(unsafe {
-                    ptr::read_unaligned(buf[data_offset..].as_ptr() as *const nlmsgerr)
-                }),
-            )
+            // Safety: nlmsgerr is POD so read is safe
+            let err =
+                unsafe { ptr::read_unaligned(buf[data_offset..].as_ptr() as *const nlmsgerr) };
+
+            // The extended ACK attributes, if any, come after the payload of the request, which
+            // is only echoed back when NETLINK_CAP_ACK isn't in effect.
+            let attrs = if header.nlmsg_flags & NLM_F_ACK_TLVS == 0 {
+                &[][..]
+            } else {
+                let attrs_offset = if header.nlmsg_flags & NLM_F_CAPPED != 0 {
+                    err_end
+                } else {
+                    let payload_len =
+                        (err.msg.nlmsg_len as usize).saturating_sub(mem::size_of::<nlmsghdr>());
+                    err_end + align_to(payload_len, NLMSG_ALIGNTO as usize)
+                };
+                buf.get(attrs_offset..msg_len).unwrap_or_default()
+            };
+            (attrs, Some(err))
         } else {
             (&buf[data_offset..msg_len], None)
         };
@@ -539,6 +663,18 @@ impl NetlinkMessage {
     }
 }
 
+// From linux/if_link.h. Values of the IFLA_XDP_ATTACHED attribute.
+const XDP_ATTACHED_DRV: u8 = 1;
+const XDP_ATTACHED_SKB: u8 = 2;
+const XDP_ATTACHED_HW: u8 = 3;
+
+// From linux/netlink.h. Flags of NLMSG_ERROR messages.
+//
+// The request payload was not echoed back.
+const NLM_F_CAPPED: u16 = 0x100;
+// Extended ACK attributes are included.
+const NLM_F_ACK_TLVS: u16 = 0x200;
+
 const fn align_to(v: usize, align: usize) -> usize {
     (v + (align - 1)) & !(align - 1)
 }
@@ -726,7 +862,11 @@ fn bytes_of<T>(val: &T) -> &[u8] {
 mod tests {
     use std::ffi::CString;
 
+    use aya_obj::generated::IFLA_XDP_ATTACHED;
+    use libc::{IFLA_IFNAME, IFLA_MTU};
+
     use super::*;
+    use crate::programs::XdpFlags;
 
     #[test]
     fn test_nested_attrs() {
@@ -855,4 +995,149 @@ mod tests {
         let name = CStr::from_bytes_with_nul(inner.data).unwrap();
         assert_eq!(name.to_str().unwrap(), "foo");
     }
+
+    #[test]
+    fn test_parse_xdp_programs() {
+        let mut buf = [0; 64];
+        let ifname = CString::new("eth0").unwrap();
+        let offset =
+            write_attr_bytes(&mut buf, 0, IFLA_IFNAME, ifname.to_bytes_with_nul()).unwrap();
+
+        let mut attrs = NestedAttrs::new(&mut buf[offset..], IFLA_XDP);
+        attrs
+            // XDP_ATTACHED_MULTI
+            .write_attr(IFLA_XDP_ATTACHED as u16, 4u8)
+            .unwrap();
+        attrs.write_attr(IFLA_XDP_SKB_PROG_ID as u16, 7u32).unwrap();
+        attrs.write_attr(IFLA_XDP_DRV_PROG_ID as u16, 9u32).unwrap();
+        let len = offset + attrs.finish().unwrap();
+
+        let programs = parse_xdp_programs(&buf[..len]).unwrap();
+        assert_eq!(
+            programs,
+            XdpAttachedPrograms {
+                skb_mode: Some(7),
+                drv_mode: Some(9),
+                hw_mode: None,
+            }
+        );
+        assert_eq!(
+            programs
+                .iter()
+                .map(|(mode, id)| (mode.bits(), id))
+                .collect::<Vec<_>>(),
+            [
+                (XdpFlags::SKB_MODE.bits(), 7),
+                (XdpFlags::DRV_MODE.bits(), 9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_xdp_programs_single_prog_id() {
+        let mut buf = [0; 32];
+        let mut attrs = NestedAttrs::new(&mut buf, IFLA_XDP);
+        attrs
+            .write_attr(IFLA_XDP_ATTACHED as u16, XDP_ATTACHED_DRV)
+            .unwrap();
+        attrs.write_attr(IFLA_XDP_PROG_ID as u16, 9u32).unwrap();
+        let len = attrs.finish().unwrap();
+
+        assert_eq!(
+            parse_xdp_programs(&buf[..len]).unwrap(),
+            XdpAttachedPrograms {
+                skb_mode: None,
+                drv_mode: Some(9),
+                hw_mode: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_xdp_programs_none() {
+        let mut buf = [0; 16];
+        let len = write_attr(&mut buf, 0, IFLA_MTU, 1500u32).unwrap();
+
+        let programs = parse_xdp_programs(&buf[..len]).unwrap();
+        assert!(programs.is_empty());
+    }
+
+    // Builds a NLMSG_ERROR message for a request with a payload of `request_payload_len` bytes,
+    // echoing the payload back unless `capped`, followed by an extended ACK message.
+    fn error_message(errno: i32, request_payload_len: usize, capped: bool, msg: &str) -> Vec<u8> {
+        let header_len = mem::size_of::<nlmsghdr>();
+        let err_len = mem::size_of::<nlmsgerr>();
+        let echoed_len = if capped { 0 } else { request_payload_len };
+        let msg = CString::new(msg).unwrap();
+        let attr_len = NLA_HDR_LEN + msg.as_bytes_with_nul().len();
+        let len = header_len + err_len + echoed_len + align_to(attr_len, NLA_ALIGNTO as usize);
+
+        let mut buf = vec![0; len];
+        let mut flags = NLM_F_ACK_TLVS;
+        if capped {
+            flags |= NLM_F_CAPPED;
+        }
+        let header = nlmsghdr {
+            nlmsg_len: len as u32,
+            nlmsg_type: NLMSG_ERROR as u16,
+            nlmsg_flags: flags,
+            nlmsg_seq: 1,
+            nlmsg_pid: 0,
+        };
+        buf[..header_len].copy_from_slice(bytes_of(&header));
+        let err = nlmsgerr {
+            error: -errno,
+            msg: nlmsghdr {
+                nlmsg_len: (header_len + request_payload_len) as u32,
+                nlmsg_type: RTM_SETLINK,
+                nlmsg_flags: (NLM_F_REQUEST | NLM_F_ACK) as u16,
+                nlmsg_seq: 1,
+                nlmsg_pid: 0,
+            },
+        };
+        buf[header_len..header_len + err_len].copy_from_slice(bytes_of(&err));
+        // the echoed payload is garbage as far as the attributes are concerned
+        buf[header_len + err_len..header_len + err_len + echoed_len].fill(0xff);
+        write_attr_bytes(
+            &mut buf,
+            header_len + err_len + echoed_len,
+            NLMSGERR_ATTR_MSG as u16,
+            msg.as_bytes_with_nul(),
+        )
+        .unwrap();
+        buf
+    }
+
+    fn ext_ack_message(message: &NetlinkMessage) -> String {
+        let attrs = parse_attrs(&message.data).unwrap();
+        let msg = attrs.get(&(NLMSGERR_ATTR_MSG as u16)).unwrap();
+        CStr::from_bytes_until_nul(msg.data)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_read_error_message_capped() {
+        let buf = error_message(libc::EBUSY, 32, true, "XDP program already attached");
+        let message = NetlinkMessage::read(&buf).unwrap();
+        assert_eq!(message.error.unwrap().error, -libc::EBUSY);
+        assert_eq!(ext_ack_message(&message), "XDP program already attached");
+    }
+
+    #[test]
+    fn test_read_error_message_not_capped() {
+        let buf = error_message(libc::EINVAL, 32, false, "invalid flags");
+        let message = NetlinkMessage::read(&buf).unwrap();
+        assert_eq!(message.error.unwrap().error, -libc::EINVAL);
+        assert_eq!(ext_ack_message(&message), "invalid flags");
+    }
+
+    #[test]
+    fn test_read_truncated_error_message() {
+        let mut buf = error_message(libc::EINVAL, 0, true, "");
+        let len = mem::size_of::<nlmsghdr>() + mem::size_of::<i32>();
+        buf[..mem::size_of::<u32>()].copy_from_slice(&(len as u32).to_ne_bytes());
+        assert!(NetlinkMessage::read(&buf).is_err());
+    }
 }
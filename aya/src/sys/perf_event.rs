@@ -171,3 +171,79 @@ impl TryFrom<u32> for perf_event_type {
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use aya_obj::generated::{
+        perf_hw_id::PERF_COUNT_HW_CPU_CYCLES, perf_type_id::PERF_TYPE_HARDWARE,
+    };
+    use libc::EFAULT;
+
+    use super::*;
+    use crate::sys::override_syscall;
+
+    #[test]
+    fn test_perf_event_open_frequency() {
+        override_syscall(|call| match call {
+            Syscall::PerfEventOpen {
+                attr,
+                pid,
+                cpu,
+                group,
+                flags,
+            } => {
+                assert_eq!(attr.type_, PERF_TYPE_HARDWARE as u32);
+                assert_eq!(attr.config, PERF_COUNT_HW_CPU_CYCLES as u64);
+                assert_eq!(attr.freq(), 1);
+                assert_eq!(unsafe { attr.__bindgen_anon_1.sample_freq }, 99);
+                assert_eq!(attr.inherit(), 1);
+                assert_eq!((pid, cpu, group, flags), (-1, 3, -1, 0));
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        perf_event_open(
+            PERF_TYPE_HARDWARE as u32,
+            PERF_COUNT_HW_CPU_CYCLES as u64,
+            -1,
+            3,
+            0,
+            Some(99),
+            false,
+            true,
+            0,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_perf_event_open_period() {
+        override_syscall(|call| match call {
+            Syscall::PerfEventOpen { attr, pid, cpu, .. } => {
+                assert_eq!(attr.type_, PERF_TYPE_SOFTWARE as u32);
+                assert_eq!(attr.freq(), 0);
+                assert_eq!(unsafe { attr.__bindgen_anon_1.sample_period }, 1000);
+                assert_eq!(attr.inherit(), 0);
+                assert_eq!((pid, cpu), (42, -1));
+                Ok(crate::MockableFd::mock_signed_fd().into())
+            }
+            _ => Err((-1, io::Error::from_raw_os_error(EFAULT))),
+        });
+
+        perf_event_open(
+            PERF_TYPE_SOFTWARE as u32,
+            0,
+            42,
+            -1,
+            1000,
+            None,
+            false,
+            false,
+            0,
+        )
+        .unwrap();
+    }
+}
@@ -13,7 +13,10 @@ use std::{
 };
 
 use aya_obj::generated::{TC_H_MAJ_MASK, TC_H_MIN_MASK};
-use libc::{if_nametoindex, sysconf, uname, utsname, _SC_PAGESIZE};
+use libc::{
+    if_nametoindex, rlimit, setrlimit, sysconf, uname, utsname, _SC_PAGESIZE, RLIMIT_MEMLOCK,
+    RLIM_INFINITY,
+};
 
 use crate::Pod;
 
@@ -186,13 +189,27 @@ const ONLINE_CPUS: &str = "/sys/devices/system/cpu/online";
 const POSSIBLE_CPUS: &str = "/sys/devices/system/cpu/possible";
 
 /// Returns the numeric IDs of the CPUs currently online.
+///
+/// See `/sys/devices/system/cpu/online`. Use this to decide which CPUs to open
+/// [`PerfEventArray`](crate::maps::PerfEventArray) buffers for, since eBPF programs only run on
+/// online CPUs.
 pub fn online_cpus() -> Result<Vec<u32>, (&'static str, io::Error)> {
     read_cpu_ranges(ONLINE_CPUS)
 }
 
-/// Get the number of possible cpus.
+/// Returns the numeric IDs of the CPUs that can ever be online, including the ones currently
+/// offline.
 ///
 /// See `/sys/devices/system/cpu/possible`.
+pub fn possible_cpus() -> Result<Vec<u32>, (&'static str, io::Error)> {
+    read_cpu_ranges(POSSIBLE_CPUS)
+}
+
+/// Get the number of possible cpus.
+///
+/// See `/sys/devices/system/cpu/possible`. The kernel stores one value per possible CPU in
+/// per-CPU maps, so this is the number of values in [`PerCpuValues`](crate::maps::PerCpuValues),
+/// which can be larger than the number of [online CPUs](online_cpus).
 pub fn nr_cpus() -> Result<usize, (&'static str, io::Error)> {
     thread_local! {
         // TODO(https://github.com/rust-lang/rust/issues/109737): Use
@@ -240,7 +257,7 @@ fn parse_cpu_ranges(data: &str) -> Result<Vec<u32>, io::Error> {
 
 /// Loads kernel symbols from `/proc/kallsyms`.
 ///
-/// See [`crate::maps::StackTraceMap`] for an example on how to use this to resolve kernel addresses to symbols.
+/// See [`SymbolResolver`] to resolve kernel addresses to symbols.
 pub fn kernel_symbols() -> Result<BTreeMap<u64, String>, io::Error> {
     let mut reader = BufReader::new(File::open("/proc/kallsyms")?);
     parse_kernel_symbols(&mut reader)
@@ -270,6 +287,59 @@ fn parse_kernel_symbols(reader: impl BufRead) -> Result<BTreeMap<u64, String>, i
         .collect()
 }
 
+/// Resolves addresses to the nearest preceding symbol.
+///
+/// This is typically built from `/proc/kallsyms` with [`SymbolResolver::kernel`], and used to
+/// symbolize the frames of a kernel [`StackTrace`](crate::maps::stack_trace::StackTrace). For user
+/// space stacks, a resolver can be built from any address to name mapping, for example the symbol
+/// table of the traced binary.
+///
+/// # Examples
+///
+/// ```no_run
+/// use aya::util::SymbolResolver;
+///
+/// let ksyms = SymbolResolver::kernel()?;
+/// if let Some((name, offset)) = ksyms.resolve(0xffffffff81000000) {
+///     println!("{name}+{offset:#x}");
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SymbolResolver {
+    symbols: BTreeMap<u64, String>,
+}
+
+impl SymbolResolver {
+    /// Loads kernel symbols from `/proc/kallsyms`.
+    pub fn kernel() -> Result<Self, io::Error> {
+        kernel_symbols().map(Self::from)
+    }
+
+    /// Parses symbols in the `/proc/kallsyms` format from `reader`.
+    pub fn from_kallsyms(reader: impl BufRead) -> Result<Self, io::Error> {
+        parse_kernel_symbols(reader).map(Self::from)
+    }
+
+    /// Returns the name of the symbol containing `addr` and the offset of `addr` from the start
+    /// of the symbol.
+    ///
+    /// Addresses that fall between two symbols resolve to the lower one. Returns `None` if
+    /// `addr` is below the first symbol.
+    pub fn resolve(&self, addr: u64) -> Option<(&str, u64)> {
+        self.symbols
+            .range(..=addr)
+            .next_back()
+            .map(|(start, name)| (name.as_str(), addr - start))
+    }
+}
+
+impl From<BTreeMap<u64, String>> for SymbolResolver {
+    fn from(symbols: BTreeMap<u64, String>) -> Self {
+        Self { symbols }
+    }
+}
+
 /// Returns the prefix used by syscalls.
 ///
 /// # Example
@@ -310,6 +380,31 @@ pub fn syscall_prefix() -> Result<&'static str, io::Error> {
     Err(io::ErrorKind::NotFound.into())
 }
 
+/// Raises the `RLIMIT_MEMLOCK` limit of the current process to `RLIM_INFINITY`.
+///
+/// Before kernel 5.11 the memory used by maps and programs is charged against `RLIMIT_MEMLOCK`,
+/// and the default limit is often too low to load anything but trivial programs, which makes
+/// map and program creation fail with `EPERM`. Since 5.11 that memory is charged to the memory
+/// cgroup of the process instead, and this function does nothing.
+///
+/// Raising the limit requires `CAP_SYS_RESOURCE` unless the hard limit is already
+/// `RLIM_INFINITY`, otherwise an error with [`io::ErrorKind::PermissionDenied`] is returned.
+///
+/// See also [`EbpfLoader::bump_memlock_rlimit`](crate::EbpfLoader::bump_memlock_rlimit).
+pub fn bump_memlock_rlimit() -> Result<(), io::Error> {
+    if KernelVersion::current().is_ok_and(|version| version >= KernelVersion::new(5, 11, 0)) {
+        return Ok(());
+    }
+    let limit = rlimit {
+        rlim_cur: RLIM_INFINITY,
+        rlim_max: RLIM_INFINITY,
+    };
+    if unsafe { setrlimit(RLIMIT_MEMLOCK, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 pub(crate) fn ifindex_from_ifname(if_name: &str) -> Result<u32, io::Error> {
     let c_str_if_name = CString::new(if_name)?;
     let c_if_name = c_str_if_name.as_ptr();
@@ -328,8 +423,8 @@ pub(crate) fn tc_handler_make(major: u32, minor: u32) -> u32 {
 /// Include bytes from a file for use in a subsequent [`crate::Ebpf::load`].
 ///
 /// This macro differs from the standard `include_bytes!` macro since it also ensures that
-/// the bytes are correctly aligned to be parsed as an ELF binary. This avoid some nasty
-/// compilation errors when the resulting byte array is not the correct alignment.
+/// the bytes are correctly aligned to be parsed as an ELF binary, which avoids copying them
+/// into an aligned buffer when they are loaded.
 ///
 /// # Examples
 /// ```ignore
@@ -432,6 +527,7 @@ mod tests {
             parse_cpu_ranges("0-5,6,7").unwrap(),
             (0..=7).collect::<Vec<_>>()
         );
+        assert_eq!(parse_cpu_ranges("0-3,5").unwrap(), vec![0, 1, 2, 3, 5]);
         assert!(parse_cpu_ranges("").is_err());
         assert!(parse_cpu_ranges("0-1,2-").is_err());
         assert!(parse_cpu_ranges("foo").is_err());
@@ -450,4 +546,17 @@ mod tests {
         );
         assert_eq!(syms.get(&0x6000u64).unwrap().as_str(), "cpu_tss_rw");
     }
+
+    #[test]
+    fn test_symbol_resolver() {
+        let data = "0000000000002000 T foo\n\
+                    0000000000006000 t bar [baz]\n"
+            .as_bytes();
+        let syms = SymbolResolver::from_kallsyms(data).unwrap();
+        assert_eq!(syms.resolve(0x1fff), None);
+        assert_eq!(syms.resolve(0x2000), Some(("foo", 0)));
+        assert_eq!(syms.resolve(0x2010), Some(("foo", 0x10)));
+        assert_eq!(syms.resolve(0x6000), Some(("bar", 0)));
+        assert_eq!(syms.resolve(0x7000), Some(("bar", 0x1000)));
+    }
 }
@@ -49,6 +49,22 @@ pub unsafe fn bpf_ktime_get_ns() -> __u64 {
     let fun: unsafe extern "C" fn() -> __u64 = ::core::mem::transmute(5usize);
     fun()
 }
+pub unsafe fn bpf_trace_printk(
+    fmt: *const ::aya_ebpf_cty::c_char,
+    fmt_size: __u32,
+    arg1: __u64,
+    arg2: __u64,
+    arg3: __u64,
+) -> ::aya_ebpf_cty::c_long {
+    let fun: unsafe extern "C" fn(
+        fmt: *const ::aya_ebpf_cty::c_char,
+        fmt_size: __u32,
+        arg1: __u64,
+        arg2: __u64,
+        arg3: __u64,
+    ) -> ::aya_ebpf_cty::c_long = ::core::mem::transmute(6usize);
+    fun(fmt, fmt_size, arg1, arg2, arg3)
+}
 pub unsafe fn bpf_get_prandom_u32() -> __u32 {
     let fun: unsafe extern "C" fn() -> __u32 = ::core::mem::transmute(7usize);
     fun()
@@ -63,11 +63,15 @@ unsafe_impl_from_btf_argument!(i64);
 unsafe_impl_from_btf_argument!(usize);
 unsafe_impl_from_btf_argument!(isize);
 
+/// A portable wrapper around pt_regs, user_pt_regs and user_regs_struct.
+///
+/// Registers are read with [`bpf_probe_read`], so the wrapped pointer doesn't need to be the
+/// program context. For example, it can be the `struct pt_regs *` that syscall wrappers like
+/// `__x64_sys_openat` receive as their first argument.
 pub struct PtRegs {
     regs: *mut pt_regs,
 }
 
-/// A portable wrapper around pt_regs, user_pt_regs and user_regs_struct.
 impl PtRegs {
     pub fn new(regs: *mut pt_regs) -> Self {
         PtRegs { regs }
@@ -83,6 +87,42 @@ impl PtRegs {
         T::from_retval(unsafe { &*self.regs })
     }
 
+    /// Returns the value of the instruction pointer.
+    pub fn ip(&self) -> Option<usize> {
+        let regs = unsafe { &*self.regs };
+        #[cfg(bpf_target_arch = "x86_64")]
+        let ip = &regs.rip;
+        #[cfg(bpf_target_arch = "arm")]
+        let ip = &regs.uregs[15];
+        #[cfg(any(bpf_target_arch = "aarch64", bpf_target_arch = "riscv64"))]
+        let ip = &regs.pc;
+        #[cfg(bpf_target_arch = "powerpc64")]
+        let ip = &regs.nip;
+        #[cfg(bpf_target_arch = "s390x")]
+        let ip = &regs.psw.addr;
+        #[cfg(bpf_target_arch = "mips")]
+        let ip = &regs.cp0_epc;
+        unsafe { bpf_probe_read(ip) }.map(|v| v as usize).ok()
+    }
+
+    /// Returns the value of the stack pointer.
+    pub fn sp(&self) -> Option<usize> {
+        let regs = unsafe { &*self.regs };
+        #[cfg(bpf_target_arch = "x86_64")]
+        let sp = &regs.rsp;
+        #[cfg(bpf_target_arch = "arm")]
+        let sp = &regs.uregs[13];
+        #[cfg(any(bpf_target_arch = "aarch64", bpf_target_arch = "riscv64"))]
+        let sp = &regs.sp;
+        #[cfg(bpf_target_arch = "powerpc64")]
+        let sp = &regs.gpr[1];
+        #[cfg(bpf_target_arch = "s390x")]
+        let sp = &regs.gprs[15];
+        #[cfg(bpf_target_arch = "mips")]
+        let sp = &regs.regs[29];
+        unsafe { bpf_probe_read(sp) }.map(|v| v as usize).ok()
+    }
+
     /// Returns a pointer to the wrapped value.
     pub fn as_ptr(&self) -> *mut pt_regs {
         self.regs
@@ -101,6 +141,8 @@ pub trait FromPtRegs: Sized {
     fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self>;
 
     /// Coerces a `T` from the return value of a pt_regs context.
+    ///
+    /// This reads the same register as libbpf's `PT_REGS_RC`.
     fn from_retval(ctx: &pt_regs) -> Option<Self>;
 }
 
@@ -170,7 +212,7 @@ impl<T> FromPtRegs for *const T {
     }
 
     fn from_retval(ctx: &pt_regs) -> Option<Self> {
-        unsafe { bpf_probe_read(&ctx.ra).map(|v| v as *const _).ok() }
+        unsafe { bpf_probe_read(&ctx.a0).map(|v| v as *const _).ok() }
     }
 }
 
@@ -216,7 +258,7 @@ impl<T> FromPtRegs for *const T {
     }
 
     fn from_retval(ctx: &pt_regs) -> Option<Self> {
-        unsafe { bpf_probe_read(&ctx.regs[31]).map(|v| v as *const _).ok() }
+        unsafe { bpf_probe_read(&ctx.regs[2]).map(|v| v as *const _).ok() }
     }
 }
 
@@ -286,7 +328,7 @@ impl<T> FromPtRegs for *mut T {
     }
 
     fn from_retval(ctx: &pt_regs) -> Option<Self> {
-        unsafe { bpf_probe_read(&ctx.ra).map(|v| v as *mut _).ok() }
+        unsafe { bpf_probe_read(&ctx.a0).map(|v| v as *mut _).ok() }
     }
 }
 
@@ -332,7 +374,7 @@ impl<T> FromPtRegs for *mut T {
     }
 
     fn from_retval(ctx: &pt_regs) -> Option<Self> {
-        unsafe { bpf_probe_read(&ctx.regs[31]).map(|v| v as *mut _).ok() }
+        unsafe { bpf_probe_read(&ctx.regs[2]).map(|v| v as *mut _).ok() }
     }
 }
 
@@ -343,18 +385,18 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 match n {
-                    0 => Some(ctx.rdi as *const $type as _),
-                    1 => Some(ctx.rsi as *const $type as _),
-                    2 => Some(ctx.rdx as *const $type as _),
-                    3 => Some(ctx.rcx as *const $type as _),
-                    4 => Some(ctx.r8 as *const $type as _),
-                    5 => Some(ctx.r9 as *const $type as _),
+                    0 => unsafe { bpf_probe_read(&ctx.rdi).map(|v| v as $type).ok() },
+                    1 => unsafe { bpf_probe_read(&ctx.rsi).map(|v| v as $type).ok() },
+                    2 => unsafe { bpf_probe_read(&ctx.rdx).map(|v| v as $type).ok() },
+                    3 => unsafe { bpf_probe_read(&ctx.rcx).map(|v| v as $type).ok() },
+                    4 => unsafe { bpf_probe_read(&ctx.r8).map(|v| v as $type).ok() },
+                    5 => unsafe { bpf_probe_read(&ctx.r9).map(|v| v as $type).ok() },
                     _ => None,
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.rax as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.rax).map(|v| v as $type).ok() }
             }
         }
 
@@ -362,14 +404,14 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 if n <= 6 {
-                    Some(ctx.uregs[n] as *const $type as _)
+                    unsafe { bpf_probe_read(&ctx.uregs[n]).map(|v| v as $type).ok() }
                 } else {
                     None
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.uregs[0] as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.uregs[0]).map(|v| v as $type).ok() }
             }
         }
 
@@ -377,14 +419,14 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 if n <= 7 {
-                    Some(ctx.regs[n] as *const $type as _)
+                    unsafe { bpf_probe_read(&ctx.regs[n]).map(|v| v as $type).ok() }
                 } else {
                     None
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.regs[0] as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.regs[0]).map(|v| v as $type).ok() }
             }
         }
 
@@ -392,20 +434,20 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 match n {
-                    0 => Some(ctx.a0 as *const $type as _),
-                    1 => Some(ctx.a1 as *const $type as _),
-                    2 => Some(ctx.a2 as *const $type as _),
-                    3 => Some(ctx.a3 as *const $type as _),
-                    4 => Some(ctx.a4 as *const $type as _),
-                    5 => Some(ctx.a5 as *const $type as _),
-                    6 => Some(ctx.a6 as *const $type as _),
-                    7 => Some(ctx.a7 as *const $type as _),
+                    0 => unsafe { bpf_probe_read(&ctx.a0).map(|v| v as $type).ok() },
+                    1 => unsafe { bpf_probe_read(&ctx.a1).map(|v| v as $type).ok() },
+                    2 => unsafe { bpf_probe_read(&ctx.a2).map(|v| v as $type).ok() },
+                    3 => unsafe { bpf_probe_read(&ctx.a3).map(|v| v as $type).ok() },
+                    4 => unsafe { bpf_probe_read(&ctx.a4).map(|v| v as $type).ok() },
+                    5 => unsafe { bpf_probe_read(&ctx.a5).map(|v| v as $type).ok() },
+                    6 => unsafe { bpf_probe_read(&ctx.a6).map(|v| v as $type).ok() },
+                    7 => unsafe { bpf_probe_read(&ctx.a7).map(|v| v as $type).ok() },
                     _ => None,
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.ra as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.a0).map(|v| v as $type).ok() }
             }
         }
 
@@ -413,14 +455,14 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 if n <= 7 {
-                    Some(ctx.gpr[3 + n] as *const $type as _)
+                    unsafe { bpf_probe_read(&ctx.gpr[3 + n]).map(|v| v as $type).ok() }
                 } else {
                     None
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.gpr[3] as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.gpr[3]).map(|v| v as $type).ok() }
             }
         }
 
@@ -428,14 +470,14 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 if n <= 4 {
-                    Some(ctx.gprs[2 + n] as *const $type as _)
+                    unsafe { bpf_probe_read(&ctx.gprs[2 + n]).map(|v| v as $type).ok() }
                 } else {
                     None
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.gprs[2] as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.gprs[2]).map(|v| v as $type).ok() }
             }
         }
 
@@ -443,14 +485,14 @@ macro_rules! impl_from_pt_regs {
         impl FromPtRegs for $type {
             fn from_argument(ctx: &pt_regs, n: usize) -> Option<Self> {
                 if n <= 7 {
-                    Some(ctx.regs[n + 4] as *const $type as _)
+                    unsafe { bpf_probe_read(&ctx.regs[n + 4]).map(|v| v as $type).ok() }
                 } else {
                     None
                 }
             }
 
             fn from_retval(ctx: &pt_regs) -> Option<Self> {
-                Some(ctx.regs[31] as *const $type as _)
+                unsafe { bpf_probe_read(&ctx.regs[2]).map(|v| v as $type).ok() }
             }
         }
     };
@@ -13,6 +13,7 @@ use core::{
     cmp::Ordering,
     ffi::CStr,
     mem::{self, MaybeUninit},
+    sync::atomic::{self, AtomicI32, AtomicI64, AtomicU32, AtomicU64},
 };
 
 pub use aya_ebpf_bindings::helpers as gen;
@@ -190,6 +191,39 @@ pub unsafe fn bpf_probe_read_user_buf(src: *const u8, dst: &mut [u8]) -> Result<
 /// # }
 /// ```
 ///
+/// # Reading nested fields
+///
+/// Fields of nested structs don't have to be read one level at a time. Take the address of the
+/// innermost field with [`addr_of!`](core::ptr::addr_of) and read only that field. Pointers still
+/// have to be read before they can be followed.
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// # use aya_ebpf::{cty::{c_int, c_long}, helpers::bpf_probe_read_kernel};
+/// # use core::ptr::addr_of;
+/// #[repr(C)]
+/// struct Inner {
+///     value: c_int,
+/// }
+///
+/// #[repr(C)]
+/// struct Outer {
+///     inner: Inner,
+///     next: *const Outer,
+/// }
+///
+/// # fn try_test() -> Result<(), c_long> {
+/// # let outer: *const Outer = 0 as _;
+/// // Reads `outer->inner.value` without reading the rest of `outer`.
+/// let value = unsafe { bpf_probe_read_kernel(addr_of!((*outer).inner.value))? };
+///
+/// // Reads `outer->next->inner.value`.
+/// let next = unsafe { bpf_probe_read_kernel(addr_of!((*outer).next))? };
+/// let next_value = unsafe { bpf_probe_read_kernel(addr_of!((*next).inner.value))? };
+/// # Ok::<(), c_long>(())
+/// # }
+/// ```
+///
 /// # Errors
 ///
 /// On failure, this function returns a negative value wrapped in an `Err`.
@@ -683,6 +717,28 @@ pub fn bpf_get_current_uid_gid() -> u64 {
     unsafe { gen::bpf_get_current_uid_gid() }
 }
 
+/// Read the time elapsed since system boot, in nanoseconds, not including the time the
+/// system was suspended.
+///
+/// The value is read from `CLOCK_MONOTONIC`, so it can be compared with timestamps taken in
+/// user space with `clock_gettime(CLOCK_MONOTONIC)`.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// # use aya_ebpf::helpers::bpf_ktime_get_ns;
+/// let start = bpf_ktime_get_ns();
+///
+/// // Do something
+///
+/// let elapsed = bpf_ktime_get_ns() - start;
+/// ```
+#[inline]
+pub fn bpf_ktime_get_ns() -> u64 {
+    unsafe { gen::bpf_ktime_get_ns() }
+}
+
 /// Prints a debug message to the BPF debugging pipe.
 ///
 /// The [format string syntax][fmt] is the same as that of the `printk` kernel
@@ -699,6 +755,9 @@ pub fn bpf_get_current_uid_gid() -> u64 {
 /// the dawn of eBPF. The return value of the BPF helper is also returned from
 /// this macro.
 ///
+/// At most 12 arguments can be passed, which is the limit of
+/// `bpf_trace_vprintk`. Passing more fails to compile.
+///
 /// Messages can be read by executing the following command in a second terminal:
 ///
 /// ```bash
@@ -726,6 +785,10 @@ pub fn bpf_get_current_uid_gid() -> u64 {
 macro_rules! bpf_printk {
     ($fmt:literal $(,)? $($arg:expr),* $(,)?) => {{
         use $crate::helpers::PrintkArg;
+        const _: () = assert!(
+            <[&str]>::len(&[$(stringify!($arg)),*]) <= 12,
+            "bpf_printk! takes at most 12 arguments"
+        );
         const FMT: [u8; { $fmt.len() + 1 }] = $crate::helpers::zero_pad_array::<
             { $fmt.len() }, { $fmt.len() + 1 }>(*$fmt);
         let data = [$(PrintkArg::from($arg)),*];
@@ -824,22 +887,15 @@ pub unsafe fn bpf_printk_impl<const FMT_LEN: usize, const NUM_ARGS: usize>(
     fmt: &[u8; FMT_LEN],
     args: &[PrintkArg; NUM_ARGS],
 ) -> i64 {
-    // This function can't be wrapped in `helpers.rs` because it has variadic
-    // arguments. We also can't turn the definitions in `helpers.rs` into
-    // `const`s because MIRI believes casting arbitrary integers to function
-    // pointers to be an error.
-    let printk: unsafe extern "C" fn(fmt: *const c_char, fmt_size: u32, ...) -> c_long =
-        mem::transmute(6usize);
-
     let fmt_ptr = fmt.as_ptr() as *const c_char;
     let fmt_size = fmt.len() as u32;
 
-    match NUM_ARGS {
-        0 => printk(fmt_ptr, fmt_size),
-        1 => printk(fmt_ptr, fmt_size, args[0]),
-        2 => printk(fmt_ptr, fmt_size, args[0], args[1]),
-        3 => printk(fmt_ptr, fmt_size, args[0], args[1], args[2]),
-        _ => gen::bpf_trace_vprintk(fmt_ptr, fmt_size, args.as_ptr() as _, (NUM_ARGS * 8) as _),
+    if NUM_ARGS <= 3 {
+        // `bpf_trace_printk` ignores the arguments the format string doesn't use.
+        let arg = |i: usize| args.get(i).map_or(0, |arg| u64::from_ne_bytes(arg.0));
+        gen::bpf_trace_printk(fmt_ptr, fmt_size, arg(0), arg(1), arg(2))
+    } else {
+        gen::bpf_trace_vprintk(fmt_ptr, fmt_size, args.as_ptr() as _, (NUM_ARGS * 8) as _)
     }
 }
 
@@ -862,3 +918,210 @@ pub fn bpf_strncmp<const N: usize>(s1: &[u8; N], s2: &CStr) -> Ordering {
     // usage of strncmp in C programs.
     unsafe { gen::bpf_strncmp(s1.as_ptr() as *const _, N as u32, s2.as_ptr() as *const _) }.cmp(&0)
 }
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Integer types that can be updated with the BPF atomic instructions.
+///
+/// This trait is sealed and implemented for `u32`, `i32`, `u64` and `i64`.
+pub trait Atomic: Copy + sealed::Sealed {
+    #[doc(hidden)]
+    unsafe fn fetch_add(ptr: *mut Self, val: Self) -> Self;
+    #[doc(hidden)]
+    unsafe fn fetch_and(ptr: *mut Self, val: Self) -> Self;
+    #[doc(hidden)]
+    unsafe fn fetch_or(ptr: *mut Self, val: Self) -> Self;
+    #[doc(hidden)]
+    unsafe fn fetch_xor(ptr: *mut Self, val: Self) -> Self;
+    #[doc(hidden)]
+    unsafe fn swap(ptr: *mut Self, val: Self) -> Self;
+    #[doc(hidden)]
+    unsafe fn compare_exchange(ptr: *mut Self, old: Self, new: Self) -> Self;
+}
+
+macro_rules! impl_atomic {
+    ($ty:ty, $atomic:ty) => {
+        impl sealed::Sealed for $ty {}
+
+        impl Atomic for $ty {
+            #[inline(always)]
+            unsafe fn fetch_add(ptr: *mut Self, val: Self) -> Self {
+                <$atomic>::from_ptr(ptr).fetch_add(val, atomic::Ordering::SeqCst)
+            }
+
+            #[inline(always)]
+            unsafe fn fetch_and(ptr: *mut Self, val: Self) -> Self {
+                <$atomic>::from_ptr(ptr).fetch_and(val, atomic::Ordering::SeqCst)
+            }
+
+            #[inline(always)]
+            unsafe fn fetch_or(ptr: *mut Self, val: Self) -> Self {
+                <$atomic>::from_ptr(ptr).fetch_or(val, atomic::Ordering::SeqCst)
+            }
+
+            #[inline(always)]
+            unsafe fn fetch_xor(ptr: *mut Self, val: Self) -> Self {
+                <$atomic>::from_ptr(ptr).fetch_xor(val, atomic::Ordering::SeqCst)
+            }
+
+            #[inline(always)]
+            unsafe fn swap(ptr: *mut Self, val: Self) -> Self {
+                <$atomic>::from_ptr(ptr).swap(val, atomic::Ordering::SeqCst)
+            }
+
+            #[inline(always)]
+            unsafe fn compare_exchange(ptr: *mut Self, old: Self, new: Self) -> Self {
+                match <$atomic>::from_ptr(ptr).compare_exchange(
+                    old,
+                    new,
+                    atomic::Ordering::SeqCst,
+                    atomic::Ordering::SeqCst,
+                ) {
+                    Ok(prev) | Err(prev) => prev,
+                }
+            }
+        }
+    };
+}
+
+impl_atomic!(u32, AtomicU32);
+impl_atomic!(i32, AtomicI32);
+impl_atomic!(u64, AtomicU64);
+impl_atomic!(i64, AtomicI64);
+
+/// Atomically adds `val` to the value pointed to by `ptr`.
+///
+/// This compiles to the `xadd` instruction (`BPF_ATOMIC | BPF_ADD`), which is supported by all
+/// kernels and CPU versions. Use `atomic_fetch_add`, which requires the `atomics` feature, to also
+/// get the previous value.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// # use aya_ebpf::{macros::map, maps::Array, helpers::atomic_add};
+/// #[map]
+/// static COUNTERS: Array<u64> = Array::with_max_entries(1, 0);
+///
+/// # fn try_test() {
+/// if let Some(counter) = COUNTERS.get_ptr_mut(0) {
+///     unsafe { atomic_add(counter, 1) };
+/// }
+/// # }
+/// ```
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads and writes and aligned to the size of `T`, for instance a
+/// pointer to a map value.
+#[inline(always)]
+pub unsafe fn atomic_add<T: Atomic>(ptr: *mut T, val: T) {
+    // LLVM emits the non-fetching form of the instruction when the result is unused.
+    let _ = T::fetch_add(ptr, val);
+}
+
+/// Atomically adds `val` to the value pointed to by `ptr`, returning the previous value.
+///
+/// Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_fetch_add<T: Atomic>(ptr: *mut T, val: T) -> T {
+    T::fetch_add(ptr, val)
+}
+
+/// Atomically bitwise ands `val` with the value pointed to by `ptr`, returning the previous value.
+///
+/// Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_fetch_and<T: Atomic>(ptr: *mut T, val: T) -> T {
+    T::fetch_and(ptr, val)
+}
+
+/// Atomically bitwise ors `val` with the value pointed to by `ptr`, returning the previous value.
+///
+/// Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_fetch_or<T: Atomic>(ptr: *mut T, val: T) -> T {
+    T::fetch_or(ptr, val)
+}
+
+/// Atomically bitwise xors `val` with the value pointed to by `ptr`, returning the previous value.
+///
+/// Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_fetch_xor<T: Atomic>(ptr: *mut T, val: T) -> T {
+    T::fetch_xor(ptr, val)
+}
+
+/// Atomically stores `val` in the value pointed to by `ptr`, returning the previous value.
+///
+/// Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_xchg<T: Atomic>(ptr: *mut T, val: T) -> T {
+    T::swap(ptr, val)
+}
+
+/// Atomically stores `new` in the value pointed to by `ptr` if it is equal to `old`.
+///
+/// Returns the previous value, so the exchange happened if and only if the returned value is
+/// equal to `old`. Requires Linux 5.12 or later, and `-C target-cpu=v3` for 32-bit values.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// # use aya_ebpf::{macros::map, maps::Array, helpers::atomic_cmpxchg};
+/// #[map]
+/// static MAX: Array<u64> = Array::with_max_entries(1, 0);
+///
+/// # fn try_test(value: u64) {
+/// if let Some(max) = MAX.get_ptr_mut(0) {
+///     let mut current = unsafe { *max };
+///     // the verifier requires loops to be bounded
+///     for _ in 0..8 {
+///         if value <= current {
+///             break;
+///         }
+///         match unsafe { atomic_cmpxchg(max, current, value) } {
+///             prev if prev == current => break,
+///             prev => current = prev,
+///         }
+///     }
+/// }
+/// # }
+/// ```
+///
+/// # Safety
+///
+/// See [`atomic_add`].
+#[cfg(feature = "atomics")]
+#[inline(always)]
+pub unsafe fn atomic_cmpxchg<T: Atomic>(ptr: *mut T, old: T, new: T) -> T {
+    T::compare_exchange(ptr, old, new)
+}
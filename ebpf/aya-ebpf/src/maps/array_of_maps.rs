@@ -0,0 +1,81 @@
+use core::{cell::UnsafeCell, mem};
+
+use aya_ebpf_cty::c_void;
+
+use crate::{
+    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_ARRAY_OF_MAPS},
+    helpers::bpf_map_lookup_elem,
+    maps::{InnerMap, PinningType},
+};
+
+/// An array of maps.
+///
+/// The inner map passed to the constructor is used as the template of the maps the array can
+/// hold. The loader creates it first and discards it once the array has been created.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// use aya_ebpf::{macros::map, maps::{Array, ArrayOfMaps}};
+///
+/// #[map]
+/// static OUTER: ArrayOfMaps<Array<u32>> =
+///     ArrayOfMaps::with_max_entries(8, 0, Array::with_max_entries(16, 0));
+///
+/// # fn try_test() -> Option<u32> {
+/// let inner = OUTER.get(0)?;
+/// let value = inner.get(1)?;
+/// # Some(*value)
+/// # }
+/// ```
+#[repr(C)]
+pub struct ArrayOfMaps<T> {
+    def: UnsafeCell<bpf_map_def>,
+    // must immediately follow `def`, that's where the loader expects it
+    inner: T,
+}
+
+unsafe impl<T: Sync> Sync for ArrayOfMaps<T> {}
+
+impl<T: InnerMap> ArrayOfMaps<T> {
+    pub const fn with_max_entries(max_entries: u32, flags: u32, inner: T) -> ArrayOfMaps<T> {
+        ArrayOfMaps {
+            def: UnsafeCell::new(build_def(max_entries, flags, PinningType::None)),
+            inner,
+        }
+    }
+
+    pub const fn pinned(max_entries: u32, flags: u32, inner: T) -> ArrayOfMaps<T> {
+        ArrayOfMaps {
+            def: UnsafeCell::new(build_def(max_entries, flags, PinningType::ByName)),
+            inner,
+        }
+    }
+
+    /// Returns the inner map stored at the given index.
+    #[inline(always)]
+    pub fn get(&self, index: u32) -> Option<&T> {
+        unsafe {
+            let ptr = bpf_map_lookup_elem(
+                self.def.get() as *mut _,
+                &index as *const _ as *const c_void,
+            );
+            // SAFETY: `InnerMap` types are transparent wrappers around a map definition, so
+            // the map pointer returned by the lookup can be used in its place.
+            (ptr as *const T).as_ref()
+        }
+    }
+}
+
+const fn build_def(max_entries: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: BPF_MAP_TYPE_ARRAY_OF_MAPS,
+        key_size: mem::size_of::<u32>() as u32,
+        value_size: mem::size_of::<u32>() as u32,
+        max_entries,
+        map_flags: flags,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
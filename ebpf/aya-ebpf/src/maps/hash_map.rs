@@ -11,6 +11,11 @@ use crate::{
     maps::PinningType,
 };
 
+/// A hash map that can be shared between eBPF programs and user space.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 3.19.
 #[repr(transparent)]
 pub struct HashMap<K, V> {
     def: UnsafeCell<bpf_map_def>,
@@ -57,6 +62,16 @@ impl<K, V> HashMap<K, V> {
         get(self.def.get(), key)
     }
 
+    /// Retrieve a mutable reference to the value associate with `key` from the map, so the value
+    /// can be modified in place.
+    /// The same caveat as `get` applies, and additionally the caller must make sure the value
+    /// isn't modified concurrently, for example by the same program running on another CPU.
+    #[inline]
+    #[allow(clippy::mut_from_ref)] // The value lives in the kernel, not in `self`.
+    pub unsafe fn get_mut(&self, key: &K) -> Option<&mut V> {
+        get_mut(self.def.get(), key)
+    }
+
     /// Retrieve the value associate with `key` from the map.
     /// The same caveat as `get` applies, but this returns a raw pointer and it's up to the caller
     /// to decide whether it's safe to dereference the pointer or not.
@@ -85,6 +100,18 @@ impl<K, V> HashMap<K, V> {
     }
 }
 
+/// A hash map that evicts the least recently used entries when it's full.
+///
+/// Inserting into a full [`LruHashMap`] removes an old entry instead of failing, which makes it
+/// suitable for caches such as per-flow state. By default the LRU list is shared by all CPUs;
+/// with the `BPF_F_NO_COMMON_LRU` flag each CPU maintains its own list, which reduces lock
+/// contention but means entries may be evicted before the map is full.
+///
+/// In user space this map is accessed with `aya::maps::HashMap`.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.10.
 #[repr(transparent)]
 pub struct LruHashMap<K, V> {
     def: UnsafeCell<bpf_map_def>,
@@ -131,6 +158,16 @@ impl<K, V> LruHashMap<K, V> {
         get(self.def.get(), key)
     }
 
+    /// Retrieve a mutable reference to the value associate with `key` from the map, so the value
+    /// can be modified in place.
+    /// The same caveat as `get` applies, and additionally the caller must make sure the value
+    /// isn't modified concurrently, for example by the same program running on another CPU.
+    #[inline]
+    #[allow(clippy::mut_from_ref)] // The value lives in the kernel, not in `self`.
+    pub unsafe fn get_mut(&self, key: &K) -> Option<&mut V> {
+        get_mut(self.def.get(), key)
+    }
+
     /// Retrieve the value associate with `key` from the map.
     /// The same caveat as `get` applies, but this returns a raw pointer and it's up to the caller
     /// to decide whether it's safe to dereference the pointer or not.
@@ -159,6 +196,11 @@ impl<K, V> LruHashMap<K, V> {
     }
 }
 
+/// Similar to [`HashMap`] but each CPU holds a separate value for a given key.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.6.
 #[repr(transparent)]
 pub struct PerCpuHashMap<K, V> {
     def: UnsafeCell<bpf_map_def>,
@@ -205,6 +247,16 @@ impl<K, V> PerCpuHashMap<K, V> {
         get(self.def.get(), key)
     }
 
+    /// Retrieve a mutable reference to the value associate with `key` from the map, so the value
+    /// can be modified in place.
+    /// The same caveat as `get` applies, and additionally the caller must make sure the value
+    /// isn't modified concurrently, for example by the same program running on another CPU.
+    #[inline]
+    #[allow(clippy::mut_from_ref)] // The value lives in the kernel, not in `self`.
+    pub unsafe fn get_mut(&self, key: &K) -> Option<&mut V> {
+        get_mut(self.def.get(), key)
+    }
+
     /// Retrieve the value associate with `key` from the map.
     /// The same caveat as `get` applies, but this returns a raw pointer and it's up to the caller
     /// to decide whether it's safe to dereference the pointer or not.
@@ -233,6 +285,15 @@ impl<K, V> PerCpuHashMap<K, V> {
     }
 }
 
+/// Similar to [`LruHashMap`] but each CPU holds a separate value for a given key.
+///
+/// The `BPF_F_NO_COMMON_LRU` flag has the same meaning as for [`LruHashMap`].
+///
+/// In user space this map is accessed with `aya::maps::PerCpuHashMap`.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.10.
 #[repr(transparent)]
 pub struct LruPerCpuHashMap<K, V> {
     def: UnsafeCell<bpf_map_def>,
@@ -279,6 +340,16 @@ impl<K, V> LruPerCpuHashMap<K, V> {
         get(self.def.get(), key)
     }
 
+    /// Retrieve a mutable reference to the value associate with `key` from the map, so the value
+    /// can be modified in place.
+    /// The same caveat as `get` applies, and additionally the caller must make sure the value
+    /// isn't modified concurrently, for example by the same program running on another CPU.
+    #[inline]
+    #[allow(clippy::mut_from_ref)] // The value lives in the kernel, not in `self`.
+    pub unsafe fn get_mut(&self, key: &K) -> Option<&mut V> {
+        get_mut(self.def.get(), key)
+    }
+
     /// Retrieve the value associate with `key` from the map.
     /// The same caveat as `get` applies, but this returns a raw pointer and it's up to the caller
     /// to decide whether it's safe to dereference the pointer or not.
@@ -338,6 +409,11 @@ unsafe fn get<'a, K, V>(def: *mut bpf_map_def, key: &K) -> Option<&'a V> {
     get_ptr(def, key).map(|p| &*p)
 }
 
+#[inline]
+unsafe fn get_mut<'a, K, V>(def: *mut bpf_map_def, key: &K) -> Option<&'a mut V> {
+    get_ptr_mut(def, key).map(|p| &mut *p)
+}
+
 #[inline]
 fn insert<K, V>(def: *mut bpf_map_def, key: &K, value: &V, flags: u64) -> Result<(), c_long> {
     let ret = unsafe {
@@ -0,0 +1,79 @@
+use core::{cell::UnsafeCell, marker::PhantomData, mem};
+
+use crate::{
+    bindings::{bpf_map_def, bpf_map_type::BPF_MAP_TYPE_HASH_OF_MAPS},
+    helpers::bpf_map_lookup_elem,
+    maps::{InnerMap, PinningType},
+};
+
+/// A hash map of maps.
+///
+/// The inner map passed to the constructor is used as the template of the maps the hash map can
+/// hold. The loader creates it first and discards it once the hash map has been created.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #![allow(dead_code)]
+/// use aya_ebpf::{macros::map, maps::{HashMap, HashOfMaps}};
+///
+/// #[map]
+/// static OUTER: HashOfMaps<u32, HashMap<u32, u64>> =
+///     HashOfMaps::with_max_entries(8, 0, HashMap::with_max_entries(1024, 0));
+///
+/// # fn try_test() -> Option<u64> {
+/// let inner = OUTER.get(&42)?;
+/// let value = unsafe { inner.get(&1) }?;
+/// # Some(*value)
+/// # }
+/// ```
+#[repr(C)]
+pub struct HashOfMaps<K, V> {
+    def: UnsafeCell<bpf_map_def>,
+    // must immediately follow `def`, that's where the loader expects it
+    inner: V,
+    _k: PhantomData<K>,
+}
+
+unsafe impl<K: Sync, V: Sync> Sync for HashOfMaps<K, V> {}
+
+impl<K, V: InnerMap> HashOfMaps<K, V> {
+    pub const fn with_max_entries(max_entries: u32, flags: u32, inner: V) -> HashOfMaps<K, V> {
+        HashOfMaps {
+            def: UnsafeCell::new(build_def::<K>(max_entries, flags, PinningType::None)),
+            inner,
+            _k: PhantomData,
+        }
+    }
+
+    pub const fn pinned(max_entries: u32, flags: u32, inner: V) -> HashOfMaps<K, V> {
+        HashOfMaps {
+            def: UnsafeCell::new(build_def::<K>(max_entries, flags, PinningType::ByName)),
+            inner,
+            _k: PhantomData,
+        }
+    }
+
+    /// Returns the inner map stored under the given key.
+    #[inline(always)]
+    pub fn get(&self, key: &K) -> Option<&V> {
+        unsafe {
+            let ptr = bpf_map_lookup_elem(self.def.get() as *mut _, key as *const _ as *const _);
+            // SAFETY: `InnerMap` types are transparent wrappers around a map definition, so
+            // the map pointer returned by the lookup can be used in its place.
+            (ptr as *const V).as_ref()
+        }
+    }
+}
+
+const fn build_def<K>(max_entries: u32, flags: u32, pin: PinningType) -> bpf_map_def {
+    bpf_map_def {
+        type_: BPF_MAP_TYPE_HASH_OF_MAPS,
+        key_size: mem::size_of::<K>() as u32,
+        value_size: mem::size_of::<u32>() as u32,
+        max_entries,
+        map_flags: flags,
+        id: 0,
+        pinning: pin as u32,
+    }
+}
@@ -6,8 +6,10 @@ pub(crate) enum PinningType {
 }
 
 pub mod array;
+pub mod array_of_maps;
 pub mod bloom_filter;
 pub mod hash_map;
+pub mod hash_of_maps;
 pub mod lpm_trie;
 pub mod per_cpu_array;
 pub mod perf;
@@ -21,8 +23,10 @@ pub mod stack_trace;
 pub mod xdp;
 
 pub use array::Array;
+pub use array_of_maps::ArrayOfMaps;
 pub use bloom_filter::BloomFilter;
 pub use hash_map::{HashMap, LruHashMap, LruPerCpuHashMap, PerCpuHashMap};
+pub use hash_of_maps::HashOfMaps;
 pub use lpm_trie::LpmTrie;
 pub use per_cpu_array::PerCpuArray;
 pub use perf::{PerfEventArray, PerfEventByteArray};
@@ -34,3 +38,23 @@ pub use sock_map::SockMap;
 pub use stack::Stack;
 pub use stack_trace::StackTrace;
 pub use xdp::{CpuMap, DevMap, DevMapHash, XskMap};
+
+/// A map that can be stored in an [`ArrayOfMaps`] or a [`HashOfMaps`].
+///
+/// # Safety
+///
+/// Implementors must be `#[repr(transparent)]` wrappers around a `bpf_map_def`, so that the map
+/// pointer returned by looking up an outer map can be used as a reference to them.
+pub unsafe trait InnerMap {}
+
+unsafe impl<T> InnerMap for Array<T> {}
+unsafe impl<T> InnerMap for BloomFilter<T> {}
+unsafe impl<K, V> InnerMap for HashMap<K, V> {}
+unsafe impl<K, V> InnerMap for LpmTrie<K, V> {}
+unsafe impl<K, V> InnerMap for LruHashMap<K, V> {}
+unsafe impl<K, V> InnerMap for LruPerCpuHashMap<K, V> {}
+unsafe impl<T> InnerMap for PerCpuArray<T> {}
+unsafe impl<K, V> InnerMap for PerCpuHashMap<K, V> {}
+unsafe impl<T> InnerMap for Queue<T> {}
+unsafe impl InnerMap for RingBuf {}
+unsafe impl<T> InnerMap for Stack<T> {}
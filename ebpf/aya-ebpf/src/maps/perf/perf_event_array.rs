@@ -7,6 +7,38 @@ use crate::{
     EbpfContext,
 };
 
+/// A map used to send events of type `T` to user space through per-CPU perf buffers.
+///
+/// User space reads the events with `aya::maps::PerfEventArray` or
+/// `aya::maps::AsyncPerfEventArray`, which open a perf buffer for each CPU and store it in the
+/// map. The map has one entry per CPU, so its size is set when it's loaded.
+///
+/// # Minimum kernel version
+///
+/// The minimum kernel version required to use this feature is 4.4.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use aya_ebpf::{bindings::xdp_action, macros::{map, xdp}, maps::PerfEventArray, programs::XdpContext};
+///
+/// #[repr(C)]
+/// pub struct PacketLog {
+///     pub len: u32,
+/// }
+///
+/// #[map]
+/// static EVENTS: PerfEventArray<PacketLog> = PerfEventArray::new(0);
+///
+/// #[xdp]
+/// fn xdp(ctx: XdpContext) -> u32 {
+///     let event = PacketLog {
+///         len: (ctx.data_end() - ctx.data()) as u32,
+///     };
+///     EVENTS.output(&ctx, &event, 0);
+///     xdp_action::XDP_PASS
+/// }
+/// ```
 #[repr(transparent)]
 pub struct PerfEventArray<T> {
     def: UnsafeCell<bpf_map_def>,
@@ -16,6 +48,7 @@ pub struct PerfEventArray<T> {
 unsafe impl<T: Sync> Sync for PerfEventArray<T> {}
 
 impl<T> PerfEventArray<T> {
+    /// Declares a perf event array with the given map flags.
     pub const fn new(flags: u32) -> PerfEventArray<T> {
         PerfEventArray {
             def: UnsafeCell::new(bpf_map_def {
@@ -31,6 +64,7 @@ impl<T> PerfEventArray<T> {
         }
     }
 
+    /// Declares a perf event array that is pinned by name, with the given map flags.
     pub const fn pinned(flags: u32) -> PerfEventArray<T> {
         PerfEventArray {
             def: UnsafeCell::new(bpf_map_def {
@@ -46,10 +80,21 @@ impl<T> PerfEventArray<T> {
         }
     }
 
+    /// Sends `data` to the perf buffer of the current CPU.
+    ///
+    /// For programs that process packets, like XDP and TC classifiers, `flags` is the number of
+    /// bytes of the packet to send right after `data`. Other programs must pass 0.
+    ///
+    /// Events are dropped if the perf buffer is full or not opened by user space. User space is
+    /// notified of dropped events through lost event records.
     pub fn output<C: EbpfContext>(&self, ctx: &C, data: &T, flags: u32) {
         self.output_at_index(ctx, BPF_F_CURRENT_CPU as u32, data, flags)
     }
 
+    /// Sends `data` to the perf buffer at `index`, see [`output`](Self::output).
+    ///
+    /// Perf buffers can only be written from the CPU they belong to, so this is mostly useful
+    /// when user space stores its perf buffers at indices that don't match the CPU ids.
     pub fn output_at_index<C: EbpfContext>(&self, ctx: &C, index: u32, data: &T, flags: u32) {
         let flags = (u64::from(flags) << 32) | u64::from(index);
         unsafe {
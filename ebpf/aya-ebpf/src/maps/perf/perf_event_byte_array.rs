@@ -7,6 +7,9 @@ use crate::{
     EbpfContext,
 };
 
+/// A map used to send variable-sized byte events to user space through per-CPU perf buffers.
+///
+/// This is the untyped version of [`PerfEventArray`](super::PerfEventArray).
 #[repr(transparent)]
 pub struct PerfEventByteArray {
     def: UnsafeCell<bpf_map_def>,
@@ -15,6 +18,7 @@ pub struct PerfEventByteArray {
 unsafe impl Sync for PerfEventByteArray {}
 
 impl PerfEventByteArray {
+    /// Declares a perf event byte array with the given map flags.
     pub const fn new(flags: u32) -> PerfEventByteArray {
         PerfEventByteArray {
             def: UnsafeCell::new(bpf_map_def {
@@ -29,6 +33,7 @@ impl PerfEventByteArray {
         }
     }
 
+    /// Declares a perf event byte array that is pinned by name, with the given map flags.
     pub const fn pinned(flags: u32) -> PerfEventByteArray {
         PerfEventByteArray {
             def: UnsafeCell::new(bpf_map_def {
@@ -43,10 +48,14 @@ impl PerfEventByteArray {
         }
     }
 
+    /// Sends `data` to the perf buffer of the current CPU.
+    ///
+    /// See [`PerfEventArray::output`](super::PerfEventArray::output) for the meaning of `flags`.
     pub fn output<C: EbpfContext>(&self, ctx: &C, data: &[u8], flags: u32) {
         self.output_at_index(ctx, BPF_F_CURRENT_CPU as u32, data, flags)
     }
 
+    /// Sends `data` to the perf buffer at `index`, see [`output`](Self::output).
     pub fn output_at_index<C: EbpfContext>(&self, ctx: &C, index: u32, data: &[u8], flags: u32) {
         let flags = (u64::from(flags) << 32) | u64::from(index);
         unsafe {
@@ -79,7 +79,10 @@ impl ProgramArray {
     /// # Return Value
     ///
     /// On success, this function **does not return** into the original program.
-    /// On failure, a negative error is returned, wrapped in `Err()`.
+    /// On failure, a negative error is returned, wrapped in `Err()`, and execution
+    /// continues in the caller. A tail call fails if `index` is out of bounds, if no
+    /// program is set at `index`, or if the maximum number of chained tail calls (33)
+    /// has been reached.
     #[cfg(not(unstable))]
     pub unsafe fn tail_call<C: EbpfContext>(&self, ctx: &C, index: u32) -> Result<(), c_long> {
         let res = bpf_tail_call(ctx.as_ptr(), self.def.get() as *mut _, index);
@@ -102,7 +105,10 @@ impl ProgramArray {
     /// # Return Value
     ///
     /// On success, this function **does not return** into the original program.
-    /// On failure, a negative error is returned, wrapped in `Err()`.
+    /// On failure, a negative error is returned, wrapped in `Err()`, and execution
+    /// continues in the caller. A tail call fails if `index` is out of bounds, if no
+    /// program is set at `index`, or if the maximum number of chained tail calls (33)
+    /// has been reached.
     #[cfg(unstable)]
     pub unsafe fn tail_call<C: EbpfContext>(&self, ctx: &C, index: u32) -> Result<!, c_long> {
         let res = bpf_tail_call(ctx.as_ptr(), self.def.get() as *mut _, index);
@@ -34,6 +34,30 @@ impl FExitContext {
     pub unsafe fn arg<T: FromBtfArgument>(&self, n: usize) -> T {
         T::from_argument(self.ctx as *const _, n)
     }
+
+    /// Returns the return value of the traced function.
+    ///
+    /// The kernel stores the return value right after the arguments, so `n_args` must be the
+    /// number of arguments the traced function takes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #![allow(dead_code)]
+    /// # use aya_ebpf::{cty::c_int, programs::FExitContext};
+    /// // int filename_lookup(int dfd, struct filename *name, unsigned flags,
+    /// //                     struct path *path, struct path *root)
+    /// unsafe fn try_filename_lookup(ctx: FExitContext) -> Result<u32, u32> {
+    ///     let retval: c_int = ctx.ret(5);
+    ///
+    ///     // Do something with retval
+    ///
+    ///     Ok(0)
+    /// }
+    /// ```
+    pub unsafe fn ret<T: FromBtfArgument>(&self, n_args: usize) -> T {
+        T::from_argument(self.ctx as *const _, n_args)
+    }
 }
 
 impl EbpfContext for FExitContext {
@@ -0,0 +1,67 @@
+use core::ffi::c_void;
+
+use aya_ebpf_bindings::helpers::bpf_skb_load_bytes;
+use aya_ebpf_cty::c_long;
+
+use crate::{
+    bindings::{__sk_buff, bpf_flow_keys},
+    EbpfContext,
+};
+
+/// The context passed to flow dissector programs.
+///
+/// Flow dissector programs may only access the `data`, `data_end` and `flow_keys` fields of
+/// the underlying `__sk_buff`. They return [`BPF_OK`](crate::bindings::bpf_ret_code::BPF_OK)
+/// once the flow keys have been filled in, or
+/// [`BPF_DROP`](crate::bindings::bpf_ret_code::BPF_DROP) if the packet could not be
+/// dissected.
+pub struct FlowDissectorContext {
+    pub skb: *mut __sk_buff,
+}
+
+impl FlowDissectorContext {
+    pub fn new(skb: *mut __sk_buff) -> FlowDissectorContext {
+        FlowDissectorContext { skb }
+    }
+
+    #[inline]
+    pub fn data(&self) -> usize {
+        unsafe { (*self.skb).data as usize }
+    }
+
+    #[inline]
+    pub fn data_end(&self) -> usize {
+        unsafe { (*self.skb).data_end as usize }
+    }
+
+    /// Returns the flow keys to be filled in by the program.
+    #[inline]
+    pub fn flow_keys(&self) -> *mut bpf_flow_keys {
+        unsafe { (*self.skb).__bindgen_anon_1.flow_keys }
+    }
+
+    /// Reads `dst.len()` bytes from the packet at `offset` into `dst`.
+    #[inline(always)]
+    pub fn load_bytes(&self, offset: usize, dst: &mut [u8]) -> Result<usize, c_long> {
+        let len = dst.len();
+        let ret = unsafe {
+            bpf_skb_load_bytes(
+                self.skb as *const _,
+                offset as u32,
+                dst.as_mut_ptr() as *mut _,
+                len as u32,
+            )
+        };
+        if ret == 0 {
+            Ok(len)
+        } else {
+            Err(ret)
+        }
+    }
+}
+
+impl EbpfContext for FlowDissectorContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.skb as *mut _
+    }
+}
@@ -0,0 +1,103 @@
+use core::ffi::{c_long, c_void};
+
+use aya_ebpf_bindings::helpers::{bpf_seq_printf, bpf_seq_write};
+
+use crate::{args::FromBtfArgument, bindings::seq_file, EbpfContext};
+
+// Mirrors `struct bpf_iter_meta`, the first field of every iterator context.
+#[repr(C)]
+struct bpf_iter_meta {
+    seq: *mut seq_file,
+    session_id: u64,
+    seq_num: u64,
+}
+
+pub struct IterContext {
+    ctx: *mut c_void,
+}
+
+impl IterContext {
+    pub fn new(ctx: *mut c_void) -> IterContext {
+        IterContext { ctx }
+    }
+
+    fn meta(&self) -> *const bpf_iter_meta {
+        unsafe { *(self.ctx as *const *const bpf_iter_meta) }
+    }
+
+    /// Returns the `n`th field of the iterator context, starting from 0.
+    ///
+    /// The context is the `struct bpf_iter__<target>` of the iterator target. The field at
+    /// index 0 is always the `bpf_iter_meta` pointer, so the fields specific to the target
+    /// start at index 1.
+    ///
+    /// SAFETY: This function is deeply unsafe, as we are reading raw pointers into kernel memory.
+    /// In particular, the value of `n` must not exceed the number of context fields.
+    pub unsafe fn arg<T: FromBtfArgument>(&self, n: usize) -> T {
+        T::from_argument(self.ctx as *const _, n)
+    }
+
+    /// Returns the element visited by this iteration, for example the `task_struct` of a `task`
+    /// iterator.
+    ///
+    /// The program is called one last time after all the elements have been visited, with a
+    /// null element. This can be used to write a trailer to the output.
+    pub fn item<T>(&self) -> *const T {
+        unsafe { self.arg(1) }
+    }
+
+    /// Returns the sequence number of this iteration, starting from 0.
+    ///
+    /// This can be used to write a header to the output before the first element.
+    pub fn seq_num(&self) -> u64 {
+        unsafe { (*self.meta()).seq_num }
+    }
+
+    /// Returns the id of the current read session of the iterator.
+    pub fn session_id(&self) -> u64 {
+        unsafe { (*self.meta()).session_id }
+    }
+
+    /// Writes formatted output to the iterator's seq_file using `bpf_seq_printf`.
+    ///
+    /// `fmt` must be a NUL terminated format string, and `args` holds the values of its
+    /// conversions, each widened to 64 bits.
+    pub fn seq_printf(&self, fmt: &[u8], args: &[u64]) -> Result<(), c_long> {
+        let ret = unsafe {
+            bpf_seq_printf(
+                (*self.meta()).seq,
+                fmt.as_ptr() as *const _,
+                fmt.len() as u32,
+                args.as_ptr() as *const c_void,
+                (args.len() * 8) as u32,
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+
+    /// Writes raw bytes to the iterator's seq_file using `bpf_seq_write`.
+    pub fn seq_write(&self, data: &[u8]) -> Result<(), c_long> {
+        let ret = unsafe {
+            bpf_seq_write(
+                (*self.meta()).seq,
+                data.as_ptr() as *const c_void,
+                data.len() as u32,
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+}
+
+impl EbpfContext for IterContext {
+    fn as_ptr(&self) -> *mut c_void {
+        self.ctx
+    }
+}
@@ -1,6 +1,9 @@
-use core::ffi::c_void;
+use core::ffi::{c_long, c_void};
 
-use crate::{args::FromRawTracepointArgs, bindings::bpf_raw_tracepoint_args, EbpfContext};
+use crate::{
+    args::FromRawTracepointArgs, bindings::bpf_raw_tracepoint_args, helpers::bpf_probe_read_kernel,
+    EbpfContext,
+};
 
 pub struct RawTracePointContext {
     ctx: *mut bpf_raw_tracepoint_args,
@@ -13,9 +16,46 @@ impl RawTracePointContext {
         }
     }
 
+    /// Returns the `n`th argument of the raw tracepoint, starting from 0.
+    ///
+    /// Raw tracepoints receive the arguments of the `TP_PROTO` of the tracepoint, not the fields
+    /// of its format file like [`TracePointContext`](crate::programs::TracePointContext). The
+    /// arguments are different for every tracepoint and can change between kernel versions, so
+    /// check the tracepoint definition in the kernel sources (for example
+    /// `include/trace/events/sched.h`) of the kernels you target.
+    ///
+    /// # Safety
+    ///
+    /// `T` must match the type of the argument, and `n` must not exceed the number of arguments
+    /// of the tracepoint.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #![allow(dead_code)]
+    /// # use aya_ebpf::{programs::RawTracePointContext, cty::c_long};
+    /// // sched_process_exec has the `TP_PROTO(struct task_struct *p, pid_t old_pid,
+    /// // struct linux_binprm *bprm)` arguments
+    /// unsafe fn try_sched_process_exec(ctx: RawTracePointContext) -> Result<u32, c_long> {
+    ///     let old_pid: u32 = ctx.arg(1);
+    ///     Ok(old_pid)
+    /// }
+    /// ```
     pub unsafe fn arg<T: FromRawTracepointArgs>(&self, n: usize) -> T {
         T::from_argument(&*self.ctx, n)
     }
+
+    /// Reads a `T` from the kernel memory pointed to by the `n`th argument of the raw
+    /// tracepoint, using [`bpf_probe_read_kernel`].
+    ///
+    /// On failure, this function returns a negative value wrapped in an `Err`.
+    ///
+    /// # Safety
+    ///
+    /// The `n`th argument must be a pointer to a `T`. See [`RawTracePointContext::arg`].
+    pub unsafe fn read_arg<T>(&self, n: usize) -> Result<T, c_long> {
+        bpf_probe_read_kernel(self.arg::<*const T>(n))
+    }
 }
 
 impl EbpfContext for RawTracePointContext {
@@ -10,6 +10,48 @@ impl SkLookupContext {
     pub fn new(lookup: *mut bpf_sk_lookup) -> SkLookupContext {
         SkLookupContext { lookup }
     }
+
+    pub fn family(&self) -> u32 {
+        unsafe { (*self.lookup).family }
+    }
+
+    pub fn protocol(&self) -> u32 {
+        unsafe { (*self.lookup).protocol }
+    }
+
+    /// Returns the remote IPv4 address, in network byte order.
+    pub fn remote_ip4(&self) -> u32 {
+        unsafe { (*self.lookup).remote_ip4 }
+    }
+
+    /// Returns the remote IPv6 address, in network byte order.
+    pub fn remote_ip6(&self) -> [u32; 4] {
+        unsafe { (*self.lookup).remote_ip6 }
+    }
+
+    /// Returns the remote port, in network byte order.
+    pub fn remote_port(&self) -> u16 {
+        unsafe { (*self.lookup).remote_port }
+    }
+
+    /// Returns the local IPv4 address, in network byte order.
+    pub fn local_ip4(&self) -> u32 {
+        unsafe { (*self.lookup).local_ip4 }
+    }
+
+    /// Returns the local IPv6 address, in network byte order.
+    pub fn local_ip6(&self) -> [u32; 4] {
+        unsafe { (*self.lookup).local_ip6 }
+    }
+
+    /// Returns the local port, in host byte order.
+    pub fn local_port(&self) -> u32 {
+        unsafe { (*self.lookup).local_port }
+    }
+
+    pub fn ingress_ifindex(&self) -> u32 {
+        unsafe { (*self.lookup).ingress_ifindex }
+    }
 }
 
 impl EbpfContext for SkLookupContext {
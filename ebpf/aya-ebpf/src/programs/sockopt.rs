@@ -1,6 +1,9 @@
 use core::ffi::c_void;
 
-use crate::{bindings::bpf_sockopt, EbpfContext};
+use crate::{
+    bindings::{bpf_sock, bpf_sockopt},
+    EbpfContext,
+};
 
 pub struct SockoptContext {
     pub sockopt: *mut bpf_sockopt,
@@ -10,6 +13,59 @@ impl SockoptContext {
     pub fn new(sockopt: *mut bpf_sockopt) -> SockoptContext {
         SockoptContext { sockopt }
     }
+
+    pub fn sk(&self) -> *mut bpf_sock {
+        unsafe { (*self.sockopt).__bindgen_anon_1.sk }
+    }
+
+    pub fn level(&self) -> i32 {
+        unsafe { (*self.sockopt).level }
+    }
+
+    pub fn optname(&self) -> i32 {
+        unsafe { (*self.sockopt).optname }
+    }
+
+    /// Returns the start of the option value buffer.
+    ///
+    /// Accesses must be bounds-checked against [`SockoptContext::optval_end`] to be accepted
+    /// by the verifier. The buffer may be written to in place to replace the option value.
+    #[inline]
+    pub fn optval(&self) -> usize {
+        unsafe { (*self.sockopt).__bindgen_anon_2.optval as usize }
+    }
+
+    /// Returns the end of the option value buffer.
+    #[inline]
+    pub fn optval_end(&self) -> usize {
+        unsafe { (*self.sockopt).__bindgen_anon_3.optval_end as usize }
+    }
+
+    pub fn optlen(&self) -> i32 {
+        unsafe { (*self.sockopt).optlen }
+    }
+
+    /// Sets the length of the option value.
+    ///
+    /// The length can only be shortened, or set to `-1` in `cgroup/setsockopt` programs to
+    /// bypass the kernel handler and pass the original value through unchanged.
+    pub fn set_optlen(&mut self, optlen: i32) {
+        unsafe { (*self.sockopt).optlen = optlen }
+    }
+
+    /// Returns the value returned by the kernel's `getsockopt` handler.
+    ///
+    /// Only available in `cgroup/getsockopt` programs.
+    pub fn retval(&self) -> i32 {
+        unsafe { (*self.sockopt).retval }
+    }
+
+    /// Overrides the value returned by the kernel's `getsockopt` handler.
+    ///
+    /// Only available in `cgroup/getsockopt` programs, where the new value must be `0`.
+    pub fn set_retval(&mut self, retval: i32) {
+        unsafe { (*self.sockopt).retval = retval }
+    }
 }
 
 impl EbpfContext for SockoptContext {
@@ -1,6 +1,15 @@
 use core::ffi::c_void;
 
-use crate::{bindings::bpf_sysctl, EbpfContext};
+use aya_ebpf_bindings::helpers::{
+    bpf_sysctl_get_current_value, bpf_sysctl_get_name, bpf_sysctl_get_new_value,
+    bpf_sysctl_set_new_value,
+};
+use aya_ebpf_cty::c_long;
+
+use crate::{
+    bindings::{bpf_sysctl, BPF_F_SYSCTL_BASE_NAME},
+    check_bounds_signed, EbpfContext,
+};
 
 pub struct SysctlContext {
     pub sysctl: *mut bpf_sysctl,
@@ -10,6 +19,100 @@ impl SysctlContext {
     pub fn new(sysctl: *mut bpf_sysctl) -> SysctlContext {
         SysctlContext { sysctl }
     }
+
+    /// Returns `true` if the sysctl is being written to, `false` if it's being read.
+    pub fn write(&self) -> bool {
+        unsafe { (*self.sysctl).write != 0 }
+    }
+
+    pub fn file_pos(&self) -> u32 {
+        unsafe { (*self.sysctl).file_pos }
+    }
+
+    pub fn set_file_pos(&mut self, file_pos: u32) {
+        unsafe { (*self.sysctl).file_pos = file_pos }
+    }
+
+    /// Reads the name of the sysctl into `buf`.
+    ///
+    /// If `base` is `true` only the base name is read (e.g. `tcp_mem`), otherwise the full
+    /// path relative to `/proc/sys` is read (e.g. `net/ipv4/tcp_mem`).
+    ///
+    /// On success the name, without the trailing NUL, is returned. If `buf` is too small
+    /// `Err(-E2BIG)` is returned and `buf` contains the truncated name.
+    #[inline]
+    pub fn name<'a>(&self, buf: &'a mut [u8], base: bool) -> Result<&'a [u8], c_long> {
+        let flags = if base { BPF_F_SYSCTL_BASE_NAME } else { 0 };
+        let len = unsafe {
+            bpf_sysctl_get_name(
+                self.sysctl,
+                buf.as_mut_ptr() as *mut _,
+                buf.len() as _,
+                flags.into(),
+            )
+        };
+        read_bytes(len, buf)
+    }
+
+    /// Reads the current value of the sysctl into `buf`.
+    ///
+    /// On success the value, without the trailing NUL, is returned. If `buf` is too small
+    /// `Err(-E2BIG)` is returned and `buf` contains the truncated value.
+    #[inline]
+    pub fn current_value<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], c_long> {
+        let len = unsafe {
+            bpf_sysctl_get_current_value(self.sysctl, buf.as_mut_ptr() as *mut _, buf.len() as _)
+        };
+        read_bytes(len, buf)
+    }
+
+    /// Reads the value being written to the sysctl into `buf`.
+    ///
+    /// On success the value, without the trailing NUL, is returned. If `buf` is too small
+    /// `Err(-E2BIG)` is returned and `buf` contains the truncated value. Returns `Err(-EINVAL)`
+    /// if the sysctl is being read rather than written.
+    #[inline]
+    pub fn new_value<'a>(&self, buf: &'a mut [u8]) -> Result<&'a [u8], c_long> {
+        let len = unsafe {
+            bpf_sysctl_get_new_value(self.sysctl, buf.as_mut_ptr() as *mut _, buf.len() as _)
+        };
+        read_bytes(len, buf)
+    }
+
+    /// Replaces the value being written to the sysctl with `value`.
+    ///
+    /// Returns `Err(-EINVAL)` if the sysctl is being read rather than written.
+    #[inline]
+    pub fn set_new_value(&mut self, value: &[u8]) -> Result<(), c_long> {
+        let ret = unsafe {
+            bpf_sysctl_set_new_value(self.sysctl, value.as_ptr() as *const _, value.len() as _)
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(ret)
+        }
+    }
+}
+
+// Same value on all architectures.
+const E2BIG: c_long = 7;
+
+// Turns the return value of the sysctl string helpers into the string read into `buf`. The
+// helpers return a negative errno on failure, which is passed through as is.
+#[inline]
+fn read_bytes(len: c_long, buf: &[u8]) -> Result<&[u8], c_long> {
+    if len < 0 {
+        return Err(len);
+    }
+    // The helpers never return more than the size of the buffer, but the verifier doesn't know
+    // that so we show it the upper bound. A larger length would mean that the string didn't fit,
+    // which the helpers report with -E2BIG.
+    if !check_bounds_signed(len, 0, buf.len() as i64) {
+        return Err(-E2BIG);
+    }
+    let len = usize::try_from(len).map_err(|core::num::TryFromIntError { .. }| -E2BIG)?;
+    buf.get(..len).ok_or(-E2BIG)
 }
 
 impl EbpfContext for SysctlContext {
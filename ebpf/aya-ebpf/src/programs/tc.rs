@@ -2,6 +2,11 @@ use aya_ebpf_cty::{c_long, c_void};
 
 use crate::{bindings::__sk_buff, programs::sk_buff::SkBuff, EbpfContext};
 
+/// The context passed to programs marked with
+/// [`#[classifier]`](aya_ebpf_macros::classifier).
+///
+/// Classifiers are attached in direct-action mode, so the program returns the verdict for the
+/// packet as one of the `TC_ACT_*` constants in [`bindings`](crate::bindings).
 pub struct TcContext {
     pub skb: SkBuff,
 }
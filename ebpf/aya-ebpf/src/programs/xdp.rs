@@ -1,4 +1,4 @@
-use core::ffi::c_void;
+use core::{ffi::c_void, mem};
 
 use crate::{bindings::xdp_md, EbpfContext};
 
@@ -21,6 +21,97 @@ impl XdpContext {
         unsafe { (*self.ctx).data_end as usize }
     }
 
+    /// Returns a pointer to a `T` at `offset` bytes into the packet, or `None` if the packet is
+    /// too short to hold a `T` at that offset.
+    ///
+    /// The verifier only allows packet accesses that are preceded by such a bounds check, so the
+    /// returned pointer can be dereferenced as long as `T` is the type being accessed. The check
+    /// only convinces the verifier if `offset` is known when the program is verified, for example
+    /// a constant or a value derived from the packet and bounded with a comparison.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use aya_ebpf::{bindings::xdp_action, macros::xdp, programs::XdpContext};
+    ///
+    /// #[repr(C)]
+    /// struct EthHdr {
+    ///     dst_addr: [u8; 6],
+    ///     src_addr: [u8; 6],
+    ///     ether_type: u16,
+    /// }
+    ///
+    /// #[xdp]
+    /// fn xdp(ctx: XdpContext) -> u32 {
+    ///     let Some(eth) = ctx.ptr_at::<EthHdr>(0) else {
+    ///         return xdp_action::XDP_PASS;
+    ///     };
+    ///     // IPv4 in network byte order
+    ///     if unsafe { (*eth).ether_type } == 0x0800u16.to_be() {
+    ///         xdp_action::XDP_DROP
+    ///     } else {
+    ///         xdp_action::XDP_PASS
+    ///     }
+    /// }
+    /// ```
+    #[inline(always)]
+    pub fn ptr_at<T>(&self, offset: usize) -> Option<*const T> {
+        let start = self.data();
+        let end = self.data_end();
+        let len = mem::size_of::<T>();
+
+        if start + offset + len > end {
+            return None;
+        }
+
+        Some((start + offset) as *const T)
+    }
+
+    /// Returns a mutable pointer to a `T` at `offset` bytes into the packet, or `None` if the
+    /// packet is too short to hold a `T` at that offset.
+    ///
+    /// See [`ptr_at`](Self::ptr_at).
+    #[inline(always)]
+    pub fn ptr_at_mut<T>(&self, offset: usize) -> Option<*mut T> {
+        self.ptr_at(offset).map(|ptr: *const T| ptr.cast_mut())
+    }
+
+    /// Returns the `len` bytes at `offset` bytes into the packet, or `Err(())` if the packet is
+    /// too short.
+    ///
+    /// This is a safe alternative to [`ptr_at`](Self::ptr_at) for reading raw bytes. As with
+    /// `ptr_at`, the bounds check only convinces the verifier if `offset` and `len` are known
+    /// when the program is verified.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use aya_ebpf::{bindings::xdp_action, macros::xdp, programs::XdpContext};
+    ///
+    /// #[xdp]
+    /// fn xdp(ctx: XdpContext) -> u32 {
+    ///     // the destination MAC address
+    ///     match ctx.slice_at(0, 6) {
+    ///         Ok(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff]) => xdp_action::XDP_DROP,
+    ///         _ => xdp_action::XDP_PASS,
+    ///     }
+    /// }
+    /// ```
+    // `()` fits programs that turn any failure into a single verdict with `?`.
+    #[allow(clippy::result_unit_err)]
+    #[inline(always)]
+    pub fn slice_at(&self, offset: usize, len: usize) -> Result<&[u8], ()> {
+        let start = self.data();
+        let end = self.data_end();
+
+        if start + offset + len > end {
+            return Err(());
+        }
+
+        // SAFETY: the bytes are within the packet, which lives as long as the context.
+        Ok(unsafe { core::slice::from_raw_parts((start + offset) as *const u8, len) })
+    }
+
     /// Return the raw address of the XdpContext metadata.
     #[inline(always)]
     pub fn metadata(&self) -> usize {
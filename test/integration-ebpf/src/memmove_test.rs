@@ -14,19 +14,6 @@ use network_types::{
     ip::Ipv6Hdr,
 };
 
-#[inline(always)]
-fn ptr_at<T>(ctx: &XdpContext, offset: usize) -> Result<*const T, ()> {
-    let start = ctx.data();
-    let end = ctx.data_end();
-    let len = mem::size_of::<T>();
-
-    if start + offset + len > end {
-        return Err(());
-    }
-
-    Ok((start + offset) as *const T)
-}
-
 struct Value {
     pub orig_ip: [u8; 16],
 }
@@ -42,11 +29,11 @@ pub fn do_dnat(ctx: XdpContext) -> u32 {
 fn try_do_dnat(ctx: XdpContext) -> Result<u32, ()> {
     let index = 0;
     if let Some(nat) = unsafe { RULES.get(&index) } {
-        let hproto: *const EtherType = ptr_at(&ctx, mem::offset_of!(EthHdr, ether_type))?;
+        let hproto: *const EtherType = ctx.ptr_at(mem::offset_of!(EthHdr, ether_type)).ok_or(())?;
         match unsafe { *hproto } {
             EtherType::Ipv6 => {
-                let ip_hdr: *const Ipv6Hdr = ptr_at(&ctx, EthHdr::LEN)?;
-                unsafe { (*ip_hdr.cast_mut()).dst_addr.in6_u.u6_addr8 = nat.orig_ip };
+                let ip_hdr: *mut Ipv6Hdr = ctx.ptr_at_mut(EthHdr::LEN).ok_or(())?;
+                unsafe { (*ip_hdr).dst_addr.in6_u.u6_addr8 = nat.orig_ip };
             }
             _ => return Ok(xdp_action::XDP_PASS),
         }
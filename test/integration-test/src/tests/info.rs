@@ -105,6 +105,18 @@ fn test_program_info() {
         maps.is_some_and(|ids| ids.is_empty()),
         KernelVersion::new(4, 15, 0),
     );
+    let insns = test_prog.translated_instructions().unwrap();
+    kernel_assert!(
+        insns.is_some_and(|insns| Some(insns.len() as u32 * 8) == test_prog.size_translated()),
+        KernelVersion::new(4, 13, 0),
+    );
+    if jit_enabled {
+        let code = test_prog.jitted_instructions().unwrap();
+        kernel_assert!(
+            code.is_some_and(|code| code.len() as u32 == test_prog.size_jitted()),
+            KernelVersion::new(4, 13, 0),
+        );
+    }
     kernel_assert_eq!(
         Some("simple_prog"),
         test_prog.name_as_str(),
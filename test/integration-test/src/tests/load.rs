@@ -1,10 +1,20 @@
-use std::{convert::TryInto as _, fs::remove_file, path::Path, thread, time::Duration};
+use std::{
+    convert::TryInto as _,
+    ffi::c_int,
+    fs::remove_file,
+    io, mem,
+    net::{Ipv4Addr, UdpSocket},
+    os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd},
+    path::Path,
+    thread,
+    time::Duration,
+};
 
 use aya::{
     maps::Array,
     programs::{
         links::{FdLink, PinnedLink},
-        loaded_links, loaded_programs, KProbe, TracePoint, UProbe, Xdp, XdpFlags,
+        loaded_links, loaded_programs, KProbe, SocketFilter, TracePoint, UProbe, Xdp, XdpFlags,
     },
     util::KernelVersion,
     Ebpf,
@@ -280,6 +290,77 @@ fn basic_tracepoint() {
     assert_unloaded("test_tracepoint");
 }
 
+#[test]
+fn tracepoint_attach_by_id() {
+    let mut bpf = Ebpf::load(crate::TEST).unwrap();
+    let prog: &mut TracePoint = bpf
+        .program_mut("test_tracepoint")
+        .unwrap()
+        .try_into()
+        .unwrap();
+
+    prog.load().unwrap();
+    let id = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"]
+        .iter()
+        .find_map(|tracefs| {
+            std::fs::read_to_string(Path::new(tracefs).join("events/syscalls/sys_enter_kill/id"))
+                .ok()
+        })
+        .unwrap();
+    let link = prog.attach_by_id(id.trim().parse().unwrap()).unwrap();
+    assert_loaded("test_tracepoint");
+
+    prog.detach(link).unwrap();
+    prog.unload().unwrap();
+    assert_unloaded("test_tracepoint");
+}
+
+#[test]
+fn socket_filter_attach_reuseport() {
+    let mut bpf = Ebpf::load(crate::SIMPLE_PROG).unwrap();
+    let prog: &mut SocketFilter = bpf.program_mut("simple_prog").unwrap().try_into().unwrap();
+    prog.load().unwrap();
+
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let link = prog.attach(&socket).unwrap();
+    prog.detach(link).unwrap();
+
+    // SO_REUSEPORT must be set before binding, which std doesn't allow.
+    let socket = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    assert!(socket >= 0, "{}", io::Error::last_os_error());
+    let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+    let one: c_int = 1;
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_REUSEPORT,
+            &one as *const _ as *const _,
+            mem::size_of_val(&one) as u32,
+        )
+    };
+    assert_eq!(ret, 0, "{}", io::Error::last_os_error());
+    let addr = libc::sockaddr_in {
+        sin_family: libc::AF_INET as u16,
+        sin_port: 0,
+        sin_addr: libc::in_addr {
+            s_addr: u32::from(Ipv4Addr::LOCALHOST).to_be(),
+        },
+        sin_zero: [0; 8],
+    };
+    let ret = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            &addr as *const _ as *const _,
+            mem::size_of_val(&addr) as u32,
+        )
+    };
+    assert_eq!(ret, 0, "{}", io::Error::last_os_error());
+
+    let link = prog.attach_reuseport(&socket).unwrap();
+    prog.detach(link).unwrap();
+}
+
 #[test]
 fn basic_uprobe() {
     let mut bpf = Ebpf::load(crate::TEST).unwrap();
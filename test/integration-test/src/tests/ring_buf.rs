@@ -11,11 +11,12 @@ use std::{
 use anyhow::Context as _;
 use assert_matches::assert_matches;
 use aya::{
-    maps::{array::PerCpuArray, ring_buf::RingBuf, MapData},
+    maps::{array::PerCpuArray, ring_buf::RingBuf, AsyncRingBuf, MapData},
     programs::UProbe,
     Ebpf, EbpfLoader,
 };
 use aya_obj::generated::BPF_RINGBUF_HDR_SZ;
+use futures::StreamExt as _;
 use integration_common::ring_buf::Registers;
 use rand::Rng as _;
 use test_log::test;
@@ -379,6 +380,35 @@ async fn ring_buf_asyncfd_events() {
     writer.join();
 }
 
+// This test is like the above test but consumes the ring buffer through AsyncRingBuf's Stream.
+#[test(tokio::test)]
+async fn ring_buf_async_stream() {
+    let mut bpf = Ebpf::load(crate::RING_BUF).unwrap();
+    let mut stream = AsyncRingBuf::try_from(bpf.take_map("RING_BUF").unwrap()).unwrap();
+    let prog: &mut UProbe = bpf
+        .program_mut("ring_buf_test")
+        .unwrap()
+        .try_into()
+        .unwrap();
+    prog.load().unwrap();
+    prog.attach(
+        "ring_buf_trigger_ebpf_program",
+        "/proc/self/exe",
+        None,
+        None,
+    )
+    .unwrap();
+
+    let mut total_events = 0;
+    let writer = WriterThread::spawn();
+    while total_events < WriterThread::NUM_MESSAGES {
+        let read = stream.next().await.unwrap().unwrap();
+        assert_eq!(read.len(), 8);
+        total_events += 1;
+    }
+    writer.join();
+}
+
 // WriterThread triggers the ring_buf write continuously until the join() method is called. It is
 // used by both the epoll and async fd test that need frequent writes to the ring buffer to trigger
 // the memory synchronization bug that was fixed.
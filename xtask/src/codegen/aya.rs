@@ -5,9 +5,9 @@ use aya_tool::{bindgen, write_to_file};
 
 use crate::codegen::{Architecture, SysrootOptions};
 
-pub fn codegen(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
+pub fn codegen(opts: &SysrootOptions, archs: &[Architecture], libbpf_dir: &Path) -> Result<()> {
     codegen_internal_btf_bindings(libbpf_dir)?;
-    codegen_bindings(opts, libbpf_dir)
+    codegen_bindings(opts, archs, libbpf_dir)
 }
 
 fn codegen_internal_btf_bindings(libbpf_dir: &Path) -> Result<()> {
@@ -34,7 +34,11 @@ fn codegen_internal_btf_bindings(libbpf_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-fn codegen_bindings(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
+fn codegen_bindings(
+    opts: &SysrootOptions,
+    archs: &[Architecture],
+    libbpf_dir: &Path,
+) -> Result<()> {
     let SysrootOptions {
         x86_64_sysroot,
         aarch64_sysroot,
@@ -147,6 +151,8 @@ fn codegen_bindings(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
             "BPF_EXIT",
             "SO_ATTACH_BPF",
             "SO_DETACH_BPF",
+            "SO_ATTACH_REUSEPORT_EBPF",
+            "SO_DETACH_REUSEPORT_BPF",
             // BTF
             "BTF_INT_.*",
             "BTF_KIND_.*",
@@ -191,21 +197,12 @@ fn codegen_bindings(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
         bindgen
     };
 
-    for arch in Architecture::supported() {
+    for arch in archs {
         let mut bindgen = builder();
 
         // Set target triple. This will set the right flags (which you can see
         // running clang -target=X  -E - -dM </dev/null)
-        let target = match arch {
-            Architecture::X86_64 => "x86_64-unknown-linux-gnu",
-            Architecture::ARMv7 => "armv7-unknown-linux-gnu",
-            Architecture::AArch64 => "aarch64-unknown-linux-gnu",
-            Architecture::RISCV64 => "riscv64-unknown-linux-gnu",
-            Architecture::PowerPC64 => "powerpc64le-unknown-linux-gnu",
-            Architecture::S390X => "s390x-unknown-linux-gnu",
-            Architecture::Mips => "mips-unknown-linux-gnu",
-        };
-        bindgen = bindgen.clang_args(&["-target", target]);
+        bindgen = bindgen.clang_args(["-target", arch.target()]);
 
         // Set the sysroot. This is needed to ensure that the correct arch
         // specific headers are imported.
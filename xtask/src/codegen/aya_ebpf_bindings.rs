@@ -19,7 +19,7 @@ use crate::{
     exec,
 };
 
-pub fn codegen(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
+pub fn codegen(opts: &SysrootOptions, archs: &[Architecture], libbpf_dir: &Path) -> Result<()> {
     let SysrootOptions {
         x86_64_sysroot,
         aarch64_sysroot,
@@ -100,21 +100,12 @@ pub fn codegen(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
         bindgen
     };
 
-    for arch in Architecture::supported() {
+    for arch in archs {
         let mut bindgen = builder();
 
         // Set target triple. This will set the right flags (which you can see
         // running clang -target=X  -E - -dM </dev/null)
-        let target = match arch {
-            Architecture::X86_64 => "x86_64-unknown-linux-gnu",
-            Architecture::ARMv7 => "armv7-unknown-linux-gnu",
-            Architecture::AArch64 => "aarch64-unknown-linux-gnu",
-            Architecture::RISCV64 => "riscv64-unknown-linux-gnu",
-            Architecture::PowerPC64 => "powerpc64le-unknown-linux-gnu",
-            Architecture::S390X => "s390x-unknown-linux-gnu",
-            Architecture::Mips => "mips-unknown-linux-gnu",
-        };
-        bindgen = bindgen.clang_args(["-target", target]);
+        bindgen = bindgen.clang_args(["-target", arch.target()]);
 
         // Set the sysroot. This is needed to ensure that the correct arch
         // specific headers are imported.
@@ -131,9 +122,11 @@ pub fn codegen(opts: &SysrootOptions, libbpf_dir: &Path) -> Result<()> {
 
         let bindings = bindgen.generate().context("bindgen failed")?.to_string();
 
-        let mut tree = parse_str::<syn::File>(&bindings).unwrap();
+        let mut tree =
+            parse_str::<syn::File>(&bindings).context("failed to parse the bindgen output")?;
 
-        let (indexes, helpers) = extract_helpers(&tree.items);
+        let (indexes, helpers) = extract_helpers(&tree.items)
+            .with_context(|| format!("failed to extract the helpers for {arch}"))?;
         let helpers = expand_helpers(&helpers);
         for index in indexes {
             tree.items[index] = Item::Verbatim(TokenStream::new())
@@ -1,12 +1,13 @@
+use anyhow::{anyhow, bail, Context as _, Result};
 use proc_macro2::TokenStream;
-use quote::{quote, TokenStreamExt};
+use quote::{format_ident, quote, ToTokens as _, TokenStreamExt};
 use syn::{
     punctuated::Punctuated, AngleBracketedGenericArguments, BareFnArg, ForeignItem,
     ForeignItemStatic, GenericArgument, Ident, Item, Path, PathArguments, ReturnType, Token, Type,
     TypeBareFn, TypePath,
 };
 
-pub fn extract_helpers(items: &[Item]) -> (Vec<usize>, Vec<Helper<'_>>) {
+pub fn extract_helpers(items: &[Item]) -> Result<(Vec<usize>, Vec<Helper<'_>>)> {
     let mut helpers = Vec::new();
     let mut indexes = Vec::new();
     for (item_index, item) in items.iter().enumerate() {
@@ -15,10 +16,10 @@ pub fn extract_helpers(items: &[Item]) -> (Vec<usize>, Vec<Helper<'_>>) {
                 if let ForeignItem::Static(s_item) = i {
                     let ident_s = s_item.ident.to_string();
                     if ident_s.starts_with("bpf_") {
-                        helpers.push(
-                            helper_from_item(s_item, helpers.len() + 1)
-                                .expect("unexpected bindgen helper signature"),
-                        );
+                        helpers
+                            .push(helper_from_item(s_item, helpers.len() + 1).with_context(
+                                || format!("unexpected signature for `{ident_s}`"),
+                            )?);
                         indexes.push(item_index);
                     }
                 }
@@ -26,43 +27,65 @@ pub fn extract_helpers(items: &[Item]) -> (Vec<usize>, Vec<Helper<'_>>) {
         }
     }
 
-    (indexes, helpers)
+    Ok((indexes, helpers))
 }
 
-pub fn helper_from_item(item: &ForeignItemStatic, call_index: usize) -> Option<Helper<'_>> {
-    if let Type::Path(TypePath {
+pub fn helper_from_item(item: &ForeignItemStatic, call_index: usize) -> Result<Helper<'_>> {
+    // bindgen emits helpers as `static bpf_foo: Option<unsafe extern "C" fn(..) -> ..>`.
+    let found = || item.ty.to_token_stream();
+    let Type::Path(TypePath {
         path: Path { segments, .. },
         ..
     }) = &*item.ty
-    {
-        let generics = &segments.last().unwrap().arguments;
-        if let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) = generics
-        {
-            if let Some(GenericArgument::Type(ty)) = args.first() {
-                if let Type::BareFn(TypeBareFn { inputs, output, .. }) = ty {
-                    return Some(Helper {
-                        ident: &item.ident,
-                        ty,
-                        inputs,
-                        output,
-                        call_index,
-                    });
-                }
-            }
-        };
+    else {
+        bail!("expected a path type, found `{}`", found());
+    };
+    let segment = segments
+        .last()
+        .ok_or_else(|| anyhow!("expected a non-empty path, found `{}`", found()))?;
+    let PathArguments::AngleBracketed(AngleBracketedGenericArguments { args, .. }) =
+        &segment.arguments
+    else {
+        bail!(
+            "expected a generic type like `Option<_>`, found `{}`",
+            found()
+        );
+    };
+    let Some(GenericArgument::Type(ty)) = args.first() else {
+        bail!("expected a type argument, found `{}`", found());
+    };
+    let Type::BareFn(TypeBareFn {
+        inputs,
+        output,
+        variadic,
+        ..
+    }) = ty
+    else {
+        bail!(
+            "expected a function pointer, found `{}`",
+            ty.to_token_stream()
+        );
+    };
+    if let Some(position) = inputs.iter().position(|arg| arg.name.is_none()) {
+        bail!(
+            "expected named arguments, argument {position} of `{}` has no name",
+            ty.to_token_stream()
+        );
     }
 
-    None
+    Ok(Helper {
+        ident: &item.ident,
+        ty,
+        inputs,
+        output,
+        variadic: variadic.is_some(),
+        call_index,
+    })
 }
 
 pub fn expand_helpers(helpers: &[Helper<'_>]) -> TokenStream {
     let mut tokens = TokenStream::new();
-    tokens.append_all(
-        helpers
-            .iter()
-            .filter(|h| *h.ident != "bpf_trace_printk")
-            .map(expand_helper),
-    );
+    tokens.append_all(helpers.iter().map(expand_helper));
 
     tokens
 }
@@ -73,6 +96,7 @@ pub fn expand_helper(helper: &Helper<'_>) -> TokenStream {
         ty,
         inputs,
         output,
+        variadic,
         call_index,
     } = helper;
 
@@ -81,6 +105,24 @@ pub fn expand_helper(helper: &Helper<'_>) -> TokenStream {
         .map(|arg| &arg.name.as_ref().unwrap().0)
         .collect::<Vec<_>>();
 
+    if *variadic {
+        // Rust can't define variadic functions, so variadic helpers like `bpf_trace_printk` are
+        // exposed with the maximum number of arguments instead. Helper arguments are passed in
+        // registers, and the ones the helper doesn't use are ignored.
+        let inputs = inputs.iter().collect::<Vec<_>>();
+        let extra_args = (1..=MAX_HELPER_ARGS - inputs.len())
+            .map(|i| format_ident!("arg{i}"))
+            .collect::<Vec<_>>();
+
+        return quote! {
+            pub unsafe fn #ident(#(#inputs,)* #(#extra_args: __u64),*) #output {
+                let fun: unsafe extern "C" fn(#(#inputs,)* #(#extra_args: __u64),*) #output =
+                    ::core::mem::transmute(#call_index);
+                fun(#(#args,)* #(#extra_args),*)
+            }
+        };
+    }
+
     let helper = quote! {
         pub unsafe fn #ident(#inputs) #output {
             let fun: #ty = ::core::mem::transmute(#call_index);
@@ -91,10 +133,14 @@ pub fn expand_helper(helper: &Helper<'_>) -> TokenStream {
     helper
 }
 
+// BPF helpers take at most five arguments.
+const MAX_HELPER_ARGS: usize = 5;
+
 pub struct Helper<'a> {
     ident: &'a Ident,
     ty: &'a Type,
     inputs: &'a Punctuated<BareFnArg, Token![,]>,
     output: &'a ReturnType,
+    variadic: bool,
     call_index: usize,
 }
@@ -32,6 +32,19 @@ impl Architecture {
     pub fn supported() -> &'static [Architecture] {
         SUPPORTED_ARCHS
     }
+
+    /// The target triple passed to clang when generating bindings for this architecture.
+    pub fn target(&self) -> &'static str {
+        match self {
+            Architecture::X86_64 => "x86_64-unknown-linux-gnu",
+            Architecture::ARMv7 => "armv7-unknown-linux-gnu",
+            Architecture::AArch64 => "aarch64-unknown-linux-gnu",
+            Architecture::RISCV64 => "riscv64-unknown-linux-gnu",
+            Architecture::PowerPC64 => "powerpc64le-unknown-linux-gnu",
+            Architecture::S390X => "s390x-unknown-linux-gnu",
+            Architecture::Mips => "mips-unknown-linux-gnu",
+        }
+    }
 }
 
 impl std::str::FromStr for Architecture {
@@ -96,6 +109,11 @@ pub struct Options {
     #[clap(flatten)]
     sysroot_options: SysrootOptions,
 
+    /// Only generate bindings for the given architectures. Defaults to all the supported
+    /// architectures.
+    #[arg(long = "arch", value_delimiter = ',')]
+    archs: Vec<Architecture>,
+
     #[clap(subcommand)]
     command: Option<Target>,
 }
@@ -111,18 +129,27 @@ enum Target {
 pub fn codegen(opts: Options, libbpf_dir: &Path) -> Result<()> {
     let Options {
         sysroot_options,
+        archs,
         command,
     } = opts;
 
+    let archs = if archs.is_empty() {
+        Architecture::supported()
+    } else {
+        archs.as_slice()
+    };
+
     match command {
         Some(command) => match command {
-            Target::Aya => aya::codegen(&sysroot_options, libbpf_dir).context("aya"),
-            Target::AyaEbpfBindings => aya_ebpf_bindings::codegen(&sysroot_options, libbpf_dir)
-                .context("aya_ebpf_bindings"),
+            Target::Aya => aya::codegen(&sysroot_options, archs, libbpf_dir).context("aya"),
+            Target::AyaEbpfBindings => {
+                aya_ebpf_bindings::codegen(&sysroot_options, archs, libbpf_dir)
+                    .context("aya_ebpf_bindings")
+            }
         },
         None => {
-            aya::codegen(&sysroot_options, libbpf_dir).context("aya")?;
-            aya_ebpf_bindings::codegen(&sysroot_options, libbpf_dir)
+            aya::codegen(&sysroot_options, archs, libbpf_dir).context("aya")?;
+            aya_ebpf_bindings::codegen(&sysroot_options, archs, libbpf_dir)
                 .context("aya_ebpf_bindings")?;
             Ok(())
         }